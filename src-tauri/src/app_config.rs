@@ -363,6 +363,33 @@ impl MultiAppConfig {
         Ok(config)
     }
 
+    /// 从指定文件读取配置，用于手动重新迁移
+    ///
+    /// 与 [`Self::load`] 不同，不会自动导入提示词、也不会写回磁盘。
+    pub fn load_from_file(path: &std::path::Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+        let mut value: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| AppError::json(path, e))?;
+        // 由 export_config_json 导出的文件附带设备设置，不属于 MultiAppConfig
+        if let Some(map) = value.as_object_mut() {
+            map.remove("settings");
+        }
+        let mut config: Self =
+            serde_json::from_value(value).map_err(|e| AppError::json(path, e))?;
+
+        if !config.apps.contains_key("gemini") {
+            config
+                .apps
+                .insert("gemini".to_string(), ProviderManager::default());
+        }
+        config.migrate_mcp_to_unified()?;
+        if let Some(old_claude_snippet) = config.claude_common_config_snippet.take() {
+            config.common_config_snippets.claude = Some(old_claude_snippet);
+        }
+
+        Ok(config)
+    }
+
     /// 保存配置到文件
     pub fn save(&self) -> Result<(), AppError> {
         let config_path = get_app_config_path();
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
 use std::sync::{OnceLock, RwLock};
@@ -8,6 +9,9 @@ use crate::error::AppError;
 /// Store 中的键名
 const STORE_KEY_APP_CONFIG_DIR: &str = "app_config_dir_override";
 
+/// 已下载安装包记录所在的 Store 文件（键为下载链接）
+const DOWNLOADS_STORE: &str = "downloads.json";
+
 /// 缓存当前的 app_config_dir 覆盖路径，避免存储 AppHandle
 static APP_CONFIG_DIR_OVERRIDE: OnceLock<RwLock<Option<PathBuf>>> = OnceLock::new();
 
@@ -105,6 +109,45 @@ pub fn set_app_config_dir_to_store(
     Ok(())
 }
 
+/// 已下载并校验完成的安装包
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRecord {
+    pub file_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// 读取某个下载链接对应的安装包记录
+pub fn get_download_record(app: &tauri::AppHandle, url: &str) -> Option<DownloadRecord> {
+    let store = match app.store_builder(DOWNLOADS_STORE).build() {
+        Ok(store) => store,
+        Err(e) => {
+            log::warn!("无法创建 Store: {e}");
+            return None;
+        }
+    };
+    serde_json::from_value(store.get(url)?).ok()
+}
+
+/// 记录下载完成的安装包（以下载链接为键，覆盖旧记录）
+pub fn set_download_record(
+    app: &tauri::AppHandle,
+    url: &str,
+    record: &DownloadRecord,
+) -> Result<(), AppError> {
+    let store = app
+        .store_builder(DOWNLOADS_STORE)
+        .build()
+        .map_err(|e| AppError::Message(format!("创建 Store 失败: {e}")))?;
+    let value = serde_json::to_value(record)
+        .map_err(|e| AppError::Message(format!("序列化下载记录失败: {e}")))?;
+    store.set(url, value);
+    store
+        .save()
+        .map_err(|e| AppError::Message(format!("保存 Store 失败: {e}")))
+}
+
 /// 解析路径，支持 ~ 开头的相对路径
 fn resolve_path(raw: &str) -> PathBuf {
     if raw == "~" {
@@ -0,0 +1,90 @@
+//! 界面外观（主题）
+//!
+//! 外观模式保存在设置文件中。`light` / `dark` 时强制原生窗口主题；`system` 时窗口跟随
+//! 系统主题，系统主题切换后向前端发送 `theme-changed` 事件。
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Theme, WebviewWindow};
+
+use crate::settings::Appearance;
+
+/// 系统主题切换事件
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
+
+/// 外观设置与当前生效的主题
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppearanceInfo {
+    pub mode: Appearance,
+    /// 当前生效的主题（`light` / `dark`）
+    pub theme: &'static str,
+}
+
+fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+/// 外观模式对应的原生窗口主题（`None` 表示跟随系统）
+fn window_theme(mode: Appearance) -> Option<Theme> {
+    match mode {
+        Appearance::System => None,
+        Appearance::Light => Some(Theme::Light),
+        Appearance::Dark => Some(Theme::Dark),
+    }
+}
+
+/// 查询外观设置与当前生效的主题
+pub fn current(app: &AppHandle) -> AppearanceInfo {
+    let mode = crate::settings::get_settings().appearance;
+    let theme = match window_theme(mode) {
+        Some(theme) => theme,
+        None => app
+            .get_webview_window("main")
+            .and_then(|window| window.theme().ok())
+            .unwrap_or(Theme::Light),
+    };
+    AppearanceInfo {
+        mode,
+        theme: theme_name(theme),
+    }
+}
+
+/// 将外观模式应用到原生窗口
+pub fn apply(window: &WebviewWindow, mode: Appearance) {
+    if let Err(e) = window.set_theme(window_theme(mode)) {
+        log::warn!("设置窗口主题失败: {e}");
+    }
+}
+
+/// 处理窗口的系统主题变化：仅在跟随系统时通知前端
+pub fn on_theme_changed(app: &AppHandle, theme: Theme) {
+    if crate::settings::get_settings().appearance != Appearance::System {
+        return;
+    }
+    let info = AppearanceInfo {
+        mode: Appearance::System,
+        theme: theme_name(theme),
+    };
+    if let Err(e) = app.emit(THEME_CHANGED_EVENT, info) {
+        log::warn!("发送主题变化事件失败: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modes_and_maps_window_theme() {
+        assert_eq!("System".parse::<Appearance>().unwrap(), Appearance::System);
+        assert_eq!(" dark ".parse::<Appearance>().unwrap(), Appearance::Dark);
+        assert!("auto".parse::<Appearance>().is_err());
+
+        assert_eq!(window_theme(Appearance::System), None);
+        assert_eq!(window_theme(Appearance::Light), Some(Theme::Light));
+        assert_eq!(theme_name(Theme::Dark), "dark");
+    }
+}
@@ -20,8 +20,19 @@ fn get_auto_launch() -> Result<AutoLaunch, AppError> {
     Ok(auto_launch)
 }
 
+/// 便携模式下程序目录可能随时移动，登记的启动项会指向失效路径，因此拒绝启用
+fn ensure_not_portable() -> Result<(), AppError> {
+    if crate::config::is_portable_mode() {
+        return Err(AppError::Message(
+            "便携模式下不支持开机自启：程序目录可能被移动，请使用安装版".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// 启用开机自启
 pub fn enable_auto_launch() -> Result<(), AppError> {
+    ensure_not_portable()?;
     let auto_launch = get_auto_launch()?;
     auto_launch
         .enable()
@@ -47,3 +58,19 @@ pub fn is_auto_launch_enabled() -> Result<bool, AppError> {
         .is_enabled()
         .map_err(|e| AppError::Message(format!("检查开机自启状态失败: {e}")))
 }
+
+/// 启动时按设置校正系统启动项（例如用户手动删除了登录项但设置仍为启用）
+pub fn reconcile_auto_launch(enabled: bool) -> Result<(), AppError> {
+    if enabled && crate::config::is_portable_mode() {
+        log::info!("便携模式下跳过开机自启校正");
+        return Ok(());
+    }
+    if is_auto_launch_enabled()? == enabled {
+        return Ok(());
+    }
+    if enabled {
+        enable_auto_launch()
+    } else {
+        disable_auto_launch()
+    }
+}
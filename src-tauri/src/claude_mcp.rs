@@ -66,7 +66,7 @@ fn read_json_value(path: &Path) -> Result<Value, AppError> {
         return Ok(serde_json::json!({}));
     }
     let content = fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
-    let value: Value = serde_json::from_str(&content).map_err(|e| AppError::json(path, e))?;
+    let value: Value = crate::config::parse_json_content(path, &content)?;
     Ok(value)
 }
 
@@ -0,0 +1,401 @@
+//! 启动参数解析
+//!
+//! 支持通过命令行切换当前供应商，便于脚本和 shell 别名调用：
+//! `cc-switch --use <providerId> [--tool claude|codex|gemini] [--no-window]`
+//! 带 `--no-window` 时不启动界面，切换完成后直接退出，失败时输出到 stderr 并返回非零退出码。
+//!
+//! 另有无界面的查询操作：`--list-providers [--tool …]` 列出供应商，`--tool-versions` 检测 CLI 版本。
+//! 加 `--json` 后，无界面操作向 stdout 输出单个 JSON 对象 `{ ok, action, data, error }`，
+//! 退出码不变（成功 0、失败 1、参数错误 2）。
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::services::ProviderService;
+use crate::store::AppState;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 参数错误的退出码
+pub const EXIT_USAGE: i32 = 2;
+/// 操作失败的退出码
+pub const EXIT_FAILURE: i32 = 1;
+
+/// 无界面执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessAction {
+    Switch,
+    ListProviders,
+    ToolVersions,
+}
+
+impl HeadlessAction {
+    /// JSON 输出中的 `action` 字段
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Switch => "switch",
+            Self::ListProviders => "list-providers",
+            Self::ToolVersions => "tool-versions",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchArgs {
+    /// 要切换到的供应商 ID
+    pub use_provider: Option<String>,
+    /// 目标应用，未指定时为 Claude
+    pub tool: AppType,
+    /// 不显示窗口，执行完直接退出
+    pub no_window: bool,
+    /// 列出目标应用的供应商后退出
+    pub list_providers: bool,
+    /// 检测 CLI 工具版本后退出
+    pub tool_versions: bool,
+    /// 无界面操作以 JSON 输出结果
+    pub json: bool,
+}
+
+impl Default for LaunchArgs {
+    fn default() -> Self {
+        Self {
+            use_provider: None,
+            tool: AppType::Claude,
+            no_window: false,
+            list_providers: false,
+            tool_versions: false,
+            json: false,
+        }
+    }
+}
+
+impl LaunchArgs {
+    /// 需要无界面执行的操作；为 `None` 时正常启动界面
+    pub fn headless_action(&self) -> Option<HeadlessAction> {
+        if self.list_providers {
+            Some(HeadlessAction::ListProviders)
+        } else if self.tool_versions {
+            Some(HeadlessAction::ToolVersions)
+        } else if self.no_window {
+            Some(HeadlessAction::Switch)
+        } else {
+            None
+        }
+    }
+}
+
+/// `--json` 模式下的输出，字段固定以便脚本解析
+#[derive(Debug, Serialize)]
+pub struct HeadlessOutput {
+    pub ok: bool,
+    /// 操作名（参数错误时为 `usage`）
+    pub action: &'static str,
+    /// 操作结果，失败时为 `null`
+    pub data: Value,
+    pub error: Option<String>,
+}
+
+impl HeadlessOutput {
+    fn print(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{line}"),
+            Err(e) => eprintln!("序列化输出失败: {e}"),
+        }
+    }
+}
+
+/// 取出可按 UTF-8 解析的参数；其它参数（如系统传入的非 UTF-8 文件路径）记录后跳过，
+/// 不能因此中断界面启动
+pub fn utf8_args<I>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = std::ffi::OsString>,
+{
+    args.into_iter()
+        .filter_map(|arg| match arg.into_string() {
+            Ok(arg) => Some(arg),
+            Err(arg) => {
+                log::warn!(
+                    "忽略无法解析为 UTF-8 的命令行参数: {}",
+                    arg.to_string_lossy()
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// 原始参数中是否带 `--json`（参数解析失败时也需按 JSON 报告）
+pub fn wants_json<I, S>(args: I) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().any(|arg| arg.as_ref() == "--json")
+}
+
+/// 报告参数错误，返回退出码
+pub fn report_usage_error(error: &str, json: bool) -> i32 {
+    if json {
+        HeadlessOutput {
+            ok: false,
+            action: "usage",
+            data: Value::Null,
+            error: Some(error.to_string()),
+        }
+        .print();
+    } else {
+        eprintln!("{error}");
+    }
+    EXIT_USAGE
+}
+
+/// 解析启动参数（不含程序路径）
+///
+/// 未识别的参数（深链接 URL、`--portable` 等）原样忽略，交由其它逻辑处理。
+pub fn parse_launch_args<I, S>(args: I) -> Result<LaunchArgs, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut parsed = LaunchArgs::default();
+    let mut iter = args.into_iter().peekable();
+
+    while let Some(arg) = iter.next() {
+        let arg = arg.as_ref();
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg, None),
+        };
+
+        match flag {
+            "--use" | "--tool" => {
+                let value = match inline_value {
+                    Some(value) => value,
+                    // 下一个参数是其它选项（如 `--use --portable`）时视为缺少取值，不吞掉该选项
+                    None => iter
+                        .next_if(|v| !v.as_ref().starts_with("--"))
+                        .map(|v| v.as_ref().to_string())
+                        .ok_or_else(|| format!("参数 {flag} 缺少取值"))?,
+                };
+                let value = value.trim();
+                if value.is_empty() {
+                    return Err(format!("参数 {flag} 的取值不能为空"));
+                }
+                if flag == "--use" {
+                    parsed.use_provider = Some(value.to_string());
+                } else {
+                    parsed.tool = AppType::from_str(value).map_err(|e| e.to_string())?;
+                }
+            }
+            "--no-window" => parsed.no_window = true,
+            "--list-providers" => parsed.list_providers = true,
+            "--tool-versions" => parsed.tool_versions = true,
+            "--json" => parsed.json = true,
+            _ => {}
+        }
+    }
+
+    let queries = [parsed.list_providers, parsed.tool_versions];
+    if queries.iter().filter(|&&q| q).count() > 1
+        || (queries.contains(&true) && parsed.use_provider.is_some())
+    {
+        return Err("--use、--list-providers 与 --tool-versions 不能同时使用".to_string());
+    }
+    if parsed.no_window && parsed.use_provider.is_none() {
+        return Err("--no-window 需要与 --use <providerId> 一起使用".to_string());
+    }
+
+    Ok(parsed)
+}
+
+/// 操作成功的结果：`data` 用于 JSON 输出，`lines` 用于普通文本输出
+struct Outcome {
+    data: Value,
+    lines: Vec<String>,
+}
+
+/// 执行无界面操作并输出结果，返回进程退出码
+pub fn run_headless(args: &LaunchArgs) -> i32 {
+    let Some(action) = args.headless_action() else {
+        return EXIT_USAGE;
+    };
+
+    let result = open_state().and_then(|state| match action {
+        HeadlessAction::Switch => switch_provider(&state, args),
+        HeadlessAction::ListProviders => list_providers(&state, &args.tool),
+        HeadlessAction::ToolVersions => tool_versions(&state),
+    });
+
+    if args.json {
+        let (ok, data, error) = match result {
+            Ok(outcome) => (true, outcome.data, None),
+            Err(e) => (false, Value::Null, Some(e)),
+        };
+        HeadlessOutput {
+            ok,
+            action: action.as_str(),
+            data,
+            error,
+        }
+        .print();
+        return if ok { 0 } else { EXIT_FAILURE };
+    }
+
+    match result {
+        Ok(outcome) => {
+            for line in outcome.lines {
+                println!("{line}");
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn open_state() -> Result<AppState, String> {
+    // 命令行切换需要写入真实密钥，忽略启动解锁设置直接读取钥匙串
+    crate::secret_store::init(false);
+    let db = Database::init().map_err(|e| format!("初始化数据库失败: {e}"))?;
+    Ok(AppState::new(Arc::new(db)))
+}
+
+/// 直接在数据库中切换供应商并写入 CLI 配置
+fn switch_provider(state: &AppState, args: &LaunchArgs) -> Result<Outcome, String> {
+    let provider_id = args.use_provider.as_deref().unwrap_or_default();
+    ProviderService::switch(state, args.tool.clone(), provider_id)
+        .map_err(|e| format!("切换供应商失败: {e}"))?;
+    log::info!(
+        "已通过命令行切换 {} 的供应商为 {provider_id}",
+        args.tool.as_str()
+    );
+    Ok(Outcome {
+        data: json!({ "tool": args.tool.as_str(), "providerId": provider_id }),
+        lines: Vec::new(),
+    })
+}
+
+/// 列出供应商（不含配置内容，避免输出密钥），当前供应商以 `*` 标记
+fn list_providers(state: &AppState, app_type: &AppType) -> Result<Outcome, String> {
+    let providers = state
+        .db
+        .get_all_providers(app_type.as_str())
+        .map_err(|e| format!("读取供应商失败: {e}"))?;
+    let current = crate::settings::get_effective_current_provider(&state.db, app_type)
+        .map_err(|e| format!("读取当前供应商失败: {e}"))?;
+
+    let is_current = |id: &str| current.as_deref() == Some(id);
+    let lines = providers
+        .values()
+        .map(|p| {
+            let marker = if is_current(&p.id) { "*" } else { " " };
+            format!("{marker} {}\t{}", p.id, p.name)
+        })
+        .collect();
+    let items: Vec<Value> = providers
+        .values()
+        .map(|p| json!({ "id": p.id, "name": p.name, "current": is_current(&p.id) }))
+        .collect();
+    Ok(Outcome {
+        data: json!({ "tool": app_type.as_str(), "current": current, "providers": items }),
+        lines,
+    })
+}
+
+/// 检测各 CLI 工具的本地版本与 npm 最新版本
+fn tool_versions(state: &AppState) -> Result<Outcome, String> {
+    let client = crate::commands::shared_http_client()?;
+    let versions = tauri::async_runtime::block_on(
+        crate::services::cli_tools::detect_tool_versions(&client, &state.db, false),
+    );
+
+    let lines = versions
+        .iter()
+        .map(|v| {
+            let local = v.version.as_deref().unwrap_or("未安装");
+            match &v.latest_version {
+                Some(latest) => format!("{}\t{local}\t(最新 {latest})", v.name),
+                None => format!("{}\t{local}", v.name),
+            }
+        })
+        .collect();
+    let data = serde_json::to_value(&versions).map_err(|e| format!("序列化版本信息失败: {e}"))?;
+    Ok(Outcome { data, lines })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_use_tool_and_no_window() {
+        let args = parse_launch_args(["--use", "p1", "--tool=codex", "--no-window"]).unwrap();
+        assert_eq!(args.use_provider.as_deref(), Some("p1"));
+        assert_eq!(args.tool, AppType::Codex);
+        assert!(args.no_window);
+    }
+
+    #[test]
+    fn ignores_unrelated_args() {
+        let args = parse_launch_args(["ccswitch://v1/import?x=1", "--portable"]).unwrap();
+        assert_eq!(args, LaunchArgs::default());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_non_utf8_args() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let args = utf8_args([
+            OsString::from("--no-window"),
+            OsString::from_vec(vec![b'/', 0xff, 0xfe]),
+        ]);
+        assert_eq!(args, vec!["--no-window".to_string()]);
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        assert!(parse_launch_args(["--use"]).is_err());
+        let err = parse_launch_args(["--use", "--portable"]).unwrap_err();
+        assert!(err.contains("缺少取值"), "{err}");
+        assert!(parse_launch_args(["--use", "p1", "--tool", "vim"]).is_err());
+        assert!(parse_launch_args(["--no-window"]).is_err());
+        assert!(parse_launch_args(["--list-providers", "--tool-versions"]).is_err());
+        assert!(parse_launch_args(["--list-providers", "--use", "p1"]).is_err());
+    }
+
+    #[test]
+    fn query_flags_run_headless() {
+        let args = parse_launch_args(["--list-providers", "--tool", "gemini", "--json"]).unwrap();
+        assert_eq!(args.headless_action(), Some(HeadlessAction::ListProviders));
+        assert_eq!(args.tool, AppType::Gemini);
+        assert!(args.json);
+
+        let args = parse_launch_args(["--tool-versions"]).unwrap();
+        assert_eq!(args.headless_action(), Some(HeadlessAction::ToolVersions));
+
+        // 不带 --no-window 的 --use 交给界面处理
+        let args = parse_launch_args(["--use", "p1", "--json"]).unwrap();
+        assert_eq!(args.headless_action(), None);
+    }
+
+    #[test]
+    fn json_output_keeps_stable_fields() {
+        let output = HeadlessOutput {
+            ok: false,
+            action: HeadlessAction::Switch.as_str(),
+            data: Value::Null,
+            error: Some("切换供应商失败".into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            json!({ "ok": false, "action": "switch", "data": null, "error": "切换供应商失败" })
+        );
+        assert!(wants_json(["--tool", "x", "--json"]));
+        assert!(!wants_json(["--json=1"]));
+    }
+}
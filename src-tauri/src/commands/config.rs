@@ -80,6 +80,61 @@ pub async fn open_config_folder(handle: AppHandle, app: String) -> Result<bool,
     Ok(true)
 }
 
+/// 工具的主配置文件：Claude `settings.json`、Codex `config.toml`、Gemini `settings.json`
+fn tool_config_path(app_type: &AppType) -> std::path::PathBuf {
+    match app_type {
+        AppType::Claude => get_claude_settings_path(),
+        AppType::Codex => codex_config::get_codex_config_path(),
+        AppType::Gemini => crate::gemini_config::get_gemini_settings_path(),
+    }
+}
+
+/// 新建配置文件时写入的最小合法内容
+fn tool_config_skeleton(app_type: &AppType) -> &'static str {
+    match app_type {
+        AppType::Claude | AppType::Gemini => "{}\n",
+        AppType::Codex => "# Codex 配置文件，参见 https://github.com/openai/codex\n",
+    }
+}
+
+/// 获取工具主配置文件的路径（按当前平台与目录覆盖设置解析）
+#[tauri::command]
+pub async fn get_tool_config_path(tool: String) -> Result<String, String> {
+    let app_type = AppType::from_str(&tool).map_err(|e| e.to_string())?;
+    Ok(tool_config_path(&app_type).to_string_lossy().to_string())
+}
+
+/// 用系统默认编辑器打开工具的主配置文件
+///
+/// 文件不存在且未传 `create` 时返回 `false`，由前端询问用户后以 `create: true` 重试，
+/// 此时写入最小合法内容后再打开。如需在文件管理器中定位，可对
+/// `get_tool_config_path` 的结果调用 `reveal_in_file_manager`。
+#[tauri::command]
+pub async fn open_tool_config(
+    handle: AppHandle,
+    tool: String,
+    create: Option<bool>,
+) -> Result<bool, String> {
+    let app_type = AppType::from_str(&tool).map_err(|e| e.to_string())?;
+    let path = tool_config_path(&app_type);
+
+    if !path.exists() {
+        if !create.unwrap_or(false) {
+            return Ok(false);
+        }
+        config::atomic_write(&path, tool_config_skeleton(&app_type).as_bytes())
+            .map_err(|e| format!("创建配置文件失败: {e}"))?;
+        log::info!("已创建配置文件: {}", path.display());
+    }
+
+    handle
+        .opener()
+        .open_path(path.to_string_lossy().to_string(), None::<String>)
+        .map_err(|e| format!("打开配置文件失败: {e}"))?;
+
+    Ok(true)
+}
+
 /// 弹出系统目录选择器并返回用户选择的路径
 #[tauri::command]
 pub async fn pick_directory(
@@ -119,6 +174,115 @@ pub async fn get_app_config_path() -> Result<String, String> {
     Ok(config_path.to_string_lossy().to_string())
 }
 
+/// 获取应用数据目录（便携模式下位于可执行文件旁的 `data/`）
+#[tauri::command]
+pub async fn get_data_dir() -> Result<String, String> {
+    Ok(config::get_data_dir().to_string_lossy().to_string())
+}
+
+/// 在文件管理器中打开应用数据目录（数据库、备份等所在位置，便携模式下位于可执行文件旁），
+/// 目录不存在时先创建，返回目录路径
+#[tauri::command]
+pub async fn open_data_dir(handle: AppHandle) -> Result<String, String> {
+    let data_dir = config::get_data_dir();
+
+    if !data_dir.exists() {
+        std::fs::create_dir_all(&data_dir).map_err(|e| format!("创建目录失败: {e}"))?;
+    }
+
+    let path = data_dir.to_string_lossy().to_string();
+    handle
+        .opener()
+        .open_path(path.clone(), None::<String>)
+        .map_err(|e| format!("打开文件夹失败: {e}"))?;
+
+    Ok(path)
+}
+
+/// 在便携目录与安装目录之间迁移数据（数据库、设置、密钥盐与备份）
+///
+/// `from` / `to` 为 `portable`、`installed` 或绝对路径；目标已有数据时需 `force` 才会覆盖。
+/// 返回迁移的文件清单。
+#[tauri::command]
+pub async fn migrate_data_location(
+    state: tauri::State<'_, crate::store::AppState>,
+    from: String,
+    to: String,
+    force: Option<bool>,
+) -> Result<crate::services::data_migration::DataMigrationReport, String> {
+    // 源目录可能是当前数据目录，先把内存中的设置写入数据库
+    state.db.flush_settings().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::services::data_migration::migrate_data_location(&from, &to, force.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("迁移数据失败: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+/// 获取日志目录（可配合 `reveal_in_file_manager` 打开）
+#[tauri::command]
+pub async fn get_log_dir() -> Result<String, String> {
+    Ok(crate::logging::log_dir().to_string_lossy().to_string())
+}
+
+/// 读取当前日志文件的最后若干行（最多 5000 行，日志已遮盖密钥）
+#[allow(non_snake_case)]
+#[tauri::command]
+pub async fn read_recent_logs(maxLines: usize) -> Result<Vec<String>, String> {
+    let Some(path) = crate::logging::current_log_file() else {
+        return Ok(Vec::new());
+    };
+    let max_lines = maxLines.min(crate::logging::MAX_TAIL_LINES);
+    tauri::async_runtime::spawn_blocking(move || crate::logging::tail_lines(&path, max_lines))
+        .await
+        .map_err(|e| format!("读取日志失败: {e}"))?
+        .map_err(|e| format!("读取日志失败: {e}"))
+}
+
+/// 系统密钥服务是否可用（不可用时 API Key 以明文保存）
+#[tauri::command]
+pub async fn is_keystore_available() -> Result<bool, String> {
+    Ok(crate::secret_store::is_keystore_available())
+}
+
+/// API Key 存储是否处于锁定状态（锁定时供应商密钥以占位符返回）
+#[tauri::command]
+pub async fn is_locked() -> Result<bool, String> {
+    Ok(crate::secret_store::is_locked())
+}
+
+/// 从系统钥匙串读取密钥并解锁 API Key 存储
+#[tauri::command]
+pub async fn unlock_store(state: tauri::State<'_, crate::store::AppState>) -> Result<bool, String> {
+    crate::secret_store::unlock()?;
+    // 锁定期间导入的明文密钥在解锁后补充加密
+    if let Err(e) = state.db.encrypt_plaintext_secrets() {
+        log::warn!("加密已有 API Key 失败: {e}");
+    }
+    Ok(true)
+}
+
+/// 锁定 API Key 存储，丢弃内存中的密钥
+#[tauri::command]
+pub async fn lock_store() -> Result<bool, String> {
+    crate::secret_store::lock()?;
+    Ok(true)
+}
+
+/// 启动 CLI 配置文件的外部修改监听（发送 `config-changed-externally` 事件）
+#[tauri::command]
+pub async fn start_config_watcher(handle: AppHandle) -> Result<bool, String> {
+    crate::services::config_watcher::start(handle)?;
+    Ok(true)
+}
+
+/// 停止 CLI 配置文件的外部修改监听
+#[tauri::command]
+pub async fn stop_config_watcher() -> Result<bool, String> {
+    crate::services::config_watcher::stop()
+}
+
 /// 打开应用配置文件夹
 #[tauri::command]
 pub async fn open_app_config_folder(handle: AppHandle) -> Result<bool, String> {
@@ -219,3 +383,17 @@ pub async fn set_common_config_snippet(
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_config_skeletons_are_valid() {
+        for app_type in [AppType::Claude, AppType::Gemini] {
+            let skeleton = tool_config_skeleton(&app_type);
+            assert!(serde_json::from_str::<serde_json::Value>(skeleton).is_ok());
+        }
+        assert!(toml::from_str::<toml::Table>(tool_config_skeleton(&AppType::Codex)).is_ok());
+    }
+}
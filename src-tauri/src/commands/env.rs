@@ -2,6 +2,7 @@ use crate::services::env_checker::{check_env_conflicts as check_conflicts, EnvCo
 use crate::services::env_manager::{
     delete_env_vars as delete_vars, restore_from_backup, BackupInfo,
 };
+use crate::services::shell_profile::{self, ShellProfile};
 
 /// Check environment variable conflicts for a specific app
 #[tauri::command]
@@ -20,3 +21,9 @@ pub fn delete_env_vars(conflicts: Vec<EnvConflict>) -> Result<BackupInfo, String
 pub fn restore_env_backup(backup_path: String) -> Result<(), String> {
     restore_from_backup(backup_path)
 }
+
+/// Detect the user's shell and the profile file env exports should go into
+#[tauri::command]
+pub fn detect_user_shell() -> Result<ShellProfile, String> {
+    shell_profile::detect_user_shell()
+}
@@ -6,7 +6,8 @@ use tauri::State;
 use tauri_plugin_dialog::DialogExt;
 
 use crate::error::AppError;
-use crate::services::provider::ProviderService;
+use crate::services::provider::{ImportStrategy, ProviderImportResult, ProviderService};
+use crate::services::{ConfigBackupEntry, ConfigService};
 use crate::store::AppState;
 
 /// 导出数据库为 SQL 备份
@@ -64,6 +65,97 @@ pub async fn import_config_from_file(
     .map_err(|e: AppError| e.to_string())
 }
 
+/// 将供应商、当前选中项与设置导出为可读的 JSON
+///
+/// 指定 `targetPath` 时写入文件并返回 `None`，否则直接返回 JSON 字符串。
+/// 默认隐藏 API Key 等敏感字段，`includeSecrets` 为 true 时原样导出。
+#[tauri::command]
+pub async fn export_config_json(
+    #[allow(non_snake_case)] targetPath: Option<String>,
+    #[allow(non_snake_case)] includeSecrets: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let json = ConfigService::export_config_json(&db, includeSecrets.unwrap_or(false))?;
+        match targetPath {
+            Some(path) => {
+                crate::config::write_text_file(&PathBuf::from(path), &json)?;
+                Ok::<_, AppError>(None)
+            }
+            None => Ok(Some(json)),
+        }
+    })
+    .await
+    .map_err(|e| format!("导出 JSON 配置失败: {e}"))?
+    .map_err(|e: AppError| e.to_string())
+}
+
+/// 导出可分享的供应商 JSON 数组（`ids` 为空时导出全部）
+///
+/// 密钥字段被清空，相应条目带 `secretRequired: true`，导入时提示用户填写自己的 Key。
+#[tauri::command]
+pub async fn export_providers(
+    ids: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let app_state = AppState::new(db);
+        let entries = ProviderService::export_providers(&app_state, ids.as_deref())?;
+        serde_json::to_string_pretty(&entries).map_err(|e| AppError::JsonSerialize { source: e })
+    })
+    .await
+    .map_err(|e| format!("导出供应商失败: {e}"))?
+    .map_err(crate::secret_store::redact_error)
+}
+
+/// 从 JSON 文件批量导入供应商（数组，每项为供应商对象并带 `app` 字段）
+///
+/// `strategy` 决定 id/名称冲突时的处理：`skip` / `overwrite` / `rename`。
+/// 任一条目校验失败时不导入任何供应商。返回每个条目的导入结果。
+#[tauri::command]
+pub async fn import_providers(
+    path: String,
+    strategy: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ProviderImportResult>, String> {
+    let strategy = strategy
+        .parse::<ImportStrategy>()
+        .map_err(|e| e.to_string())?;
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let app_state = AppState::new(db);
+        ProviderService::import_providers(&app_state, &PathBuf::from(path), strategy)
+    })
+    .await
+    .map_err(|e| format!("导入供应商失败: {e}"))?
+    .map_err(crate::secret_store::redact_error)
+}
+
+/// 列出 JSON → SQLite 迁移前生成的 config.json 备份（新的在前）
+#[tauri::command]
+pub async fn list_config_backups() -> Result<Vec<ConfigBackupEntry>, String> {
+    let dir = crate::config::get_app_config_dir();
+    ConfigService::list_migration_backups(&dir).map_err(|e| e.to_string())
+}
+
+/// 清理旧备份：每个备份目标保留最新的 `keepPerTarget` 个（至少 1 个），其余超过
+/// `maxAgeDays` 天的删除，返回删除的文件数与释放的字节数
+#[allow(non_snake_case)]
+#[tauri::command]
+pub async fn prune_backups(
+    keepPerTarget: usize,
+    maxAgeDays: u64,
+) -> Result<crate::services::backup_prune::PruneReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::services::backup_prune::prune_backups(keepPerTarget, maxAgeDays)
+    })
+    .await
+    .map_err(|e| format!("清理备份失败: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn sync_current_providers_live(state: State<'_, AppState>) -> Result<Value, String> {
     let db = state.db.clone();
@@ -1,10 +1,28 @@
 #![allow(non_snake_case)]
 
-use crate::init_status::InitErrorPayload;
-use tauri::AppHandle;
+use crate::database::RepairReport;
+use crate::error::{format_command_error, CommandErrorCode};
+use crate::init_status::{InitErrorPayload, MigrationErrorPayload};
+use crate::no_proxy::NoProxyRules;
+use crate::secret_store::{redact_error, redact_secrets};
+use crate::services::api_key::{self, ApiKeyValidation};
+use crate::services::cli_tools::{self, ManagedTool, ToolBinary, ToolInstallation, ToolVersion};
+use crate::services::connection_test::{self, ConnectionTestResult};
+use crate::services::diagnostics::{self, DiagnosticsInfo};
+use crate::services::environment_check::{self, EnvironmentCheck};
+use crate::services::release::{self, ReleaseInfo};
+use crate::services::tool_install;
+use crate::settings::{NetworkProxy, UpdateSource};
+use crate::store::AppState;
+use tauri::{AppHandle, Emitter, State};
 use tauri_plugin_opener::OpenerExt;
 
 use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
 use tokio::io::AsyncWriteExt;
 
 #[cfg(target_os = "windows")]
@@ -13,14 +31,13 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// 下载前磁盘空间预检的安全余量（64 MiB）
+const DOWNLOAD_DISK_SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+
 /// 打开外部链接
 #[tauri::command]
 pub async fn open_external(app: AppHandle, url: String) -> Result<bool, String> {
-    let url = if url.starts_with("http://") || url.starts_with("https://") {
-        url
-    } else {
-        format!("https://{url}")
-    };
+    let url = normalize_external_url(&url)?;
 
     app.opener()
         .open_url(&url, None::<String>)
@@ -29,6 +46,106 @@ pub async fn open_external(app: AppHandle, url: String) -> Result<bool, String>
     Ok(true)
 }
 
+/// 复制文本到系统剪贴板
+///
+/// 复制 API Key 等敏感内容时可传入 `autoClearMs`，到期后若剪贴板内容仍是该文本则自动清空，
+/// 避免用户之后复制的其它内容被误删。
+#[tauri::command]
+pub async fn copy_to_clipboard(
+    app: AppHandle,
+    text: String,
+    #[allow(non_snake_case)] autoClearMs: Option<u64>,
+) -> Result<bool, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    app.clipboard()
+        .write_text(text.as_str())
+        .map_err(|e| format!("写入剪贴板失败: {e}"))?;
+
+    if let Some(delay_ms) = autoClearMs.filter(|ms| *ms > 0) {
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+            match app.clipboard().read_text() {
+                Ok(current) if current == text => {
+                    if let Err(e) = app.clipboard().clear() {
+                        log::warn!("自动清空剪贴板失败: {e}");
+                    }
+                }
+                Ok(_) => {} // 剪贴板已被用户替换，保持不动
+                Err(e) => log::debug!("读取剪贴板失败，跳过自动清空: {e}"),
+            }
+        });
+    }
+
+    Ok(true)
+}
+
+/// 在系统文件管理器中定位并选中指定文件（或目录）
+///
+/// Windows 使用资源管理器选中、macOS 使用 Finder 定位；Linux 通过 D-Bus 的 FileManager1 选中，
+/// 不支持时退化为打开所在目录。
+#[tauri::command]
+pub async fn reveal_in_file_manager(app: AppHandle, path: String) -> Result<bool, String> {
+    let target = std::path::PathBuf::from(path.trim());
+    if !target.exists() {
+        return Err(format!("路径不存在: {}", target.display()));
+    }
+
+    match app.opener().reveal_item_in_dir(&target) {
+        Ok(()) => Ok(true),
+        Err(e) if cfg!(target_os = "linux") => {
+            log::warn!("定位文件失败，改为打开所在目录: {e}");
+            let dir = if target.is_dir() {
+                target.as_path()
+            } else {
+                target.parent().unwrap_or(target.as_path())
+            };
+            app.opener()
+                .open_path(dir.to_string_lossy().to_string(), None::<String>)
+                .map_err(|e| format!("打开文件夹失败: {e}"))?;
+            Ok(true)
+        }
+        Err(e) => Err(format!("在文件管理器中定位失败: {e}")),
+    }
+}
+
+/// 目标安装包版本是否低于当前运行版本
+fn is_downgrade(current: &str, target: &str) -> Result<bool, String> {
+    let parse = |v: &str| semver::Version::parse(v.trim().trim_start_matches('v'));
+    let invalid = |e: semver::Error| {
+        format_command_error(CommandErrorCode::InvalidVersion, Some(&e.to_string()))
+    };
+    let current = parse(current).map_err(invalid)?;
+    let target = parse(target).map_err(invalid)?;
+    Ok(target < current)
+}
+
+/// 规范化外部链接：无协议时补全 `https://`，且只允许 http/https。
+///
+/// `file:`、`javascript:`、`mailto:` 等协议一律拒绝，避免被用于打开本地文件或执行脚本。
+fn normalize_external_url(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    let url = match url::Url::parse(input) {
+        // `localhost:3000` 也能被解析为 scheme 为 localhost 的 URL，需按主机名处理
+        Ok(_) if !is_host_with_port(input) => input.to_string(),
+        _ => format!("https://{input}"),
+    };
+
+    let parsed = url::Url::parse(&url).map_err(|e| format!("无效的链接: {e}"))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(url),
+        _ => Err("不支持的链接协议".to_string()),
+    }
+}
+
+/// 是否形如 `host:port`（冒号后到路径前全为数字）
+fn is_host_with_port(input: &str) -> bool {
+    input.split_once(':').is_some_and(|(_, rest)| {
+        let port = rest.split('/').next().unwrap_or_default();
+        !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
 /// 获取当前运行平台（用于前端按系统选择安装包）。
 ///
 /// 返回值与前端 `Pan123Platform` 对齐：`windows` / `macos` / `linux`。
@@ -37,11 +154,310 @@ pub async fn get_runtime_platform() -> Result<String, String> {
     Ok(std::env::consts::OS.to_string())
 }
 
+/// 获取当前运行的 CPU 架构，用于挑选对应架构的安装包。
+///
+/// 返回 `x64` / `arm64` / `x86`，其它架构原样返回 Rust 的架构名。
+#[tauri::command]
+pub async fn get_runtime_arch() -> Result<String, String> {
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "x86",
+        other => other,
+    };
+    Ok(arch.to_string())
+}
+
+/// 当前进程是否运行在 Rosetta 转译下（Apple Silicon 上运行 Intel 版本）。
+///
+/// 非 macOS 平台始终返回 `false`。
+#[tauri::command]
+pub async fn is_running_under_rosetta() -> Result<bool, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut translated: libc::c_int = 0;
+        let mut size = std::mem::size_of::<libc::c_int>();
+        let ret = unsafe {
+            libc::sysctlbyname(
+                c"sysctl.proc_translated".as_ptr(),
+                &mut translated as *mut libc::c_int as *mut libc::c_void,
+                &mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        // Intel Mac 上不存在该 sysctl 项（ENOENT），视为未转译
+        Ok(ret == 0 && translated == 1)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(false)
+    }
+}
+
+/// 获取操作系统版本描述，用于诊断信息与安装包的最低系统版本检查。
+///
+/// 形如 `Windows 11 (22631)`、`macOS 14.5`、`Ubuntu 22.04.4 LTS (kernel 6.5.0)`。
+#[tauri::command]
+pub async fn get_os_version() -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(detect_os_version)
+        .await
+        .map_err(|e| format!("获取系统版本失败: {e}"))?
+}
+
+/// 收集诊断用的系统信息与 CLI 版本
+async fn collect_diagnostics_info(state: &AppState) -> Result<DiagnosticsInfo, String> {
+    let client = shared_http_client()?;
+    let tool_versions = cli_tools::detect_tool_versions(&client, &state.db, false).await;
+    let os_version = get_os_version().await.unwrap_or_else(|e| e);
+
+    Ok(DiagnosticsInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os_version,
+        arch: get_runtime_arch().await?,
+        portable: crate::config::is_portable_mode(),
+        generated_at: chrono::Local::now().to_rfc3339(),
+        tool_versions,
+    })
+}
+
+/// 导出诊断包：系统信息、CLI 版本、去除密钥的数据库导出与最近的日志，返回 zip 路径
+#[tauri::command]
+pub async fn export_diagnostics(
+    state: State<'_, AppState>,
+    #[allow(non_snake_case)] targetPath: String,
+) -> Result<String, String> {
+    let info = collect_diagnostics_info(&state).await?;
+
+    let db = state.db.clone();
+    let target = std::path::PathBuf::from(targetPath);
+    let path = tauri::async_runtime::spawn_blocking(move || {
+        diagnostics::export_bundle(&db, &info, &crate::logging::log_dir(), &target)
+    })
+    .await
+    .map_err(|e| format!("导出诊断包失败: {e}"))?
+    .map_err(redact_error)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 生成 Markdown 格式的诊断摘要（版本、系统、CLI 版本、启动与迁移错误），已去除密钥，
+/// 可直接粘贴到 GitHub issue
+#[tauri::command]
+pub async fn diagnostics_markdown(state: State<'_, AppState>) -> Result<String, String> {
+    let info = collect_diagnostics_info(&state).await?;
+    Ok(diagnostics::render_markdown(
+        &info,
+        crate::init_status::get_init_error().as_ref(),
+        crate::init_status::get_migration_error().as_ref(),
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn detect_os_version() -> Result<String, String> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion")
+        .map_err(|e| format!("读取系统版本注册表失败: {e}"))?;
+    let build: String = key
+        .get_value("CurrentBuildNumber")
+        .map_err(|e| format!("读取系统构建号失败: {e}"))?;
+    // Windows 11 的 ProductName 仍为 "Windows 10 ..."，需按构建号区分
+    let name = match build.parse::<u32>() {
+        Ok(n) if n >= 22000 => "Windows 11",
+        Ok(n) if n >= 10240 => "Windows 10",
+        _ => "Windows",
+    };
+    Ok(format!("{name} ({build})"))
+}
+
+#[cfg(target_os = "macos")]
+fn detect_os_version() -> Result<String, String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .map_err(|e| format!("执行 sw_vers 失败: {e}"))?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || version.is_empty() {
+        return Err("无法获取 macOS 版本".to_string());
+    }
+    Ok(format!("macOS {version}"))
+}
+
+#[cfg(target_os = "linux")]
+fn detect_os_version() -> Result<String, String> {
+    let distro = std::fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                line.strip_prefix("PRETTY_NAME=")
+                    .map(|v| v.trim_matches('"').to_string())
+            })
+        })
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "Linux".to_string());
+    let kernel = std::process::Command::new("uname")
+        .arg("-r")
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .filter(|v| !v.is_empty());
+    Ok(match kernel {
+        Some(kernel) => format!("{distro} (kernel {kernel})"),
+        None => distro,
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn detect_os_version() -> Result<String, String> {
+    Err(format!("不支持的平台: {}", std::env::consts::OS))
+}
+
+/// 按用户设置为出站 HTTP 客户端配置网络代理。
+///
+/// `System` 沿用 reqwest 默认行为（读取 HTTPS_PROXY / HTTP_PROXY，并遵循 NO_PROXY）；
+/// `Custom` 下命中 NO_PROXY 排除规则的主机直连。
+fn apply_network_proxy(builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+    match crate::settings::get_settings().network_proxy {
+        NetworkProxy::System => Ok(builder),
+        NetworkProxy::Direct => Ok(builder.no_proxy()),
+        NetworkProxy::Custom { url } => {
+            let no_proxy = NoProxyRules::from_env();
+            if no_proxy.is_empty() {
+                let proxy =
+                    reqwest::Proxy::all(&url).map_err(|e| format!("无效的代理地址: {e}"))?;
+                return Ok(builder.proxy(proxy));
+            }
+            let proxy_url = reqwest::Url::parse(&url)
+                .or_else(|_| reqwest::Url::parse(&format!("http://{url}")))
+                .map_err(|e| format!("无效的代理地址: {e}"))?;
+            let proxy = reqwest::Proxy::custom(move |target| {
+                let bypass = target.host_str().is_some_and(|host| no_proxy.matches(host));
+                (!bypass).then(|| proxy_url.clone())
+            });
+            Ok(builder.proxy(proxy))
+        }
+    }
+}
+
+/// 共享的出站 HTTP 客户端（复用连接池），网络代理设置变化时自动重建
+static SHARED_HTTP_CLIENT: OnceLock<RwLock<Option<(NetworkProxy, reqwest::Client)>>> =
+    OnceLock::new();
+
+/// 获取按当前代理设置配置好的共享 HTTP 客户端（版本检查、连通性测试等）
+pub(crate) fn shared_http_client() -> Result<reqwest::Client, String> {
+    let cell = SHARED_HTTP_CLIENT.get_or_init(|| RwLock::new(None));
+    let proxy = crate::settings::get_settings().network_proxy;
+
+    if let Ok(guard) = cell.read() {
+        if let Some((cached_proxy, client)) = guard.as_ref() {
+            if *cached_proxy == proxy {
+                return Ok(client.clone());
+            }
+        }
+    }
+
+    let client = apply_network_proxy(
+        reqwest::Client::builder().user_agent(format!("cc-switch/{}", env!("CARGO_PKG_VERSION"))),
+    )?
+    .build()
+    .map_err(|e| format!("创建 HTTP 客户端失败: {e}"))?;
+
+    if let Ok(mut guard) = cell.write() {
+        *guard = Some((proxy, client.clone()));
+    }
+    Ok(client)
+}
+
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DownloadAndOpenResult {
-    filePath: String,
+    file_path: String,
+    size_bytes: u64,
+    sha256: String,
+    duration_ms: u64,
+    /// 是否直接使用了此前下载并校验过的安装包（未重新下载）
+    from_cache: bool,
+    /// Windows MSI 安装的详细日志路径（`msiexec /l*v`），便于用户反馈问题时附带
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install_log_path: Option<String>,
+    /// Windows MSI 的 Authenticode 签名者名称（已通过签名验证时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signer: Option<String>,
+    /// 是否已启动安装程序（msiexec、macOS installer 等）；为 `false` 时安装包仅用系统默认方式
+    /// 打开，或安装程序启动失败（见 `installError`）
+    install_started: bool,
+    /// 安装完成后是否会自动重新启动应用（Windows MSI 以 `AUTOLAUNCHAPP=1` 安装时）
+    auto_launch_requested: bool,
+    /// 安装程序启动失败的原因（带错误码的 JSON），此时需提示用户手动运行 `filePath`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    install_error: Option<String>,
+}
+
+/// 安装包下载链接的元数据（HEAD 请求的响应头）
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadMetadata {
+    /// 文件大小（字节），服务器未返回 `Content-Length` 时为空
+    size: Option<u64>,
+    etag: Option<String>,
+    /// 服务器是否支持断点续传（`Accept-Ranges: bytes`）
+    accept_ranges: bool,
+    content_type: Option<String>,
+}
+
+impl DownloadMetadata {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_TYPE, ETAG};
+
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            size: header(CONTENT_LENGTH).and_then(|v| v.parse().ok()),
+            etag: header(ETAG),
+            accept_ranges: header(ACCEPT_RANGES)
+                .is_some_and(|v| v.split(',').any(|unit| unit.trim() == "bytes")),
+            content_type: header(CONTENT_TYPE),
+        }
+    }
+}
+
+/// Windows MSI 安装状态（安装器在后台运行，前端通过轮询获取退出码）
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MsiInstallStatus {
+    log_path: String,
+    finished: bool,
+    exit_code: Option<i32>,
+    /// passive 安装失败后是否已回退到交互式安装
+    interactive_fallback: bool,
+}
+
+static MSI_INSTALL_STATUS: OnceLock<RwLock<Option<MsiInstallStatus>>> = OnceLock::new();
+
+fn msi_install_status_cell() -> &'static RwLock<Option<MsiInstallStatus>> {
+    MSI_INSTALL_STATUS.get_or_init(|| RwLock::new(None))
 }
 
+/// Windows 保留的设备名（不区分大小写，带扩展名同样保留，如 `NUL.txt`）
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 将下载文件名规范为安全的单一文件名
+///
+/// 去除目录部分、替换非法字符；仅由点或空格组成的名称使用默认名；
+/// Windows 保留设备名前加 `_`；最长 120 个字符（截断时保留扩展名）。
 fn sanitize_download_file_name(raw: &str) -> String {
     let fallback = "aicodewith-update.bin";
     let name = std::path::Path::new(raw)
@@ -53,105 +469,1171 @@ fn sanitize_download_file_name(raw: &str) -> String {
     for ch in name.chars() {
         match ch {
             '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => out.push('_'),
+            c if c.is_control() => out.push('_'),
             _ => out.push(ch),
         }
     }
 
-    let trimmed = out.trim();
+    // Windows 会静默去掉末尾的点和空格
+    let trimmed = out.trim().trim_end_matches(['.', ' ']);
     if trimmed.is_empty() {
-        fallback.to_string()
+        return fallback.to_string();
+    }
+
+    let base = trimmed.split('.').next().unwrap_or(trimmed).trim_end();
+    let name = if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| base.eq_ignore_ascii_case(reserved))
+    {
+        format!("_{trimmed}")
     } else {
-        trimmed.chars().take(120).collect()
+        trimmed.to_string()
+    };
+    truncate_file_name(&name, 120)
+}
+
+/// 截断文件名到 `max` 个字符，保留扩展名（如 `.msi`）以免影响按扩展名的安装分支
+fn truncate_file_name(name: &str, max: usize) -> String {
+    if name.chars().count() <= max {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() && ext.chars().count() < 16 => {
+            let stem_len = max - ext.chars().count() - 1;
+            format!("{}.{ext}", stem.chars().take(stem_len).collect::<String>())
+        }
+        _ => name.chars().take(max).collect(),
     }
 }
 
+/// 检查目标目录所在卷是否有足够空间容纳下载内容（含安全余量）
+fn ensure_disk_space(dir: &std::path::Path, content_length: u64) -> Result<(), String> {
+    let available = match fs4::available_space(dir) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("查询磁盘可用空间失败，跳过预检: {e}");
+            return Ok(());
+        }
+    };
+
+    let required = content_length.saturating_add(DOWNLOAD_DISK_SPACE_MARGIN);
+    if available < required {
+        return Err(format_command_error(
+            CommandErrorCode::InsufficientDiskSpace,
+            Some(&format!("需要 {required} 字节，可用 {available} 字节")),
+        ));
+    }
+
+    Ok(())
+}
+
+/// 构造 msiexec 安装命令。`passive` 为 false 时走交互式向导（用于被动安装失败后的兜底）。
 #[cfg(target_os = "windows")]
-fn try_start_windows_msi_install(msi_path: &std::path::Path) -> Result<(), String> {
-    use std::process::Command;
+fn build_msiexec_command(
+    msi_path: &std::path::Path,
+    log_path: &std::path::Path,
+    passive: bool,
+) -> std::process::Command {
+    let mut cmd = std::process::Command::new("msiexec");
+    cmd.arg("/i").arg(msi_path);
+    if passive {
+        cmd.arg("/passive");
+    }
+    cmd.arg("/norestart")
+        // 配合 `src-tauri/wix/per-user-main.wxs` 的自定义动作：安装完成后自动启动应用。
+        .arg("AUTOLAUNCHAPP=1")
+        .arg("/l*v")
+        .arg(log_path)
+        .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+/// 判断 msiexec 退出码是否需要回退到交互式安装。
+///
+/// 0 / 3010 / 1641 均表示安装成功（后两者需要重启），1602 表示用户主动取消，这些情况都不重试。
+#[cfg(target_os = "windows")]
+fn should_retry_msi_interactively(exit_code: Option<i32>) -> bool {
+    !matches!(exit_code, Some(0) | Some(3010) | Some(1641) | Some(1602))
+}
+
+/// msiexec 退出码是否表示安装成功（3010 / 1641 表示成功但需要重启）
+#[cfg(target_os = "windows")]
+fn msi_install_succeeded(exit_code: Option<i32>) -> bool {
+    matches!(exit_code, Some(0) | Some(3010) | Some(1641))
+}
+
+#[cfg(target_os = "windows")]
+fn set_msi_install_status(status: MsiInstallStatus) {
+    if let Ok(mut guard) = msi_install_status_cell().write() {
+        *guard = Some(status);
+    }
+}
+
+/// 使用 `WinVerifyTrust` 校验安装包的 Authenticode 签名，成功时返回签名者名称
+#[cfg(target_os = "windows")]
+fn verify_installer_signature(path: &std::path::Path) -> Result<String, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Security::WinTrust::{
+        WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_FILE_INFO,
+        WTD_CHOICE_FILE, WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY,
+        WTD_UI_NONE,
+    };
+
+    const TRUST_E_NOSIGNATURE: u32 = 0x800B_0100;
+    const TRUST_E_BAD_DIGEST: u32 = 0x8009_6010;
+    const CERT_E_UNTRUSTEDROOT: u32 = 0x800B_0109;
+
+    let wide_path: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PCWSTR(wide_path.as_ptr()),
+        ..Default::default()
+    };
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        ..Default::default()
+    };
+    data.Anonymous.pFile = &mut file_info;
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+
+    let status = unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut std::ffi::c_void,
+        )
+    };
+    let signer = if status == 0 {
+        unsafe { signer_subject(data.hWVTStateData) }
+    } else {
+        None
+    };
+
+    // VERIFY 会分配状态数据，无论结果如何都需要 CLOSE 释放
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    unsafe {
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut std::ffi::c_void,
+        );
+    }
+
+    match status as u32 {
+        0 => Ok(signer.unwrap_or_default()),
+        TRUST_E_NOSIGNATURE => Err("安装包未签名".to_string()),
+        TRUST_E_BAD_DIGEST => Err("签名与文件内容不匹配，安装包可能已被篡改".to_string()),
+        CERT_E_UNTRUSTEDROOT => Err("签名证书不受信任".to_string()),
+        code => Err(format!("WinVerifyTrust 返回 0x{code:08X}")),
+    }
+}
+
+/// 从验证状态中读取首个签名者证书的显示名称
+///
+/// `state` 必须是 `WTD_STATEACTION_VERIFY` 成功后、尚未 CLOSE 的状态数据。
+#[cfg(target_os = "windows")]
+unsafe fn signer_subject(state: windows::Win32::Foundation::HANDLE) -> Option<String> {
+    use windows::Win32::Security::Cryptography::{
+        CertGetNameStringW, CERT_NAME_SIMPLE_DISPLAY_TYPE,
+    };
+    use windows::Win32::Security::WinTrust::{
+        WTHelperGetProvCertFromChain, WTHelperGetProvSignerFromChain, WTHelperProvDataFromStateData,
+    };
+
+    unsafe {
+        let provider_data = WTHelperProvDataFromStateData(state);
+        if provider_data.is_null() {
+            return None;
+        }
+        let signer = WTHelperGetProvSignerFromChain(provider_data, 0, false, 0);
+        if signer.is_null() {
+            return None;
+        }
+        let cert = WTHelperGetProvCertFromChain(signer, 0);
+        if cert.is_null() || (*cert).pCert.is_null() {
+            return None;
+        }
+        let len = CertGetNameStringW((*cert).pCert, CERT_NAME_SIMPLE_DISPLAY_TYPE, 0, None, None);
+        if len <= 1 {
+            return None;
+        }
+        let mut name = vec![0u16; len as usize];
+        CertGetNameStringW(
+            (*cert).pCert,
+            CERT_NAME_SIMPLE_DISPLAY_TYPE,
+            0,
+            None,
+            Some(&mut name),
+        );
+        Some(String::from_utf16_lossy(&name[..len as usize - 1]))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn try_start_windows_msi_install(
+    app: &AppHandle,
+    msi_path: &std::path::Path,
+    package_version: Option<String>,
+) -> Result<std::path::PathBuf, String> {
+    let log_path = msi_path.with_extension("install.log");
 
     // 业务约束：更新按钮触发的安装应尽量减少交互，避免“向导式安装页”打断用户。
     // 技术约束：MSI 本身无法在双击时强制静默；但应用内触发安装可以通过 `msiexec` 参数做到。
-    Command::new("msiexec")
-        .arg("/i")
-        .arg(msi_path)
-        .arg("/passive")
-        .arg("/norestart")
-        // 配合 `src-tauri/wix/per-user-main.wxs` 的自定义动作：安装完成后自动启动应用。
-        .arg("AUTOLAUNCHAPP=1")
-        .creation_flags(CREATE_NO_WINDOW)
+    let child = build_msiexec_command(msi_path, &log_path, true)
         .spawn()
         .map_err(|e| format!("启动 Windows 安装器失败: {e}"))?;
+    log::info!(
+        "已启动 Windows 安装器（passive 模式），日志: {}",
+        log_path.display()
+    );
 
-    Ok(())
+    set_msi_install_status(MsiInstallStatus {
+        log_path: log_path.to_string_lossy().to_string(),
+        finished: false,
+        exit_code: None,
+        interactive_fallback: false,
+    });
+
+    // 在后台线程等待安装器退出，记录退出码（如 1603 表示安装失败）
+    let msi_path = msi_path.to_path_buf();
+    let passive_log_path = log_path.clone();
+    let app = app.clone();
+    let notify_if_succeeded = move |exit_code: Option<i32>| {
+        if msi_install_succeeded(exit_code) {
+            let body = match &package_version {
+                Some(version) => format!("AiCodeWith 已更新到 {version}"),
+                None => "AiCodeWith 更新安装完成".to_string(),
+            };
+            crate::show_notification(&app, &body);
+        }
+    };
+    std::thread::spawn(move || {
+        let wait_exit_code = |mut child: std::process::Child| match child.wait() {
+            Ok(status) => status.code(),
+            Err(e) => {
+                log::error!("等待 Windows 安装器退出失败: {e}");
+                None
+            }
+        };
+
+        let exit_code = wait_exit_code(child);
+        log::info!(
+            "Windows 安装器（passive 模式）已退出，退出码: {exit_code:?}，日志: {}",
+            passive_log_path.display()
+        );
+
+        if !should_retry_msi_interactively(exit_code) {
+            set_msi_install_status(MsiInstallStatus {
+                log_path: passive_log_path.to_string_lossy().to_string(),
+                finished: true,
+                exit_code,
+                interactive_fallback: false,
+            });
+            notify_if_succeeded(exit_code);
+            return;
+        }
+
+        // 部分受管控的企业环境会拒绝 passive 安装，回退一次交互式向导让用户手动完成
+        let interactive_log_path = msi_path.with_extension("install-interactive.log");
+        log::warn!("passive 安装失败，回退到交互式安装");
+        set_msi_install_status(MsiInstallStatus {
+            log_path: interactive_log_path.to_string_lossy().to_string(),
+            finished: false,
+            exit_code: None,
+            interactive_fallback: true,
+        });
+
+        let exit_code = match build_msiexec_command(&msi_path, &interactive_log_path, false).spawn()
+        {
+            Ok(child) => wait_exit_code(child),
+            Err(e) => {
+                log::error!("启动交互式 Windows 安装器失败: {e}");
+                exit_code
+            }
+        };
+        log::info!(
+            "Windows 安装器（交互模式）已退出，退出码: {exit_code:?}，日志: {}",
+            interactive_log_path.display()
+        );
+
+        set_msi_install_status(MsiInstallStatus {
+            log_path: interactive_log_path.to_string_lossy().to_string(),
+            finished: true,
+            exit_code,
+            interactive_fallback: true,
+        });
+        notify_if_succeeded(exit_code);
+    });
+
+    Ok(log_path)
+}
+
+/// 按平台直接启动安装包：macOS 处理 `.dmg` / `.pkg`，Linux 处理 `.AppImage` / `.deb`。
+///
+/// 返回 `Ok(false)` 表示该扩展名没有专用处理，调用方应回退到 `open_path`。
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn try_start_platform_installer(path: &std::path::Path) -> Result<bool, String> {
+    use std::process::Command;
+
+    let ext = path
+        .extension()
+        .and_then(|v| v.to_str())
+        .map(|v| v.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    #[cfg(target_os = "macos")]
+    {
+        match ext.as_str() {
+            // 挂载磁盘镜像，Finder 会自动打开挂载后的卷供用户拖拽安装
+            "dmg" => {
+                Command::new("hdiutil")
+                    .arg("attach")
+                    .arg(path)
+                    .spawn()
+                    .map_err(|e| format!("挂载磁盘镜像失败: {e}"))?;
+                Ok(true)
+            }
+            "pkg" => {
+                Command::new("installer")
+                    .arg("-pkg")
+                    .arg(path)
+                    .arg("-target")
+                    .arg("CurrentUserHomeDirectory")
+                    .spawn()
+                    .map_err(|e| format!("启动 macOS 安装器失败: {e}"))?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        match ext.as_str() {
+            "appimage" => {
+                use std::os::unix::fs::PermissionsExt;
+
+                let mut perms = std::fs::metadata(path)
+                    .map_err(|e| format!("读取 AppImage 权限失败: {e}"))?
+                    .permissions();
+                perms.set_mode(perms.mode() | 0o755);
+                std::fs::set_permissions(path, perms)
+                    .map_err(|e| format!("设置 AppImage 可执行权限失败: {e}"))?;
+
+                Command::new(path)
+                    .spawn()
+                    .map_err(|e| format!("启动 AppImage 失败: {e}"))?;
+                Ok(true)
+            }
+            // 优先交给软件中心安装；不可用时回退到系统默认打开方式
+            "deb" => match Command::new("gnome-software")
+                .arg("--local-filename")
+                .arg(path)
+                .spawn()
+            {
+                Ok(_) => Ok(true),
+                Err(e) => {
+                    log::warn!("无法通过软件中心打开 .deb 安装包，回退到默认打开方式: {e}");
+                    Ok(false)
+                }
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+/// 下载过程中的文件写入错误
+fn download_write_error(e: std::io::Error) -> String {
+    format_command_error(CommandErrorCode::DownloadWriteFailed, Some(&e.to_string()))
+}
+
+/// 安装包下载域名白名单：默认仅允许 123 云盘，下载来源为 GitHub 时额外允许 GitHub Release 域名
+fn is_trusted_download_host(host: &str, source: UpdateSource) -> bool {
+    let host = host.to_lowercase();
+    let pan123 = host.ends_with(".cjjd19.com")
+        || host.ends_with(".123pan.com")
+        || host.ends_with(".123865.com");
+    pan123
+        || (source == UpdateSource::Github
+            && matches!(
+                host.as_str(),
+                "github.com"
+                    | "objects.githubusercontent.com"
+                    | "release-assets.githubusercontent.com"
+            ))
 }
 
-/// 下载网盘安装包并打开（触发系统安装流程）
+/// 解析并校验安装包下载链接：仅允许 http/https 与受信任的下载域名
+fn parse_download_url(url: &str, source: UpdateSource) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url).map_err(|e| {
+        format_command_error(CommandErrorCode::InvalidDownloadUrl, Some(&e.to_string()))
+    })?;
+    match parsed.scheme() {
+        "http" | "https" => {}
+        _ => {
+            return Err(format_command_error(
+                CommandErrorCode::UnsupportedDownloadScheme,
+                None,
+            ))
+        }
+    }
+
+    // 安全兜底：仅允许从受信任的下载域名拉取安装包，避免被误用为“任意下载并打开”能力。
+    let Some(host) = parsed.host_str() else {
+        return Err(format_command_error(
+            CommandErrorCode::InvalidDownloadUrl,
+            Some("缺少域名"),
+        ));
+    };
+    if !is_trusted_download_host(host, source) {
+        return Err(format_command_error(
+            CommandErrorCode::UntrustedDownloadHost,
+            Some(&host.to_lowercase()),
+        ));
+    }
+    Ok(parsed)
+}
+
+/// 按更新下载基础地址解析下载链接：相对路径拼接到基础地址下，绝对链接原样返回
+fn resolve_update_url(url: &str, base: Option<&str>) -> String {
+    let url = url.trim();
+    match (url::Url::parse(url), base) {
+        (Err(url::ParseError::RelativeUrlWithoutBase), Some(base)) => {
+            url::Url::parse(&format!("{}/", base.trim_end_matches('/')))
+                .and_then(|base| base.join(url.trim_start_matches('/')))
+                .map(|resolved| resolved.to_string())
+                .unwrap_or_else(|_| url.to_string())
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// 获取更新安装包的下载基础地址（未设置时为 `None`）
+#[tauri::command]
+pub async fn get_update_download_base() -> Result<Option<String>, String> {
+    Ok(crate::settings::get_settings().update_download_base)
+}
+
+/// 设置更新安装包的下载基础地址（镜像），传入空字符串清除
+///
+/// 仅用于解析相对路径的安装包下载链接，版本检查仍使用 npm registry 设置；地址须为 http/https，
+/// 且域名在下载域名白名单内。
 #[tauri::command]
+pub async fn set_update_download_base(url: String) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    let trimmed = url.trim();
+    if !trimmed.is_empty() {
+        parse_download_url(trimmed, settings.update_source)?;
+    }
+    settings.update_download_base = Some(trimmed.to_string());
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 安装包下载最多跟随的重定向次数
+const MAX_DOWNLOAD_REDIRECTS: usize = 10;
+
+/// 重定向目标不在下载域名白名单内
+#[derive(Debug)]
+struct UntrustedRedirect(String);
+
+impl std::fmt::Display for UntrustedRedirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "重定向目标域名不受信任: {}", self.0)
+    }
+}
+
+impl std::error::Error for UntrustedRedirect {}
+
+/// 对每一跳重定向重新校验协议与域名白名单，防止受信任链接跳转到任意地址
+fn trusted_redirect_policy(
+    is_trusted: impl Fn(&str) -> bool + Send + Sync + 'static,
+) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_DOWNLOAD_REDIRECTS {
+            return attempt.error("重定向次数过多");
+        }
+        let url = attempt.url();
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        if matches!(url.scheme(), "http" | "https") && is_trusted(&host) {
+            attempt.follow()
+        } else {
+            attempt.error(UntrustedRedirect(host))
+        }
+    })
+}
+
+/// 下载安装包使用的 HTTP 客户端（遵循网络代理设置，重定向同样受域名白名单约束）
+fn download_client() -> Result<reqwest::Client, String> {
+    let source = crate::settings::get_settings().update_source;
+    download_client_with(move |host| is_trusted_download_host(host, source))
+}
+
+fn download_client_with(
+    is_trusted: impl Fn(&str) -> bool + Send + Sync + 'static,
+) -> Result<reqwest::Client, String> {
+    let user_agent = crate::settings::get_settings()
+        .download_user_agent
+        .unwrap_or_else(|| format!("AI-Code-With/{}", env!("CARGO_PKG_VERSION")));
+    apply_network_proxy(
+        reqwest::Client::builder()
+            .user_agent(user_agent)
+            .redirect(trusted_redirect_policy(is_trusted)),
+    )
+    .map_err(|e| format_command_error(CommandErrorCode::HttpClientFailed, Some(&e)))?
+    .build()
+    .map_err(|e| format_command_error(CommandErrorCode::HttpClientFailed, Some(&e.to_string())))
+}
+
+/// 不允许调用方覆盖的请求头：涉及凭据、目标主机、报文边界或由客户端自行管理
+const FORBIDDEN_DOWNLOAD_HEADERS: &[&str] = &[
+    "accept-encoding",
+    "authorization",
+    "connection",
+    "content-length",
+    "cookie",
+    "expect",
+    "host",
+    "keep-alive",
+    "origin",
+    "range",
+    "referer",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// 校验并转换调用方传入的下载请求头
+///
+/// 名称或值含控制字符、名称不合法时返回 `InvalidRequestHeader`；凭据、代理（`Proxy-*`）、
+/// 浏览器保留（`Sec-*`）等敏感请求头返回 `ForbiddenRequestHeader`。
+fn download_headers(
+    headers: Option<HashMap<String, String>>,
+) -> Result<reqwest::header::HeaderMap, String> {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+    let mut map = HeaderMap::new();
+    for (name, value) in headers.unwrap_or_default() {
+        let name = name.trim();
+        if name.chars().chain(value.chars()).any(char::is_control) {
+            return Err(format_command_error(
+                CommandErrorCode::InvalidRequestHeader,
+                Some(name),
+            ));
+        }
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            format_command_error(CommandErrorCode::InvalidRequestHeader, Some(name))
+        })?;
+        let lower = header_name.as_str();
+        if FORBIDDEN_DOWNLOAD_HEADERS.contains(&lower)
+            || lower.starts_with("proxy-")
+            || lower.starts_with("sec-")
+        {
+            return Err(format_command_error(
+                CommandErrorCode::ForbiddenRequestHeader,
+                Some(name),
+            ));
+        }
+        let header_value = HeaderValue::from_str(value.trim()).map_err(|_| {
+            format_command_error(CommandErrorCode::InvalidRequestHeader, Some(name))
+        })?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// 安装包文件扩展名（下载内容校验仅针对这些文件）
+const INSTALLER_EXTENSIONS: &[&str] = &[
+    "msi", "exe", "dmg", "pkg", "appimage", "deb", "rpm", "zip", "gz",
+];
+
+fn is_installer_file_name(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| INSTALLER_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Content-Type 明显不是安装包（HTML 错误页、JSON / XML 错误信息、纯文本等）
+fn is_non_binary_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(mime.as_str(), "application/json" | "application/xml")
+}
+
+/// 内容开头是 HTML 文档（镜像返回错误页时 Content-Type 可能仍是二进制类型）
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let start = bytes
+        .strip_prefix(b"\xEF\xBB\xBF".as_slice())
+        .unwrap_or(bytes);
+    let trimmed = &start[start
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(start.len())..];
+    let head: Vec<u8> = trimmed
+        .iter()
+        .take(14)
+        .map(u8::to_ascii_lowercase)
+        .collect();
+    head.starts_with(b"<!doctype html") || head.starts_with(b"<html")
+}
+
+/// 判断 HTML 错误页时最多缓存的开头字节数
+const HTML_SNIFF_LIMIT: usize = 512;
+
+/// 跨数据块检查下载内容开头是否为 HTML（首个数据块可能很短，或以 BOM / 空白开头）
+#[derive(Default)]
+struct HtmlSniffer {
+    head: Vec<u8>,
+    decided: bool,
+}
+
+impl HtmlSniffer {
+    /// 追加一个数据块；已能判定为 HTML 时返回 true
+    fn push(&mut self, chunk: &[u8]) -> bool {
+        if self.decided {
+            return false;
+        }
+        let take = chunk.len().min(HTML_SNIFF_LIMIT - self.head.len());
+        self.head.extend_from_slice(&chunk[..take]);
+        let start = self
+            .head
+            .strip_prefix(b"\xEF\xBB\xBF".as_slice())
+            .unwrap_or(&self.head);
+        let content = start.iter().filter(|b| !b.is_ascii_whitespace()).count();
+        // 去掉空白后已足够匹配 `<!doctype html`，或已达缓存上限
+        if content >= 14 || self.head.len() >= HTML_SNIFF_LIMIT {
+            return self.finish();
+        }
+        false
+    }
+
+    /// 数据流结束时对已缓存的开头做最终判定
+    fn finish(&mut self) -> bool {
+        if self.decided {
+            return false;
+        }
+        self.decided = true;
+        looks_like_html(&self.head)
+    }
+}
+
+/// 缓存目录中的安装包仍与下载记录一致时返回其大小与 SHA-256（重新计算摘要校验，
+/// 已知期望摘要时同时比对）
+fn verify_cached_download(
+    record: &crate::app_store::DownloadRecord,
+    path: &Path,
+    expected_sha256: Option<&str>,
+) -> Option<(u64, String)> {
+    if Path::new(&record.file_path) != path
+        || expected_sha256.is_some_and(|expected| !expected.eq_ignore_ascii_case(&record.sha256))
+    {
+        return None;
+    }
+    if std::fs::metadata(path).ok()?.len() != record.size_bytes {
+        return None;
+    }
+    let sha256 = hex::encode(Sha256::digest(std::fs::read(path).ok()?));
+    (sha256 == record.sha256).then_some((record.size_bytes, sha256))
+}
+
+fn suspicious_content_error(detail: &str) -> String {
+    format_command_error(CommandErrorCode::SuspiciousDownloadContent, Some(detail))
+}
+
+/// 下载请求发送失败：被拒绝的重定向单独标识
+fn download_request_error(e: reqwest::Error) -> String {
+    let mut source = std::error::Error::source(&e);
+    while let Some(err) = source {
+        if let Some(redirect) = err.downcast_ref::<UntrustedRedirect>() {
+            return format_command_error(
+                CommandErrorCode::UntrustedRedirectHost,
+                Some(&redirect.0),
+            );
+        }
+        source = err.source();
+    }
+    format_command_error(
+        CommandErrorCode::DownloadRequestFailed,
+        Some(&redact_error(e)),
+    )
+}
+
+/// 将版本查询错误转换为带错误码的错误（限流单独标识，前端可静默忽略）
+fn release_command_error(e: String) -> String {
+    if e == release::RELEASE_RATE_LIMITED {
+        format_command_error(CommandErrorCode::GithubRateLimited, None)
+    } else {
+        format_command_error(CommandErrorCode::VersionCheckFailed, Some(&e))
+    }
+}
+
+/// 按更新通道查询 GitHub 最新版本，返回当前平台的安装包及其版本号
+async fn resolve_github_release_asset() -> Result<(release::ReleaseAsset, String), String> {
+    let client = shared_http_client()
+        .map_err(|e| format_command_error(CommandErrorCode::HttpClientFailed, Some(&e)))?;
+    let channel = crate::settings::get_settings().update_channel;
+    let info = release::fetch_latest_release(&client, channel)
+        .await
+        .map_err(release_command_error)?;
+    let asset = release::platform_asset(&info.assets)
+        .cloned()
+        .ok_or_else(|| {
+            format_command_error(
+                CommandErrorCode::NoPlatformAsset,
+                Some(&format!(
+                    "{} {} ({})",
+                    std::env::consts::OS,
+                    std::env::consts::ARCH,
+                    info.latest
+                )),
+            )
+        })?;
+    Ok((asset, info.latest))
+}
+
+/// 正在下载的安装包（按目标文件路径），同一文件同时只允许一个下载
+#[derive(Default)]
+pub struct DownloadLocks(std::sync::Mutex<HashSet<PathBuf>>);
+
+impl DownloadLocks {
+    /// 占用目标路径；已有下载在进行时返回 `None`
+    fn acquire(&self, path: &Path) -> Option<DownloadGuard<'_>> {
+        let mut active = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        active.insert(path.to_path_buf()).then(|| DownloadGuard {
+            locks: self,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+/// 下载结束（含任何错误返回）时释放目标路径
+struct DownloadGuard<'a> {
+    locks: &'a DownloadLocks,
+    path: PathBuf,
+}
+
+impl Drop for DownloadGuard<'_> {
+    fn drop(&mut self) {
+        let mut active = self.locks.0.lock().unwrap_or_else(|e| e.into_inner());
+        active.remove(&self.path);
+    }
+}
+
+/// 查询安装包下载链接的大小、ETag、是否支持断点续传与内容类型（HEAD 请求，不下载文件）
+///
+/// 与 `download_and_open_update_package` 使用相同的域名白名单、网络代理与附加请求头校验，
+/// 错误同样以带错误码的 JSON 字符串返回。
+#[tauri::command]
+pub async fn get_download_metadata(
+    url: String,
+    headers: Option<HashMap<String, String>>,
+) -> Result<DownloadMetadata, String> {
+    let settings = crate::settings::get_settings();
+    let url = resolve_update_url(&url, settings.update_download_base.as_deref());
+    let parsed = parse_download_url(&url, settings.update_source)?;
+    let headers = download_headers(headers)?;
+    let res = download_client()?
+        .head(parsed)
+        .headers(headers)
+        .send()
+        .await
+        .map_err(download_request_error)?
+        .error_for_status()
+        .map_err(|e| {
+            format_command_error(
+                CommandErrorCode::DownloadResponseFailed,
+                Some(&redact_error(e)),
+            )
+        })?;
+    Ok(DownloadMetadata::from_headers(res.headers()))
+}
+
+/// 下载链接的探测结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadLinkStatus {
+    Ok,
+    /// 分享链接已过期或被取消（401 / 403 / 404 / 410，或安装包链接返回网页）
+    Expired,
+    /// 网络不通、服务器错误或限流，稍后可重试
+    Unreachable,
+}
+
+/// 下载链接探测结果
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadLinkProbe {
+    status: DownloadLinkStatus,
+    http_status: Option<u16>,
+    /// 面向用户的提示
+    message: String,
+}
+
+/// 按响应状态与内容类型判断下载链接是否可用
+fn classify_link_response(
+    status: reqwest::StatusCode,
+    content_type: Option<&str>,
+    installer: bool,
+) -> (DownloadLinkStatus, String) {
+    let code = status.as_u16();
+    if status.is_success() {
+        // 123 云盘的过期分享链接会以 200 返回提示页面
+        if installer && content_type.is_some_and(is_non_binary_content_type) {
+            return (
+                DownloadLinkStatus::Expired,
+                "下载链接返回了网页而非安装包，链接可能已失效，请刷新后重试或改用 GitHub 下载"
+                    .to_string(),
+            );
+        }
+        return (DownloadLinkStatus::Ok, "下载链接可用".to_string());
+    }
+    match code {
+        401 | 403 | 404 | 410 => (
+            DownloadLinkStatus::Expired,
+            format!("下载链接已失效（HTTP {code}），请刷新后获取新链接或改用 GitHub 下载"),
+        ),
+        429 => (
+            DownloadLinkStatus::Unreachable,
+            "下载服务器请求过于频繁（HTTP 429），请稍后重试".to_string(),
+        ),
+        _ => (
+            DownloadLinkStatus::Unreachable,
+            format!("下载服务器暂时不可用（HTTP {code}），请稍后重试"),
+        ),
+    }
+}
+
+/// 下载前探测安装包链接是否仍然有效（HEAD 请求，不下载文件）
+///
+/// 与 `download_and_open_update_package` 使用相同的域名白名单、网络代理与附加请求头；
+/// 服务器不支持 HEAD 时改用只请求首字节的 GET。链接格式无效或域名不受信任时返回带错误码的错误，
+/// 其余情况返回 `ok` / `expired` / `unreachable` 与相应提示。
+#[tauri::command]
+pub async fn probe_download_link(
+    url: String,
+    headers: Option<HashMap<String, String>>,
+) -> Result<DownloadLinkProbe, String> {
+    let settings = crate::settings::get_settings();
+    let url = resolve_update_url(&url, settings.update_download_base.as_deref());
+    let parsed = parse_download_url(&url, settings.update_source)?;
+    let headers = download_headers(headers)?;
+    let installer = parsed
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .is_some_and(is_installer_file_name);
+    let client = download_client()?;
+
+    let mut response = client
+        .head(parsed.clone())
+        .headers(headers.clone())
+        .send()
+        .await;
+    if let Ok(res) = &response {
+        if matches!(res.status().as_u16(), 405 | 501) {
+            response = client
+                .get(parsed)
+                .headers(headers)
+                .header(reqwest::header::RANGE, "bytes=0-0")
+                .send()
+                .await;
+        }
+    }
+
+    let res = match response {
+        Ok(res) => res,
+        Err(e) => {
+            let detail = redact_error(e);
+            log::info!("探测下载链接失败: {detail}");
+            return Ok(DownloadLinkProbe {
+                status: DownloadLinkStatus::Unreachable,
+                http_status: None,
+                message: format!("无法连接下载服务器，请检查网络或代理设置: {detail}"),
+            });
+        }
+    };
+    let content_type = res
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let (status, message) = classify_link_response(res.status(), content_type, installer);
+    Ok(DownloadLinkProbe {
+        status,
+        http_status: Some(res.status().as_u16()),
+        message,
+    })
+}
+
+/// 下载安装包并打开（触发系统安装流程）
+///
+/// 默认从 123 云盘链接下载；设置中的下载来源为 GitHub 时，可不传 `url`，由后端按更新通道
+/// 选取最新 Release 中适用于当前系统与架构的附件，并用 GitHub 提供的摘要校验文件。
+/// `url` 为相对路径时按设置 `updateDownloadBase` 解析。
+///
+/// Windows 下启动 MSI 前会验证 Authenticode 签名，未签名或签名无效时拒绝安装，
+/// 除非 `allowUnsigned` 为 `true`。
+///
+/// 同一安装包已在下载时返回 `DownloadInProgress`，不同文件可同时下载。
+///
+/// 文件名为安装包时，响应的 Content-Type 为 HTML / 文本等非二进制类型，或内容以 HTML 文档
+/// 开头，视为镜像返回的错误页面并返回 `SuspiciousDownloadContent`（不写入文件）；
+/// `allowUnexpectedContent` 为 `true` 时跳过该检查。服务器声明了 Content-Length 时，
+/// 实际大小不一致同样视为下载失败。
+///
+/// `headers` 为附加请求头（供需要特定请求头的镜像或企业网关使用），凭据等敏感请求头不允许覆盖；
+/// User-Agent 可通过设置 `downloadUserAgent` 修改。
+///
+/// 安装程序启动失败时不返回错误，而是在结果的 `installError` 中说明原因，前端据此提示用户
+/// 手动运行 `filePath`；`installStarted` / `autoLaunchRequested` 表示安装是否已在后台进行、
+/// 完成后应用是否会自动重启。
+///
+/// 下载完成的安装包按下载链接记录大小与 SHA-256；再次请求同一链接且缓存文件重新计算的摘要
+/// 与记录一致时跳过下载直接启动安装，`ignoreCache` 为 `true` 时强制重新下载。
+///
+/// 错误以带错误码的 JSON 字符串返回（见 [`CommandErrorCode`]），前端可据此本地化提示。
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(file_name = ?fileName, version = ?packageVersion), err)]
 pub async fn download_and_open_update_package(
     app: AppHandle,
-    url: String,
-    #[allow(non_snake_case)] fileName: String,
+    download_locks: State<'_, DownloadLocks>,
+    url: Option<String>,
+    #[allow(non_snake_case)] fileName: Option<String>,
+    #[allow(non_snake_case)] packageVersion: Option<String>,
+    #[allow(non_snake_case)] allowDowngrade: Option<bool>,
+    #[allow(non_snake_case)]
+    #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+    allowUnsigned: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    #[allow(non_snake_case)] allowUnexpectedContent: Option<bool>,
+    #[allow(non_snake_case)] ignoreCache: Option<bool>,
 ) -> Result<DownloadAndOpenResult, String> {
-    let parsed = url::Url::parse(&url).map_err(|e| format!("无效的下载链接: {e}"))?;
-    match parsed.scheme() {
-        "http" | "https" => {}
-        _ => return Err("不支持的下载链接协议".to_string()),
-    }
+    let headers = download_headers(headers)?;
+    let settings = crate::settings::get_settings();
+    let update_source = settings.update_source;
+    let (url, file_name, package_version, expected_sha256) = match url {
+        Some(url) => (
+            resolve_update_url(&url, settings.update_download_base.as_deref()),
+            fileName,
+            packageVersion,
+            None,
+        ),
+        None if update_source == UpdateSource::Github => {
+            let (asset, version) = resolve_github_release_asset().await?;
+            let expected_sha256 = asset.sha256();
+            (
+                asset.browser_download_url,
+                Some(asset.name),
+                Some(version),
+                expected_sha256,
+            )
+        }
+        None => {
+            return Err(format_command_error(
+                CommandErrorCode::InvalidDownloadUrl,
+                Some("缺少下载链接"),
+            ))
+        }
+    };
 
-    // 安全兜底：仅允许从 123 云盘下载域名拉取安装包，避免被误用为“任意下载并打开”能力。
-    if let Some(host) = parsed.host_str() {
-        let host = host.to_lowercase();
-        let trusted = host.ends_with(".cjjd19.com")
-            || host.ends_with(".123pan.com")
-            || host.ends_with(".123865.com");
-        if !trusted {
-            return Err("下载链接域名不受信任".to_string());
+    // 防止误点旧版本链接或镜像返回过期文件导致的版本回退
+    if let Some(version) = package_version.as_deref() {
+        if !allowDowngrade.unwrap_or(false) && is_downgrade(env!("CARGO_PKG_VERSION"), version)? {
+            return Err(format_command_error(
+                CommandErrorCode::DowngradeRefused,
+                None,
+            ));
         }
-    } else {
-        return Err("下载链接缺少域名".to_string());
     }
 
-    let file_name = sanitize_download_file_name(&fileName);
-    let cache_dir = std::env::temp_dir().join("aicodewith-updates");
-    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("创建缓存目录失败: {e}"))?;
+    let parsed = parse_download_url(&url, update_source)?;
+
+    let file_name = file_name
+        .or_else(|| {
+            parsed
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    let file_name = sanitize_download_file_name(&file_name);
+    let cache_dir = crate::settings::get_download_cache_dir();
+    std::fs::create_dir_all(&cache_dir).map_err(|e| {
+        format_command_error(CommandErrorCode::CacheDirCreateFailed, Some(&e.to_string()))
+    })?;
 
     let final_path = cache_dir.join(&file_name);
     let temp_path = cache_dir.join(format!("{file_name}.partial"));
+    let Some(_download_guard) = download_locks.acquire(&final_path) else {
+        return Err(format_command_error(
+            CommandErrorCode::DownloadInProgress,
+            None,
+        ));
+    };
 
-    let client = reqwest::Client::builder()
-        .user_agent(format!("AI-Code-With/{}", env!("CARGO_PKG_VERSION")))
-        .build()
-        .map_err(|e| format!("创建下载客户端失败: {e}"))?;
-
-    let res = client
-        .get(parsed)
-        .send()
+    let started_at = std::time::Instant::now();
+    let cached = if ignoreCache.unwrap_or(false) {
+        None
+    } else if let Some(record) = crate::app_store::get_download_record(&app, &url) {
+        let path = final_path.clone();
+        let expected = expected_sha256.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            verify_cached_download(&record, &path, expected.as_deref())
+        })
         .await
-        .map_err(|e| format!("下载请求失败: {e}"))?
-        .error_for_status()
-        .map_err(|e| format!("下载响应异常: {e}"))?;
+        .ok()
+        .flatten()
+    } else {
+        None
+    };
+    let from_cache = cached.is_some();
 
-    let mut file = tokio::fs::File::create(&temp_path)
-        .await
-        .map_err(|e| format!("创建下载文件失败: {e}"))?;
+    let (size_bytes, sha256) = if let Some(cached) = cached {
+        log::info!("安装包已下载且校验一致，跳过下载: {}", final_path.display());
+        cached
+    } else {
+        let client = download_client()?;
 
-    let mut stream = res.bytes_stream();
-    while let Some(chunk) = stream.next().await {
-        let bytes = chunk.map_err(|e| format!("读取下载数据失败: {e}"))?;
-        file.write_all(&bytes)
+        let res = client
+            .get(parsed)
+            .headers(headers)
+            .send()
             .await
-            .map_err(|e| format!("写入下载文件失败: {e}"))?;
-    }
+            .map_err(download_request_error)?
+            .error_for_status()
+            .map_err(|e| {
+                format_command_error(
+                    CommandErrorCode::DownloadResponseFailed,
+                    Some(&redact_error(e)),
+                )
+            })?;
 
-    file.flush()
-        .await
-        .map_err(|e| format!("刷新下载文件失败: {e}"))?;
-    drop(file);
+        // 镜像配置错误时可能以 200 返回 HTML 错误页，写入前先检查内容类型
+        let check_content =
+            is_installer_file_name(&file_name) && !allowUnexpectedContent.unwrap_or(false);
+        if check_content {
+            if let Some(content_type) = res
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .filter(|v| is_non_binary_content_type(v))
+            {
+                return Err(suspicious_content_error(content_type));
+            }
+        }
 
-    if let Err(e) = tokio::fs::rename(&temp_path, &final_path).await {
-        let _ = tokio::fs::remove_file(&temp_path).await;
-        return Err(format!("保存下载文件失败: {e}"));
-    }
+        // 已知 Content-Length 时预检磁盘空间，避免写入大量数据后才在 flush 阶段失败。
+        let content_length = res.content_length();
+        if let Some(content_length) = content_length {
+            ensure_disk_space(&cache_dir, content_length)?;
+        }
+
+        let mut file = tokio::fs::File::create(&temp_path)
+            .await
+            .map_err(download_write_error)?;
+
+        let mut hasher = Sha256::new();
+        let mut size_bytes: u64 = 0;
+        let mut sniffer = HtmlSniffer::default();
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(|e| {
+                format_command_error(
+                    CommandErrorCode::DownloadResponseFailed,
+                    Some(&redact_error(e)),
+                )
+            })?;
+            if check_content && sniffer.push(&bytes) {
+                drop(file);
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(suspicious_content_error("text/html"));
+            }
+            hasher.update(&bytes);
+            size_bytes += bytes.len() as u64;
+            file.write_all(&bytes).await.map_err(download_write_error)?;
+        }
+
+        file.flush().await.map_err(download_write_error)?;
+        drop(file);
+
+        if check_content && sniffer.finish() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(suspicious_content_error("text/html"));
+        }
+
+        if let Some(expected) = content_length.filter(|len| *len != size_bytes) {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(format_command_error(
+                CommandErrorCode::DownloadResponseFailed,
+                Some(&format!(
+                    "下载不完整: 期望 {expected} 字节，实际 {size_bytes} 字节"
+                )),
+            ));
+        }
+
+        let sha256 = hex::encode(hasher.finalize());
+        if let Some(expected) = expected_sha256 {
+            if sha256 != expected {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(format_command_error(
+                    CommandErrorCode::ChecksumMismatch,
+                    Some(&format!("期望 {expected}，实际 {sha256}")),
+                ));
+            }
+        }
+
+        if let Err(e) = tokio::fs::rename(&temp_path, &final_path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(download_write_error(e));
+        }
+
+        tracing::info!(
+            size_bytes,
+            duration_ms = started_at.elapsed().as_millis() as u64,
+            "安装包下载完成"
+        );
+
+        let record = crate::app_store::DownloadRecord {
+            file_path: final_path.to_string_lossy().to_string(),
+            sha256: sha256.clone(),
+            size_bytes,
+        };
+        if let Err(e) = crate::app_store::set_download_record(&app, &url, &record) {
+            log::warn!("记录已下载的安装包失败: {e}");
+        }
+        (size_bytes, sha256)
+    };
+
+    let result = DownloadAndOpenResult {
+        file_path: final_path.to_string_lossy().to_string(),
+        size_bytes,
+        sha256,
+        duration_ms: started_at.elapsed().as_millis() as u64,
+        from_cache,
+        install_log_path: None,
+        signer: None,
+        install_started: false,
+        auto_launch_requested: false,
+        install_error: None,
+    };
 
     // Windows 下如果是 MSI，则用 msiexec 的 passive 模式启动安装，以避免向导式安装页面。
     #[cfg(target_os = "windows")]
@@ -161,20 +1643,103 @@ pub async fn download_and_open_update_package(
             .and_then(|v| v.to_str())
             .is_some_and(|ext| ext.eq_ignore_ascii_case("msi"))
         {
-            try_start_windows_msi_install(&final_path)?;
-            return Ok(DownloadAndOpenResult {
-                filePath: final_path.to_string_lossy().to_string(),
-            });
+            // 校验和只能发现传输损坏，签名验证可拦截被替换的安装包
+            let signer = match verify_installer_signature(&final_path) {
+                Ok(signer) => {
+                    log::info!("安装包签名验证通过，签名者: {signer}");
+                    Some(signer)
+                }
+                Err(e) if allowUnsigned.unwrap_or(false) => {
+                    log::warn!("安装包签名验证失败，已按用户选择继续安装: {e}");
+                    None
+                }
+                Err(e) => {
+                    return Err(format_command_error(
+                        CommandErrorCode::InstallerSignatureInvalid,
+                        Some(&e),
+                    ))
+                }
+            };
+            return Ok(
+                match try_start_windows_msi_install(&app, &final_path, package_version) {
+                    Ok(log_path) => DownloadAndOpenResult {
+                        install_log_path: Some(log_path.to_string_lossy().to_string()),
+                        signer,
+                        install_started: true,
+                        // build_msiexec_command 总是传入 AUTOLAUNCHAPP=1
+                        auto_launch_requested: true,
+                        ..result
+                    },
+                    Err(e) => {
+                        log::warn!("启动 Windows 安装器失败，需手动运行安装包: {e}");
+                        DownloadAndOpenResult {
+                            signer,
+                            install_error: Some(format_command_error(
+                                CommandErrorCode::InstallerLaunchFailed,
+                                Some(&e),
+                            )),
+                            ..result
+                        }
+                    }
+                },
+            );
+        }
+    }
+
+    // macOS / Linux 下按安装包类型直接触发安装，未识别的扩展名回退到系统默认打开方式。
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        match try_start_platform_installer(&final_path) {
+            Ok(true) => {
+                return Ok(DownloadAndOpenResult {
+                    install_started: true,
+                    ..result
+                })
+            }
+            Ok(false) => {}
+            Err(e) => {
+                log::warn!("启动安装程序失败，需手动运行安装包: {e}");
+                return Ok(DownloadAndOpenResult {
+                    install_error: Some(format_command_error(
+                        CommandErrorCode::InstallerLaunchFailed,
+                        Some(&e),
+                    )),
+                    ..result
+                });
+            }
         }
     }
 
     app.opener()
         .open_path(final_path.to_string_lossy().to_string(), None::<String>)
-        .map_err(|e| format!("打开安装包失败: {e}"))?;
+        .map_err(|e| {
+            format_command_error(CommandErrorCode::OpenPackageFailed, Some(&e.to_string()))
+        })?;
 
-    Ok(DownloadAndOpenResult {
-        filePath: final_path.to_string_lossy().to_string(),
-    })
+    Ok(result)
+}
+
+/// 获取最近一次 Windows MSI 安装的状态（退出码与日志路径）。
+/// 非 Windows 平台或尚未触发安装时返回 `None`。
+#[tauri::command]
+pub async fn get_msi_install_status() -> Result<Option<MsiInstallStatus>, String> {
+    Ok(msi_install_status_cell()
+        .read()
+        .ok()
+        .and_then(|g| g.clone()))
+}
+
+/// 按设置中的更新通道查询 GitHub 最新版本并与当前版本比较（不打开浏览器）。
+///
+/// 错误以带错误码的 JSON 字符串返回；GitHub API 限流时错误码为 `GithubRateLimited`，前端可静默忽略。
+#[tauri::command]
+pub async fn get_latest_release() -> Result<ReleaseInfo, String> {
+    let client = shared_http_client()
+        .map_err(|e| format_command_error(CommandErrorCode::HttpClientFailed, Some(&e)))?;
+    let channel = crate::settings::get_settings().update_channel;
+    release::fetch_latest_release(&client, channel)
+        .await
+        .map_err(release_command_error)
 }
 
 /// 检查更新
@@ -192,23 +1757,37 @@ pub async fn check_for_updates(handle: AppHandle) -> Result<bool, String> {
 }
 
 /// 判断是否为便携版（绿色版）运行
+///
+/// 支持 `--portable` 参数、`CCSWITCH_PORTABLE=1` 环境变量与 `portable.ini` 三种方式，
+/// 详见 [`crate::config::is_portable_mode`]。
 #[tauri::command]
 pub async fn is_portable_mode() -> Result<bool, String> {
-    let exe_path = std::env::current_exe().map_err(|e| format!("获取可执行路径失败: {e}"))?;
-    if let Some(dir) = exe_path.parent() {
-        Ok(dir.join("portable.ini").is_file())
-    } else {
-        Ok(false)
-    }
+    Ok(crate::config::is_portable_mode())
+}
+
+/// 判断 macOS 是否以 App Translocation（随机只读路径）方式运行，
+/// 为 `true` 时前端应提示用户将应用移动到“应用程序”文件夹；其它平台始终为 `false`
+#[tauri::command]
+pub async fn is_app_translocated() -> Result<bool, String> {
+    Ok(crate::config::is_app_translocated())
 }
 
 /// 获取应用启动阶段的初始化错误（若有）。
-/// 用于前端在早期主动拉取，避免事件订阅竞态导致的提示缺失。
+/// 用于前端在早期主动拉取，避免事件订阅竞态导致的提示缺失；
+/// 此后的变化通过 `init-error` / `init-error-cleared` 事件通知，无需轮询。
 #[tauri::command]
 pub async fn get_init_error() -> Result<Option<InitErrorPayload>, String> {
     Ok(crate::init_status::get_init_error())
 }
 
+/// 清除初始化错误，供前端在用户关闭错误提示后调用。
+/// `get_init_error` 本身不会清除，以保证早期拉取不受影响。
+#[tauri::command]
+pub async fn clear_init_error() -> Result<bool, String> {
+    crate::init_status::clear_init_error();
+    Ok(true)
+}
+
 /// 获取 JSON→SQLite 迁移结果（若有）。
 /// 只返回一次 true，之后返回 false，用于前端显示一次性 Toast 通知。
 #[tauri::command]
@@ -216,220 +1795,776 @@ pub async fn get_migration_result() -> Result<bool, String> {
     Ok(crate::init_status::take_migration_success())
 }
 
-#[derive(serde::Serialize)]
-pub struct ToolVersion {
-    name: String,
-    version: Option<String>,
-    latest_version: Option<String>, // 新增字段：最新版本
-    error: Option<String>,
+/// 获取 JSON→SQLite 迁移失败详情（若有），包含失败阶段与迁移前备份路径。
+#[tauri::command]
+pub async fn get_migration_error() -> Result<Option<MigrationErrorPayload>, String> {
+    Ok(crate::init_status::get_migration_error())
 }
 
+/// 手动重新执行 JSON→SQLite 迁移（用于启动迁移失败后的恢复）。
+///
+/// 成功返回 `None`；失败返回包含阶段与源文件路径的错误详情。
+/// `force` 为 true 时覆盖数据库中已有的供应商、MCP、提示词与 Skills 数据。
 #[tauri::command]
-pub async fn get_tool_versions() -> Result<Vec<ToolVersion>, String> {
-    let tools = vec!["claude", "codex", "gemini"];
-    let mut results = Vec::new();
+pub async fn rerun_migration(
+    state: State<'_, AppState>,
+    force: bool,
+) -> Result<Option<MigrationErrorPayload>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _guard =
+            match crate::services::migration_lock::acquire(&crate::config::get_app_config_dir()) {
+                Ok(guard) => guard,
+                Err(e) => {
+                    return Some(MigrationErrorPayload {
+                        stage: "migrate".to_string(),
+                        message: e.to_string(),
+                        backup_path: None,
+                    })
+                }
+            };
+        match crate::services::ConfigService::rerun_migration(&db, force) {
+            Ok(source) => {
+                log::info!("✓ 已从 {} 重新迁移配置", source.display());
+                crate::init_status::clear_migration_error();
+                if crate::init_status::get_init_error().is_some_and(|err| {
+                    err.kind == crate::init_status::InitErrorKind::MigrationFailed
+                }) {
+                    crate::init_status::clear_init_error();
+                }
+                let app_state = AppState::new(db);
+                if let Err(err) = crate::services::ProviderService::sync_current_to_live(&app_state)
+                {
+                    log::warn!("重新迁移后同步 live 配置失败: {err}");
+                }
+                None
+            }
+            Err(payload) => {
+                log::error!("重新迁移失败 [{}]: {}", payload.stage, payload.message);
+                Some(payload)
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("重新迁移失败: {e}"))
+}
 
-    // 用于获取远程版本的 client
-    let client = reqwest::Client::builder()
-        .user_agent("cc-switch/1.0")
-        .build()
+/// 清理上次迁移中断残留的锁与临时文件，下次启动时重新从 config.json 迁移。
+///
+/// 迁移正在进行时拒绝执行。返回被清理的文件列表。
+#[tauri::command]
+pub async fn reset_migration_state() -> Result<Vec<String>, String> {
+    let cleared = crate::services::migration_lock::reset(&crate::config::get_app_config_dir())
         .map_err(|e| e.to_string())?;
+    log::info!("已重置迁移状态，清理 {} 项", cleared.len());
+    Ok(cleared)
+}
 
-    for tool in tools {
-        // 1. 获取本地版本 - 先尝试直接执行，失败则扫描常见路径
-        let (local_version, local_error) = {
-            // 先尝试直接执行
-            let direct_result = try_get_version(tool);
-
-            if direct_result.0.is_some() {
-                direct_result
-            } else {
-                // 扫描常见的 npm 全局安装路径
-                scan_cli_version(tool)
-            }
-        };
+/// 检查数据库完整性，损坏时抢救可读数据重建，失败再从最近的数据库备份恢复。
+///
+/// 返回采用的修复方式（`healthy` / `salvaged` / `restoredFromBackup`）；损坏的原文件改名保留。
+/// 数据库损坏导致应用以内存数据库降级启动时，修复成功后切换回修复后的数据库文件，
+/// 并清除 `Corrupt` 类初始化错误。
+#[tauri::command]
+pub async fn repair_store(state: State<'_, AppState>) -> Result<RepairReport, String> {
+    let db = state.db.clone();
+    let db_path = crate::config::get_app_config_dir().join("cc-switch.db");
+    let report = tauri::async_runtime::spawn_blocking(move || db.repair_file(&db_path))
+        .await
+        .map_err(|e| format!("修复数据库失败: {e}"))?
+        .map_err(|e| e.to_string())?;
 
-        // 2. 获取远程最新版本
-        let latest_version = match tool {
-            "claude" => fetch_npm_latest_version(&client, "@anthropic-ai/claude-code").await,
-            "codex" => fetch_npm_latest_version(&client, "@openai/codex").await,
-            "gemini" => fetch_npm_latest_version(&client, "@google/gemini-cli").await,
-            _ => None,
-        };
+    if crate::init_status::get_init_error()
+        .is_some_and(|err| err.kind == crate::init_status::InitErrorKind::Corrupt)
+    {
+        crate::init_status::clear_init_error();
+    }
+    Ok(report)
+}
 
-        results.push(ToolVersion {
-            name: tool.to_string(),
-            version: local_version,
-            latest_version,
-            error: local_error,
-        });
+/// 获取各 CLI 工具的本地版本与 npm 最新版本。
+///
+/// `force` 为 true 时跳过最新版本缓存，直接请求 npm registry（用于手动刷新）。
+/// 启动后已预取且未过期时，非强制刷新直接返回预取结果。
+#[tauri::command]
+pub async fn get_tool_versions(
+    state: State<'_, AppState>,
+    force: Option<bool>,
+) -> Result<Vec<ToolVersion>, String> {
+    if !force.unwrap_or(false) {
+        if let Some(versions) = crate::version_prefetch::take_prefetched() {
+            return Ok(versions);
+        }
     }
 
-    Ok(results)
+    // 用于获取远程版本的 client
+    let client = shared_http_client()?;
+
+    Ok(cli_tools::detect_tool_versions(&client, &state.db, force.unwrap_or(false)).await)
 }
 
-/// Helper function to fetch latest version from npm registry
-async fn fetch_npm_latest_version(client: &reqwest::Client, package: &str) -> Option<String> {
-    let url = format!("https://registry.npmjs.org/{package}");
-    match client.get(&url).send().await {
-        Ok(resp) => {
-            if let Ok(json) = resp.json::<serde_json::Value>().await {
-                json.get("dist-tags")
-                    .and_then(|tags| tags.get("latest"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            } else {
-                None
-            }
-        }
-        Err(_) => None,
+/// 仅刷新各 CLI 工具在 npm 上的最新版本（遵循缓存有效期），不重新检测本地版本，
+/// 返回工具 ID → 最新版本，由前端与此前检测到的本地版本合并
+#[tauri::command]
+pub async fn refresh_latest_versions(
+    state: State<'_, AppState>,
+) -> Result<std::collections::BTreeMap<String, cli_tools::LatestToolVersion>, String> {
+    let client = shared_http_client()?;
+    Ok(cli_tools::fetch_latest_versions(&client, &state.db).await)
+}
+
+/// 将当前 CLI 版本报告（版本、最新版本、是否可更新、路径与安装方式等）导出为 `json` 或 `csv`
+/// 文件，返回写入的路径
+#[tauri::command]
+pub async fn export_tool_versions(
+    state: State<'_, AppState>,
+    format: String,
+    path: String,
+) -> Result<String, String> {
+    let format = cli_tools::VersionReportFormat::from_str(&format)?;
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() || path.is_dir() {
+        return Err(format!("无效的导出路径: {}", path.display()));
     }
+    // 版本检测较慢，先确认目标目录可写
+    let parent = path.parent().unwrap_or(Path::new("/"));
+    tempfile::NamedTempFile::new_in(parent)
+        .map_err(|e| format!("导出目录不可写 {}: {e}", parent.display()))?;
+
+    let client = shared_http_client()?;
+    let versions = cli_tools::detect_tool_versions(&client, &state.db, false).await;
+    let report = cli_tools::render_version_report(&versions, format)?;
+    crate::config::atomic_write(&path, report.as_bytes()).map_err(|e| e.to_string())?;
+    log::info!("已导出 CLI 版本报告: {}", path.display());
+    Ok(path.to_string_lossy().to_string())
 }
 
-/// 从版本输出中提取纯版本号
-fn extract_version(raw: &str) -> String {
-    // 匹配 semver 格式: x.y.z 或 x.y.z-xxx
-    let re = regex::Regex::new(r"\d+\.\d+\.\d+(-[\w.]+)?").unwrap();
-    re.find(raw)
-        .map(|m| m.as_str().to_string())
-        .unwrap_or_else(|| raw.to_string())
+/// 清除 npm 最新版本缓存（不传 `tool` 时清除全部工具），返回清除的条目数。
+///
+/// 清除后下一次 `get_tool_versions` 会重新查询 npm registry。
+#[tauri::command]
+pub async fn clear_version_cache(
+    state: State<'_, AppState>,
+    tool: Option<String>,
+) -> Result<usize, String> {
+    let package = tool
+        .map(|tool| find_managed_tool(&tool).map(|managed| managed.npm_package))
+        .transpose()?;
+    state
+        .db
+        .clear_npm_version_cache(package)
+        .map_err(|e| format!("清除版本缓存失败: {e}"))
 }
 
-/// 尝试直接执行命令获取版本
-fn try_get_version(tool: &str) -> (Option<String>, Option<String>) {
-    use std::process::Command;
+/// 运行环境自检：CLI 安装、配置文件、当前供应商、网络与 npm registry 连通性。
+///
+/// 每项返回 `ok` / `warn` / `fail` 状态及修复建议，供诊断面板展示。
+#[tauri::command]
+pub async fn run_environment_check(
+    state: State<'_, AppState>,
+) -> Result<Vec<EnvironmentCheck>, String> {
+    let client = shared_http_client()?;
+    Ok(environment_check::run_environment_check(&client, &state.db).await)
+}
 
-    #[cfg(target_os = "windows")]
-    let output = {
-        Command::new("cmd")
-            .args(["/C", &format!("{tool} --version")])
-            .creation_flags(CREATE_NO_WINDOW)
-            .output()
+/// 检查本机 Node.js 版本是否满足 CLI 工具声明的最低要求（npm 元数据中的 `engines.node`）
+#[tauri::command]
+pub async fn check_node_compatibility(
+    tool: String,
+) -> Result<cli_tools::NodeCompatibility, String> {
+    let managed = find_managed_tool(&tool)?;
+    let client = shared_http_client()?;
+    cli_tools::check_node_compatibility(&client, &managed)
+        .await
+        .map_err(|e| format!("检查 {} 的 Node.js 要求失败: {e}", managed.id))
+}
+
+/// 测试供应商 API 的连通性与密钥是否有效（请求模型列表，不消耗额度）。
+///
+/// `kind` 为 `claude` / `codex` / `gemini`。
+#[tauri::command]
+pub async fn test_provider_connection(
+    #[allow(non_snake_case)] baseUrl: String,
+    #[allow(non_snake_case)] apiKey: String,
+    kind: String,
+) -> Result<ConnectionTestResult, String> {
+    let app_type = crate::app_config::AppType::from_str(&kind).map_err(|e| e.to_string())?;
+    let client = shared_http_client()?;
+    connection_test::test_connection(&client, &app_type, &baseUrl, &apiKey)
+        .await
+        .map_err(|e| redact_secrets(&e))
+}
+
+/// 批量测试供应商连通性（不传 `tool` 时测试所有未隐藏工具的供应商）。
+///
+/// 最多同时测试 4 个，单个供应商超时不影响其余测试；每完成一个发送
+/// `provider-test-progress` 事件（含已完成数、总数与该供应商结果）。
+#[tauri::command]
+pub async fn test_all_providers(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    tool: Option<String>,
+) -> Result<Vec<connection_test::ProviderTestResult>, String> {
+    let app_types = match tool {
+        Some(tool) => vec![crate::app_config::AppType::from_str(&tool).map_err(|e| e.to_string())?],
+        None => cli_tools::managed_tools()
+            .into_iter()
+            .filter(|tool| !tool.hidden)
+            .filter_map(|tool| tool.id.parse().ok())
+            .collect(),
     };
+    let mut targets = Vec::new();
+    for app_type in &app_types {
+        targets.extend(
+            crate::services::provider::ProviderService::connection_targets(&state, app_type)
+                .map_err(redact_error)?,
+        );
+    }
+
+    let client = shared_http_client()?;
+    let total = targets.len();
+    let mut completed = 0;
+    Ok(connection_test::test_all(&client, targets, |result| {
+        completed += 1;
+        let payload = serde_json::json!({
+            "completed": completed,
+            "total": total,
+            "result": result,
+        });
+        if let Err(e) = app.emit("provider-test-progress", payload) {
+            log::warn!("发送连通性测试进度事件失败: {e}");
+        }
+    })
+    .await)
+}
+
+/// 本地校验 API Key 格式（去除空白、检查厂商前缀），结果仅作提示，不阻止保存。
+#[tauri::command]
+pub async fn validate_api_key(kind: String, key: String) -> Result<ApiKeyValidation, String> {
+    let app_type = crate::app_config::AppType::from_str(&kind).map_err(|e| e.to_string())?;
+    Ok(api_key::validate_api_key(&app_type, &key))
+}
 
-    #[cfg(not(target_os = "windows"))]
-    let output = {
-        Command::new("sh")
-            .arg("-c")
-            .arg(format!("{tool} --version"))
-            .output()
+/// 获取受管理的 CLI 工具列表（含用户隐藏状态）
+#[tauri::command]
+pub async fn get_managed_tools() -> Result<Vec<ManagedTool>, String> {
+    Ok(cli_tools::managed_tools())
+}
+
+/// 检测某个 CLI 工具的所有安装位置（多处安装且版本不一致时前端给出提示）
+#[tauri::command]
+pub async fn detect_tool_conflicts(tool: String) -> Result<Vec<ToolInstallation>, String> {
+    let Some(managed) = cli_tools::managed_tools()
+        .into_iter()
+        .find(|t| t.id == tool || t.command == tool)
+    else {
+        return Err(format!("未知的 CLI 工具: {tool}"));
     };
 
-    match output {
-        Ok(out) => {
-            let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
-            if out.status.success() {
-                let raw = if stdout.is_empty() { &stderr } else { &stdout };
-                if raw.is_empty() {
-                    (None, Some("未安装或无法执行".to_string()))
-                } else {
-                    (Some(extract_version(raw)), None)
-                }
-            } else {
-                let err = if stderr.is_empty() { stdout } else { stderr };
-                (
-                    None,
-                    Some(if err.is_empty() {
-                        "未安装或无法执行".to_string()
-                    } else {
-                        err
-                    }),
-                )
-            }
+    tauri::async_runtime::spawn_blocking(move || cli_tools::detect_tool_conflicts(managed.command))
+        .await
+        .map_err(|e| format!("检测 CLI 安装失败: {e}"))
+}
+
+/// 列出某个 CLI 工具在 PATH 与常见安装目录中的全部可执行文件，标注版本、安装方式及实际使用的文件
+#[tauri::command]
+pub async fn list_tool_binaries(tool: String) -> Result<Vec<ToolBinary>, String> {
+    let managed = find_managed_tool(&tool)?;
+    tauri::async_runtime::spawn_blocking(move || cli_tools::list_tool_binaries(managed.command))
+        .await
+        .map_err(|e| format!("检测 CLI 安装失败: {e}"))
+}
+
+/// 将 CLI 工具固定在指定版本：本地版本与之相同时版本面板不再提示更新；
+/// `version` 为空时取消固定，恢复正常的版本比较
+#[tauri::command]
+pub async fn set_tool_pinned(tool: String, version: Option<String>) -> Result<bool, String> {
+    let managed = find_managed_tool(&tool)?;
+    let version = version
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+
+    let mut settings = crate::settings::get_settings();
+    match version {
+        Some(version) => {
+            settings
+                .pinned_tool_versions
+                .insert(managed.id.to_string(), version);
+        }
+        None => {
+            settings.pinned_tool_versions.remove(managed.id);
         }
-        Err(e) => (None, Some(e.to_string())),
     }
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    // 预取的版本信息按旧的固定状态计算，需重新检测
+    crate::version_prefetch::clear();
+    Ok(true)
 }
 
-/// 扫描常见路径查找 CLI
-fn scan_cli_version(tool: &str) -> (Option<String>, Option<String>) {
-    use std::process::Command;
+fn find_managed_tool(tool: &str) -> Result<ManagedTool, String> {
+    cli_tools::managed_tools()
+        .into_iter()
+        .find(|t| t.id == tool || t.command == tool)
+        .ok_or_else(|| format!("未知的 CLI 工具: {tool}"))
+}
 
-    let home = dirs::home_dir().unwrap_or_default();
+/// 执行安装并转发 `tool-install-output` / `tool-install-done` 事件，成功时显示系统通知，
+/// 返回安装后的版本
+async fn run_tool_install(
+    app: &AppHandle,
+    managed: &ManagedTool,
+    version: Option<&str>,
+) -> Result<String, String> {
+    let done = tool_install::install_tool(managed, version, |line| {
+        if let Err(e) = app.emit("tool-install-output", line) {
+            log::warn!("发射 tool-install-output 事件失败: {e}");
+        }
+    })
+    .await;
+    crate::version_prefetch::clear();
+    if let Err(e) = app.emit("tool-install-done", &done) {
+        log::warn!("发射 tool-install-done 事件失败: {e}");
+    }
+    match done.version {
+        Some(version) if done.success => {
+            crate::show_notification(app, &format!("{} 已更新到 {version}", managed.id));
+            Ok(version)
+        }
+        _ => Err(done.error.unwrap_or_else(|| "安装失败".to_string())),
+    }
+}
 
-    // 常见的 npm 全局安装路径
-    let mut search_paths: Vec<std::path::PathBuf> = vec![
-        home.join(".npm-global/bin"),
-        home.join(".local/bin"),
-        home.join("n/bin"), // n version manager
-    ];
+/// 安装或更新 CLI 工具（按检测到的安装方式选择 npm / pnpm / yarn），返回安装后的版本。
+///
+/// 安装输出逐行通过 `tool-install-output` 事件发送，结束时发送 `tool-install-done`
+/// （含退出码与新版本）；同一工具同时只允许一个安装任务。
+#[tauri::command]
+pub async fn install_tool(app: AppHandle, tool: String) -> Result<String, String> {
+    let managed = find_managed_tool(&tool)?;
+    run_tool_install(&app, &managed, None).await
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        search_paths.push(std::path::PathBuf::from("/opt/homebrew/bin"));
-        search_paths.push(std::path::PathBuf::from("/usr/local/bin"));
+/// 将 CLI 工具回退（或固定）到指定版本，返回安装后检测到的版本。
+///
+/// 版本号须为合法的 semver；安装过程与 [`install_tool`] 相同，发送同样的事件。
+#[tauri::command]
+pub async fn rollback_tool(
+    app: AppHandle,
+    tool: String,
+    version: String,
+) -> Result<String, String> {
+    let managed = find_managed_tool(&tool)?;
+    let version = tool_install::normalize_version(&version)?;
+    let installed = run_tool_install(&app, &managed, Some(&version)).await?;
+    if installed != version {
+        log::warn!(
+            "{} 已回退到 {version}，但检测到的版本为 {installed}（PATH 中可能有其它安装）",
+            managed.id
+        );
     }
+    Ok(installed)
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        search_paths.push(std::path::PathBuf::from("/usr/local/bin"));
-        search_paths.push(std::path::PathBuf::from("/usr/bin"));
-    }
+/// 获取 CLI 工具在 npm 上的全部已发布版本（从新到旧），供回退时选择。
+#[tauri::command]
+pub async fn get_tool_versions_list(tool: String) -> Result<Vec<String>, String> {
+    let managed = find_managed_tool(&tool)?;
+    let client = shared_http_client()?;
+    cli_tools::query_npm_versions(&client, managed.npm_package)
+        .await
+        .map_err(|e| format!("获取 {} 版本列表失败: {e}", managed.npm_package))
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        if let Some(appdata) = dirs::data_dir() {
-            search_paths.push(appdata.join("npm"));
+/// 分页列出 CLI 工具在 npm 上的已发布版本（按 semver 从新到旧）及 `latest` 标签，
+/// 供固定版本与回退时选择；`limit` 默认且最多为 50
+#[tauri::command]
+pub async fn list_tool_versions(
+    state: State<'_, AppState>,
+    tool: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<cli_tools::ToolVersionPage, String> {
+    let managed = find_managed_tool(&tool)?;
+    let client = shared_http_client()?;
+    cli_tools::list_npm_versions(
+        &client,
+        &state.db,
+        managed.npm_package,
+        offset.unwrap_or(0),
+        limit.unwrap_or(cli_tools::MAX_VERSION_PAGE_SIZE),
+    )
+    .await
+    .map_err(|e| format!("获取 {} 版本列表失败: {e}", managed.npm_package))
+}
+
+/// 检查 npm registry 的连通性、延迟与限流状态
+///
+/// 不传 `registry` 时检查设置中的镜像地址；传入时仅检查该地址（用于保存前校验）。
+#[tauri::command]
+pub async fn check_registry_status(
+    registry: Option<String>,
+) -> Result<cli_tools::RegistryStatus, String> {
+    let registry = match registry.map(|r| r.trim().to_string()) {
+        Some(registry) if !registry.is_empty() => {
+            let parsed =
+                url::Url::parse(&registry).map_err(|e| format!("无效的 registry 地址: {e}"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("registry 地址仅支持 http/https 协议".to_string());
+            }
+            registry
         }
-        search_paths.push(std::path::PathBuf::from("C:\\Program Files\\nodejs"));
+        _ => crate::settings::get_npm_registry(),
+    };
+    let client = shared_http_client()?;
+    Ok(cli_tools::check_registry_status(&client, &registry).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_lock_is_per_file_and_released_on_drop() {
+        let locks = DownloadLocks::default();
+        let msi = Path::new("/cache/app.msi");
+        let guard = locks.acquire(msi).expect("first download");
+        assert!(locks.acquire(msi).is_none());
+        assert!(locks.acquire(Path::new("/cache/app.dmg")).is_some());
+        drop(guard);
+        assert!(locks.acquire(msi).is_some());
     }
 
-    // 扫描 nvm 目录下的所有 node 版本
-    let nvm_base = home.join(".nvm/versions/node");
-    if nvm_base.exists() {
-        if let Ok(entries) = std::fs::read_dir(&nvm_base) {
-            for entry in entries.flatten() {
-                let bin_path = entry.path().join("bin");
-                if bin_path.exists() {
-                    search_paths.push(bin_path);
-                }
-            }
+    #[test]
+    fn download_headers_reject_control_chars_and_sensitive_names() {
+        let headers = |pairs: &[(&str, &str)]| {
+            Some(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            )
+        };
+
+        let map = download_headers(headers(&[
+            ("X-Gateway-Token", "abc"),
+            ("User-Agent", "Corp/1.0"),
+        ]))
+        .unwrap();
+        assert_eq!(map["x-gateway-token"], "abc");
+        assert_eq!(map["user-agent"], "Corp/1.0");
+        assert!(download_headers(None).unwrap().is_empty());
+
+        for (name, value) in [("X-Test", "a\r\nInjected: 1"), ("Bad Name", "v")] {
+            let err = download_headers(headers(&[(name, value)])).unwrap_err();
+            assert!(err.contains("InvalidRequestHeader"), "{name}: {err}");
+        }
+        for name in [
+            "Authorization",
+            "cookie",
+            "Host",
+            "Proxy-Authorization",
+            "Sec-Fetch-Mode",
+        ] {
+            let err = download_headers(headers(&[(name, "v")])).unwrap_err();
+            assert!(err.contains("ForbiddenRequestHeader"), "{name}: {err}");
         }
     }
 
-    // 在每个路径中查找工具
-    for path in &search_paths {
-        let tool_path = if cfg!(target_os = "windows") {
-            path.join(format!("{tool}.cmd"))
-        } else {
-            path.join(tool)
+    #[test]
+    fn detects_error_pages_served_as_installers() {
+        assert!(is_installer_file_name("AiCodeWith_1.2.0_x64.MSI"));
+        assert!(is_installer_file_name("app.AppImage"));
+        assert!(!is_installer_file_name("latest.json"));
+
+        assert!(is_non_binary_content_type("text/html; charset=utf-8"));
+        assert!(is_non_binary_content_type("application/problem+json"));
+        assert!(!is_non_binary_content_type("application/octet-stream"));
+        assert!(!is_non_binary_content_type("application/x-msi"));
+
+        assert!(looks_like_html(b"\xEF\xBB\xBF\n  <!DOCTYPE HTML><html>"));
+        assert!(looks_like_html(b"<html><body>404</body></html>"));
+        assert!(!looks_like_html(b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1"));
+    }
+
+    #[test]
+    fn html_sniffer_detects_pages_split_across_chunks() {
+        let mut sniffer = HtmlSniffer::default();
+        assert!(!sniffer.push(b"\xEF\xBB\xBF\r\n  <!DOC"));
+        assert!(sniffer.push(b"TYPE html><html><body>404</body></html>"));
+
+        // 整个响应都很短时在数据流结束后判定
+        let mut sniffer = HtmlSniffer::default();
+        assert!(!sniffer.push(b" <ht"));
+        assert!(!sniffer.push(b"ml>"));
+        assert!(sniffer.finish());
+
+        let mut sniffer = HtmlSniffer::default();
+        assert!(!sniffer.push(b"\xD0\xCF\x11\xE0"));
+        assert!(!sniffer.push(&[0u8; 600]));
+        assert!(!sniffer.finish());
+    }
+
+    #[test]
+    fn cached_download_requires_matching_size_and_hash() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("app.msi");
+        std::fs::write(&path, b"installer").unwrap();
+        let record = crate::app_store::DownloadRecord {
+            file_path: path.to_string_lossy().to_string(),
+            sha256: hex::encode(Sha256::digest(b"installer")),
+            size_bytes: 9,
         };
 
-        if tool_path.exists() {
-            // 构建 PATH 环境变量，确保 node 可被找到
-            let current_path = std::env::var("PATH").unwrap_or_default();
-            let new_path = format!("{}:{}", path.display(), current_path);
-
-            #[cfg(target_os = "windows")]
-            let output = {
-                Command::new(&tool_path)
-                    .arg("--version")
-                    .env("PATH", &new_path)
-                    .creation_flags(CREATE_NO_WINDOW)
-                    .output()
-            };
+        assert_eq!(
+            verify_cached_download(&record, &path, None),
+            Some((9, record.sha256.clone()))
+        );
+        assert!(verify_cached_download(&record, &path, Some(&record.sha256)).is_some());
+        assert!(verify_cached_download(&record, &path, Some("deadbeef")).is_none());
+        assert!(verify_cached_download(&record, &dir.path().join("other.msi"), None).is_none());
 
-            #[cfg(not(target_os = "windows"))]
-            let output = {
-                Command::new(&tool_path)
-                    .arg("--version")
-                    .env("PATH", &new_path)
-                    .output()
-            };
+        // 同样大小但内容被替换
+        std::fs::write(&path, b"tampered!").unwrap();
+        assert!(verify_cached_download(&record, &path, None).is_none());
+        std::fs::remove_file(&path).unwrap();
+        assert!(verify_cached_download(&record, &path, None).is_none());
+    }
 
-            if let Ok(out) = output {
-                let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
-                if out.status.success() {
-                    let raw = if stdout.is_empty() { &stderr } else { &stdout };
-                    if !raw.is_empty() {
-                        return (Some(extract_version(raw)), None);
-                    }
-                }
-            }
+    #[test]
+    fn link_probe_classifies_expired_and_unreachable_responses() {
+        use reqwest::StatusCode;
+
+        let status = |code: u16, content_type: Option<&str>| {
+            classify_link_response(StatusCode::from_u16(code).unwrap(), content_type, true).0
+        };
+        assert_eq!(
+            status(200, Some("application/octet-stream")),
+            DownloadLinkStatus::Ok
+        );
+        assert_eq!(status(206, None), DownloadLinkStatus::Ok);
+        assert_eq!(
+            status(200, Some("text/html; charset=utf-8")),
+            DownloadLinkStatus::Expired
+        );
+        assert_eq!(status(403, None), DownloadLinkStatus::Expired);
+        assert_eq!(status(404, None), DownloadLinkStatus::Expired);
+        assert_eq!(status(429, None), DownloadLinkStatus::Unreachable);
+        assert_eq!(status(502, None), DownloadLinkStatus::Unreachable);
+        // 非安装包链接不按内容类型判断
+        assert_eq!(
+            classify_link_response(StatusCode::OK, Some("text/html"), false).0,
+            DownloadLinkStatus::Ok
+        );
+    }
+
+    #[test]
+    fn relative_update_urls_resolve_against_download_base() {
+        let base = Some("https://mirror.123pan.com/cc-switch");
+        assert_eq!(
+            resolve_update_url("v3.2.0/app.msi", base),
+            "https://mirror.123pan.com/cc-switch/v3.2.0/app.msi"
+        );
+        assert_eq!(
+            resolve_update_url("/app.msi", Some("https://mirror.123pan.com/cc-switch/")),
+            "https://mirror.123pan.com/cc-switch/app.msi"
+        );
+        // 绝对链接不受影响，未设置基础地址时原样返回
+        assert_eq!(
+            resolve_update_url("https://x.123pan.com/a.msi", base),
+            "https://x.123pan.com/a.msi"
+        );
+        assert_eq!(resolve_update_url("app.msi", None), "app.msi");
+    }
+
+    #[test]
+    fn download_metadata_reads_response_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("content-length", HeaderValue::from_static("1048576"));
+        headers.insert("etag", HeaderValue::from_static("\"abc123\""));
+        headers.insert("accept-ranges", HeaderValue::from_static("bytes"));
+        headers.insert(
+            "content-type",
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        let meta = DownloadMetadata::from_headers(&headers);
+        assert_eq!(meta.size, Some(1_048_576));
+        assert_eq!(meta.etag.as_deref(), Some("\"abc123\""));
+        assert!(meta.accept_ranges);
+        assert_eq!(
+            meta.content_type.as_deref(),
+            Some("application/octet-stream")
+        );
+
+        headers.insert("accept-ranges", HeaderValue::from_static("none"));
+        assert!(!DownloadMetadata::from_headers(&headers).accept_ranges);
+        assert_eq!(DownloadMetadata::from_headers(&HeaderMap::new()).size, None);
+    }
+
+    #[test]
+    fn download_url_rejects_untrusted_hosts() {
+        assert!(parse_download_url("https://x.123pan.com/a.msi", UpdateSource::Pan123).is_ok());
+        let err = parse_download_url("https://evil.example/a.msi", UpdateSource::Pan123)
+            .expect_err("untrusted host");
+        assert!(err.contains("UntrustedDownloadHost"), "{err}");
+        assert!(parse_download_url("ftp://x.123pan.com/a.msi", UpdateSource::Pan123).is_err());
+    }
+
+    #[tokio::test]
+    async fn download_rejects_redirect_to_untrusted_host() {
+        use axum::response::Redirect;
+        use axum::routing::get;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new()
+            .route(
+                "/trusted",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://127.0.0.1:{port}/file"))
+                }),
+            )
+            .route(
+                "/untrusted",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://localhost:{port}/file"))
+                }),
+            )
+            .route("/file", get(|| async { "package" }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = download_client_with(|host| host == "127.0.0.1").expect("build client");
+
+        let ok = client
+            .get(format!("http://127.0.0.1:{port}/trusted"))
+            .send()
+            .await
+            .expect("redirect within allowlist is followed");
+        assert_eq!(ok.text().await.unwrap(), "package");
+
+        let err = client
+            .get(format!("http://127.0.0.1:{port}/untrusted"))
+            .send()
+            .await
+            .expect_err("redirect off the allowlist is rejected");
+        let err = download_request_error(err);
+        assert!(err.contains("UntrustedRedirectHost"), "{err}");
+        assert!(err.contains("重定向目标域名不受信任"), "{err}");
+    }
+
+    #[test]
+    fn github_hosts_are_trusted_only_when_opted_in() {
+        assert!(is_trusted_download_host(
+            "dl.123pan.com",
+            UpdateSource::Pan123
+        ));
+        assert!(!is_trusted_download_host(
+            "github.com",
+            UpdateSource::Pan123
+        ));
+        assert!(is_trusted_download_host("GitHub.com", UpdateSource::Github));
+        assert!(is_trusted_download_host(
+            "objects.githubusercontent.com",
+            UpdateSource::Github
+        ));
+        assert!(!is_trusted_download_host(
+            "evil-github.com",
+            UpdateSource::Github
+        ));
+        assert!(is_trusted_download_host(
+            "x.cjjd19.com",
+            UpdateSource::Github
+        ));
+    }
+
+    #[test]
+    fn github_release_asset_redirect_host_is_trusted_for_github_only() {
+        let host = "release-assets.githubusercontent.com";
+        assert!(is_trusted_download_host(host, UpdateSource::Github));
+        assert!(!is_trusted_download_host(host, UpdateSource::Pan123));
+    }
+
+    #[test]
+    fn download_file_name_drops_directory_components() {
+        assert_eq!(sanitize_download_file_name("../../evil"), "evil");
+        assert_eq!(
+            sanitize_download_file_name("/tmp/AiCodeWith_1.2.0.dmg"),
+            "AiCodeWith_1.2.0.dmg"
+        );
+    }
+
+    #[test]
+    fn download_file_name_handles_dots_and_reserved_names() {
+        for input in ["..", "...", " . . ", ""] {
+            assert_eq!(sanitize_download_file_name(input), "aicodewith-update.bin");
+        }
+        assert_eq!(sanitize_download_file_name("CON"), "_CON");
+        assert_eq!(sanitize_download_file_name("nul.msi"), "_nul.msi");
+        assert_eq!(sanitize_download_file_name("com1.tar.gz"), "_com1.tar.gz");
+        assert_eq!(sanitize_download_file_name("setup.exe. "), "setup.exe");
+        assert_eq!(sanitize_download_file_name("console.exe"), "console.exe");
+        assert_eq!(sanitize_download_file_name(&"a".repeat(200)).len(), 120);
+    }
+
+    #[test]
+    fn download_file_name_truncation_keeps_extension() {
+        let name = sanitize_download_file_name(&format!("{}.msi", "a".repeat(200)));
+        assert!(name.ends_with(".msi"), "extension lost: {name}");
+        assert_eq!(name.chars().count(), 120);
+    }
+
+    #[test]
+    fn external_url_rejects_non_web_schemes() {
+        for input in [
+            "file:///etc/passwd",
+            "FILE:///C:/Windows/win.ini",
+            "mailto:someone@example.com",
+            "javascript:alert(1)",
+        ] {
+            assert_eq!(
+                normalize_external_url(input),
+                Err("不支持的链接协议".to_string()),
+                "{input} should be rejected"
+            );
         }
     }
 
-    (None, Some("未安装或无法执行".to_string()))
+    #[test]
+    fn downgrade_detection_uses_semver() {
+        assert_eq!(is_downgrade("1.0.5", "1.0.4"), Ok(true));
+        assert_eq!(is_downgrade("1.0.5", "v1.0.5"), Ok(false));
+        assert_eq!(is_downgrade("1.0.5", "1.1.0-beta.1"), Ok(false));
+        assert_eq!(is_downgrade("1.0.5", "1.0.5-beta.1"), Ok(true));
+        assert!(is_downgrade("1.0.5", "latest").is_err());
+    }
+
+    #[test]
+    fn download_errors_carry_error_code() {
+        let err = is_downgrade("1.0.5", "latest").unwrap_err();
+        let parsed: serde_json::Value = serde_json::from_str(&err).expect("json error");
+        assert_eq!(parsed["code"], "InvalidVersion");
+        assert!(parsed["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("无效的版本号: "));
+    }
+
+    #[test]
+    fn external_url_prepends_https_for_bare_hosts() {
+        assert_eq!(
+            normalize_external_url("example.com").as_deref(),
+            Ok("https://example.com")
+        );
+        assert_eq!(
+            normalize_external_url("localhost:3000/docs").as_deref(),
+            Ok("https://localhost:3000/docs")
+        );
+        assert_eq!(
+            normalize_external_url("http://example.com/a").as_deref(),
+            Ok("http://example.com/a")
+        );
+    }
 }
@@ -8,6 +8,7 @@ mod import_export;
 mod mcp;
 mod misc;
 mod plugin;
+mod profile;
 mod prompt;
 mod provider;
 mod proxy;
@@ -24,6 +25,7 @@ pub use import_export::*;
 pub use mcp::*;
 pub use misc::*;
 pub use plugin::*;
+pub use profile::*;
 pub use prompt::*;
 pub use provider::*;
 pub use proxy::*;
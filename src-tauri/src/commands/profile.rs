@@ -0,0 +1,42 @@
+use tauri::{Emitter, State};
+
+use crate::database::Profile;
+use crate::secret_store::redact_error;
+use crate::services::ProfileService;
+use crate::store::AppState;
+
+/// 获取所有配置档案（含当前档案标记）
+#[tauri::command]
+pub fn list_profiles(state: State<'_, AppState>) -> Result<Vec<Profile>, String> {
+    ProfileService::list(state.inner()).map_err(redact_error)
+}
+
+/// 新建空的配置档案（不切换）
+#[tauri::command]
+pub fn create_profile(state: State<'_, AppState>, name: String) -> Result<Profile, String> {
+    ProfileService::create(state.inner(), &name).map_err(redact_error)
+}
+
+/// 切换配置档案：互换供应商集合并按新档案的当前供应商重写 CLI 配置文件
+///
+/// 成功后发送 `profile-switched` 事件（载荷为切换后的档案）。
+#[tauri::command]
+pub fn switch_profile(
+    state: State<'_, AppState>,
+    handle: tauri::AppHandle,
+    id: String,
+) -> Result<Profile, String> {
+    let profile = ProfileService::switch(state.inner(), &id).map_err(redact_error)?;
+    crate::tray::refresh_tray_menu(&handle);
+    if let Err(e) = handle.emit("profile-switched", &profile) {
+        log::error!("发射配置档案切换事件失败: {e}");
+    }
+    Ok(profile)
+}
+
+/// 删除配置档案（默认档案与当前档案不可删除）
+#[tauri::command]
+pub fn delete_profile(state: State<'_, AppState>, id: String) -> Result<bool, String> {
+    ProfileService::delete(state.inner(), &id).map_err(redact_error)?;
+    Ok(true)
+}
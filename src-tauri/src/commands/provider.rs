@@ -4,25 +4,43 @@ use tauri::State;
 use crate::app_config::AppType;
 use crate::error::AppError;
 use crate::provider::Provider;
+use crate::secret_store::redact_error;
+use crate::services::provider::ProviderSort;
 use crate::services::{EndpointLatency, ProviderService, ProviderSortUpdate, SpeedtestService};
 use crate::store::AppState;
+use std::collections::HashMap;
 use std::str::FromStr;
 
-/// 获取所有供应商
+/// 获取所有供应商（`sort`：order / name / recent / most_used，默认 order）
 #[tauri::command]
 pub fn get_providers(
     state: State<'_, AppState>,
     app: String,
+    sort: Option<String>,
 ) -> Result<IndexMap<String, Provider>, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::list(state.inner(), app_type).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    let sort = match sort {
+        Some(sort) => sort.parse().map_err(redact_error)?,
+        None => ProviderSort::default(),
+    };
+    ProviderService::list_sorted(state.inner(), app_type, sort).map_err(redact_error)
 }
 
 /// 获取当前供应商ID
 #[tauri::command]
 pub fn get_current_provider(state: State<'_, AppState>, app: String) -> Result<String, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::current(state.inner(), app_type).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::current(state.inner(), app_type).map_err(redact_error)
+}
+
+/// 获取工具当前生效的供应商（仅 ID 与名称），没有当前供应商时返回空
+#[tauri::command]
+pub fn get_active_provider(
+    state: State<'_, AppState>,
+    tool: String,
+) -> Result<Option<crate::services::provider::ActiveProvider>, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::active(state.inner(), &app_type).map_err(redact_error)
 }
 
 /// 添加供应商
@@ -32,8 +50,8 @@ pub fn add_provider(
     app: String,
     provider: Provider,
 ) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::add(state.inner(), app_type, provider).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::add(state.inner(), app_type, provider).map_err(redact_error)
 }
 
 /// 更新供应商
@@ -43,8 +61,22 @@ pub fn update_provider(
     app: String,
     provider: Provider,
 ) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::update(state.inner(), app_type, provider).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::update(state.inner(), app_type, provider).map_err(redact_error)
+}
+
+/// 复制供应商（新 ID，名称追加 " (copy)"，排在原供应商之后）
+#[tauri::command]
+pub fn duplicate_provider(
+    handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+) -> Result<Provider, String> {
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    let copy = ProviderService::duplicate(state.inner(), app_type, &id).map_err(redact_error)?;
+    crate::tray::refresh_tray_menu(&handle);
+    Ok(copy)
 }
 
 /// 删除供应商
@@ -54,10 +86,10 @@ pub fn delete_provider(
     app: String,
     id: String,
 ) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::delete(state.inner(), app_type, &id)
         .map(|_| true)
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 切换供应商
@@ -74,16 +106,106 @@ pub fn switch_provider_test_hook(
     switch_provider_internal(state, app_type, id)
 }
 
+/// 切换供应商
+///
+/// 写入的配置文件同时也是其他工具的配置文件（通过符号链接共享）时，切换照常完成，
+/// 并发送 `shared-config-warning` 事件提示风险。
 #[tauri::command]
 pub fn switch_provider(
     state: State<'_, AppState>,
+    handle: tauri::AppHandle,
     app: String,
     id: String,
 ) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    switch_provider_internal(&state, app_type, &id)
-        .map(|_| true)
-        .map_err(|e| e.to_string())
+    use tauri::Emitter;
+
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    let shared = ProviderService::shared_config_warnings(&state, &app_type);
+    switch_provider_internal(&state, app_type, &id).map_err(redact_error)?;
+
+    if !shared.is_empty() {
+        for warning in &shared {
+            log::warn!(
+                "{app} 的配置文件 {} 与 {} 共用同一文件 {}，切换会同时改变 {} 的配置",
+                warning.path,
+                warning.other_app,
+                warning.resolved_path,
+                warning.other_app
+            );
+        }
+        let event_data = serde_json::json!({ "appType": app, "files": shared });
+        if let Err(e) = handle.emit(crate::services::provider::SHARED_CONFIG_EVENT, event_data) {
+            log::error!("发射共享配置警告事件失败: {e}");
+        }
+    }
+    emit_provider_switched(&handle, &app, &id);
+    Ok(true)
+}
+
+fn switch_all_tools_internal(
+    state: &AppState,
+    selections: &HashMap<String, String>,
+) -> Result<Vec<(AppType, String)>, AppError> {
+    let mut parsed = Vec::with_capacity(selections.len());
+    for (tool, id) in selections {
+        let app_type = AppType::from_str(tool)?;
+        if parsed.iter().any(|(existing, _)| *existing == app_type) {
+            return Err(AppError::Message(format!(
+                "{} 被重复选择",
+                app_type.as_str()
+            )));
+        }
+        parsed.push((app_type, id.clone()));
+    }
+    // 固定顺序写入，失败时的回滚与日志可复现
+    parsed.sort_by_key(|(app_type, _)| match app_type {
+        AppType::Claude => 0,
+        AppType::Codex => 1,
+        AppType::Gemini => 2,
+    });
+    ProviderService::switch_all(state, &parsed)?;
+    Ok(parsed)
+}
+
+#[cfg_attr(not(feature = "test-hooks"), doc(hidden))]
+pub fn switch_all_tools_test_hook(
+    state: &AppState,
+    selections: &HashMap<String, String>,
+) -> Result<(), AppError> {
+    switch_all_tools_internal(state, selections).map(|_| ())
+}
+
+/// 同时切换多个工具的供应商（`providerSelections` 为工具到供应商 ID 的映射）
+///
+/// 先校验全部选择，再写入所有配置文件并在同一事务中更新当前供应商；任一步失败时
+/// 所有配置文件从 `.bak` 恢复，不会出现只切换了部分工具的情况。成功后每个工具发送
+/// 一次 `provider-switched` 事件。
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn switch_all_tools(
+    state: State<'_, AppState>,
+    handle: tauri::AppHandle,
+    providerSelections: HashMap<String, String>,
+) -> Result<bool, String> {
+    let switched = switch_all_tools_internal(&state, &providerSelections).map_err(redact_error)?;
+    for (app_type, id) in &switched {
+        emit_provider_switched(&handle, app_type.as_str(), id);
+    }
+    Ok(true)
+}
+
+/// 通知前端供应商已切换（`provider-switched` 事件，`appType` 与 `tool` 相同）
+pub(crate) fn emit_provider_switched(handle: &tauri::AppHandle, tool: &str, provider_id: &str) {
+    use tauri::Emitter;
+
+    let event_data = serde_json::json!({
+        "appType": tool,
+        "tool": tool,
+        "providerId": provider_id
+    });
+    if let Err(e) = handle.emit("provider-switched", event_data) {
+        log::error!("发射供应商切换事件失败: {e}");
+    }
 }
 
 fn import_default_config_internal(state: &AppState, app_type: AppType) -> Result<bool, AppError> {
@@ -101,10 +223,36 @@ pub fn import_default_config_test_hook(
 /// 导入当前配置为默认供应商
 #[tauri::command]
 pub fn import_default_config(state: State<'_, AppState>, app: String) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     import_default_config_internal(&state, app_type).map_err(Into::into)
 }
 
+/// 将工具当前的配置文件（Base URL / 模型 / API Key）导入为新供应商，`activate` 为 true 时同时切换到它
+#[tauri::command]
+pub fn import_from_tool_config(
+    state: State<'_, AppState>,
+    handle: tauri::AppHandle,
+    tool: String,
+    activate: Option<bool>,
+) -> Result<Provider, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    let activate = activate.unwrap_or(false);
+    let provider = ProviderService::import_from_tool_config(&state, app_type, activate)
+        .map_err(redact_error)?;
+    if activate {
+        crate::tray::refresh_tray_menu(&handle);
+        emit_provider_switched(&handle, &tool, &provider.id);
+    }
+    Ok(provider)
+}
+
+/// 确保工具的配置目录与配置文件存在（缺失时创建最小有效配置），返回是否创建了内容
+#[tauri::command]
+pub fn ensure_tool_config(tool: String) -> Result<bool, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    crate::services::provider::ensure_live_config(&app_type).map_err(Into::into)
+}
+
 /// 查询供应商用量
 #[allow(non_snake_case)]
 #[tauri::command]
@@ -113,10 +261,10 @@ pub async fn queryProviderUsage(
     #[allow(non_snake_case)] providerId: String, // 使用 camelCase 匹配前端
     app: String,
 ) -> Result<crate::provider::UsageResult, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::query_usage(state.inner(), app_type, &providerId)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 测试用量脚本（使用当前编辑器中的脚本，不保存）
@@ -134,7 +282,7 @@ pub async fn testUsageScript(
     #[allow(non_snake_case)] accessToken: Option<String>,
     #[allow(non_snake_case)] userId: Option<String>,
 ) -> Result<crate::provider::UsageResult, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::test_usage_script(
         state.inner(),
         app_type,
@@ -147,14 +295,14 @@ pub async fn testUsageScript(
         userId.as_deref(),
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(redact_error)
 }
 
 /// 读取当前生效的配置内容
 #[tauri::command]
 pub fn read_live_provider_settings(app: String) -> Result<serde_json::Value, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::read_live_settings(app_type).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::read_live_settings(app_type).map_err(redact_error)
 }
 
 /// 测试第三方/自定义供应商端点的网络延迟
@@ -165,7 +313,7 @@ pub async fn test_api_endpoints(
 ) -> Result<Vec<EndpointLatency>, String> {
     SpeedtestService::test_endpoints(urls, timeoutSecs)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 获取自定义端点列表
@@ -175,9 +323,9 @@ pub fn get_custom_endpoints(
     app: String,
     #[allow(non_snake_case)] providerId: String,
 ) -> Result<Vec<crate::settings::CustomEndpoint>, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::get_custom_endpoints(state.inner(), app_type, &providerId)
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 添加自定义端点
@@ -188,9 +336,9 @@ pub fn add_custom_endpoint(
     #[allow(non_snake_case)] providerId: String,
     url: String,
 ) -> Result<(), String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::add_custom_endpoint(state.inner(), app_type, &providerId, url)
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 删除自定义端点
@@ -201,9 +349,9 @@ pub fn remove_custom_endpoint(
     #[allow(non_snake_case)] providerId: String,
     url: String,
 ) -> Result<(), String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::remove_custom_endpoint(state.inner(), app_type, &providerId, url)
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 更新端点最后使用时间
@@ -214,9 +362,9 @@ pub fn update_endpoint_last_used(
     #[allow(non_snake_case)] providerId: String,
     url: String,
 ) -> Result<(), String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
     ProviderService::update_endpoint_last_used(state.inner(), app_type, &providerId, url)
-        .map_err(|e| e.to_string())
+        .map_err(redact_error)
 }
 
 /// 更新多个供应商的排序
@@ -226,6 +374,129 @@ pub fn update_providers_sort_order(
     app: String,
     updates: Vec<ProviderSortUpdate>,
 ) -> Result<bool, String> {
-    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::update_sort_order(state.inner(), app_type, updates).map_err(|e| e.to_string())
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::update_sort_order(state.inner(), app_type, updates).map_err(redact_error)
+}
+
+/// 按完整 ID 列表重排供应商（未列出的供应商追加到末尾），返回最终顺序
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn reorder_providers(
+    handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    app: String,
+    orderedIds: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    let order =
+        ProviderService::reorder(state.inner(), app_type, &orderedIds).map_err(redact_error)?;
+    crate::tray::refresh_tray_menu(&handle);
+    Ok(order)
+}
+
+/// 按名称 / Base URL 搜索供应商并按标签筛选（不区分大小写与重音），保持列表顺序
+#[tauri::command]
+pub fn search_providers(
+    state: State<'_, AppState>,
+    app: String,
+    query: String,
+    tags: Vec<String>,
+) -> Result<Vec<Provider>, String> {
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::search(state.inner(), app_type, &query, &tags).map_err(redact_error)
+}
+
+/// 设置供应商标签，返回去重整理后的标签
+#[tauri::command]
+pub fn set_provider_tags(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let app_type = AppType::from_str(&app).map_err(redact_error)?;
+    ProviderService::set_tags(state.inner(), app_type, &id, &tags).map_err(redact_error)
+}
+
+/// 检查工具配置文件中的 Base URL / API Key / 模型是否与当前供应商一致（密钥已遮盖）
+#[tauri::command]
+pub fn verify_active_config(
+    state: State<'_, AppState>,
+    tool: String,
+) -> Result<crate::services::provider::ConfigVerification, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::verify_active_config(state.inner(), app_type).map_err(redact_error)
+}
+
+/// 逐项对比同一工具下两个供应商的生效配置（Base URL、模型、环境变量等，密钥仅显示是否已设置）
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn diff_providers(
+    state: State<'_, AppState>,
+    tool: String,
+    idA: String,
+    idB: String,
+) -> Result<crate::services::provider::ProviderDiff, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::diff_providers(&state, &app_type, &idA, &idB).map_err(redact_error)
+}
+
+/// 批量校验供应商（必填字段、Base URL 与 API Key 格式），不做任何写入；
+/// 返回每个 ID 的结果，供切换配置档案前确认所有供应商均有效
+#[tauri::command]
+pub fn validate_providers(
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<Vec<crate::services::provider::ProviderValidation>, String> {
+    ProviderService::validate_providers(&state, &ids).map_err(redact_error)
+}
+
+/// 检查工具的 JSON 配置文件能否解析，返回出错文件的行列与上下文片段（密钥已遮盖）
+#[tauri::command]
+pub fn check_tool_config(tool: String) -> Result<Vec<crate::config::ConfigParseError>, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::check_live_configs(&app_type).map_err(redact_error)
+}
+
+/// 将无法解析的工具配置文件备份为 `<文件名>.invalid-<时间戳>` 并重置为有效的空配置
+#[tauri::command]
+pub fn reset_invalid_tool_config(
+    tool: String,
+) -> Result<Vec<crate::services::provider::ConfigResetEntry>, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::reset_invalid_live_configs(&app_type).map_err(redact_error)
+}
+
+/// 预览切换供应商将写入的配置文件及生效的模型与 Base URL（不实际写入，密钥已遮盖）
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn preview_switch(
+    state: State<'_, AppState>,
+    tool: String,
+    providerId: String,
+) -> Result<crate::services::provider::SwitchPreview, String> {
+    let app_type = AppType::from_str(&tool).map_err(redact_error)?;
+    ProviderService::preview_switch(&state, app_type, &providerId).map_err(redact_error)
+}
+
+/// 撤销上次切换：从备份恢复切换前的配置文件并恢复原供应商
+///
+/// 配置文件在切换后被修改过时拒绝撤销。返回值包含已恢复的文件列表。
+#[tauri::command]
+pub fn undo_last_switch(
+    state: State<'_, AppState>,
+    handle: tauri::AppHandle,
+) -> Result<crate::services::provider::UndoSwitchResult, String> {
+    let result = ProviderService::undo_last_switch(state.inner()).map_err(redact_error)?;
+    crate::tray::refresh_tray_menu(&handle);
+    emit_provider_switched(&handle, &result.app_type, &result.provider_id);
+    Ok(result)
+}
+
+/// 规范化 Base URL（补全协议、去除多余的末尾斜杠），返回规范化结果、是否变更及提示信息
+#[tauri::command]
+pub fn normalize_base_url(
+    url: String,
+) -> Result<crate::services::provider::NormalizedBaseUrl, String> {
+    crate::services::provider::normalize_base_url(url).map_err(|e| e.to_string())
 }
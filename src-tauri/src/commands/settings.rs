@@ -1,6 +1,8 @@
 #![allow(non_snake_case)]
 
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
 
 /// 获取设置
 #[tauri::command]
@@ -15,6 +17,318 @@ pub async fn save_settings(settings: crate::settings::AppSettings) -> Result<boo
     Ok(true)
 }
 
+/// 导出可迁移的设置项（不含设备级状态与代理账号密码）
+#[tauri::command]
+pub async fn export_settings() -> Result<serde_json::Value, String> {
+    crate::services::settings_transfer::export(&crate::settings::get_settings())
+        .map_err(|e| e.to_string())
+}
+
+/// 导入设置；`overwrite` 为 false 时保留本机已修改的设置项
+///
+/// 全部字段校验通过后一次性写入，随后应用日志级别、外观、快捷键与开机自启。
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    json: String,
+    overwrite: bool,
+) -> Result<crate::services::settings_transfer::SettingsImportReport, String> {
+    let current = crate::settings::get_settings();
+    let (settings, report) = crate::services::settings_transfer::merge(&current, &json, overwrite)
+        .map_err(|e| e.to_string())?;
+
+    if settings.switch_hotkey != current.switch_hotkey {
+        crate::hotkey::replace(
+            &app,
+            current.switch_hotkey.as_deref(),
+            settings.switch_hotkey.as_deref(),
+        )?;
+    }
+    crate::settings::update_settings(settings.clone()).map_err(|e| e.to_string())?;
+
+    if let Some(level) = &settings.log_level {
+        if let Err(e) = crate::logging::parse_level(level).and_then(crate::logging::set_level) {
+            log::warn!("应用导入的日志级别失败: {e}");
+        }
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        crate::appearance::apply(&window, settings.appearance);
+    }
+    if settings.launch_on_startup != current.launch_on_startup {
+        if let Err(e) = crate::auto_launch::reconcile_auto_launch(settings.launch_on_startup) {
+            log::warn!("应用导入的开机自启设置失败: {e}");
+        }
+    }
+    log::info!(
+        "已导入设置：写入 {} 项，保留 {} 项，忽略 {} 项",
+        report.applied.len(),
+        report.skipped.len(),
+        report.ignored.len()
+    );
+    Ok(report)
+}
+
+/// 获取网络代理设置
+#[tauri::command]
+pub async fn get_network_proxy() -> Result<crate::settings::NetworkProxy, String> {
+    Ok(crate::settings::get_settings().network_proxy)
+}
+
+/// 保存网络代理设置（用于下载更新与版本检查）
+#[tauri::command]
+pub async fn set_network_proxy(proxy: crate::settings::NetworkProxy) -> Result<bool, String> {
+    if let crate::settings::NetworkProxy::Custom { url } = &proxy {
+        let parsed = url::Url::parse(url.trim()).map_err(|e| format!("无效的代理地址: {e}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("代理地址仅支持 http/https 协议".to_string());
+        }
+    }
+
+    let mut settings = crate::settings::get_settings();
+    settings.network_proxy = proxy;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取应用更新通道
+#[tauri::command]
+pub async fn get_update_channel() -> Result<crate::settings::UpdateChannel, String> {
+    Ok(crate::settings::get_settings().update_channel)
+}
+
+/// 设置应用更新通道（`stable` / `beta`）
+#[tauri::command]
+pub async fn set_update_channel(channel: crate::settings::UpdateChannel) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    settings.update_channel = channel;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取界面外观设置及当前生效的主题
+#[tauri::command]
+pub async fn get_appearance(app: AppHandle) -> Result<crate::appearance::AppearanceInfo, String> {
+    Ok(crate::appearance::current(&app))
+}
+
+/// 设置界面外观（`system` / `light` / `dark`）并应用到原生窗口
+#[tauri::command]
+pub async fn set_appearance(
+    app: AppHandle,
+    mode: String,
+) -> Result<crate::appearance::AppearanceInfo, String> {
+    let mode: crate::settings::Appearance = mode.parse().map_err(|e: AppError| e.to_string())?;
+    let mut settings = crate::settings::get_settings();
+    settings.appearance = mode;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    if let Some(window) = app.get_webview_window("main") {
+        crate::appearance::apply(&window, mode);
+    }
+    Ok(crate::appearance::current(&app))
+}
+
+/// 获取更新安装包的下载来源
+#[tauri::command]
+pub async fn get_update_source() -> Result<crate::settings::UpdateSource, String> {
+    Ok(crate::settings::get_settings().update_source)
+}
+
+/// 设置更新安装包的下载来源（`pan123` / `github`）
+#[tauri::command]
+pub async fn set_update_source(source: crate::settings::UpdateSource) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    settings.update_source = source;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取是否在后台定时检查应用更新
+#[tauri::command]
+pub async fn get_auto_update_check() -> Result<bool, String> {
+    Ok(crate::settings::get_settings().auto_update_check)
+}
+
+/// 设置是否在后台定时检查应用更新（启动时及每 24 小时检查一次，仅提醒不下载）
+#[tauri::command]
+pub async fn set_auto_update_check(enabled: bool) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    settings.auto_update_check = enabled;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取是否在启动后预取 CLI 版本信息
+#[tauri::command]
+pub async fn get_prefetch_versions() -> Result<bool, String> {
+    Ok(crate::settings::get_settings().prefetch_versions)
+}
+
+/// 设置是否在启动后于后台预取 CLI 版本信息（下次启动生效）
+#[tauri::command]
+pub async fn set_prefetch_versions(enabled: bool) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    settings.prefetch_versions = enabled;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 将单个设置项恢复为默认值，返回恢复后生效的值
+///
+/// 可重置的键见 [`crate::settings::RESETTABLE_SETTINGS`]，未知的键返回错误。
+#[tauri::command]
+pub async fn reset_setting(app: AppHandle, key: String) -> Result<serde_json::Value, String> {
+    let value = crate::settings::reset_setting(key.trim()).map_err(|e| e.to_string())?;
+    match key.trim() {
+        "logLevel" => {
+            crate::logging::set_level(crate::logging::parse_level(
+                crate::logging::DEFAULT_LOG_LEVEL,
+            )?)?;
+        }
+        "appearance" => {
+            if let Some(window) = app.get_webview_window("main") {
+                crate::appearance::apply(&window, crate::settings::get_settings().appearance);
+            }
+        }
+        _ => {}
+    }
+    Ok(value)
+}
+
+/// 获取是否显示系统通知
+#[tauri::command]
+pub async fn get_notifications_enabled() -> Result<bool, String> {
+    Ok(crate::settings::get_settings().notifications_enabled)
+}
+
+/// 设置是否显示系统通知（工具更新完成、发现新版本、快捷键切换等）
+#[tauri::command]
+pub async fn set_notifications_enabled(enabled: bool) -> Result<bool, String> {
+    let mut settings = crate::settings::get_settings();
+    settings.notifications_enabled = enabled;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取文件日志级别
+#[tauri::command]
+pub async fn get_log_level() -> Result<String, String> {
+    Ok(crate::settings::get_settings()
+        .log_level
+        .unwrap_or_else(|| crate::logging::DEFAULT_LOG_LEVEL.to_string()))
+}
+
+/// 设置文件日志级别（trace / debug / info / warn / error），立即生效
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<bool, String> {
+    let filter = crate::logging::parse_level(&level)?;
+    crate::logging::set_level(filter)?;
+    let mut settings = crate::settings::get_settings();
+    settings.log_level = Some(level.trim().to_lowercase());
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 设置是否在启动时锁定 API Key 存储（需系统密钥服务可用）
+#[tauri::command]
+pub async fn set_require_unlock(enabled: bool) -> Result<bool, String> {
+    if enabled && !crate::secret_store::is_keystore_available() {
+        return Err("系统密钥服务不可用，无法启用解锁保护".to_string());
+    }
+    let mut settings = crate::settings::get_settings();
+    settings.require_unlock = enabled;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取循环切换供应商的全局快捷键
+#[tauri::command]
+pub async fn get_switch_hotkey() -> Result<Option<String>, String> {
+    Ok(crate::settings::get_settings().switch_hotkey)
+}
+
+/// 设置循环切换供应商的全局快捷键，传入空字符串清除
+///
+/// 快捷键已被系统或其他应用占用时返回错误，并保留原快捷键。
+#[tauri::command]
+pub async fn set_switch_hotkey(app: AppHandle, accelerator: String) -> Result<bool, String> {
+    let trimmed = accelerator.trim();
+    let new = (!trimmed.is_empty()).then(|| trimmed.to_string());
+    let mut settings = crate::settings::get_settings();
+    if settings.switch_hotkey == new {
+        return Ok(true);
+    }
+    crate::hotkey::replace(&app, settings.switch_hotkey.as_deref(), new.as_deref())?;
+    settings.switch_hotkey = new;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 记录主界面当前聚焦的工具，全局快捷键切换该工具的供应商
+#[tauri::command]
+pub async fn set_active_tool(app: String) -> Result<bool, String> {
+    let app_type = app
+        .parse::<crate::app_config::AppType>()
+        .map_err(|e| e.to_string())?;
+    crate::hotkey::set_active_tool(app_type);
+    Ok(true)
+}
+
+/// 获取版本检查使用的 npm registry 地址
+#[tauri::command]
+pub async fn get_npm_registry() -> Result<String, String> {
+    Ok(crate::settings::get_npm_registry())
+}
+
+/// 设置版本检查使用的 npm registry 镜像，传入空字符串恢复官方 registry
+#[tauri::command]
+pub async fn set_npm_registry(url: String) -> Result<bool, String> {
+    let trimmed = url.trim();
+    if !trimmed.is_empty() {
+        let parsed = url::Url::parse(trimmed).map_err(|e| format!("无效的 registry 地址: {e}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("registry 地址仅支持 http/https 协议".to_string());
+        }
+    }
+
+    let mut settings = crate::settings::get_settings();
+    settings.npm_registry = Some(trimmed.to_string());
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 获取更新安装包下载缓存目录（未配置时为默认位置）
+#[tauri::command]
+pub async fn get_download_cache_dir() -> Result<String, String> {
+    Ok(crate::settings::get_download_cache_dir()
+        .to_string_lossy()
+        .to_string())
+}
+
+/// 设置更新安装包下载缓存目录，传入空字符串恢复默认位置
+///
+/// 目录不存在时自动创建；不可写入时返回错误且不保存。
+#[tauri::command]
+pub async fn set_download_cache_dir(path: String) -> Result<bool, String> {
+    let trimmed = path.trim();
+    if !trimmed.is_empty() {
+        ensure_dir_writable(&crate::settings::resolve_override_path(trimmed))?;
+    }
+
+    let mut settings = crate::settings::get_settings();
+    settings.download_cache_dir = (!trimmed.is_empty()).then(|| trimmed.to_string());
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 创建目录并写入探测文件，确认目录可写
+fn ensure_dir_writable(dir: &std::path::Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("无法创建目录 {}: {e}", dir.display()))?;
+    let probe = dir.join(format!(".aicodewith-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"ok").map_err(|e| format!("目录不可写 {}: {e}", dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
 /// 重启应用程序（当 app_config_dir 变更后使用）
 #[tauri::command]
 pub async fn restart_app(app: AppHandle) -> Result<bool, String> {
@@ -43,7 +357,9 @@ pub async fn set_app_config_dir_override(
     Ok(true)
 }
 
-/// 设置开机自启
+/// 设置开机自启，并将偏好保存到设置中（启动时据此校正系统启动项）
+///
+/// 便携模式下拒绝启用。
 #[tauri::command]
 pub async fn set_auto_launch(enabled: bool) -> Result<bool, String> {
     if enabled {
@@ -51,11 +367,58 @@ pub async fn set_auto_launch(enabled: bool) -> Result<bool, String> {
     } else {
         crate::auto_launch::disable_auto_launch().map_err(|e| format!("禁用开机自启失败: {e}"))?;
     }
+    let mut settings = crate::settings::get_settings();
+    settings.launch_on_startup = enabled;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
     Ok(true)
 }
 
-/// 获取开机自启状态
+/// 查询系统中是否已登记开机自启
 #[tauri::command]
-pub async fn get_auto_launch_status() -> Result<bool, String> {
+pub async fn is_auto_launch_enabled() -> Result<bool, String> {
     crate::auto_launch::is_auto_launch_enabled().map_err(|e| format!("获取开机自启状态失败: {e}"))
 }
+
+/// 获取开机自启状态（旧命令名，等同 `is_auto_launch_enabled`）
+#[tauri::command]
+pub async fn get_auto_launch_status() -> Result<bool, String> {
+    is_auto_launch_enabled().await
+}
+
+/// 清除保存的窗口尺寸与位置，并将主窗口恢复为默认大小居中显示
+#[tauri::command]
+pub async fn reset_window_state(app: AppHandle) -> Result<bool, String> {
+    crate::window_state::reset(&app)?;
+    Ok(true)
+}
+
+/// 立即将尚未写入的设置保存到数据库
+#[tauri::command]
+pub async fn flush_settings(
+    state: tauri::State<'_, crate::store::AppState>,
+) -> Result<bool, String> {
+    state.db.flush_settings().map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_dir_writable_creates_missing_dir() {
+        let temp = tempfile::tempdir().expect("create temp dir");
+        let dir = temp.path().join("nested").join("cache");
+        ensure_dir_writable(&dir).expect("dir should be writable");
+        assert!(dir.is_dir());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0, "probe removed");
+    }
+
+    #[test]
+    fn ensure_dir_writable_rejects_file_path() {
+        let temp = tempfile::tempdir().expect("create temp dir");
+        let file = temp.path().join("file");
+        std::fs::write(&file, b"x").unwrap();
+        assert!(ensure_dir_writable(&file).is_err());
+    }
+}
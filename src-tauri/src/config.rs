@@ -62,17 +62,179 @@ pub fn get_claude_settings_path() -> PathBuf {
     settings
 }
 
-/// 获取应用配置目录路径 (~/.cc-switch)
-pub fn get_app_config_dir() -> PathBuf {
-    if let Some(custom) = crate::app_store::get_app_config_dir_override() {
-        return custom;
+/// 便携模式环境变量名
+pub const PORTABLE_ENV_VAR: &str = "CCSWITCH_PORTABLE";
+
+/// 判断是否以便携模式（绿色版）运行
+///
+/// 依次检查以下信号，任一满足即视为便携模式：
+/// 1. 命令行参数 `--portable`
+/// 2. 环境变量 `CCSWITCH_PORTABLE` 为 `1` / `true`（不区分大小写）
+/// 3. 可执行文件同目录下存在 `portable.ini`
+///
+/// 信号之间是"或"的关系，`CCSWITCH_PORTABLE=0` 不会关闭由 `portable.ini` 开启的便携模式。
+pub fn is_portable_mode() -> bool {
+    if std::env::args_os().skip(1).any(|arg| arg == "--portable") {
+        return true;
+    }
+
+    if let Ok(value) = std::env::var(PORTABLE_ENV_VAR) {
+        let value = value.trim();
+        if value == "1" || value.eq_ignore_ascii_case("true") {
+            return true;
+        }
     }
 
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("portable.ini").is_file()))
+        .unwrap_or(false)
+}
+
+/// 判断 macOS 是否以 App Translocation 方式运行本应用
+///
+/// 从下载目录直接打开、仍带隔离属性的应用会被 Gatekeeper 复制到
+/// `/private/var/folders/.../AppTranslocation/` 下的随机只读路径运行，导致 `portable.ini`
+/// 检测与可执行文件旁的 `data/` 目录失效。其它平台始终返回 false。
+pub fn is_app_translocated() -> bool {
+    cfg!(target_os = "macos") && std::env::current_exe().is_ok_and(|exe| is_translocated_path(&exe))
+}
+
+fn is_translocated_path(path: &Path) -> bool {
+    let in_temp_folders =
+        path.starts_with("/private/var/folders") || path.starts_with("/var/folders");
+    in_temp_folders
+        && path
+            .components()
+            .any(|component| component.as_os_str() == "AppTranslocation")
+}
+
+/// 便携模式下解析出的数据目录（进程内只解析一次）
+static PORTABLE_DATA_DIR: std::sync::OnceLock<Option<PathBuf>> = std::sync::OnceLock::new();
+
+/// 获取应用数据目录
+///
+/// 便携模式下为可执行文件同目录的 `data/`，否则为 `~/.cc-switch`。
+/// 便携目录不可写（如只读挂载）时回退到系统临时目录，并记录初始化错误供前端提示。
+pub fn get_data_dir() -> PathBuf {
+    let portable =
+        PORTABLE_DATA_DIR.get_or_init(|| is_portable_mode().then(resolve_portable_data_dir));
+    if let Some(dir) = portable {
+        return dir.clone();
+    }
+
+    installed_data_dir()
+}
+
+/// 安装模式下的数据目录 `~/.cc-switch`（与当前是否为便携模式无关）
+pub fn installed_data_dir() -> PathBuf {
     dirs::home_dir()
         .expect("无法获取用户主目录")
         .join(".cc-switch")
 }
 
+/// 便携模式下的数据目录：可执行文件同目录的 `data/`（与当前是否为便携模式无关）
+pub fn portable_data_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .parent()
+        .map(|dir| dir.join("data"))
+}
+
+/// 便携实例锁文件句柄（进程存活期间一直持有）
+static PORTABLE_INSTANCE_LOCK: std::sync::OnceLock<fs::File> = std::sync::OnceLock::new();
+
+/// 便携模式下独占数据目录，防止同一便携副本被重复启动
+///
+/// 单实例插件按应用标识区分实例，无法区分不同目录下的便携副本，因此便携模式改用
+/// 数据目录内的文件锁。返回 false 表示已有实例在使用该数据目录；锁文件无法创建时
+/// 不阻止启动。
+pub fn acquire_portable_instance_lock() -> bool {
+    use fs4::fs_std::FileExt;
+
+    let path = get_data_dir().join(".instance.lock");
+    let file = match fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("创建便携实例锁失败 {}: {e}", path.display());
+            return true;
+        }
+    };
+
+    match file.try_lock_exclusive() {
+        Ok(true) => {
+            let _ = PORTABLE_INSTANCE_LOCK.set(file);
+            true
+        }
+        Ok(false) => false,
+        Err(e) => {
+            log::warn!("获取便携实例锁失败 {}: {e}", path.display());
+            true
+        }
+    }
+}
+
+fn resolve_portable_data_dir() -> PathBuf {
+    let fallback = std::env::temp_dir().join("cc-switch-portable");
+
+    let preferred = match std::env::current_exe() {
+        Ok(exe) => match exe.parent() {
+            Some(dir) => dir.join("data"),
+            None => return fallback,
+        },
+        Err(e) => {
+            log::error!(
+                "便携模式获取可执行路径失败，数据目录回退到 {}: {e}",
+                fallback.display()
+            );
+            return fallback;
+        }
+    };
+
+    match ensure_dir_writable(&preferred) {
+        Ok(()) => preferred,
+        Err(e) => {
+            log::error!(
+                "便携模式数据目录不可写: {}，回退到 {}: {e}",
+                preferred.display(),
+                fallback.display()
+            );
+            crate::init_status::set_init_error(crate::init_status::InitErrorPayload {
+                kind: crate::init_status::InitErrorKind::PermissionDenied,
+                path: preferred.to_string_lossy().to_string(),
+                error: format!(
+                    "便携模式数据目录不可写，本次数据将临时保存在 {}（退出后可能丢失）: {e}",
+                    fallback.display()
+                ),
+            });
+            fallback
+        }
+    }
+}
+
+/// 确保目录存在且可写入
+fn ensure_dir_writable(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(".write-test");
+    fs::write(&probe, b"")?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// 获取应用配置目录路径（默认 ~/.cc-switch，便携模式下见 [`get_data_dir`]）
+pub fn get_app_config_dir() -> PathBuf {
+    if let Some(custom) = crate::app_store::get_app_config_dir_override() {
+        return custom;
+    }
+
+    get_data_dir()
+}
+
 /// 获取应用配置文件路径
 pub fn get_app_config_path() -> PathBuf {
     get_app_config_dir().join("config.json")
@@ -100,6 +262,109 @@ pub fn get_provider_config_path(provider_id: &str, provider_name: Option<&str>)
     get_claude_config_dir().join(format!("settings-{base_name}.json"))
 }
 
+/// 配置文件片段每行最多展示的字符数（压缩成一行的 JSON 以出错列为中心截取）
+const SNIPPET_WIDTH: usize = 120;
+
+/// 配置文件 JSON 解析失败的位置与上下文
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigParseError {
+    pub path: String,
+    /// 出错行号（从 1 开始）
+    pub line: usize,
+    /// 出错列号（从 1 开始，按字符计）
+    pub column: usize,
+    /// 不含位置信息的错误描述
+    pub message: String,
+    /// 出错行及其上一行，末行以 `^` 标出出错位置（密钥已遮盖）
+    pub snippet: String,
+}
+
+impl ConfigParseError {
+    pub fn new(path: &Path, content: &str, err: &serde_json::Error) -> Self {
+        let raw = err.to_string();
+        let message = raw
+            .strip_suffix(&format!(" at line {} column {}", err.line(), err.column()))
+            .unwrap_or(&raw)
+            .to_string();
+        let lines: Vec<&str> = content.lines().collect();
+        let error_line = err
+            .line()
+            .checked_sub(1)
+            .and_then(|i| lines.get(i))
+            .copied()
+            .unwrap_or_default();
+        // serde_json 的列号按字节计
+        let byte_col = err.column().saturating_sub(1).min(error_line.len());
+        let column = error_line
+            .get(..byte_col)
+            .map_or(byte_col, |prefix| prefix.chars().count())
+            + 1;
+
+        Self {
+            path: path.display().to_string(),
+            line: err.line(),
+            column,
+            message,
+            snippet: parse_error_snippet(&lines, err.line(), column),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} 第 {} 行第 {} 列: {}",
+            self.path, self.line, self.column, self.message
+        )
+    }
+}
+
+/// 遮盖片段中的密钥：敏感字段的字符串值整体替换，其余按常见密钥格式匹配
+fn redact_snippet_line(line: &str) -> String {
+    static KEY_VALUE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r#""([^"\\]+)"(\s*:\s*)"[^"]*("?)"#).unwrap()
+    });
+    let masked = KEY_VALUE.replace_all(line, |caps: &regex::Captures| {
+        if crate::secret_store::is_secret_key(&caps[1]) {
+            format!("\"{}\"{}\"****{}", &caps[1], &caps[2], &caps[3])
+        } else {
+            caps[0].to_string()
+        }
+    });
+    crate::secret_store::redact_secrets(&masked)
+}
+
+fn parse_error_snippet(lines: &[&str], line: usize, column: usize) -> String {
+    let Some(error_line) = line.checked_sub(1).and_then(|i| lines.get(i)) else {
+        return String::new();
+    };
+    let start = column.saturating_sub(1 + SNIPPET_WIDTH / 2);
+    let window = |text: &str| -> String { text.chars().skip(start).take(SNIPPET_WIDTH).collect() };
+
+    let mut snippet = String::new();
+    for n in line.saturating_sub(1).max(1)..=line {
+        let text = redact_snippet_line(lines[n - 1]);
+        snippet.push_str(&format!("{n:>5} | {}\n", window(&text)));
+    }
+    let prefix: String = error_line.chars().take(column - 1).collect();
+    let caret = redact_snippet_line(&prefix)
+        .chars()
+        .count()
+        .saturating_sub(start);
+    snippet.push_str(&format!("{:>5} | {}^", "", " ".repeat(caret)));
+    snippet
+}
+
+/// 解析 JSON 配置内容，失败时返回带行列与上下文片段的 [`AppError::ConfigParse`]
+pub fn parse_json_content<T: for<'a> Deserialize<'a>>(
+    path: &Path,
+    content: &str,
+) -> Result<T, AppError> {
+    serde_json::from_str(content).map_err(|e| AppError::config_parse(path, content, &e))
+}
+
 /// 读取 JSON 配置文件
 pub fn read_json_file<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, AppError> {
     if !path.exists() {
@@ -108,7 +373,7 @@ pub fn read_json_file<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, AppE
 
     let content = fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
 
-    serde_json::from_str(&content).map_err(|e| AppError::json(path, e))
+    parse_json_content(path, &content)
 }
 
 /// 写入 JSON 配置文件
@@ -132,6 +397,32 @@ pub fn write_text_file(path: &Path, data: &str) -> Result<(), AppError> {
     atomic_write(path, data.as_bytes())
 }
 
+/// 将配置文件当前内容复制为同目录下的 `<文件名>.bak`
+///
+/// 返回备份文件路径（目标文件不存在时为 `None`）。
+pub fn backup_config_file(path: &Path) -> Result<Option<PathBuf>, AppError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| AppError::Config("无效的文件名".to_string()))?
+        .to_string_lossy()
+        .to_string();
+    let backup_path = path.with_file_name(format!("{file_name}.bak"));
+    copy_file(path, &backup_path)?;
+    Ok(Some(backup_path))
+}
+
+/// 原子写入 CLI 配置文件，并将旧内容保留为同目录下的 `<文件名>.bak`
+///
+/// 返回备份文件路径（目标文件原本不存在时为 `None`），供前端提供撤销操作。
+pub fn write_config_atomic(path: &Path, contents: &[u8]) -> Result<Option<PathBuf>, AppError> {
+    let backup_path = backup_config_file(path)?;
+    atomic_write(path, contents)?;
+    Ok(backup_path)
+}
+
 /// 原子写入：写入临时文件后 rename 替换，避免半写状态
 pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), AppError> {
     if let Some(parent) = path.parent() {
@@ -157,6 +448,8 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), AppError> {
         let mut f = fs::File::create(&tmp).map_err(|e| AppError::io(&tmp, e))?;
         f.write_all(data).map_err(|e| AppError::io(&tmp, e))?;
         f.flush().map_err(|e| AppError::io(&tmp, e))?;
+        // 落盘后再 rename，避免崩溃/断电后留下空文件
+        f.sync_all().map_err(|e| AppError::io(&tmp, e))?;
     }
 
     #[cfg(unix)]
@@ -187,6 +480,9 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), AppError> {
             source: e,
         })?;
     }
+
+    // 记录自身写入，避免配置监听把它当作外部修改
+    crate::services::config_watcher::note_self_write(path, data);
     Ok(())
 }
 
@@ -194,6 +490,52 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), AppError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn detects_translocated_executable_paths() {
+        assert!(is_translocated_path(Path::new(
+            "/private/var/folders/xy/abc123/T/AppTranslocation/0F1E-2D3C/d/CC Switch.app/Contents/MacOS/cc-switch"
+        )));
+        assert!(!is_translocated_path(Path::new(
+            "/Applications/CC Switch.app/Contents/MacOS/cc-switch"
+        )));
+        assert!(!is_translocated_path(Path::new(
+            "/Users/u/AppTranslocation/CC Switch.app/Contents/MacOS/cc-switch"
+        )));
+    }
+
+    #[test]
+    fn parse_error_reports_position_and_masks_secrets() {
+        let content = "{\n  \"env\": {\n    \"ANTHROPIC_AUTH_TOKEN\": \"short-secret\",\n    \"ANTHROPIC_MODEL\": \"opus\"\n    \"X\": 1\n  }\n}";
+        let err = parse_json_content::<serde_json::Value>(Path::new("settings.json"), content)
+            .expect_err("missing comma");
+        let AppError::ConfigParse(parsed) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!((parsed.line, parsed.column), (5, 5));
+        assert!(!parsed.message.contains("line"), "{}", parsed.message);
+        assert!(parsed
+            .snippet
+            .contains("    4 |     \"ANTHROPIC_MODEL\": \"opus\""));
+        assert!(
+            parsed.snippet.ends_with("      |     ^"),
+            "{}",
+            parsed.snippet
+        );
+
+        let content = "{\"ANTHROPIC_AUTH_TOKEN\": \"short-secret\" \"a\": 1}";
+        let err = parse_json_content::<serde_json::Value>(Path::new("s.json"), content)
+            .expect_err("missing comma");
+        let AppError::ConfigParse(parsed) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert!(
+            !parsed.snippet.contains("short-secret"),
+            "{}",
+            parsed.snippet
+        );
+        assert!(parsed.to_string().contains("第 1 行第 41 列"), "{parsed}");
+    }
+
     #[test]
     fn derive_mcp_path_from_override_preserves_folder_name() {
         let override_dir = PathBuf::from("/tmp/profile/.claude");
@@ -218,6 +560,22 @@ mod tests {
         assert_eq!(derived, PathBuf::from("claude.json"));
     }
 
+    #[test]
+    fn write_config_atomic_keeps_previous_contents_as_bak() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("settings.json");
+
+        let first = write_config_atomic(&path, b"{\"v\":1}").expect("first write");
+        assert!(first.is_none(), "no backup when target did not exist");
+
+        let backup = write_config_atomic(&path, b"{\"v\":2}")
+            .expect("second write")
+            .expect("backup path");
+        assert_eq!(backup, dir.path().join("settings.json.bak"));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{\"v\":1}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"v\":2}");
+    }
+
     #[test]
     fn derive_mcp_path_from_root_like_dir_returns_none() {
         let override_dir = PathBuf::from("/");
@@ -28,6 +28,56 @@ impl Database {
         crate::config::atomic_write(target_path, dump.as_bytes())
     }
 
+    /// 导出去除密钥的 SQL 文本（用于诊断包，不可用于恢复）
+    ///
+    /// 供应商配置、MCP 服务器配置、live 备份与配置档案快照中的敏感字段替换为占位符，
+    /// 其余文本中形似密钥的内容同样被遮盖。
+    pub fn export_sql_redacted(&self) -> Result<String, AppError> {
+        const JSON_COLUMNS: [(&str, &str); 5] = [
+            ("providers", "settings_config"),
+            ("providers", "env"),
+            ("mcp_servers", "server_config"),
+            ("proxy_live_backup", "original_config"),
+            ("profiles", "snapshot"),
+        ];
+
+        let snapshot = self.snapshot_to_memory()?;
+        for (table, column) in JSON_COLUMNS {
+            let rows: Vec<(i64, String)> = {
+                let mut stmt = snapshot
+                    .prepare(&format!(
+                        "SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL"
+                    ))
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                let iter = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+                iter.collect::<Result<_, _>>()
+                    .map_err(|e| AppError::Database(e.to_string()))?
+            };
+
+            for (rowid, raw) in rows {
+                let redacted = match serde_json::from_str::<serde_json::Value>(&raw) {
+                    Ok(mut value) => {
+                        crate::secret_store::redact_json_secrets(&mut value);
+                        serde_json::to_string(&value)
+                            .map_err(|e| AppError::JsonSerialize { source: e })?
+                    }
+                    Err(_) => crate::secret_store::redact_secrets(&raw),
+                };
+                snapshot
+                    .execute(
+                        &format!("UPDATE {table} SET {column} = ?1 WHERE rowid = ?2"),
+                        rusqlite::params![redacted, rowid],
+                    )
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+        }
+
+        let dump = Self::dump_sql(&snapshot)?;
+        Ok(crate::secret_store::redact_secrets(&dump))
+    }
+
     /// 从 SQL 文件导入，返回生成的备份 ID（若无备份则为空字符串）
     pub fn import_sql(&self, source_path: &Path) -> Result<String, AppError> {
         if !source_path.exists() {
@@ -81,6 +131,7 @@ impl Database {
 
     /// 创建内存快照以避免长时间持有数据库锁
     pub(crate) fn snapshot_to_memory(&self) -> Result<Connection, AppError> {
+        self.flush_settings()?;
         let conn = lock_conn!(self.conn);
         let mut snapshot =
             Connection::open_in_memory().map_err(|e| AppError::Database(e.to_string()))?;
@@ -110,11 +161,15 @@ impl Database {
     }
 
     /// 生成一致性快照备份，返回备份文件路径（不存在主库时返回 None）
-    fn backup_database_file(&self) -> Result<Option<PathBuf>, AppError> {
+    pub(crate) fn backup_database_file(&self) -> Result<Option<PathBuf>, AppError> {
         let db_path = get_app_config_dir().join("cc-switch.db");
         if !db_path.exists() {
             return Ok(None);
         }
+        // 数据库损坏后以内存数据库降级运行时，不能让空快照成为“最新备份”
+        if lock_conn!(self.conn).path().is_none_or(str::is_empty) {
+            return Ok(None);
+        }
 
         let backup_dir = db_path
             .parent()
@@ -133,6 +188,7 @@ impl Database {
             counter += 1;
         }
 
+        self.flush_settings()?;
         {
             let conn = lock_conn!(self.conn);
             let mut dest_conn =
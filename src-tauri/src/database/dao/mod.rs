@@ -4,6 +4,8 @@
 
 pub mod failover;
 pub mod mcp;
+pub mod npm_cache;
+pub mod profiles;
 pub mod prompts;
 pub mod providers;
 pub mod proxy;
@@ -14,3 +16,4 @@ pub mod stream_check;
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
 // 导出 FailoverQueueItem 供外部使用
 pub use failover::FailoverQueueItem;
+pub use profiles::{AppProfileSnapshot, Profile, ProfileSnapshot, DEFAULT_PROFILE_ID};
@@ -0,0 +1,68 @@
+//! npm 最新版本缓存 DAO
+//!
+//! 将 `dist-tags.latest` 查询结果缓存到 settings 表，减少对 npm registry 的重复请求。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// npm 最新版本缓存条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NpmVersionCacheEntry {
+    pub version: String,
+    /// 缓存写入时间（Unix 秒）
+    pub fetched_at: i64,
+}
+
+/// 缓存条目在 settings 表中的 key 前缀
+const CACHE_KEY_PREFIX: &str = "npm_latest_version_";
+
+fn cache_key(package: &str) -> String {
+    format!("{CACHE_KEY_PREFIX}{package}")
+}
+
+impl Database {
+    /// 获取指定 npm 包的缓存版本
+    pub fn get_npm_version_cache(
+        &self,
+        package: &str,
+    ) -> Result<Option<NpmVersionCacheEntry>, AppError> {
+        match self.get_setting(&cache_key(package))? {
+            Some(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| AppError::Message(format!("解析 npm 版本缓存失败: {e}"))),
+            None => Ok(None),
+        }
+    }
+
+    /// 写入指定 npm 包的最新版本缓存（时间戳取当前时间）
+    pub fn set_npm_version_cache(&self, package: &str, version: &str) -> Result<(), AppError> {
+        let entry = NpmVersionCacheEntry {
+            version: version.to_string(),
+            fetched_at: chrono::Utc::now().timestamp(),
+        };
+        let json = serde_json::to_string(&entry)
+            .map_err(|e| AppError::Message(format!("序列化 npm 版本缓存失败: {e}")))?;
+        self.set_setting(&cache_key(package), &json)
+    }
+
+    /// 删除 npm 版本缓存（`package` 为空时删除全部），返回删除的条目数
+    pub fn clear_npm_version_cache(&self, package: Option<&str>) -> Result<usize, AppError> {
+        self.flush_settings()?;
+        let conn = lock_conn!(self.conn);
+        match package {
+            Some(package) => conn.execute(
+                "DELETE FROM settings WHERE key = ?1",
+                params![cache_key(package)],
+            ),
+            // 按前缀精确比较，LIKE 会把 `_` 当作通配符
+            None => conn.execute(
+                "DELETE FROM settings WHERE substr(key, 1, length(?1)) = ?1",
+                params![CACHE_KEY_PREFIX],
+            ),
+        }
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
@@ -0,0 +1,173 @@
+//! 配置档案 DAO
+//!
+//! 当前档案的供应商直接存放在 providers 表；其它档案的供应商与各应用的当前选择
+//! 以 JSON 快照（敏感字段加密）保存在 profiles 表，切换档案时两者互换。
+
+use std::collections::BTreeMap;
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{begin_write, lock_conn, Database};
+use crate::error::AppError;
+use crate::provider::Provider;
+
+/// 默认档案 ID（升级前的数据归入该档案，不可删除）
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// 配置档案
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    /// 是否为当前档案
+    pub active: bool,
+}
+
+/// 单个应用在档案中的供应商与当前选择
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProfileSnapshot {
+    pub current: Option<String>,
+    pub providers: Vec<Provider>,
+}
+
+/// 非当前档案的快照，按应用类型（`claude` / `codex` / `gemini`）索引
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    pub apps: BTreeMap<String, AppProfileSnapshot>,
+}
+
+fn encode_snapshot(snapshot: &ProfileSnapshot) -> Result<String, AppError> {
+    let value =
+        serde_json::to_value(snapshot).map_err(|e| AppError::JsonSerialize { source: e })?;
+    serde_json::to_string(&crate::secret_store::encrypt_secrets(&value))
+        .map_err(|e| AppError::JsonSerialize { source: e })
+}
+
+fn decode_snapshot(raw: Option<&str>) -> ProfileSnapshot {
+    let Some(raw) = raw else {
+        return ProfileSnapshot::default();
+    };
+    let mut value = serde_json::from_str(raw).unwrap_or(serde_json::Value::Null);
+    crate::secret_store::decrypt_secrets(&mut value);
+    serde_json::from_value(value).unwrap_or_default()
+}
+
+impl Database {
+    /// 获取所有档案（默认档案在前，其余按创建时间排序）
+    pub fn list_profiles(&self) -> Result<Vec<Profile>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, created_at, is_active FROM profiles
+                 ORDER BY id != ?1, created_at ASC, id ASC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![DEFAULT_PROFILE_ID], |row| {
+                Ok(Profile {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    created_at: row.get(2)?,
+                    active: row.get(3)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 新建空档案（不切换）
+    pub fn create_profile(&self, profile: &Profile) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO profiles (id, name, created_at, is_active, snapshot)
+             VALUES (?1, ?2, ?3, 0, ?4)",
+            params![
+                profile.id,
+                profile.name,
+                profile.created_at,
+                encode_snapshot(&ProfileSnapshot::default())?
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 删除非当前档案，返回是否删除了记录
+    pub fn delete_profile(&self, id: &str) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let affected = conn
+            .execute(
+                "DELETE FROM profiles WHERE id = ?1 AND is_active = 0",
+                params![id],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(affected > 0)
+    }
+
+    /// 切换档案：将 `outgoing`（当前档案的数据）存为快照，并用目标档案的快照替换
+    /// providers 表，返回目标档案的快照。全部在同一事务中完成。
+    pub fn swap_profile(
+        &self,
+        outgoing: &ProfileSnapshot,
+        target_id: &str,
+    ) -> Result<ProfileSnapshot, AppError> {
+        crate::secret_store::ensure_unlocked()?;
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+
+        let target: Option<(bool, Option<String>)> = tx
+            .query_row(
+                "SELECT is_active, snapshot FROM profiles WHERE id = ?1",
+                params![target_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let Some((is_active, raw)) = target else {
+            return Err(AppError::Message(format!("配置档案 {target_id} 不存在")));
+        };
+        if is_active {
+            return Err(AppError::Message(format!(
+                "配置档案 {target_id} 已是当前档案"
+            )));
+        }
+        let incoming = decode_snapshot(raw.as_deref());
+
+        tx.execute(
+            "UPDATE profiles SET is_active = 0, snapshot = ?1 WHERE is_active = 1",
+            params![encode_snapshot(outgoing)?],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.execute("DELETE FROM provider_endpoints", [])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        tx.execute("DELETE FROM providers", [])
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        for (app_type, app) in &incoming.apps {
+            for provider in &app.providers {
+                Self::save_provider_in_tx(&tx, app_type, provider)?;
+            }
+            if let Some(current) = &app.current {
+                tx.execute(
+                    "UPDATE providers SET is_current = 1 WHERE id = ?1 AND app_type = ?2",
+                    params![current, app_type],
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+        }
+
+        tx.execute(
+            "UPDATE profiles SET is_active = 1, snapshot = NULL WHERE id = ?1",
+            params![target_id],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(incoming)
+    }
+}
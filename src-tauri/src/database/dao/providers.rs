@@ -2,7 +2,7 @@
 //!
 //! 提供供应商（Provider）的 CRUD 操作。
 
-use crate::database::{lock_conn, Database};
+use crate::database::{begin_write, lock_conn, Database};
 use crate::error::AppError;
 use crate::provider::{Provider, ProviderMeta};
 use indexmap::IndexMap;
@@ -17,7 +17,7 @@ impl Database {
     ) -> Result<IndexMap<String, Provider>, AppError> {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn.prepare(
-            "SELECT id, name, settings_config, website_url, category, created_at, sort_index, notes, icon, icon_color, meta, in_failover_queue
+            "SELECT id, name, settings_config, website_url, category, created_at, sort_index, notes, icon, icon_color, meta, in_failover_queue, env, model, base_url, tags, last_used_at, use_count
              FROM providers WHERE app_type = ?1
              ORDER BY COALESCE(sort_index, 999999), created_at ASC, id ASC"
         ).map_err(|e| AppError::Database(e.to_string()))?;
@@ -36,9 +36,16 @@ impl Database {
                 let icon_color: Option<String> = row.get(9)?;
                 let meta_str: String = row.get(10)?;
                 let in_failover_queue: bool = row.get(11)?;
-
-                let settings_config =
+                let env_str: String = row.get(12)?;
+                let model: Option<String> = row.get(13)?;
+                let base_url: Option<String> = row.get(14)?;
+                let tags_str: String = row.get(15)?;
+                let last_used_at: Option<i64> = row.get(16)?;
+                let use_count: u64 = row.get(17)?;
+
+                let mut settings_config =
                     serde_json::from_str(&settings_config_str).unwrap_or(serde_json::Value::Null);
+                crate::secret_store::decrypt_secrets(&mut settings_config);
                 let meta: ProviderMeta = serde_json::from_str(&meta_str).unwrap_or_default();
 
                 Ok((
@@ -56,6 +63,12 @@ impl Database {
                         icon,
                         icon_color,
                         in_failover_queue,
+                        env: decode_env(&env_str),
+                        model,
+                        base_url,
+                        tags: decode_tags(&tags_str),
+                        last_used_at,
+                        use_count,
                     },
                 ))
             })
@@ -131,7 +144,7 @@ impl Database {
     ) -> Result<Option<Provider>, AppError> {
         let conn = lock_conn!(self.conn);
         let result = conn.query_row(
-            "SELECT name, settings_config, website_url, category, created_at, sort_index, notes, icon, icon_color, meta, in_failover_queue
+            "SELECT name, settings_config, website_url, category, created_at, sort_index, notes, icon, icon_color, meta, in_failover_queue, env, model, base_url, tags, last_used_at, use_count
              FROM providers WHERE id = ?1 AND app_type = ?2",
             params![id, app_type],
             |row| {
@@ -146,8 +159,15 @@ impl Database {
                 let icon_color: Option<String> = row.get(8)?;
                 let meta_str: String = row.get(9)?;
                 let in_failover_queue: bool = row.get(10)?;
-
-                let settings_config = serde_json::from_str(&settings_config_str).unwrap_or(serde_json::Value::Null);
+                let env_str: String = row.get(11)?;
+                let model: Option<String> = row.get(12)?;
+                let base_url: Option<String> = row.get(13)?;
+                let tags_str: String = row.get(14)?;
+                let last_used_at: Option<i64> = row.get(15)?;
+                let use_count: u64 = row.get(16)?;
+
+                let mut settings_config = serde_json::from_str(&settings_config_str).unwrap_or(serde_json::Value::Null);
+                crate::secret_store::decrypt_secrets(&mut settings_config);
                 let meta: ProviderMeta = serde_json::from_str(&meta_str).unwrap_or_default();
 
                 Ok(Provider {
@@ -163,6 +183,12 @@ impl Database {
                     icon,
                     icon_color,
                     in_failover_queue,
+                    env: decode_env(&env_str),
+                    model,
+                    base_url,
+                    tags: decode_tags(&tags_str),
+                    last_used_at,
+                    use_count,
                 })
             },
         );
@@ -179,11 +205,31 @@ impl Database {
     /// 注意：更新模式下不同步 endpoints，因为编辑模式下端点通过单独的 API 管理
     /// （add_custom_endpoint / remove_custom_endpoint），避免覆盖用户的修改。
     pub fn save_provider(&self, app_type: &str, provider: &Provider) -> Result<(), AppError> {
+        crate::secret_store::ensure_unlocked()?;
         let mut conn = lock_conn!(self.conn);
-        let tx = conn
-            .transaction()
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let tx = begin_write(&mut conn)?;
+        Self::save_provider_in_tx(&tx, app_type, provider)?;
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 在同一事务中批量保存供应商（`(app_type, provider)`），任一失败则全部回滚
+    pub fn save_providers(&self, providers: &[(String, Provider)]) -> Result<(), AppError> {
+        crate::secret_store::ensure_unlocked()?;
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+        for (app_type, provider) in providers {
+            Self::save_provider_in_tx(&tx, app_type, provider)?;
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
 
+    pub(super) fn save_provider_in_tx(
+        tx: &rusqlite::Transaction<'_>,
+        app_type: &str,
+        provider: &Provider,
+    ) -> Result<(), AppError> {
         // 处理 meta：取出 endpoints 以便单独处理
         let mut meta_clone = provider.meta.clone().unwrap_or_default();
         let endpoints = std::mem::take(&mut meta_clone.custom_endpoints);
@@ -216,11 +262,18 @@ impl Database {
                     icon_color = ?9,
                     meta = ?10,
                     is_current = ?11,
-                    in_failover_queue = ?12
-                WHERE id = ?13 AND app_type = ?14",
+                    in_failover_queue = ?12,
+                    env = ?13,
+                    model = ?14,
+                    base_url = ?15,
+                    tags = ?16
+                WHERE id = ?17 AND app_type = ?18",
                 params![
                     provider.name,
-                    serde_json::to_string(&provider.settings_config).unwrap(),
+                    serde_json::to_string(&crate::secret_store::encrypt_secrets(
+                        &provider.settings_config
+                    ))
+                    .unwrap(),
                     provider.website_url,
                     provider.category,
                     provider.created_at,
@@ -231,6 +284,10 @@ impl Database {
                     serde_json::to_string(&meta_clone).unwrap(),
                     is_current,
                     in_failover_queue,
+                    encode_env(&provider.env),
+                    provider.model,
+                    provider.base_url,
+                    encode_tags(&provider.tags),
                     provider.id,
                     app_type,
                 ],
@@ -241,13 +298,15 @@ impl Database {
             tx.execute(
                 "INSERT INTO providers (
                     id, app_type, name, settings_config, website_url, category,
-                    created_at, sort_index, notes, icon, icon_color, meta, is_current, in_failover_queue
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                    created_at, sort_index, notes, icon, icon_color, meta, is_current, in_failover_queue, env,
+                    model, base_url, tags, last_used_at, use_count
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
                 params![
                     provider.id,
                     app_type,
                     provider.name,
-                    serde_json::to_string(&provider.settings_config).unwrap(),
+                    serde_json::to_string(&crate::secret_store::encrypt_secrets(&provider.settings_config))
+                        .unwrap(),
                     provider.website_url,
                     provider.category,
                     provider.created_at,
@@ -258,6 +317,12 @@ impl Database {
                     serde_json::to_string(&meta_clone).unwrap(),
                     is_current,
                     in_failover_queue,
+                    encode_env(&provider.env),
+                    provider.model,
+                    provider.base_url,
+                    encode_tags(&provider.tags),
+                    provider.last_used_at,
+                    provider.use_count,
                 ],
             )
             .map_err(|e| AppError::Database(e.to_string()))?;
@@ -273,7 +338,6 @@ impl Database {
             }
         }
 
-        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
         Ok(())
     }
 
@@ -288,6 +352,113 @@ impl Database {
         Ok(())
     }
 
+    /// 将库中仍为明文的 API Key 加密（首次启用加密时执行，执行前备份数据库）
+    ///
+    /// 返回被加密的供应商数量；系统密钥服务不可用时不做任何修改。
+    pub fn encrypt_plaintext_secrets(&self) -> Result<usize, AppError> {
+        if !crate::secret_store::is_keystore_available() || crate::secret_store::is_locked() {
+            return Ok(0);
+        }
+
+        let pending: Vec<(String, String, serde_json::Value)> = {
+            let conn = lock_conn!(self.conn);
+            let mut stmt = conn
+                .prepare("SELECT id, app_type, settings_config FROM providers")
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })
+                .map_err(|e| AppError::Database(e.to_string()))?;
+
+            let mut pending = Vec::new();
+            for row in rows {
+                let (id, app_type, raw) = row.map_err(|e| AppError::Database(e.to_string()))?;
+                let Ok(value) = serde_json::from_str::<serde_json::Value>(&raw) else {
+                    continue;
+                };
+                if crate::secret_store::contains_plaintext_secrets(&value) {
+                    pending.push((id, app_type, value));
+                }
+            }
+            pending
+        };
+
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(backup) = self.backup_database_file()? {
+            log::info!("加密 API Key 前已备份数据库: {}", backup.display());
+        }
+
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+        for (id, app_type, value) in &pending {
+            tx.execute(
+                "UPDATE providers SET settings_config = ?1 WHERE id = ?2 AND app_type = ?3",
+                params![
+                    serde_json::to_string(&crate::secret_store::encrypt_secrets(value)).unwrap(),
+                    id,
+                    app_type
+                ],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(pending.len())
+    }
+
+    /// 按给定 ID 顺序重排供应商（单个事务内写入 sort_index）
+    ///
+    /// 未出现在 `ordered_ids` 中的供应商保持原有相对顺序并追加到末尾，
+    /// 不存在的 ID 会被忽略。返回最终顺序。
+    pub fn reorder_providers(
+        &self,
+        app_type: &str,
+        ordered_ids: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+
+        let existing: Vec<String> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT id FROM providers WHERE app_type = ?1
+                     ORDER BY COALESCE(sort_index, 999999), created_at ASC, id ASC",
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            let rows = stmt
+                .query_map(params![app_type], |row| row.get(0))
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| AppError::Database(e.to_string()))?
+        };
+
+        let mut final_order: Vec<String> = Vec::with_capacity(existing.len());
+        for id in ordered_ids.iter().chain(existing.iter()) {
+            if existing.contains(id) && !final_order.contains(id) {
+                final_order.push(id.clone());
+            }
+        }
+
+        for (index, id) in final_order.iter().enumerate() {
+            tx.execute(
+                "UPDATE providers SET sort_index = ?1 WHERE id = ?2 AND app_type = ?3",
+                params![index as i64, id, app_type],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(final_order)
+    }
+
     /// 删除指定应用的所有供应商
     pub fn delete_providers_by_app_type(&self, app_type: &str) -> Result<(), AppError> {
         let conn = lock_conn!(self.conn);
@@ -302,9 +473,7 @@ impl Database {
     /// 设置当前供应商
     pub fn set_current_provider(&self, app_type: &str, id: &str) -> Result<(), AppError> {
         let mut conn = lock_conn!(self.conn);
-        let tx = conn
-            .transaction()
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let tx = begin_write(&mut conn)?;
 
         // 重置所有为 0
         tx.execute(
@@ -324,6 +493,36 @@ impl Database {
         Ok(())
     }
 
+    /// 切换到供应商：设为当前供应商，并记录最近使用时间与使用次数（同一事务）
+    pub fn record_provider_switch(&self, app_type: &str, id: &str) -> Result<(), AppError> {
+        self.record_provider_switches(&[(app_type, id)])
+    }
+
+    /// 同时切换多个应用的当前供应商（`(app_type, id)` 列表），全部在同一事务中提交
+    pub fn record_provider_switches(&self, switches: &[(&str, &str)]) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        for (app_type, id) in switches {
+            tx.execute(
+                "UPDATE providers SET is_current = 0 WHERE app_type = ?1",
+                params![app_type],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+            tx.execute(
+                "UPDATE providers
+                 SET is_current = 1, last_used_at = ?3, use_count = use_count + 1
+                 WHERE id = ?1 AND app_type = ?2",
+                params![id, app_type, now],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     /// 更新供应商的 settings_config（仅更新配置，不改变其他字段）
     pub fn update_provider_settings_config(
         &self,
@@ -331,11 +530,13 @@ impl Database {
         provider_id: &str,
         settings_config: &serde_json::Value,
     ) -> Result<(), AppError> {
+        crate::secret_store::ensure_unlocked()?;
         let conn = lock_conn!(self.conn);
         conn.execute(
             "UPDATE providers SET settings_config = ?1 WHERE id = ?2 AND app_type = ?3",
             params![
-                serde_json::to_string(settings_config).unwrap(),
+                serde_json::to_string(&crate::secret_store::encrypt_secrets(settings_config))
+                    .unwrap(),
                 provider_id,
                 app_type
             ],
@@ -376,3 +577,24 @@ impl Database {
         Ok(())
     }
 }
+
+/// 序列化供应商环境变量，敏感变量（如 `*_TOKEN`）按 settings_config 同样的规则加密
+fn encode_env(env: &HashMap<String, String>) -> String {
+    let value = serde_json::to_value(env).unwrap_or_default();
+    serde_json::to_string(&crate::secret_store::encrypt_secrets(&value))
+        .unwrap_or_else(|_| "{}".to_string())
+}
+
+fn encode_tags(tags: &[String]) -> String {
+    serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn decode_tags(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+fn decode_env(raw: &str) -> HashMap<String, String> {
+    let mut value = serde_json::from_str(raw).unwrap_or(serde_json::Value::Null);
+    crate::secret_store::decrypt_secrets(&mut value);
+    serde_json::from_value(value).unwrap_or_default()
+}
@@ -1,14 +1,71 @@
 //! 通用设置数据访问对象
 //!
 //! 提供键值对形式的通用设置存储。
+//!
+//! 启动后台写入线程后（见 `start_settings_flusher`），`set_setting` 只更新内存，
+//! 在 500ms 内没有新的写入时批量写入 SQLite；退出时通过 `flush_settings` 立即写入。
 
-use crate::database::{lock_conn, Database};
+use crate::database::{begin_write, lock_conn, Database};
 use crate::error::AppError;
 use rusqlite::params;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// 设置写入的合并窗口
+const SETTINGS_FLUSH_DEBOUNCE: Duration = Duration::from_millis(500);
 
 impl Database {
+    /// 启动设置的后台延迟写入线程
+    ///
+    /// 线程只持有弱引用，数据库释放后自动退出。
+    pub fn start_settings_flusher(db: &Arc<Database>) -> Result<(), AppError> {
+        let (tx, rx) = mpsc::channel::<()>();
+        let weak = Arc::downgrade(db);
+        std::thread::Builder::new()
+            .name("settings-flusher".to_string())
+            .spawn(move || run_settings_flusher(weak, rx))
+            .map_err(|e| AppError::Message(format!("启动设置写入线程失败: {e}")))?;
+        *lock_conn!(db.settings_flush_tx) = Some(tx);
+        Ok(())
+    }
+
+    /// 将内存中尚未写入的设置批量写入 settings 表
+    pub fn flush_settings(&self) -> Result<(), AppError> {
+        let pending = std::mem::take(&mut *lock_conn!(self.pending_settings));
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let result = (|| {
+            let mut conn = lock_conn!(self.conn);
+            let tx = begin_write(&mut conn)?;
+            for (key, value) in &pending {
+                tx.execute(
+                    "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+            tx.commit().map_err(|e| AppError::Database(e.to_string()))
+        })();
+
+        if result.is_err() {
+            // 写入失败时放回内存，期间的新值优先
+            let mut current = lock_conn!(self.pending_settings);
+            for (key, value) in pending {
+                current.entry(key).or_insert(value);
+            }
+        }
+        result
+    }
+
     /// 获取设置值
     pub fn get_setting(&self, key: &str) -> Result<Option<String>, AppError> {
+        if let Some(value) = lock_conn!(self.pending_settings).get(key) {
+            return Ok(Some(value.clone()));
+        }
+
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare("SELECT value FROM settings WHERE key = ?1")
@@ -28,7 +85,17 @@ impl Database {
     }
 
     /// 设置值
+    ///
+    /// 后台写入线程已启动时仅更新内存并安排延迟写入，否则立即写入。
     pub fn set_setting(&self, key: &str, value: &str) -> Result<(), AppError> {
+        if let Some(tx) = lock_conn!(self.settings_flush_tx).as_ref() {
+            lock_conn!(self.pending_settings).insert(key.to_string(), value.to_string());
+            if tx.send(()).is_ok() {
+                return Ok(());
+            }
+        }
+
+        self.flush_settings()?;
         let conn = lock_conn!(self.conn);
         conn.execute(
             "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
@@ -56,6 +123,7 @@ impl Database {
             self.set_setting(&key, &value)
         } else {
             // 如果为 None 则删除
+            lock_conn!(self.pending_settings).remove(&key);
             let conn = lock_conn!(self.conn);
             conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
                 .map_err(|e| AppError::Database(e.to_string()))?;
@@ -92,6 +160,7 @@ impl Database {
 
     /// 检查是否有任一应用开启了代理接管
     pub fn has_any_proxy_takeover(&self) -> Result<bool, AppError> {
+        self.flush_settings()?;
         let conn = lock_conn!(self.conn);
         let count: i64 = conn
             .query_row(
@@ -105,6 +174,7 @@ impl Database {
 
     /// 清除所有代理接管状态（将所有 proxy_takeover_* 设置为 false）
     pub fn clear_all_proxy_takeover(&self) -> Result<(), AppError> {
+        self.flush_settings()?;
         let conn = lock_conn!(self.conn);
         conn.execute(
             "UPDATE settings SET value = 'false' WHERE key LIKE 'proxy_takeover_%'",
@@ -115,3 +185,25 @@ impl Database {
         Ok(())
     }
 }
+
+/// 后台写入循环：收到写入通知后等待合并窗口内不再有新写入，再批量写入
+fn run_settings_flusher(db: Weak<Database>, rx: mpsc::Receiver<()>) {
+    while rx.recv().is_ok() {
+        let disconnected = loop {
+            match rx.recv_timeout(SETTINGS_FLUSH_DEBOUNCE) {
+                Ok(()) => continue,
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+        };
+        let Some(db) = db.upgrade() else {
+            return;
+        };
+        if let Err(e) = db.flush_settings() {
+            log::warn!("写入设置失败，将在下次写入时重试: {e}");
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
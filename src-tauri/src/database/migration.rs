@@ -2,7 +2,7 @@
 //!
 //! 将旧版 config.json (MultiAppConfig) 数据迁移到 SQLite 数据库。
 
-use super::{lock_conn, to_json_string, Database};
+use super::{begin_write, lock_conn, to_json_string, Database};
 use crate::app_config::MultiAppConfig;
 use crate::error::AppError;
 use rusqlite::{params, Connection};
@@ -11,9 +11,7 @@ impl Database {
     /// 从 MultiAppConfig 迁移数据到数据库
     pub fn migrate_from_json(&self, config: &MultiAppConfig) -> Result<(), AppError> {
         let mut conn = lock_conn!(self.conn);
-        let tx = conn
-            .transaction()
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let tx = begin_write(&mut conn)?;
 
         Self::migrate_from_json_tx(&tx, config)?;
 
@@ -22,6 +20,55 @@ impl Database {
         Ok(())
     }
 
+    /// 手动重新迁移：先清空供应商、MCP、提示词与 Skills 数据，再从 JSON 重新导入
+    ///
+    /// 清空与导入在同一事务中执行，任一步失败都会回滚，原有数据保持不变。
+    pub fn remigrate_from_json(&self, config: &MultiAppConfig) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = begin_write(&mut conn)?;
+
+        for table in [
+            "provider_endpoints",
+            "providers",
+            "mcp_servers",
+            "prompts",
+            "skills",
+        ] {
+            tx.execute(&format!("DELETE FROM {table}"), [])
+                .map_err(|e| AppError::Database(format!("Clear {table} failed: {e}")))?;
+        }
+        Self::migrate_from_json_tx(&tx, config)?;
+
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("Commit migration failed: {e}")))?;
+        Ok(())
+    }
+
+    /// 将数据库内容导出为 MultiAppConfig（迁移的逆操作）
+    pub fn export_to_multi_app_config(&self) -> Result<MultiAppConfig, AppError> {
+        let mut config = MultiAppConfig::default();
+
+        for (app_key, manager) in config.apps.iter_mut() {
+            manager.providers = self.get_all_providers(app_key)?;
+            manager.current = self.get_current_provider(app_key)?.unwrap_or_default();
+        }
+
+        config.mcp.servers = Some(self.get_all_mcp_servers()?.into_iter().collect());
+
+        config.prompts.claude.prompts = self.get_prompts("claude")?.into_iter().collect();
+        config.prompts.codex.prompts = self.get_prompts("codex")?.into_iter().collect();
+        config.prompts.gemini.prompts = self.get_prompts("gemini")?.into_iter().collect();
+
+        config.skills.skills = self.get_skills()?.into_iter().collect();
+        config.skills.repos = self.get_skill_repos()?;
+
+        config.common_config_snippets.claude = self.get_config_snippet("claude")?;
+        config.common_config_snippets.codex = self.get_config_snippet("codex")?;
+        config.common_config_snippets.gemini = self.get_config_snippet("gemini")?;
+
+        Ok(config)
+    }
+
     /// 运行迁移的 dry-run 模式（在内存数据库中验证，不写入磁盘）
     ///
     /// 用于部署前验证迁移逻辑是否正确。
@@ -83,13 +130,16 @@ impl Database {
                 tx.execute(
                     "INSERT OR REPLACE INTO providers (
                         id, app_type, name, settings_config, website_url, category,
-                        created_at, sort_index, notes, icon, icon_color, meta, is_current
-                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                        created_at, sort_index, notes, icon, icon_color, meta, is_current, env,
+                        model, base_url, tags
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                     params![
                         id,
                         app_type,
                         provider.name,
-                        to_json_string(&provider.settings_config)?,
+                        to_json_string(&crate::secret_store::encrypt_secrets(
+                            &provider.settings_config
+                        ))?,
                         provider.website_url,
                         provider.category,
                         provider.created_at,
@@ -99,6 +149,13 @@ impl Database {
                         provider.icon_color,
                         to_json_string(&meta_clone)?,
                         is_current,
+                        to_json_string(&crate::secret_store::encrypt_secrets(
+                            &serde_json::to_value(&provider.env)
+                                .map_err(|e| AppError::JsonSerialize { source: e })?
+                        ))?,
+                        provider.model,
+                        provider.base_url,
+                        to_json_string(&provider.tags)?,
                     ],
                 )
                 .map_err(|e| AppError::Database(format!("Migrate provider failed: {e}")))?;
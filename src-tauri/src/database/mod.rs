@@ -15,6 +15,7 @@
 //! ├── schema.rs     - 表结构定义 + Schema 迁移
 //! ├── backup.rs     - SQL 导入导出 + 快照备份
 //! ├── migration.rs  - JSON → SQLite 数据迁移
+//! ├── repair.rs     - 损坏检测与修复
 //! └── dao/          - 数据访问对象
 //!     ├── providers.rs
 //!     ├── mcp.rs
@@ -26,6 +27,7 @@
 mod backup;
 mod dao;
 mod migration;
+mod repair;
 mod schema;
 
 #[cfg(test)]
@@ -33,21 +35,29 @@ mod tests;
 
 // DAO 类型导出供外部使用
 pub use dao::FailoverQueueItem;
+pub use dao::{AppProfileSnapshot, Profile, ProfileSnapshot, DEFAULT_PROFILE_ID};
+pub use repair::RepairReport;
 
 use crate::config::get_app_config_dir;
 use crate::error::AppError;
-use rusqlite::Connection;
+use rusqlite::{Connection, Transaction, TransactionBehavior};
 use serde::Serialize;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
 
 // DAO 方法通过 impl Database 提供，无需额外导出
 
 /// 数据库备份保留数量
 const DB_BACKUP_RETAIN: usize = 10;
 
+/// 其他连接（如命令行 `--use` 进程）持有写锁时的等待时长
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// 当前 Schema 版本号
 /// 每次修改表结构时递增，并在 schema.rs 中添加相应的迁移逻辑
-pub(crate) const SCHEMA_VERSION: i32 = 2;
+pub(crate) const SCHEMA_VERSION: i32 = 7;
 
 /// 安全地序列化 JSON，避免 unwrap panic
 pub(crate) fn to_json_string<T: Serialize>(value: &T) -> Result<String, AppError> {
@@ -67,40 +77,61 @@ macro_rules! lock_conn {
 // 导出宏供子模块使用
 pub(crate) use lock_conn;
 
+/// 开启写事务
+///
+/// 使用 IMMEDIATE 在开始时即获取写锁：busy_timeout 只在此时生效，
+/// 默认的 DEFERRED 事务在“先读后写”时若有其他连接已写入，会直接返回 `database is locked`。
+pub(crate) fn begin_write(conn: &mut Connection) -> Result<Transaction<'_>, AppError> {
+    conn.transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| AppError::Database(e.to_string()))
+}
+
 /// 数据库连接封装
 ///
 /// 使用 Mutex 包装 Connection 以支持在多线程环境（如 Tauri State）中共享。
 /// rusqlite::Connection 本身不是 Sync 的，因此需要这层包装。
+/// 应用内所有访问都经由 `AppState` 中同一个实例串行化；文件库启用 WAL 与 busy_timeout，
+/// 使另一个进程（命令行切换、便携副本）同时访问时等待而不是立即返回 `database is locked`。
 pub struct Database {
     pub(crate) conn: Mutex<Connection>,
+    /// 尚未写入 settings 表的设置值（见 `set_setting`）
+    pub(crate) pending_settings: Mutex<HashMap<String, String>>,
+    /// 通知后台线程延迟写入设置；未启动后台线程时设置立即写入
+    pub(crate) settings_flush_tx: Mutex<Option<mpsc::Sender<()>>>,
 }
 
 impl Database {
     /// 初始化数据库连接并创建表
     ///
-    /// 数据库文件位于 `~/.cc-switch/cc-switch.db`
+    /// 数据库文件位于应用配置目录下的 `cc-switch.db`（默认 `~/.cc-switch`，便携模式下为 `data/`）
     pub fn init() -> Result<Self, AppError> {
-        let db_path = get_app_config_dir().join("cc-switch.db");
+        Self::open(&get_app_config_dir().join("cc-switch.db"))
+    }
+
+    /// 打开指定路径的数据库文件并创建表
+    pub(crate) fn open(db_path: &Path) -> Result<Self, AppError> {
+        let db = Self::from_connection(Self::open_file_connection(db_path)?);
+        db.create_tables()?;
+        db.apply_schema_migrations()?;
+        db.ensure_model_pricing_seeded()?;
 
+        Ok(db)
+    }
+
+    /// 打开数据库文件并完成连接设置（不建表）
+    fn open_file_connection(db_path: &Path) -> Result<Connection, AppError> {
         // 确保父目录存在
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
         }
 
-        let conn = Connection::open(&db_path).map_err(|e| AppError::Database(e.to_string()))?;
+        let conn = Connection::open(db_path).map_err(|e| AppError::Database(e.to_string()))?;
 
         // 启用外键约束
         conn.execute("PRAGMA foreign_keys = ON;", [])
             .map_err(|e| AppError::Database(e.to_string()))?;
-
-        let db = Self {
-            conn: Mutex::new(conn),
-        };
-        db.create_tables()?;
-        db.apply_schema_migrations()?;
-        db.ensure_model_pricing_seeded()?;
-
-        Ok(db)
+        Self::configure_file_connection(&conn)?;
+        Ok(conn)
     }
 
     /// 创建内存数据库（用于测试）
@@ -111,15 +142,37 @@ impl Database {
         conn.execute("PRAGMA foreign_keys = ON;", [])
             .map_err(|e| AppError::Database(e.to_string()))?;
 
-        let db = Self {
-            conn: Mutex::new(conn),
-        };
+        let db = Self::from_connection(conn);
         db.create_tables()?;
         db.ensure_model_pricing_seeded()?;
 
         Ok(db)
     }
 
+    fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn: Mutex::new(conn),
+            pending_settings: Mutex::new(HashMap::new()),
+            settings_flush_tx: Mutex::new(None),
+        }
+    }
+
+    /// 文件库连接设置：WAL 日志模式允许读写并发，busy_timeout 避免跨进程写入时立即失败
+    fn configure_file_connection(conn: &Connection) -> Result<(), AppError> {
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mode: String = conn
+            .query_row("PRAGMA journal_mode = WAL;", [], |row| row.get(0))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if !mode.eq_ignore_ascii_case("wal") {
+            log::warn!("数据库未能启用 WAL 模式，当前为 {mode}");
+        }
+        // WAL 模式下 NORMAL 已能保证崩溃后数据库一致
+        conn.execute_batch("PRAGMA synchronous = NORMAL;")
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     /// 检查 MCP 服务器表是否为空
     pub fn is_mcp_table_empty(&self) -> Result<bool, AppError> {
         let conn = lock_conn!(self.conn);
@@ -129,6 +182,15 @@ impl Database {
         Ok(count == 0)
     }
 
+    /// 检查供应商表是否为空
+    pub fn is_providers_table_empty(&self) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM providers", [], |row| row.get(0))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(count == 0)
+    }
+
     /// 检查提示词表是否为空
     pub fn is_prompts_table_empty(&self) -> Result<bool, AppError> {
         let conn = lock_conn!(self.conn);
@@ -0,0 +1,332 @@
+//! 数据库损坏检测与修复
+//!
+//! 先用 `PRAGMA integrity_check` 检查数据库文件；损坏时逐表读出仍可读取的行，
+//! 重建到新的数据库文件（相当于 `.dump` 后重新导入）。抢救失败时退回到 `backups/`
+//! 中最新的完好快照。原文件改名为 `*.corrupt-<时间>` 保留，不会删除。
+
+use super::{lock_conn, Database};
+use crate::error::AppError;
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 报告中保留的完整性问题条数
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+/// 修复所采用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepairOutcome {
+    /// 完整性检查通过，无需修复
+    Healthy,
+    /// 从损坏的数据库中抢救数据并重建
+    Salvaged,
+    /// 抢救失败，已从最近的数据库备份恢复
+    RestoredFromBackup,
+}
+
+/// 修复结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    pub outcome: RepairOutcome,
+    /// 完整性检查发现的问题（最多 20 条）
+    pub problems: Vec<String>,
+    /// 抢救时未能完整读取的表（可能丢失部分行）
+    pub incomplete_tables: Vec<String>,
+    /// 恢复所用的备份文件
+    pub backup_path: Option<String>,
+    /// 损坏文件的留存路径
+    pub corrupt_copy: Option<String>,
+}
+
+impl RepairReport {
+    fn healthy() -> Self {
+        Self {
+            outcome: RepairOutcome::Healthy,
+            problems: Vec::new(),
+            incomplete_tables: Vec::new(),
+            backup_path: None,
+            corrupt_copy: None,
+        }
+    }
+}
+
+impl Database {
+    /// 检查并修复指定的数据库文件，完成后当前实例改为使用修复后的文件
+    ///
+    /// 修复期间持有连接锁；原连接先被关闭，以便替换文件（Windows 上无法改名已打开的文件）。
+    /// 初始化失败时应用以内存数据库降级启动，同样通过该方法切换到修复后的文件。
+    pub fn repair_file(&self, db_path: &Path) -> Result<RepairReport, AppError> {
+        if let Err(e) = self.flush_settings() {
+            log::warn!("修复前写入设置失败: {e}");
+        }
+
+        let report = {
+            let mut conn = lock_conn!(self.conn);
+            *conn = Connection::open_in_memory().map_err(|e| AppError::Database(e.to_string()))?;
+            let result = repair_database_file(db_path);
+            // 修复失败时同样重新打开原文件，保持修复前的行为
+            match Self::open_file_connection(db_path) {
+                Ok(reopened) => *conn = reopened,
+                Err(e) if result.is_ok() => return Err(e),
+                Err(e) => log::warn!("重新打开数据库失败: {e}"),
+            }
+            result?
+        };
+
+        self.create_tables()?;
+        self.apply_schema_migrations()?;
+        self.ensure_model_pricing_seeded()?;
+        Ok(report)
+    }
+}
+
+/// 运行完整性检查，返回发现的问题（为空表示完好）；无法打开或读取时错误信息即为问题
+fn integrity_problems(db_path: &Path) -> Vec<String> {
+    let check = || -> rusqlite::Result<Vec<String>> {
+        let conn = Connection::open(db_path)?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut problems = Vec::new();
+        for row in rows {
+            let row = row?;
+            if row != "ok" && problems.len() < MAX_REPORTED_PROBLEMS {
+                problems.push(row);
+            }
+        }
+        Ok(problems)
+    };
+    check().unwrap_or_else(|e| vec![e.to_string()])
+}
+
+/// 修复数据库文件（不涉及已打开的连接）
+fn repair_database_file(db_path: &Path) -> Result<RepairReport, AppError> {
+    if !db_path.exists() {
+        return Ok(RepairReport::healthy());
+    }
+    let problems = integrity_problems(db_path);
+    if problems.is_empty() {
+        return Ok(RepairReport::healthy());
+    }
+    log::warn!("数据库完整性检查失败: {}", problems.join("; "));
+
+    let rebuilt = db_path.with_extension("db.repair");
+    remove_database_files(&rebuilt);
+
+    let salvage_error = match salvage(db_path, &rebuilt) {
+        Ok(incomplete_tables) => {
+            let corrupt_copy = replace_database_file(db_path, &rebuilt)?;
+            log::info!(
+                "已从损坏的数据库中抢救数据，未完整读取的表: {incomplete_tables:?}，原文件保留为 {}",
+                corrupt_copy.display()
+            );
+            return Ok(RepairReport {
+                outcome: RepairOutcome::Salvaged,
+                problems,
+                incomplete_tables,
+                backup_path: None,
+                corrupt_copy: Some(corrupt_copy.to_string_lossy().to_string()),
+            });
+        }
+        Err(e) => e,
+    };
+    log::warn!("抢救数据库失败: {salvage_error}，尝试从备份恢复");
+    remove_database_files(&rebuilt);
+
+    let Some(backup) = latest_healthy_backup(db_path) else {
+        return Err(AppError::localized(
+            "database.repair.failed",
+            format!("数据库已损坏且无法修复（{salvage_error}），也没有可用的数据库备份"),
+            format!(
+                "The database is corrupt and could not be repaired ({salvage_error}); no usable backup was found"
+            ),
+        ));
+    };
+    fs::copy(&backup, &rebuilt).map_err(|e| AppError::io(&rebuilt, e))?;
+    let corrupt_copy = replace_database_file(db_path, &rebuilt)?;
+    log::info!(
+        "已从备份 {} 恢复数据库，原文件保留为 {}",
+        backup.display(),
+        corrupt_copy.display()
+    );
+    Ok(RepairReport {
+        outcome: RepairOutcome::RestoredFromBackup,
+        problems,
+        incomplete_tables: Vec::new(),
+        backup_path: Some(backup.to_string_lossy().to_string()),
+        corrupt_copy: Some(corrupt_copy.to_string_lossy().to_string()),
+    })
+}
+
+/// 将仍可读取的表结构与数据复制到新文件，返回未能完整读取的表
+///
+/// 表结构无法读取或所有表都无法读取时视为抢救失败。
+fn salvage(source: &Path, target: &Path) -> Result<Vec<String>, String> {
+    let old = Connection::open(source).map_err(|e| e.to_string())?;
+    let user_version: i64 = old
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("读取版本号失败: {e}"))?;
+    let objects = {
+        let mut stmt = old
+            .prepare(
+                "SELECT type, name, sql FROM sqlite_master
+                 WHERE sql NOT NULL AND name NOT LIKE 'sqlite_%'
+                 ORDER BY type = 'table' DESC, name",
+            )
+            .map_err(|e| format!("读取表结构失败: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("读取表结构失败: {e}"))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("读取表结构失败: {e}"))?
+    };
+
+    let mut new = Connection::open(target).map_err(|e| e.to_string())?;
+    new.execute_batch("PRAGMA foreign_keys = OFF;")
+        .map_err(|e| e.to_string())?;
+
+    let mut readable_tables = 0;
+    let mut incomplete_tables = Vec::new();
+    let tx = new.transaction().map_err(|e| e.to_string())?;
+    for (kind, name, sql) in &objects {
+        if kind != "table" {
+            if let Err(e) = tx.execute_batch(sql) {
+                log::warn!("重建 {kind} {name} 失败: {e}");
+            }
+            continue;
+        }
+        tx.execute_batch(sql)
+            .map_err(|e| format!("重建表 {name} 失败: {e}"))?;
+        match copy_rows(&old, &tx, name) {
+            Ok(_) => readable_tables += 1,
+            Err((copied, e)) => {
+                log::warn!("表 {name} 读取中断（已抢救 {copied} 行）: {e}");
+                if copied > 0 {
+                    readable_tables += 1;
+                }
+                incomplete_tables.push(name.clone());
+            }
+        }
+    }
+    tx.pragma_update(None, "user_version", user_version)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if readable_tables == 0 {
+        return Err("所有表均无法读取".to_string());
+    }
+    Database::create_tables_on_conn(&new).map_err(|e| e.to_string())?;
+    Database::apply_schema_migrations_on_conn(&new).map_err(|e| e.to_string())?;
+    drop(new);
+
+    let problems = integrity_problems(target);
+    if !problems.is_empty() {
+        return Err(format!(
+            "重建后的数据库仍未通过检查: {}",
+            problems.join("; ")
+        ));
+    }
+    Ok(incomplete_tables)
+}
+
+/// 逐行复制表数据；读取中断时返回已复制的行数与错误
+fn copy_rows(
+    old: &Connection,
+    new: &Connection,
+    table: &str,
+) -> Result<usize, (usize, rusqlite::Error)> {
+    let mut select = old
+        .prepare(&format!("SELECT * FROM \"{table}\""))
+        .map_err(|e| (0, e))?;
+    let columns: Vec<String> = select
+        .column_names()
+        .into_iter()
+        .map(|c| format!("\"{c}\""))
+        .collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut insert = new
+        .prepare(&format!(
+            "INSERT OR IGNORE INTO \"{table}\" ({}) VALUES ({placeholders})",
+            columns.join(", ")
+        ))
+        .map_err(|e| (0, e))?;
+
+    let mut copied = 0;
+    let mut rows = select.query([]).map_err(|e| (0, e))?;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok(copied),
+            Err(e) => return Err((copied, e)),
+        };
+        let values = (0..columns.len())
+            .map(|idx| row.get::<_, Value>(idx))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| (copied, e))?;
+        insert
+            .execute(rusqlite::params_from_iter(values))
+            .map_err(|e| (copied, e))?;
+        copied += 1;
+    }
+}
+
+/// `backups/` 中最新的、通过完整性检查的数据库快照
+fn latest_healthy_backup(db_path: &Path) -> Option<PathBuf> {
+    let dir = db_path.parent()?.join("backups");
+    let mut backups: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|path| Some((path.metadata().ok()?.modified().ok()?, path)))
+        .collect();
+    backups.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    backups
+        .into_iter()
+        .map(|(_, path)| path)
+        .find(|path| integrity_problems(path).is_empty())
+}
+
+/// 数据库文件及其 WAL / SHM 附属文件的路径后缀
+const SIDE_FILE_SUFFIXES: [&str; 2] = ["-wal", "-shm"];
+
+fn side_file(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn remove_database_files(path: &Path) {
+    let _ = fs::remove_file(path);
+    for suffix in SIDE_FILE_SUFFIXES {
+        let _ = fs::remove_file(side_file(path, suffix));
+    }
+}
+
+/// 将损坏的数据库（连同 WAL / SHM）改名保留，再用 `replacement` 替换，返回留存路径
+fn replace_database_file(db_path: &Path, replacement: &Path) -> Result<PathBuf, AppError> {
+    let corrupt_copy = side_file(
+        db_path,
+        &format!(".corrupt-{}", Utc::now().format("%Y%m%d_%H%M%S")),
+    );
+    fs::rename(db_path, &corrupt_copy).map_err(|e| AppError::io(db_path, e))?;
+    for suffix in SIDE_FILE_SUFFIXES {
+        let side = side_file(db_path, suffix);
+        if side.exists() {
+            fs::rename(&side, side_file(&corrupt_copy, suffix))
+                .map_err(|e| AppError::io(&side, e))?;
+        }
+    }
+    fs::rename(replacement, db_path).map_err(|e| AppError::io(replacement, e))?;
+    Ok(corrupt_copy)
+}
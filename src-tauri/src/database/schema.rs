@@ -6,6 +6,54 @@ use super::{lock_conn, Database, SCHEMA_VERSION};
 use crate::error::AppError;
 use rusqlite::Connection;
 
+/// 单个 Schema 迁移步骤：将数据库从 `version - 1` 升级到 `version`
+struct SchemaMigration {
+    version: i32,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<(), AppError>,
+}
+
+/// 按版本顺序排列的迁移步骤，最后一步的版本必须等于 `SCHEMA_VERSION`
+///
+/// 修改表结构时：递增 `SCHEMA_VERSION`，实现 `migrate_vN_to_vM` 并在此追加一项。
+const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
+        version: 1,
+        description: "补齐缺失列",
+        apply: Database::migrate_v0_to_v1,
+    },
+    SchemaMigration {
+        version: 2,
+        description: "添加使用统计表和完整字段，重构 skills 表",
+        apply: Database::migrate_v1_to_v2,
+    },
+    SchemaMigration {
+        version: 3,
+        description: "供应商自定义环境变量",
+        apply: Database::migrate_v2_to_v3,
+    },
+    SchemaMigration {
+        version: 4,
+        description: "供应商模型与 Base URL 覆盖",
+        apply: Database::migrate_v3_to_v4,
+    },
+    SchemaMigration {
+        version: 5,
+        description: "配置档案",
+        apply: Database::migrate_v4_to_v5,
+    },
+    SchemaMigration {
+        version: 6,
+        description: "供应商标签",
+        apply: Database::migrate_v5_to_v6,
+    },
+    SchemaMigration {
+        version: 7,
+        description: "供应商最近使用时间与使用次数",
+        apply: Database::migrate_v6_to_v7,
+    },
+];
+
 impl Database {
     /// 创建所有数据库表
     pub(crate) fn create_tables(&self) -> Result<(), AppError> {
@@ -32,6 +80,12 @@ impl Database {
                 meta TEXT NOT NULL DEFAULT '{}',
                 is_current BOOLEAN NOT NULL DEFAULT 0,
                 in_failover_queue BOOLEAN NOT NULL DEFAULT 0,
+                env TEXT NOT NULL DEFAULT '{}',
+                model TEXT,
+                base_url TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                last_used_at INTEGER,
+                use_count INTEGER NOT NULL DEFAULT 0,
                 PRIMARY KEY (id, app_type)
             )",
             [],
@@ -307,6 +361,9 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 17. Profiles 表 (配置档案)
+        Self::create_profiles_table(conn)?;
+
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
             "ALTER TABLE proxy_config ADD COLUMN live_takeover_active INTEGER NOT NULL DEFAULT 0",
@@ -336,17 +393,25 @@ impl Database {
     }
 
     /// 应用 Schema 迁移
+    ///
+    /// 失败时已回滚，并记录迁移失败详情供前端展示。
     pub(crate) fn apply_schema_migrations(&self) -> Result<(), AppError> {
         let conn = lock_conn!(self.conn);
-        Self::apply_schema_migrations_on_conn(&conn)
+        Self::apply_schema_migrations_on_conn(&conn).inspect_err(|e| {
+            log::error!("数据库 Schema 迁移失败: {e}");
+            crate::init_status::set_migration_error("schema", e.to_string());
+        })
     }
 
     /// 在指定连接上应用 Schema 迁移
+    ///
+    /// 按 `SCHEMA_MIGRATIONS` 顺序执行高于当前版本的步骤，全部在同一 savepoint 中完成；
+    /// 当前版本记录在 `PRAGMA user_version`，每个已应用的步骤同时写入 `schema_version` 表。
     pub(crate) fn apply_schema_migrations_on_conn(conn: &Connection) -> Result<(), AppError> {
         conn.execute("SAVEPOINT schema_migration;", [])
             .map_err(|e| AppError::Database(format!("开启迁移 savepoint 失败: {e}")))?;
 
-        let mut version = Self::get_user_version(conn)?;
+        let version = Self::get_user_version(conn)?;
 
         if version > SCHEMA_VERSION {
             conn.execute("ROLLBACK TO schema_migration;", []).ok();
@@ -357,27 +422,26 @@ impl Database {
         }
 
         let result = (|| {
-            while version < SCHEMA_VERSION {
-                match version {
-                    0 => {
-                        log::info!("检测到 user_version=0，迁移到 1（补齐缺失列并设置版本）");
-                        Self::migrate_v0_to_v1(conn)?;
-                        Self::set_user_version(conn, 1)?;
-                    }
-                    1 => {
-                        log::info!(
-                            "迁移数据库从 v1 到 v2（添加使用统计表和完整字段，重构 skills 表）"
-                        );
-                        Self::migrate_v1_to_v2(conn)?;
-                        Self::set_user_version(conn, 2)?;
-                    }
-                    _ => {
-                        return Err(AppError::Database(format!(
-                            "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
-                        )));
-                    }
+            Self::ensure_schema_version_table(conn, version)?;
+            for migration in SCHEMA_MIGRATIONS {
+                if migration.version <= version {
+                    continue;
                 }
-                version = Self::get_user_version(conn)?;
+                log::info!(
+                    "迁移数据库到 v{}（{}）",
+                    migration.version,
+                    migration.description
+                );
+                (migration.apply)(conn)?;
+                Self::set_user_version(conn, migration.version)?;
+                Self::record_schema_version(conn, migration.version, migration.description)?;
+            }
+
+            let version = Self::get_user_version(conn)?;
+            if version != SCHEMA_VERSION {
+                return Err(AppError::Database(format!(
+                    "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
+                )));
             }
             Ok(())
         })();
@@ -396,6 +460,41 @@ impl Database {
         }
     }
 
+    /// 创建 schema_version 表；旧数据库首次创建时记录当前版本作为基线
+    fn ensure_schema_version_table(conn: &Connection, current: i32) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(format!("创建 schema_version 表失败: {e}")))?;
+
+        let recorded: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if recorded == 0 && current > 0 {
+            Self::record_schema_version(conn, current, "baseline")?;
+        }
+        Ok(())
+    }
+
+    fn record_schema_version(
+        conn: &Connection,
+        version: i32,
+        description: &str,
+    ) -> Result<(), AppError> {
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version (version, description, applied_at)
+             VALUES (?1, ?2, ?3)",
+            rusqlite::params![version, description, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| AppError::Database(format!("记录 schema 版本失败: {e}")))?;
+        Ok(())
+    }
+
     /// v0 -> v1 迁移：补齐所有缺失列
     fn migrate_v0_to_v1(conn: &Connection) -> Result<(), AppError> {
         // providers 表
@@ -560,6 +659,67 @@ impl Database {
         Ok(())
     }
 
+    /// v2 -> v3 迁移：providers 表添加 env 列（切换时注入的环境变量）
+    fn migrate_v2_to_v3(conn: &Connection) -> Result<(), AppError> {
+        Self::add_column_if_missing(conn, "providers", "env", "TEXT NOT NULL DEFAULT '{}'")?;
+        Ok(())
+    }
+
+    /// v3 -> v4 迁移：providers 表添加 model / base_url 覆盖列
+    fn migrate_v3_to_v4(conn: &Connection) -> Result<(), AppError> {
+        Self::add_column_if_missing(conn, "providers", "model", "TEXT")?;
+        Self::add_column_if_missing(conn, "providers", "base_url", "TEXT")?;
+        Ok(())
+    }
+
+    /// v4 -> v5 迁移：添加 profiles 表，现有数据归入默认档案
+    fn migrate_v4_to_v5(conn: &Connection) -> Result<(), AppError> {
+        Self::create_profiles_table(conn)
+    }
+
+    /// v5 -> v6 迁移：providers 表添加 tags 列（JSON 数组）
+    fn migrate_v5_to_v6(conn: &Connection) -> Result<(), AppError> {
+        Self::add_column_if_missing(conn, "providers", "tags", "TEXT NOT NULL DEFAULT '[]'")?;
+        Ok(())
+    }
+
+    /// v6 -> v7 迁移：providers 表添加 last_used_at / use_count 列
+    fn migrate_v6_to_v7(conn: &Connection) -> Result<(), AppError> {
+        Self::add_column_if_missing(conn, "providers", "last_used_at", "INTEGER")?;
+        Self::add_column_if_missing(conn, "providers", "use_count", "INTEGER NOT NULL DEFAULT 0")?;
+        Ok(())
+    }
+
+    /// 创建 profiles 表并确保默认档案存在（默认档案初始为当前档案）
+    ///
+    /// 当前档案的供应商保存在 providers 表中，`snapshot` 为空；其它档案的供应商与
+    /// 当前选择以 JSON 快照保存。
+    fn create_profiles_table(conn: &Connection) -> Result<(), AppError> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                is_active BOOLEAN NOT NULL DEFAULT 0,
+                snapshot TEXT
+            )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO profiles (id, name, created_at, is_active)
+             SELECT ?1, ?2, ?3, NOT EXISTS (SELECT 1 FROM profiles WHERE is_active = 1)",
+            rusqlite::params![
+                super::dao::profiles::DEFAULT_PROFILE_ID,
+                "默认",
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     /// 迁移 skills 表：从单 key 主键改为 (directory, app_type) 复合主键
     fn migrate_skills_table(conn: &Connection) -> Result<(), AppError> {
         // 检查是否已经是新表结构
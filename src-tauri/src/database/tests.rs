@@ -130,6 +130,7 @@ fn migration_adds_missing_columns_for_providers() {
     for (table, column) in [
         ("providers", "meta"),
         ("providers", "is_current"),
+        ("providers", "tags"),
         ("provider_endpoints", "added_at"),
         ("mcp_servers", "enabled_gemini"),
         ("prompts", "updated_at"),
@@ -246,6 +247,12 @@ fn dry_run_validates_schema_compatibility() {
             icon: None,
             icon_color: None,
             in_failover_queue: false,
+            env: Default::default(),
+            model: None,
+            base_url: None,
+            tags: Vec::new(),
+            last_used_at: None,
+            use_count: 0,
         },
     );
 
@@ -332,3 +339,335 @@ fn model_pricing_is_seeded_on_init() {
         gemini_count
     );
 }
+
+#[test]
+fn npm_version_cache_roundtrip() {
+    let db = Database::memory().expect("create memory db");
+
+    assert!(db
+        .get_npm_version_cache("@openai/codex")
+        .expect("read empty cache")
+        .is_none());
+
+    db.set_npm_version_cache("@openai/codex", "1.2.3")
+        .expect("write cache");
+    let entry = db
+        .get_npm_version_cache("@openai/codex")
+        .expect("read cache")
+        .expect("cache entry should exist");
+    assert_eq!(entry.version, "1.2.3");
+    assert!(entry.fetched_at > 0);
+
+    // 不同包的缓存互不影响
+    assert!(db
+        .get_npm_version_cache("@google/gemini-cli")
+        .expect("read other cache")
+        .is_none());
+}
+
+#[test]
+fn clear_npm_version_cache_by_package_or_all() {
+    let db = Database::memory().expect("create memory db");
+    db.set_npm_version_cache("@openai/codex", "1.2.3")
+        .expect("write codex cache");
+    db.set_npm_version_cache("@google/gemini-cli", "0.9.0")
+        .expect("write gemini cache");
+
+    assert_eq!(
+        db.clear_npm_version_cache(Some("@openai/codex"))
+            .expect("clear codex cache"),
+        1
+    );
+    assert!(db
+        .get_npm_version_cache("@openai/codex")
+        .expect("read codex cache")
+        .is_none());
+    assert!(db
+        .get_npm_version_cache("@google/gemini-cli")
+        .expect("read gemini cache")
+        .is_some());
+
+    // `_` 不能被当作通配符误删其它设置
+    db.set_setting("npmXlatestXversionXother", "keep")
+        .expect("write unrelated setting");
+    assert_eq!(db.clear_npm_version_cache(None).expect("clear all"), 1);
+    assert_eq!(db.clear_npm_version_cache(None).expect("clear empty"), 0);
+    assert_eq!(
+        db.get_setting("npmXlatestXversionXother")
+            .expect("read unrelated setting")
+            .as_deref(),
+        Some("keep")
+    );
+}
+
+#[test]
+fn remigrate_replaces_existing_providers() {
+    fn config_with_provider(id: &str) -> MultiAppConfig {
+        let mut providers = IndexMap::new();
+        providers.insert(
+            id.to_string(),
+            Provider::with_id(id.to_string(), id.to_string(), json!({}), None),
+        );
+        let mut manager = ProviderManager::default();
+        manager.providers = providers;
+        manager.current = id.to_string();
+
+        let mut apps = HashMap::new();
+        apps.insert("claude".to_string(), manager);
+        MultiAppConfig {
+            version: 2,
+            apps,
+            ..Default::default()
+        }
+    }
+
+    let db = Database::memory().expect("create memory db");
+    db.migrate_from_json(&config_with_provider("old"))
+        .expect("initial migration");
+    assert!(!db.is_providers_table_empty().expect("check providers"));
+
+    db.remigrate_from_json(&config_with_provider("new"))
+        .expect("remigration");
+
+    let providers = db.get_all_providers("claude").expect("load providers");
+    assert_eq!(providers.keys().collect::<Vec<_>>(), vec!["new"]);
+}
+
+#[test]
+fn reorder_providers_appends_unlisted_ids() {
+    let db = Database::memory().expect("create memory db");
+    for id in ["a", "b", "c", "d"] {
+        db.save_provider(
+            "claude",
+            &Provider::with_id(id.to_string(), id.to_string(), json!({}), None),
+        )
+        .expect("save provider");
+    }
+
+    let order = db
+        .reorder_providers(
+            "claude",
+            &["c".to_string(), "missing".to_string(), "a".to_string()],
+        )
+        .expect("reorder providers");
+    assert_eq!(order, vec!["c", "a", "b", "d"]);
+
+    let providers = db.get_all_providers("claude").expect("load providers");
+    assert_eq!(
+        providers.keys().collect::<Vec<_>>(),
+        vec!["c", "a", "b", "d"]
+    );
+    assert_eq!(providers["b"].sort_index, Some(2));
+}
+
+#[test]
+fn provider_tags_roundtrip() {
+    let db = Database::memory().expect("create memory db");
+    let mut provider = Provider::with_id("a".to_string(), "A".to_string(), json!({}), None);
+    provider.tags = vec!["work".to_string(), "fast".to_string()];
+    db.save_provider("claude", &provider)
+        .expect("save provider");
+
+    let loaded = db
+        .get_provider_by_id("a", "claude")
+        .expect("load provider")
+        .expect("provider exists");
+    assert_eq!(loaded.tags, vec!["work", "fast"]);
+    assert_eq!(
+        db.get_all_providers("claude").unwrap()["a"].tags,
+        loaded.tags
+    );
+}
+
+#[test]
+fn file_database_uses_wal_and_tolerates_concurrent_writers() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("cc-switch.db");
+    let first = Database::open(&path).expect("open first connection");
+    let second = Database::open(&path).expect("open second connection");
+
+    {
+        let conn = first.conn.lock().expect("lock conn");
+        let mode: String = conn
+            .query_row("PRAGMA journal_mode;", [], |row| row.get(0))
+            .expect("query journal mode");
+        assert_eq!(mode.to_lowercase(), "wal");
+    }
+
+    std::thread::scope(|scope| {
+        for (db, prefix) in [(&first, "a"), (&second, "b")] {
+            scope.spawn(move || {
+                for i in 0..20 {
+                    let id = format!("{prefix}{i}");
+                    db.save_provider(
+                        "claude",
+                        &Provider::with_id(id.clone(), id, json!({}), None),
+                    )
+                    .expect("concurrent save should not hit database is locked");
+                }
+            });
+        }
+    });
+
+    assert_eq!(
+        first
+            .get_all_providers("claude")
+            .expect("load providers")
+            .len(),
+        40
+    );
+}
+
+#[test]
+fn settings_writes_are_coalesced_until_flush() {
+    fn stored_value(db: &Database, key: &str) -> Option<String> {
+        let conn = db.conn.lock().expect("lock conn");
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .ok()
+    }
+
+    let db = std::sync::Arc::new(Database::memory().expect("create memory db"));
+    Database::start_settings_flusher(&db).expect("start flusher");
+
+    for i in 0..10 {
+        db.set_setting("window", &i.to_string())
+            .expect("set setting");
+    }
+    assert_eq!(db.get_setting("window").unwrap().as_deref(), Some("9"));
+    assert_eq!(stored_value(&db, "window"), None);
+
+    db.flush_settings().expect("flush settings");
+    assert_eq!(stored_value(&db, "window").as_deref(), Some("9"));
+
+    db.set_setting("window", "10").expect("set setting");
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    assert_eq!(stored_value(&db, "window").as_deref(), Some("10"));
+}
+
+#[test]
+fn schema_migrations_record_applied_versions() {
+    fn recorded_versions(conn: &Connection) -> Vec<(i32, String)> {
+        let mut stmt = conn
+            .prepare("SELECT version, description FROM schema_version ORDER BY version")
+            .expect("prepare query");
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query versions")
+            .collect::<Result<_, _>>()
+            .expect("collect versions")
+    }
+
+    let legacy = Connection::open_in_memory().expect("open memory db");
+    legacy
+        .execute_batch(LEGACY_SCHEMA_SQL)
+        .expect("seed old schema");
+    Database::apply_schema_migrations_on_conn(&legacy).expect("apply migrations");
+    let versions: Vec<i32> = recorded_versions(&legacy)
+        .into_iter()
+        .map(|(v, _)| v)
+        .collect();
+    assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+
+    // 已是最新版本的数据库只记录基线，重复执行不会新增记录
+    let current = Connection::open_in_memory().expect("open memory db");
+    Database::create_tables_on_conn(&current).expect("create tables");
+    Database::set_user_version(&current, SCHEMA_VERSION).expect("set version");
+    Database::apply_schema_migrations_on_conn(&current).expect("apply migrations");
+    Database::apply_schema_migrations_on_conn(&current).expect("apply migrations again");
+    assert_eq!(
+        recorded_versions(&current),
+        vec![(SCHEMA_VERSION, "baseline".to_string())]
+    );
+}
+
+fn file_database_with_provider(path: &std::path::Path) {
+    let db = Database::open(path).expect("open file database");
+    db.save_provider(
+        "claude",
+        &Provider::with_id("keep".into(), "Keep".into(), json!({}), None),
+    )
+    .expect("save provider");
+}
+
+#[test]
+fn repair_leaves_healthy_database_untouched() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("cc-switch.db");
+    file_database_with_provider(&path);
+
+    let db = Database::memory().expect("memory db");
+    let report = db.repair_file(&path).expect("repair");
+    assert_eq!(report.outcome, repair::RepairOutcome::Healthy);
+    assert!(report.corrupt_copy.is_none());
+    // 降级运行的实例切换到文件数据库
+    assert!(db.get_all_providers("claude").unwrap().contains_key("keep"));
+}
+
+#[test]
+fn repair_salvages_readable_tables() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("cc-switch.db");
+    file_database_with_provider(&path);
+    {
+        let conn = Connection::open(&path).expect("open raw");
+        conn.execute_batch("CREATE TABLE filler (id INTEGER PRIMARY KEY, data BLOB);")
+            .unwrap();
+        for i in 0..200 {
+            conn.execute(
+                "INSERT INTO filler (id, data) VALUES (?1, zeroblob(1000))",
+                [i],
+            )
+            .unwrap();
+        }
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+            .unwrap();
+    }
+    // 破坏最后一页（属于最后写入的 filler 表）
+    let mut bytes = std::fs::read(&path).unwrap();
+    let len = bytes.len();
+    bytes[len - 4096..].fill(0xA5);
+    std::fs::write(&path, bytes).unwrap();
+
+    let db = Database::memory().expect("memory db");
+    let report = db.repair_file(&path).expect("repair");
+    assert_eq!(report.outcome, repair::RepairOutcome::Salvaged);
+    assert!(!report.problems.is_empty());
+    assert_eq!(report.incomplete_tables, ["filler"]);
+    assert!(std::path::Path::new(report.corrupt_copy.as_deref().unwrap()).exists());
+    assert!(db.get_all_providers("claude").unwrap().contains_key("keep"));
+}
+
+#[test]
+fn repair_falls_back_to_latest_backup() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("cc-switch.db");
+    let backups = dir.path().join("backups");
+    std::fs::create_dir_all(&backups).unwrap();
+    file_database_with_provider(&backups.join("db_backup_20260101_000000.db"));
+    std::fs::write(&path, b"definitely not a sqlite database").unwrap();
+
+    let db = Database::memory().expect("memory db");
+    let report = db.repair_file(&path).expect("repair");
+    assert_eq!(report.outcome, repair::RepairOutcome::RestoredFromBackup);
+    assert!(report
+        .backup_path
+        .as_deref()
+        .is_some_and(|p| p.ends_with("db_backup_20260101_000000.db")));
+    assert!(db.get_all_providers("claude").unwrap().contains_key("keep"));
+}
+
+#[test]
+fn repair_fails_without_salvage_or_backup() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("cc-switch.db");
+    std::fs::write(&path, b"definitely not a sqlite database").unwrap();
+
+    let db = Database::memory().expect("memory db");
+    assert!(db.repair_file(&path).is_err());
+    // 原文件保持不变
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"definitely not a sqlite database"
+    );
+}
@@ -7,6 +7,8 @@
 //! - Prompts
 //! - Skills
 //!
+//! It also supports switching the active provider (`ccswitch://switch?tool=..&provider=..`).
+//!
 //! See docs/ccswitch-deeplink-design.md for detailed design.
 
 mod mcp;
@@ -14,19 +16,41 @@ mod parser;
 mod prompt;
 mod provider;
 mod skill;
+mod switch;
 mod utils;
 
 #[cfg(test)]
 mod tests;
 
+use crate::app_config::AppType;
 use serde::{Deserialize, Serialize};
 
 // Re-export public API
 pub use mcp::import_mcp_from_deeplink;
-pub use parser::parse_deeplink_url;
+pub use parser::{parse_deeplink_action, parse_deeplink_url};
 pub use prompt::import_prompt_from_deeplink;
 pub use provider::{import_provider_from_deeplink, parse_and_merge_config};
 pub use skill::import_skill_from_deeplink;
+pub use switch::resolve_switch_target;
+
+/// Action carried by a ccswitch:// URL
+#[derive(Debug, Clone)]
+pub enum DeepLinkAction {
+    /// `ccswitch://v1/import?...`
+    Import(Box<DeepLinkImportRequest>),
+    /// `ccswitch://switch?tool=...&provider=...`
+    Switch(DeepLinkSwitchRequest),
+}
+
+/// Deep link provider switch request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkSwitchRequest {
+    /// Target application (claude/codex/gemini)
+    pub tool: AppType,
+    /// Provider ID to activate
+    pub provider: String,
+}
 
 /// Deep link import request model
 ///
@@ -3,11 +3,71 @@
 //! Parses ccswitch:// URLs into DeepLinkImportRequest structures.
 
 use super::utils::validate_url;
-use super::DeepLinkImportRequest;
+use super::{DeepLinkAction, DeepLinkImportRequest, DeepLinkSwitchRequest};
+use crate::app_config::AppType;
 use crate::error::AppError;
 use std::collections::HashMap;
 use url::Url;
 
+/// Parse a ccswitch:// URL into the action it requests
+///
+/// Supported formats:
+/// - ccswitch://v1/import?resource={type}&...
+/// - ccswitch://switch?tool={app}&provider={id}
+pub fn parse_deeplink_action(url_str: &str) -> Result<DeepLinkAction, AppError> {
+    let url = Url::parse(url_str)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid deep link URL: {e}")))?;
+
+    let scheme = url.scheme();
+    if scheme != "ccswitch" {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid scheme: expected 'ccswitch', got '{scheme}'"
+        )));
+    }
+
+    match url.host_str() {
+        Some("switch") => parse_switch_deeplink(&url).map(DeepLinkAction::Switch),
+        Some("v1") => {
+            parse_deeplink_url(url_str).map(|request| DeepLinkAction::Import(Box::new(request)))
+        }
+        Some(action) => Err(AppError::InvalidInput(format!(
+            "Unsupported deep link action: {action}"
+        ))),
+        None => Err(AppError::InvalidInput(
+            "Missing action in URL host".to_string(),
+        )),
+    }
+}
+
+/// Parse switch deep link parameters
+fn parse_switch_deeplink(url: &Url) -> Result<DeepLinkSwitchRequest, AppError> {
+    let path = url.path();
+    if !path.is_empty() && path != "/" {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid path for switch action: '{path}'"
+        )));
+    }
+
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let tool = params
+        .get("tool")
+        .ok_or_else(|| AppError::InvalidInput("Missing 'tool' parameter".to_string()))?;
+    let tool = tool.parse::<AppType>().map_err(|_| {
+        AppError::InvalidInput(format!(
+            "Invalid tool: must be 'claude', 'codex', or 'gemini', got '{tool}'"
+        ))
+    })?;
+
+    let provider = params
+        .get("provider")
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::InvalidInput("Missing 'provider' parameter".to_string()))?;
+
+    Ok(DeepLinkSwitchRequest { tool, provider })
+}
+
 /// Parse a ccswitch:// URL into a DeepLinkImportRequest
 ///
 /// Expected format:
@@ -133,6 +133,12 @@ pub(crate) fn build_provider_from_request(
         icon: request.icon.clone(),
         icon_color: None,
         in_failover_queue: false,
+        env: Default::default(),
+        model: None,
+        base_url: None,
+        tags: Vec::new(),
+        last_used_at: None,
+        use_count: 0,
     };
 
     Ok(provider)
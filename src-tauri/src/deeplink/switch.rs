@@ -0,0 +1,26 @@
+//! Provider switch via deep link
+//!
+//! Handles `ccswitch://switch?tool={app}&provider={id}` URLs.
+
+use super::DeepLinkSwitchRequest;
+use crate::error::AppError;
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+/// Validate a switch request against the store and return the target provider name
+pub fn resolve_switch_target(
+    state: &AppState,
+    request: &DeepLinkSwitchRequest,
+) -> Result<String, AppError> {
+    let providers = ProviderService::list(state, request.tool.clone())?;
+    providers
+        .get(&request.provider)
+        .map(|p| p.name.clone())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Provider '{}' not found for {}",
+                request.provider,
+                request.tool.as_str()
+            ))
+        })
+}
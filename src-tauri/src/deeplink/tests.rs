@@ -1,11 +1,12 @@
 //! Deep link module tests
 
 use super::mcp::parse_mcp_apps;
-use super::parser::parse_deeplink_url;
+use super::parser::{parse_deeplink_action, parse_deeplink_url};
 use super::prompt::import_prompt_from_deeplink;
 use super::provider::parse_and_merge_config;
+use super::switch::resolve_switch_target;
 use super::utils::{infer_homepage_from_endpoint, validate_url};
-use super::DeepLinkImportRequest;
+use super::{DeepLinkAction, DeepLinkImportRequest, DeepLinkSwitchRequest};
 use crate::AppType;
 use crate::{store::AppState, Database};
 use base64::prelude::*;
@@ -404,3 +405,71 @@ fn test_parse_skill_deeplink() {
     assert_eq!(request.directory.unwrap(), "skills");
     assert_eq!(request.branch.unwrap(), "dev");
 }
+
+// =============================================================================
+// Switch Action Tests
+// =============================================================================
+
+#[test]
+fn test_parse_switch_deeplink() {
+    let action = parse_deeplink_action("ccswitch://switch?tool=claude&provider=xyz").unwrap();
+    match action {
+        DeepLinkAction::Switch(request) => {
+            assert_eq!(request.tool, AppType::Claude);
+            assert_eq!(request.provider, "xyz");
+        }
+        other => panic!("expected switch action, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_switch_deeplink_rejects_invalid_params() {
+    assert!(parse_deeplink_action("ccswitch://switch?provider=xyz").is_err());
+    assert!(parse_deeplink_action("ccswitch://switch?tool=cursor&provider=xyz").is_err());
+    assert!(parse_deeplink_action("ccswitch://switch?tool=codex&provider=").is_err());
+    assert!(parse_deeplink_action("ccswitch://switch/extra?tool=codex&provider=x").is_err());
+}
+
+#[test]
+fn test_parse_deeplink_action_rejects_unknown_action() {
+    let err = parse_deeplink_action("ccswitch://delete?tool=claude&provider=xyz").unwrap_err();
+    assert!(err.to_string().contains("Unsupported deep link action"));
+}
+
+#[test]
+fn test_parse_deeplink_action_keeps_import_urls() {
+    let action = parse_deeplink_action(
+        "ccswitch://v1/import?resource=provider&app=claude&name=Test&endpoint=https%3A%2F%2Fapi.example.com",
+    )
+    .unwrap();
+    assert!(matches!(action, DeepLinkAction::Import(request) if request.resource == "provider"));
+}
+
+#[test]
+fn test_resolve_switch_target_validates_provider() {
+    let db = Arc::new(Database::memory().expect("create memory db"));
+    let provider = crate::provider::Provider::with_id(
+        "p1".to_string(),
+        "Provider One".to_string(),
+        serde_json::json!({}),
+        None,
+    );
+    db.save_provider("claude", &provider)
+        .expect("save provider");
+    let state = AppState::new(db);
+
+    let found = DeepLinkSwitchRequest {
+        tool: AppType::Claude,
+        provider: "p1".to_string(),
+    };
+    assert_eq!(
+        resolve_switch_target(&state, &found).unwrap(),
+        "Provider One"
+    );
+
+    let missing = DeepLinkSwitchRequest {
+        tool: AppType::Codex,
+        provider: "p1".to_string(),
+    };
+    assert!(resolve_switch_target(&state, &missing).is_err());
+}
@@ -32,6 +32,8 @@ pub enum AppError {
         #[source]
         source: serde_json::Error,
     },
+    #[error("配置文件 JSON 格式错误: {0}")]
+    ConfigParse(Box<crate::config::ConfigParseError>),
     #[error("TOML 解析错误: {path}: {source}")]
     Toml {
         path: String,
@@ -69,6 +71,15 @@ impl AppError {
         }
     }
 
+    /// 配置文件 JSON 解析失败：附带出错行列与上下文片段
+    pub fn config_parse(path: impl AsRef<Path>, content: &str, source: &serde_json::Error) -> Self {
+        Self::ConfigParse(Box::new(crate::config::ConfigParseError::new(
+            path.as_ref(),
+            content,
+            source,
+        )))
+    }
+
     pub fn toml(path: impl AsRef<Path>, source: toml::de::Error) -> Self {
         Self::Toml {
             path: path.as_ref().display().to_string(),
@@ -112,6 +123,86 @@ impl serde::Serialize for AppError {
     }
 }
 
+/// 命令错误码，前端据此选择本地化文案；未识别的错误码可直接显示 `message`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum CommandErrorCode {
+    DowngradeRefused,
+    InvalidVersion,
+    InvalidDownloadUrl,
+    UnsupportedDownloadScheme,
+    UntrustedDownloadHost,
+    UntrustedRedirectHost,
+    InvalidRequestHeader,
+    ForbiddenRequestHeader,
+    CacheDirCreateFailed,
+    HttpClientFailed,
+    DownloadRequestFailed,
+    DownloadResponseFailed,
+    SuspiciousDownloadContent,
+    InsufficientDiskSpace,
+    DownloadWriteFailed,
+    ChecksumMismatch,
+    NoPlatformAsset,
+    DownloadInProgress,
+    InstallerLaunchFailed,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    InstallerSignatureInvalid,
+    OpenPackageFailed,
+    VersionCheckFailed,
+    GithubRateLimited,
+}
+
+impl CommandErrorCode {
+    /// 默认（中文）提示文案
+    pub fn default_message(self) -> &'static str {
+        match self {
+            Self::DowngradeRefused => "目标版本低于当前版本",
+            Self::InvalidVersion => "无效的版本号",
+            Self::InvalidDownloadUrl => "无效的下载链接",
+            Self::UnsupportedDownloadScheme => "不支持的下载链接协议",
+            Self::UntrustedDownloadHost => "下载链接域名不受信任",
+            Self::UntrustedRedirectHost => "重定向目标域名不受信任",
+            Self::InvalidRequestHeader => "无效的请求头",
+            Self::ForbiddenRequestHeader => "不允许自定义该请求头",
+            Self::CacheDirCreateFailed => "创建缓存目录失败",
+            Self::HttpClientFailed => "创建下载客户端失败",
+            Self::DownloadRequestFailed => "下载请求失败",
+            Self::DownloadResponseFailed => "下载响应异常",
+            Self::SuspiciousDownloadContent => "下载内容异常（疑似错误页面）",
+            Self::InsufficientDiskSpace => "磁盘空间不足",
+            Self::DownloadWriteFailed => "写入下载文件失败",
+            Self::ChecksumMismatch => "安装包校验失败",
+            Self::NoPlatformAsset => "未找到适用于当前系统的安装包",
+            Self::DownloadInProgress => "下载已在进行中",
+            Self::InstallerLaunchFailed => "启动安装程序失败",
+            Self::InstallerSignatureInvalid => "安装包签名验证失败",
+            Self::OpenPackageFailed => "打开安装包失败",
+            Self::VersionCheckFailed => "查询最新版本失败",
+            Self::GithubRateLimited => "GitHub API 请求过于频繁，请稍后再试",
+        }
+    }
+}
+
+/// 格式化为带错误码的 JSON 错误字符串：`{"code", "message", "detail"}`
+///
+/// `message` 为中文默认文案（附带 `detail`），前端可按 `code` 本地化并单独展示 `detail`。
+pub fn format_command_error(code: CommandErrorCode, detail: Option<&str>) -> String {
+    use serde_json::json;
+
+    let message = match detail {
+        Some(detail) => format!("{}: {detail}", code.default_message()),
+        None => code.default_message().to_string(),
+    };
+
+    let error_obj = json!({
+        "code": code,
+        "message": message,
+        "detail": detail,
+    });
+
+    serde_json::to_string(&error_obj).unwrap_or(message)
+}
+
 /// 格式化为 JSON 错误字符串，前端可解析为结构化错误
 pub fn format_skill_error(
     code: &str,
@@ -277,6 +277,11 @@ pub fn validate_gemini_settings_strict(settings: &Value) -> Result<(), AppError>
     Ok(())
 }
 
+/// API Key 模式的 `security.auth.selectedType`
+pub(crate) const SELECTED_TYPE_API_KEY: &str = "gemini-api-key";
+/// Google OAuth 模式的 `security.auth.selectedType`
+pub(crate) const SELECTED_TYPE_OAUTH: &str = "oauth-personal";
+
 /// 获取 Gemini settings.json 文件路径
 ///
 /// 返回路径：`~/.gemini/settings.json`（与 `.env` 文件同级）
@@ -310,8 +315,17 @@ fn update_selected_type(selected_type: &str) -> Result<(), AppError> {
         serde_json::json!({})
     };
 
-    // 只更新 security.auth.selectedType 字段
-    if let Some(obj) = settings_content.as_object_mut() {
+    apply_selected_type(&mut settings_content, selected_type);
+
+    // 写入文件
+    crate::config::write_json_file(&settings_path, &settings_content)?;
+
+    Ok(())
+}
+
+/// 只更新 `security.auth.selectedType` 字段，保留其他所有字段
+pub(crate) fn apply_selected_type(settings: &mut Value, selected_type: &str) {
+    if let Some(obj) = settings.as_object_mut() {
         let security = obj
             .entry("security")
             .or_insert_with(|| serde_json::json!({}));
@@ -329,11 +343,6 @@ fn update_selected_type(selected_type: &str) -> Result<(), AppError> {
             }
         }
     }
-
-    // 写入文件
-    crate::config::write_json_file(&settings_path, &settings_content)?;
-
-    Ok(())
 }
 
 /// 为 Packycode Gemini 供应商写入 settings.json
@@ -351,7 +360,7 @@ fn update_selected_type(selected_type: &str) -> Result<(), AppError> {
 ///
 /// 保留文件中的其他所有字段。
 pub fn write_packycode_settings() -> Result<(), AppError> {
-    update_selected_type("gemini-api-key")
+    update_selected_type(SELECTED_TYPE_API_KEY)
 }
 
 /// 为 Google 官方 Gemini 供应商写入 settings.json（OAuth 模式）
@@ -369,7 +378,7 @@ pub fn write_packycode_settings() -> Result<(), AppError> {
 ///
 /// 保留文件中的其他所有字段。
 pub fn write_google_oauth_settings() -> Result<(), AppError> {
-    update_selected_type("oauth-personal")
+    update_selected_type(SELECTED_TYPE_OAUTH)
 }
 
 #[cfg(test)]
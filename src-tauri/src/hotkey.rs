@@ -0,0 +1,178 @@
+//! 全局快捷键
+//!
+//! 按下设置中的快捷键时，将当前聚焦工具（主界面所选的应用）切换到下一个供应商，
+//! 并通过系统通知提示新的供应商名称。
+
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+static ACTIVE_TOOL: OnceLock<RwLock<AppType>> = OnceLock::new();
+
+fn active_tool_cell() -> &'static RwLock<AppType> {
+    ACTIVE_TOOL.get_or_init(|| RwLock::new(AppType::Claude))
+}
+
+/// 记录主界面当前聚焦的工具（快捷键作用于该工具）
+pub fn set_active_tool(app_type: AppType) {
+    *active_tool_cell()
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = app_type;
+}
+
+/// 主界面当前聚焦的工具，默认 Claude
+pub fn active_tool() -> AppType {
+    active_tool_cell()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// 解析并校验快捷键字符串（如 `CmdOrCtrl+Shift+P`）
+pub fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    let trimmed = accelerator.trim();
+    if trimmed.is_empty() {
+        return Err("快捷键不能为空".to_string());
+    }
+    Shortcut::from_str(trimmed).map_err(|e| format!("无效的快捷键 '{trimmed}': {e}"))
+}
+
+/// 注册快捷键；已被系统或其他应用占用时返回错误
+pub fn register(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcut = parse_accelerator(accelerator)?;
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                let app = app.clone();
+                tauri::async_runtime::spawn_blocking(move || cycle_provider(&app));
+            }
+        })
+        .map_err(|e| {
+            format!(
+                "快捷键 '{}' 已被系统或其他应用占用: {e}",
+                accelerator.trim()
+            )
+        })
+}
+
+/// 注销快捷键（未注册时忽略）
+pub fn unregister(app: &AppHandle, accelerator: &str) {
+    let Ok(shortcut) = parse_accelerator(accelerator) else {
+        return;
+    };
+    if app.global_shortcut().is_registered(shortcut) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut) {
+            log::warn!("注销快捷键 {accelerator} 失败: {e}");
+        }
+    }
+}
+
+/// 替换快捷键：先注销旧快捷键再注册新快捷键，注册失败时恢复旧快捷键
+pub fn replace(app: &AppHandle, old: Option<&str>, new: Option<&str>) -> Result<(), String> {
+    if let Some(new) = new {
+        parse_accelerator(new)?;
+    }
+    if let Some(old) = old {
+        unregister(app, old);
+    }
+    let Some(new) = new else {
+        return Ok(());
+    };
+    if let Err(e) = register(app, new) {
+        if let Some(old) = old {
+            if let Err(restore_err) = register(app, old) {
+                log::warn!("恢复原快捷键 {old} 失败: {restore_err}");
+            }
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 启动时注册设置中保存的快捷键
+pub fn register_saved(app: &AppHandle) {
+    if let Some(accelerator) = crate::settings::get_settings().switch_hotkey {
+        match register(app, &accelerator) {
+            Ok(()) => log::info!("✓ 已注册切换快捷键 {accelerator}"),
+            Err(e) => log::warn!("注册切换快捷键失败: {e}"),
+        }
+    }
+}
+
+/// 按显示顺序取当前供应商的下一个（末尾回到第一个）
+fn next_provider_id(ordered_ids: &[&String], current: &str) -> Option<String> {
+    if ordered_ids.is_empty() {
+        return None;
+    }
+    let next = ordered_ids
+        .iter()
+        .position(|id| id.as_str() == current)
+        .map(|idx| (idx + 1) % ordered_ids.len())
+        .unwrap_or(0);
+    Some(ordered_ids[next].clone())
+}
+
+fn cycle_provider(app: &AppHandle) {
+    let app_type = active_tool();
+    match switch_to_next(app, app_type.clone()) {
+        Ok(Some(name)) => crate::show_notification(app, &format!("{}: {name}", app_type.as_str())),
+        Ok(None) => log::info!("{} 没有可切换的供应商", app_type.as_str()),
+        Err(e) => {
+            log::error!("快捷键切换供应商失败: {e}");
+            crate::show_notification(app, &format!("切换供应商失败: {e}"));
+        }
+    }
+}
+
+fn switch_to_next(app: &AppHandle, app_type: AppType) -> Result<Option<String>, AppError> {
+    use tauri::Manager;
+
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(None);
+    };
+    let providers = ProviderService::list(&state, app_type.clone())?;
+    let current = ProviderService::current(&state, app_type.clone())?;
+    let ordered = crate::tray::sorted_providers(&providers);
+    let ids: Vec<&String> = ordered.iter().map(|(id, _)| *id).collect();
+    let Some(next_id) = next_provider_id(&ids, &current) else {
+        return Ok(None);
+    };
+    if next_id == current {
+        return Ok(None);
+    }
+    let name = providers
+        .get(&next_id)
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| next_id.clone());
+    crate::tray::switch_provider_internal(app, app_type, next_id)?;
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_provider_wraps_around() {
+        let (a, b, c) = ("a".to_string(), "b".to_string(), "c".to_string());
+        let ids = vec![&a, &b, &c];
+        assert_eq!(next_provider_id(&ids, "a").as_deref(), Some("b"));
+        assert_eq!(next_provider_id(&ids, "c").as_deref(), Some("a"));
+        assert_eq!(next_provider_id(&ids, "missing").as_deref(), Some("a"));
+        assert_eq!(next_provider_id(&[], "a"), None);
+    }
+
+    #[test]
+    fn parse_accelerator_validates_input() {
+        assert!(parse_accelerator("CmdOrCtrl+Shift+P").is_ok());
+        assert!(parse_accelerator("  ").is_err());
+        assert!(parse_accelerator("Ctrl+NotAKey").is_err());
+    }
+}
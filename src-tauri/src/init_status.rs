@@ -1,30 +1,98 @@
 use serde::Serialize;
 use std::sync::{OnceLock, RwLock};
 
+/// 初始化错误分类，供前端展示对应的帮助与恢复操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum InitErrorKind {
+    /// 数据库被其它进程占用
+    DbLocked,
+    /// 目录或文件无读写权限
+    PermissionDenied,
+    /// JSON → SQLite 迁移失败（可重新迁移）
+    MigrationFailed,
+    /// 数据库或配置文件已损坏
+    Corrupt,
+    Unknown,
+}
+
+impl InitErrorKind {
+    /// 根据错误信息推断分类（SQLite / IO 错误文本）
+    pub fn classify(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("database is locked") || lower.contains("database is busy") {
+            Self::DbLocked
+        } else if lower.contains("permission denied")
+            || lower.contains("read-only")
+            || lower.contains("readonly")
+            || lower.contains("access is denied")
+        {
+            Self::PermissionDenied
+        } else if lower.contains("malformed") || lower.contains("not a database") {
+            Self::Corrupt
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct InitErrorPayload {
+    pub kind: InitErrorKind,
     pub path: String,
+    /// 展示给用户的错误信息
     pub error: String,
 }
 
 static INIT_ERROR: OnceLock<RwLock<Option<InitErrorPayload>>> = OnceLock::new();
 
+/// 绑定后，初始化错误的记录与清除会以事件通知前端
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
 fn cell() -> &'static RwLock<Option<InitErrorPayload>> {
     INIT_ERROR.get_or_init(|| RwLock::new(None))
 }
 
-#[allow(dead_code)]
+/// 绑定 AppHandle（应用 setup 开始时调用）
+///
+/// 绑定前记录的错误不会发送事件，前端仍需通过 `get_init_error` 主动拉取一次。
+pub fn attach_app_handle(handle: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(handle);
+}
+
+fn emit(event: &str, payload: impl Serialize + Clone) {
+    use tauri::Emitter;
+
+    if let Some(handle) = APP_HANDLE.get() {
+        if let Err(e) = handle.emit(event, payload) {
+            log::warn!("发射 {event} 事件失败: {e}");
+        }
+    }
+}
+
+/// 记录初始化错误并发送 `init-error` 事件
 pub fn set_init_error(payload: InitErrorPayload) {
     #[allow(clippy::unwrap_used)]
     if let Ok(mut guard) = cell().write() {
-        *guard = Some(payload);
+        *guard = Some(payload.clone());
     }
+    emit("init-error", payload);
 }
 
 pub fn get_init_error() -> Option<InitErrorPayload> {
     cell().read().ok()?.clone()
 }
 
+/// 清除初始化错误（前端确认提示后调用）；此前存在错误时发送 `init-error-cleared` 事件
+pub fn clear_init_error() {
+    let had_error = match cell().write() {
+        Ok(mut guard) => guard.take().is_some(),
+        Err(_) => false,
+    };
+    if had_error {
+        emit("init-error-cleared", ());
+    }
+}
+
 // ============================================================
 // 迁移结果状态
 // ============================================================
@@ -52,6 +120,71 @@ pub fn take_migration_success() -> bool {
     }
 }
 
+// ============================================================
+// 迁移前备份路径
+// ============================================================
+
+static MIGRATION_BACKUP_PATH: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+
+fn migration_backup_cell() -> &'static RwLock<Option<String>> {
+    MIGRATION_BACKUP_PATH.get_or_init(|| RwLock::new(None))
+}
+
+/// 记录本次启动迁移前生成的 config.json 备份路径
+pub fn set_migration_backup_path(path: String) {
+    if let Ok(mut guard) = migration_backup_cell().write() {
+        *guard = Some(path);
+    }
+}
+
+/// 获取本次启动迁移前生成的备份路径（未发生迁移时为 None）
+pub fn get_migration_backup_path() -> Option<String> {
+    migration_backup_cell().read().ok()?.clone()
+}
+
+// ============================================================
+// 迁移失败详情
+// ============================================================
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationErrorPayload {
+    /// 失败阶段：`load`（读取 config.json）/ `migrate`（写入数据库）/ `schema`（数据库结构升级）
+    pub stage: String,
+    pub message: String,
+    /// 迁移前生成的 config.json 备份（若有）
+    pub backup_path: Option<String>,
+}
+
+static MIGRATION_ERROR: OnceLock<RwLock<Option<MigrationErrorPayload>>> = OnceLock::new();
+
+fn migration_error_cell() -> &'static RwLock<Option<MigrationErrorPayload>> {
+    MIGRATION_ERROR.get_or_init(|| RwLock::new(None))
+}
+
+/// 记录迁移失败详情，自动附带本次迁移前的备份路径
+pub fn set_migration_error(stage: &str, message: String) {
+    let payload = MigrationErrorPayload {
+        stage: stage.to_string(),
+        message,
+        backup_path: get_migration_backup_path(),
+    };
+    if let Ok(mut guard) = migration_error_cell().write() {
+        *guard = Some(payload);
+    }
+}
+
+/// 清除迁移失败详情（如用户重试加载成功后）
+pub fn clear_migration_error() {
+    if let Ok(mut guard) = migration_error_cell().write() {
+        *guard = None;
+    }
+}
+
+pub fn get_migration_error() -> Option<MigrationErrorPayload> {
+    migration_error_cell().read().ok()?.clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,12 +192,44 @@ mod tests {
     #[test]
     fn init_error_roundtrip() {
         let payload = InitErrorPayload {
+            kind: InitErrorKind::Corrupt,
             path: "/tmp/config.json".into(),
             error: "broken json".into(),
         };
         set_init_error(payload.clone());
         let got = get_init_error().expect("should get payload back");
+        assert_eq!(got.kind, InitErrorKind::Corrupt);
         assert_eq!(got.path, payload.path);
         assert_eq!(got.error, payload.error);
     }
+
+    #[test]
+    fn migration_error_includes_backup_path() {
+        set_migration_backup_path("/tmp/config.backup-20250101_000000.json".into());
+        set_migration_error("migrate", "disk full".into());
+        let got = get_migration_error().expect("should get migration error");
+        assert_eq!(got.stage, "migrate");
+        assert_eq!(got.message, "disk full");
+        assert_eq!(
+            got.backup_path.as_deref(),
+            Some("/tmp/config.backup-20250101_000000.json")
+        );
+    }
+
+    #[test]
+    fn init_error_kind_classifies_common_failures() {
+        assert_eq!(
+            InitErrorKind::classify("数据库错误: database is locked"),
+            InitErrorKind::DbLocked
+        );
+        assert_eq!(
+            InitErrorKind::classify("Permission denied (os error 13)"),
+            InitErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            InitErrorKind::classify("database disk image is malformed"),
+            InitErrorKind::Corrupt
+        );
+        assert_eq!(InitErrorKind::classify("boom"), InitErrorKind::Unknown);
+    }
 }
@@ -1,8 +1,10 @@
 mod app_config;
 mod app_store;
+mod appearance;
 mod auto_launch;
 mod claude_mcp;
 mod claude_plugin;
+mod cli;
 mod codex_config;
 mod commands;
 mod config;
@@ -11,18 +13,26 @@ mod deeplink;
 mod error;
 mod gemini_config;
 mod gemini_mcp;
+mod hotkey;
 mod init_status;
+mod logging;
 mod mcp;
+mod no_proxy;
 mod prompt;
 mod prompt_files;
 mod provider;
 mod provider_defaults;
 mod proxy;
+mod secret_store;
 mod services;
 mod settings;
+mod shell_args;
 mod store;
 mod tray;
+mod update_check;
 mod usage_script;
+mod version_prefetch;
+mod window_state;
 
 pub use app_config::{AppType, McpApps, McpServer, MultiAppConfig};
 pub use codex_config::{get_codex_auth_path, get_codex_config_path, write_codex_live_atomic};
@@ -39,8 +49,9 @@ pub use mcp::{
 };
 pub use provider::{Provider, ProviderMeta};
 pub use services::{
-    ConfigService, EndpointLatency, McpService, PromptService, ProviderService, ProxyService,
-    SkillService, SpeedtestService,
+    ConfigBackupEntry, ConfigService, EndpointLatency, ImportStrategy, McpService, ProfileService,
+    PromptService, ProviderImportStatus, ProviderService, ProxyService, SkillService,
+    SpeedtestService,
 };
 pub use settings::{update_settings, AppSettings};
 pub use store::AppState;
@@ -57,8 +68,9 @@ use tauri::{Emitter, Manager};
 /// 统一处理 ccswitch:// 深链接 URL
 ///
 /// - 解析 URL
-/// - 向前端发射 `deeplink-import` / `deeplink-error` 事件
-/// - 可选：在成功时聚焦主窗口
+/// - `switch` 动作：校验并切换供应商，发射 `deeplink-switched` 事件并聚焦主窗口
+/// - 导入动作：向前端发射 `deeplink-import` 事件，可选在成功时聚焦主窗口
+/// - 失败时发射 `deeplink-error` 事件
 fn handle_deeplink_url(
     app: &tauri::AppHandle,
     url_str: &str,
@@ -71,8 +83,39 @@ fn handle_deeplink_url(
 
     log::info!("✓ Deep link URL detected from {source}: {url_str}");
 
-    match crate::deeplink::parse_deeplink_url(url_str) {
-        Ok(request) => {
+    match crate::deeplink::parse_deeplink_action(url_str) {
+        Ok(crate::deeplink::DeepLinkAction::Switch(request)) => {
+            log::info!(
+                "✓ Successfully parsed switch deep link: tool={}, provider={}",
+                request.tool.as_str(),
+                request.provider
+            );
+
+            match switch_provider_from_deeplink(app, &request) {
+                Ok(provider_name) => {
+                    let payload = serde_json::json!({
+                        "appType": request.tool.as_str(),
+                        "providerId": request.provider,
+                        "providerName": provider_name,
+                    });
+                    if let Err(e) = app.emit("deeplink-switched", payload) {
+                        log::error!("✗ Failed to emit deeplink-switched event: {e}");
+                    }
+                }
+                Err(e) => {
+                    log::error!("✗ Failed to switch provider from deep link: {e}");
+                    emit_deeplink_error(app, url_str, &e.to_string());
+                }
+            }
+
+            // 无论成功与否都聚焦主窗口，便于用户看到提示
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        Ok(crate::deeplink::DeepLinkAction::Import(request)) => {
             log::info!(
                 "✓ Successfully parsed deep link: resource={}, app={:?}, name={:?}",
                 request.resource,
@@ -97,22 +140,66 @@ fn handle_deeplink_url(
         }
         Err(e) => {
             log::error!("✗ Failed to parse deep link URL: {e}");
-
-            if let Err(emit_err) = app.emit(
-                "deeplink-error",
-                serde_json::json!({
-                    "url": url_str,
-                    "error": e.to_string()
-                }),
-            ) {
-                log::error!("✗ Failed to emit deeplink-error event: {emit_err}");
-            }
+            emit_deeplink_error(app, url_str, &e.to_string());
         }
     }
 
     true
 }
 
+/// 校验并执行深链接中的供应商切换，返回切换后的供应商名称
+fn switch_provider_from_deeplink(
+    app: &tauri::AppHandle,
+    request: &crate::deeplink::DeepLinkSwitchRequest,
+) -> Result<String, AppError> {
+    let state = app
+        .try_state::<AppState>()
+        .ok_or_else(|| AppError::Message("应用尚未初始化完成".to_string()))?;
+    let provider_name = crate::deeplink::resolve_switch_target(&state, request)?;
+    tray::switch_provider_internal(app, request.tool.clone(), request.provider.clone())?;
+    Ok(provider_name)
+}
+
+/// 立即写入尚在合并窗口内的设置（关闭窗口与退出时调用）
+fn flush_pending_settings(app: &tauri::AppHandle) {
+    if let Some(state) = app.try_state::<AppState>() {
+        if let Err(e) = state.db.flush_settings() {
+            log::warn!("写入设置失败: {e}");
+        }
+    }
+}
+
+fn emit_deeplink_error(app: &tauri::AppHandle, url_str: &str, error: &str) {
+    if let Err(emit_err) = app.emit(
+        "deeplink-error",
+        serde_json::json!({
+            "url": url_str,
+            "error": error
+        }),
+    ) {
+        log::error!("✗ Failed to emit deeplink-error event: {emit_err}");
+    }
+}
+
+/// 显示系统通知（标题为应用名），已在设置中关闭通知时不显示
+pub(crate) fn show_notification(app: &tauri::AppHandle, body: &str) {
+    use tauri_plugin_notification::NotificationExt;
+
+    if !crate::settings::get_settings().notifications_enabled {
+        return;
+    }
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("AiCodeWith")
+        .body(body)
+        .show()
+    {
+        log::warn!("显示系统通知失败: {e}");
+    }
+}
+
 /// 更新托盘菜单的Tauri命令
 #[tauri::command]
 async fn update_tray_menu(
@@ -150,10 +237,35 @@ fn macos_tray_icon() -> Option<Image<'static>> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 初始化文件日志（按天滚动，写入前遮盖密钥）
+    logging::init();
+
+    // 命令行操作：--no-window 切换、--list-providers / --tool-versions 查询时不启动界面，执行后直接退出
+    let raw_args = cli::utf8_args(std::env::args_os().skip(1));
+    let launch_args = match cli::parse_launch_args(&raw_args) {
+        Ok(args) => args,
+        Err(e) => {
+            let json = cli::wants_json(&raw_args);
+            std::process::exit(cli::report_usage_error(&e, json));
+        }
+    };
+    if launch_args.headless_action().is_some() {
+        std::process::exit(cli::run_headless(&launch_args));
+    }
+
     let mut builder = tauri::Builder::default();
 
+    // 便携模式：单实例插件按应用标识区分实例，会把便携副本的启动转发给已安装版本，
+    // 因此改为按数据目录加锁，同一便携副本重复启动时直接退出。
+    let portable = crate::config::is_portable_mode();
+    if portable && !crate::config::acquire_portable_instance_lock() {
+        eprintln!("该便携版目录已有实例在运行");
+        std::process::exit(0);
+    }
+
     #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
-    {
+    if !portable {
+        // 第二次启动时将参数转发给已运行的实例并聚焦其窗口，避免两个实例争用数据库与配置文件
         builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             log::info!("=== Single Instance Callback Triggered ===");
             log::info!("Args count: {}", args.len());
@@ -174,6 +286,21 @@ pub fn run() {
                 log::info!("ℹ No deep link URL found in args (this is expected on macOS when launched via system)");
             }
 
+            // 已有实例运行时，将 --use 参数转交给当前实例执行切换
+            match cli::parse_launch_args(args.iter().skip(1)) {
+                Ok(cli::LaunchArgs {
+                    use_provider: Some(provider_id),
+                    tool,
+                    ..
+                }) => {
+                    if let Err(e) = tray::switch_provider_internal(app, tool, provider_id) {
+                        log::error!("命令行切换供应商失败: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("忽略无效的启动参数: {e}"),
+            }
+
             // Show and focus window regardless
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.unminimize();
@@ -188,7 +315,23 @@ pub fn run() {
         .plugin(tauri_plugin_deep_link::init())
         // 拦截窗口关闭：根据设置决定是否最小化到托盘
         .on_window_event(|window, event| {
+            if matches!(
+                event,
+                tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+            ) && window.label() == "main"
+            {
+                if let Some(webview) = window.app_handle().get_webview_window("main") {
+                    window_state::track(&webview);
+                }
+            }
+            if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                if window.label() == "main" {
+                    appearance::on_theme_changed(window.app_handle(), *theme);
+                }
+            }
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                window_state::save();
+                flush_pending_settings(window.app_handle());
                 let settings = crate::settings::get_settings();
 
                 if settings.minimize_to_tray_on_close {
@@ -210,8 +353,14 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::new().build())
-        .setup(|app| {
+        .manage(commands::DownloadLocks::default())
+        .setup(move |app| {
+            crate::init_status::attach_app_handle(app.handle().clone());
+
             // 注册 Updater 插件（桌面端）
             #[cfg(desktop)]
             {
@@ -223,20 +372,14 @@ pub fn run() {
                     log::warn!("初始化 Updater 插件失败，已跳过：{e}");
                 }
             }
-            // 初始化日志
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
-
             // 修正桌面端窗口标题显示（部分环境下会显示为默认值或内部标识）
             if let Some(window) = app.handle().get_webview_window("main") {
                 if let Err(err) = window.set_title("AiCodeWith") {
                     log::warn!("设置窗口标题失败: {err}");
                 }
+                // 恢复上次关闭时的窗口尺寸与位置
+                window_state::restore(&window);
+                appearance::apply(&window, crate::settings::get_settings().appearance);
             }
 
             // 预先刷新 Store 覆盖配置，确保 AppState 初始化时可读取到最新路径
@@ -247,10 +390,35 @@ pub fn run() {
             let db_path = app_config_dir.join("cc-switch.db");
             let json_path = app_config_dir.join("config.json");
 
+            // 用户重置过迁移状态：先将未完成迁移留下的数据库移到一旁，以便重新迁移
+            match crate::services::migration_lock::apply_pending_retry(&app_config_dir, &db_path) {
+                Ok(moved) => {
+                    for path in moved {
+                        log::info!("✓ 已移走未完成迁移的数据库: {}", path.display());
+                    }
+                }
+                Err(e) => log::warn!("处理迁移重试失败: {e}"),
+            }
+
             // 检查是否需要从 config.json 迁移到 SQLite
             let has_json = json_path.exists();
             let has_db = db_path.exists();
 
+            // 上次迁移中途退出：数据库可能不完整，提示用户重置迁移状态
+            if has_db {
+                if let Some(lock_path) =
+                    crate::services::migration_lock::stale_lock(&app_config_dir)
+                {
+                    log::warn!("检测到未完成的迁移: {}", lock_path.display());
+                    crate::init_status::set_init_error(crate::init_status::InitErrorPayload {
+                        kind: crate::init_status::InitErrorKind::MigrationFailed,
+                        path: lock_path.to_string_lossy().to_string(),
+                        error: "上次配置迁移未完成，数据可能不完整。请重置迁移状态后重启应用"
+                            .to_string(),
+                    });
+                }
+            }
+
             // 如果需要迁移，先验证 config.json 是否可以加载（在创建数据库之前）
             // 这样如果加载失败用户选择退出，数据库文件还没被创建，下次可以正常重试
             let migration_config = if !has_db && has_json {
@@ -261,10 +429,12 @@ pub fn run() {
                     match crate::app_config::MultiAppConfig::load() {
                         Ok(config) => {
                             log::info!("✓ 配置文件加载成功");
+                            crate::init_status::clear_migration_error();
                             break Some(config);
                         }
                         Err(e) => {
                             log::error!("加载旧配置文件失败: {e}");
+                            crate::init_status::set_migration_error("load", e.to_string());
                             // 弹出系统对话框让用户选择
                             if !show_migration_error_dialog(app.handle(), &e.to_string()) {
                                 // 用户选择退出（此时数据库还没创建，下次启动可以重试）
@@ -280,17 +450,64 @@ pub fn run() {
                 None
             };
 
+            // 加载 API Key 加密密钥（系统钥匙串不可用时退回明文模式）
+            crate::secret_store::init(crate::settings::get_settings().require_unlock);
+
+            // 迁移期间持有锁，中途崩溃时锁文件残留，下次启动可据此发现
+            let migration_guard = match migration_config {
+                Some(_) => match crate::services::migration_lock::acquire(&app_config_dir) {
+                    Ok(guard) => Some(guard),
+                    Err(e) => {
+                        log::warn!("获取迁移锁失败: {e}");
+                        None
+                    }
+                },
+                None => None,
+            };
+
             // 现在创建数据库
             let db = match crate::database::Database::init() {
                 Ok(db) => Arc::new(db),
                 Err(e) => {
                     log::error!("Failed to init database: {e}");
-                    return Err(Box::new(e));
+                    let kind = crate::init_status::InitErrorKind::classify(&e.to_string());
+                    crate::init_status::set_init_error(crate::init_status::InitErrorPayload {
+                        kind,
+                        path: db_path.to_string_lossy().to_string(),
+                        error: e.to_string(),
+                    });
+                    // 数据库损坏时以内存数据库降级启动，界面据此提示用户执行 repair_store
+                    if kind != crate::init_status::InitErrorKind::Corrupt {
+                        return Err(Box::new(e));
+                    }
+                    match crate::database::Database::memory() {
+                        Ok(db) => {
+                            log::warn!("数据库已损坏，以内存数据库降级启动，修复前的修改不会保存");
+                            Arc::new(db)
+                        }
+                        Err(_) => return Err(Box::new(e)),
+                    }
                 }
             };
+            // 设置写入合并：高频写入（如窗口状态）只在空闲后批量落盘
+            if let Err(e) = crate::database::Database::start_settings_flusher(&db) {
+                log::warn!("{e}，设置将立即写入");
+            }
 
             // 如果有预加载的配置，执行迁移
             if let Some(config) = migration_config {
+                let _span = tracing::info_span!("config_migration").entered();
+                // 迁移前先备份 config.json，迁移出错时用户可手动恢复
+                match crate::services::ConfigService::create_migration_backup(&json_path) {
+                    Ok(backup_path) => {
+                        log::info!("✓ 已备份旧配置文件: {}", backup_path.display());
+                        crate::init_status::set_migration_backup_path(
+                            backup_path.to_string_lossy().to_string(),
+                        );
+                    }
+                    Err(e) => log::warn!("备份旧配置文件失败: {e}"),
+                }
+
                 log::info!("开始执行数据迁移...");
 
                 match db.migrate_from_json(&config) {
@@ -307,11 +524,26 @@ pub fn run() {
                         }
                     }
                     Err(e) => {
-                        // 配置加载成功但迁移失败的情况极少（磁盘满等），仅记录日志
+                        // 配置加载成功但迁移失败的情况极少（磁盘满等），仅记录日志。
+                        // 迁移在单个事务中执行，失败时数据库已回滚，备份文件保留
                         log::error!("配置迁移失败: {e}，将从现有配置导入");
+                        crate::init_status::set_migration_error("migrate", e.to_string());
+                        crate::init_status::set_init_error(crate::init_status::InitErrorPayload {
+                            kind: crate::init_status::InitErrorKind::MigrationFailed,
+                            path: json_path.to_string_lossy().to_string(),
+                            error: format!("配置迁移失败: {e}"),
+                        });
                     }
                 }
             }
+            drop(migration_guard);
+
+            // 首次启用加密时将已有的明文 API Key 加密（执行前自动备份数据库）
+            match db.encrypt_plaintext_secrets() {
+                Ok(0) => {}
+                Ok(count) => log::info!("✓ 已加密 {count} 个供应商的 API Key"),
+                Err(e) => log::warn!("加密已有 API Key 失败: {e}"),
+            }
 
             let app_state = AppState::new(db);
 
@@ -513,6 +745,34 @@ pub fn run() {
             // 将同一个实例注入到全局状态，避免重复创建导致的不一致
             app.manage(app_state);
 
+            // 处理启动参数中的 --use：切换供应商并刷新托盘菜单
+            if let Some(provider_id) = launch_args.use_provider.clone() {
+                if let Err(e) = tray::switch_provider_internal(
+                    app.handle(),
+                    launch_args.tool.clone(),
+                    provider_id,
+                ) {
+                    log::error!("命令行切换供应商失败: {e}");
+                    eprintln!("切换供应商失败: {e}");
+                }
+            }
+
+            // 按设置校正开机自启（登录项可能被用户或系统清理工具移除）
+            if let Err(e) = crate::auto_launch::reconcile_auto_launch(
+                crate::settings::get_settings().launch_on_startup,
+            ) {
+                log::warn!("校正开机自启失败: {e}");
+            }
+
+            // 注册设置中保存的供应商切换快捷键
+            hotkey::register_saved(app.handle());
+
+            // 后台定时检查应用更新（可在设置中关闭）
+            update_check::start(app.handle().clone());
+
+            // 后台预取 CLI 版本信息，版本面板打开时即可显示（可在设置中开启）
+            version_prefetch::start(app.handle().clone());
+
             // 初始化 SkillService
             match SkillService::new() {
                 Ok(skill_service) => {
@@ -557,23 +817,75 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::get_providers,
             commands::get_current_provider,
+            commands::get_active_provider,
             commands::add_provider,
             commands::update_provider,
             commands::delete_provider,
+            commands::duplicate_provider,
             commands::switch_provider,
+            commands::switch_all_tools,
+            commands::preview_switch,
+            commands::verify_active_config,
+            commands::diff_providers,
+            commands::validate_providers,
+            commands::check_tool_config,
+            commands::reset_invalid_tool_config,
+            commands::undo_last_switch,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::switch_profile,
+            commands::delete_profile,
+            commands::normalize_base_url,
             commands::import_default_config,
+            commands::import_from_tool_config,
+            commands::ensure_tool_config,
             commands::get_claude_config_status,
             commands::get_config_status,
             commands::get_claude_code_config_path,
             commands::get_config_dir,
             commands::open_config_folder,
+            commands::get_tool_config_path,
+            commands::open_tool_config,
             commands::pick_directory,
             commands::open_external,
             commands::get_runtime_platform,
+            commands::reveal_in_file_manager,
+            commands::copy_to_clipboard,
+            commands::get_runtime_arch,
+            commands::is_running_under_rosetta,
+            commands::get_os_version,
+            commands::export_diagnostics,
+            commands::diagnostics_markdown,
+            commands::get_download_metadata,
+            commands::probe_download_link,
+            commands::get_update_download_base,
+            commands::set_update_download_base,
             commands::download_and_open_update_package,
+            commands::get_msi_install_status,
             commands::get_init_error,
+            commands::clear_init_error,
             commands::get_migration_result,
+            commands::get_migration_error,
+            commands::rerun_migration,
+            commands::reset_migration_state,
+            commands::repair_store,
             commands::get_app_config_path,
+            commands::get_data_dir,
+            commands::open_data_dir,
+            commands::migrate_data_location,
+            commands::start_config_watcher,
+            commands::stop_config_watcher,
+            commands::is_keystore_available,
+            commands::get_log_dir,
+            commands::read_recent_logs,
+            commands::is_locked,
+            commands::unlock_store,
+            commands::lock_store,
+            commands::list_config_backups,
+            commands::prune_backups,
+            commands::export_config_json,
+            commands::export_providers,
+            commands::import_providers,
             commands::open_app_config_folder,
             commands::get_claude_common_config_snippet,
             commands::set_claude_common_config_snippet,
@@ -582,9 +894,38 @@ pub fn run() {
             commands::read_live_provider_settings,
             commands::get_settings,
             commands::save_settings,
+            commands::get_network_proxy,
+            commands::set_network_proxy,
+            commands::get_npm_registry,
+            commands::set_npm_registry,
+            commands::get_update_channel,
+            commands::set_update_channel,
+            commands::export_settings,
+            commands::import_settings,
+            commands::get_appearance,
+            commands::set_appearance,
+            commands::get_update_source,
+            commands::set_update_source,
+            commands::get_auto_update_check,
+            commands::set_auto_update_check,
+            commands::get_notifications_enabled,
+            commands::set_notifications_enabled,
+            commands::reset_setting,
+            commands::get_prefetch_versions,
+            commands::set_prefetch_versions,
+            commands::get_download_cache_dir,
+            commands::set_download_cache_dir,
+            commands::set_require_unlock,
+            commands::get_log_level,
+            commands::set_log_level,
+            commands::get_switch_hotkey,
+            commands::set_switch_hotkey,
+            commands::set_active_tool,
             commands::restart_app,
             commands::check_for_updates,
+            commands::get_latest_release,
             commands::is_portable_mode,
+            commands::is_app_translocated,
             commands::get_claude_plugin_status,
             commands::read_claude_plugin_config,
             commands::apply_claude_plugin_config,
@@ -628,6 +969,9 @@ pub fn run() {
             commands::set_app_config_dir_override,
             // provider sort order management
             commands::update_providers_sort_order,
+            commands::reorder_providers,
+            commands::search_providers,
+            commands::set_provider_tags,
             // theirs: config import/export and dialogs
             commands::export_config_to_file,
             commands::import_config_from_file,
@@ -644,6 +988,7 @@ pub fn run() {
             commands::check_env_conflicts,
             commands::delete_env_vars,
             commands::restore_env_backup,
+            commands::detect_user_shell,
             // Skill management
             commands::get_skills,
             commands::get_skills_for_app,
@@ -657,6 +1002,9 @@ pub fn run() {
             // Auto launch
             commands::set_auto_launch,
             commands::get_auto_launch_status,
+            commands::is_auto_launch_enabled,
+            commands::reset_window_state,
+            commands::flush_settings,
             // Proxy server management
             commands::start_proxy_server,
             commands::stop_proxy_with_restore,
@@ -698,6 +1046,23 @@ pub fn run() {
             commands::get_stream_check_config,
             commands::save_stream_check_config,
             commands::get_tool_versions,
+            commands::refresh_latest_versions,
+            commands::export_tool_versions,
+            commands::clear_version_cache,
+            commands::run_environment_check,
+            commands::check_node_compatibility,
+            commands::get_managed_tools,
+            commands::test_provider_connection,
+            commands::test_all_providers,
+            commands::validate_api_key,
+            commands::detect_tool_conflicts,
+            commands::list_tool_binaries,
+            commands::set_tool_pinned,
+            commands::install_tool,
+            commands::rollback_tool,
+            commands::get_tool_versions_list,
+            commands::list_tool_versions,
+            commands::check_registry_status,
         ]);
 
     let app = builder
@@ -708,6 +1073,8 @@ pub fn run() {
         // 处理退出请求（所有平台）
         if let RunEvent::ExitRequested { api, .. } = &event {
             log::info!("收到退出请求，开始清理...");
+            window_state::save();
+            flush_pending_settings(app_handle);
             // 阻止立即退出，执行清理
             api.prevent_exit();
 
@@ -0,0 +1,223 @@
+//! 文件日志
+//!
+//! 使用 `tracing` 按天滚动写入 `<数据目录>/logs/cc-switch.YYYY-MM-DD.log`，保留最近若干天。
+//! 代码中现有的 `log::` 宏通过 `tracing-log` 桥接到同一订阅者；写入前统一遮盖密钥。
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+/// 日志文件名前缀
+const LOG_FILE_PREFIX: &str = "cc-switch";
+/// 保留的日志文件数量（按天滚动）
+const LOG_MAX_FILES: usize = 14;
+
+/// 单次读取日志的最大行数
+pub const MAX_TAIL_LINES: usize = 5000;
+/// 从文件末尾向前读取的块大小
+const TAIL_CHUNK_SIZE: u64 = 8 * 1024;
+
+/// 未配置时的默认日志级别
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+static WORKER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+/// 日志目录
+pub fn log_dir() -> PathBuf {
+    crate::config::get_data_dir().join("logs")
+}
+
+/// 当前日志文件（日志目录中最近修改的 `cc-switch.*.log`）
+pub fn current_log_file() -> Option<PathBuf> {
+    std::fs::read_dir(log_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with(LOG_FILE_PREFIX) && name.ends_with(".log")
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// 读取文件最后 `max_lines` 行（从末尾按块向前读取，不加载整个文件）
+pub fn tail_lines(path: &Path, max_lines: usize) -> io::Result<Vec<String>> {
+    if max_lines == 0 {
+        return Ok(Vec::new());
+    }
+    let mut file = File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut newlines = 0;
+    // 末尾通常有换行，需多读一个换行才能保证凑满 max_lines 个完整行
+    while pos > 0 && newlines <= max_lines {
+        let size = TAIL_CHUNK_SIZE.min(pos);
+        pos -= size;
+        let mut chunk = vec![0; size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut chunk)?;
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().collect();
+    // 未读到文件开头时，第一行可能不完整
+    if pos > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let start = lines.len().saturating_sub(max_lines);
+    Ok(lines[start..].iter().map(|l| l.to_string()).collect())
+}
+
+/// 解析日志级别（trace / debug / info / warn / error / off）
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level.trim()).map_err(|_| format!("无效的日志级别: {level}"))
+}
+
+/// 初始化日志（应在启动早期调用一次，重复调用无效）
+///
+/// 级别取设置中的 `logLevel`，未设置或无效时为 INFO。调试构建额外输出到标准输出。
+pub fn init() {
+    if WORKER_GUARD.get().is_some() {
+        return;
+    }
+
+    let level = crate::settings::get_settings()
+        .log_level
+        .as_deref()
+        .and_then(|l| parse_level(l).ok())
+        .unwrap_or(LevelFilter::INFO);
+    let (level_layer, level_handle) = reload::Layer::new(level);
+
+    let dir = log_dir();
+    let file_writer = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(LOG_MAX_FILES)
+        .build(&dir)
+    {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = WORKER_GUARD.set(guard);
+            Some(writer)
+        }
+        Err(e) => {
+            eprintln!("创建日志文件失败 {}: {e}", dir.display());
+            None
+        }
+    };
+
+    let file_layer = file_writer.map(|writer| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(Redacting(writer))
+    });
+    let stdout_layer = cfg!(debug_assertions)
+        .then(|| tracing_subscriber::fmt::layer().with_writer(Redacting(io::stdout)));
+
+    if tracing_subscriber::registry()
+        .with(level_layer)
+        .with(file_layer)
+        .with(stdout_layer)
+        .try_init()
+        .is_ok()
+    {
+        let _ = LEVEL_HANDLE.set(level_handle);
+    }
+}
+
+/// 运行时调整日志级别
+pub fn set_level(level: LevelFilter) -> Result<(), String> {
+    let handle = LEVEL_HANDLE
+        .get()
+        .ok_or_else(|| "日志尚未初始化".to_string())?;
+    handle
+        .reload(level)
+        .map_err(|e| format!("调整日志级别失败: {e}"))
+}
+
+/// 写入前遮盖密钥的 writer 包装
+struct Redacting<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // fmt 层每条日志只写入一次完整缓冲区，可以直接按整条遮盖
+        let text = String::from_utf8_lossy(buf);
+        self.0
+            .write_all(crate::secret_store::redact_secrets(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacting_writer_masks_keys() {
+        let mut writer = RedactingWriter(Vec::new());
+        writer
+            .write_all(b"request failed with key sk-ant-api03-secret\n")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(writer.0).unwrap(),
+            "request failed with key sk-****\n"
+        );
+    }
+
+    #[test]
+    fn tail_lines_reads_from_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cc-switch.log");
+        let content: String = (0..3000).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&path, content).unwrap();
+
+        assert_eq!(
+            tail_lines(&path, 3).unwrap(),
+            ["line 2997", "line 2998", "line 2999"]
+        );
+        let many = tail_lines(&path, 2500).unwrap();
+        assert_eq!(many.len(), 2500);
+        assert_eq!(many[0], "line 500");
+        assert_eq!(tail_lines(&path, 10_000).unwrap().len(), 3000);
+        assert!(tail_lines(&path, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_level_accepts_known_levels() {
+        assert_eq!(parse_level("INFO"), Ok(LevelFilter::INFO));
+        assert_eq!(parse_level(" debug "), Ok(LevelFilter::DEBUG));
+        assert!(parse_level("verbose").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! `NO_PROXY` 代理排除规则
+//!
+//! 使用自定义代理时，命中排除规则的主机直连（如企业内网的 npm 镜像）。规则以逗号分隔，
+//! 支持 `*`（全部直连）、`.example.com` / `example.com`（域名及其子域名）以及 `*` 通配符
+//! （如 `*.corp.internal`、`10.0.*`）。条目中的端口会被忽略。
+
+/// 解析后的排除规则
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoProxyRules {
+    entries: Vec<String>,
+}
+
+impl NoProxyRules {
+    /// 读取 `NO_PROXY` / `no_proxy` 环境变量
+    pub fn from_env() -> Self {
+        let value = ["NO_PROXY", "no_proxy"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok().filter(|v| !v.trim().is_empty()))
+            .unwrap_or_default();
+        Self::parse(&value)
+    }
+
+    pub fn parse(value: &str) -> Self {
+        let entries = value
+            .split([',', ' '])
+            .map(|entry| strip_port(entry.trim()).to_ascii_lowercase())
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 主机是否应绕过代理直连
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        self.entries.iter().any(|entry| {
+            if entry.contains('*') {
+                return wildcard_match(entry, &host);
+            }
+            let domain = entry.trim_start_matches('.');
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// 去掉条目末尾的 `:端口`（IPv6 地址需带方括号才会识别端口）
+fn strip_port(entry: &str) -> &str {
+    let entry = match entry.rsplit_once(':') {
+        Some((host, port))
+            if !port.is_empty()
+                && port.chars().all(|c| c.is_ascii_digit())
+                && (!host.contains(':') || host.ends_with(']')) =>
+        {
+            host
+        }
+        _ => entry,
+    };
+    entry.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// `*` 匹配任意长度（含空）的字符
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().expect("split yields at least one part");
+    let Some(mut remaining) = text.strip_prefix(first) else {
+        return false;
+    };
+    let Some((last, middle)) = rest.split_last() else {
+        return remaining.is_empty();
+    };
+    for part in middle {
+        match remaining.find(part) {
+            Some(pos) => remaining = &remaining[pos + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_domains_and_subdomains() {
+        let rules = NoProxyRules::parse("npm.corp.internal, .example.com,localhost:8080");
+        assert!(rules.matches("npm.corp.internal"));
+        assert!(rules.matches("a.npm.corp.internal"));
+        assert!(rules.matches("example.com"));
+        assert!(rules.matches("registry.EXAMPLE.com"));
+        assert!(rules.matches("localhost"));
+        assert!(!rules.matches("notexample.com"));
+        assert!(!rules.matches("github.com"));
+    }
+
+    #[test]
+    fn supports_wildcards() {
+        let rules = NoProxyRules::parse("*.corp.internal,10.0.*");
+        assert!(rules.matches("npm.corp.internal"));
+        assert!(!rules.matches("corp.internal"));
+        assert!(rules.matches("10.0.3.7"));
+        assert!(!rules.matches("10.1.0.1"));
+
+        let all = NoProxyRules::parse("*");
+        assert!(all.matches("objects.githubusercontent.com"));
+    }
+
+    #[test]
+    fn handles_empty_values_and_ipv6() {
+        assert!(NoProxyRules::parse(" , ").is_empty());
+        let rules = NoProxyRules::parse("[::1]:443,::1");
+        assert!(rules.matches("[::1]"));
+        assert!(!rules.matches("::2"));
+    }
+}
@@ -40,6 +40,27 @@ pub struct Provider {
     #[serde(default)]
     #[serde(rename = "inFailoverQueue")]
     pub in_failover_queue: bool,
+    /// 切换时额外注入的环境变量（Claude 写入 settings.json 的 env，Gemini 写入 .env）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// 模型覆盖（切换时写入工具配置，未设置时使用 settingsConfig 或工具默认值）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Base URL 覆盖（切换时写入工具配置）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "baseUrl")]
+    pub base_url: Option<String>,
+    /// 标签（用于搜索与筛选）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 最近一次切换到该供应商的时间（毫秒时间戳）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<i64>,
+    /// 切换到该供应商的次数
+    #[serde(default)]
+    #[serde(rename = "useCount")]
+    pub use_count: u64,
 }
 
 impl Provider {
@@ -63,6 +84,12 @@ impl Provider {
             icon: None,
             icon_color: None,
             in_failover_queue: false,
+            env: HashMap::new(),
+            model: None,
+            base_url: None,
+            tags: Vec::new(),
+            last_used_at: None,
+            use_count: 0,
         }
     }
 }
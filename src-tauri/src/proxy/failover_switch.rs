@@ -119,6 +119,7 @@ impl FailoverSwitchManager {
             // 发射事件到前端
             let event_data = serde_json::json!({
                 "appType": app_type,
+                "tool": app_type,
                 "providerId": provider_id,
                 "source": "failover"  // 标识来源是故障转移
             });
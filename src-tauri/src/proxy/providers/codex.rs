@@ -175,6 +175,12 @@ mod tests {
             icon: None,
             icon_color: None,
             in_failover_queue: false,
+            env: Default::default(),
+            model: None,
+            base_url: None,
+            tags: Vec::new(),
+            last_used_at: None,
+            use_count: 0,
         }
     }
 
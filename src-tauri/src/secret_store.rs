@@ -0,0 +1,473 @@
+//! API Key 静态加密
+//!
+//! 供应商配置中的敏感字段（API Key / Token 等）写入 SQLite 前使用 AES-256-GCM 加密。
+//! 加密密钥由系统钥匙串（macOS Keychain / Windows Credential Manager / Linux Secret Service）
+//! 中保存的随机主密钥与本机随机盐派生。
+//!
+//! 系统密钥服务不可用时（如无桌面环境的 Linux）退回明文模式，`is_keystore_available()`
+//! 返回 false 供前端提示用户。
+//!
+//! 启用"启动时需解锁"后，应用启动时不读取密钥，直到调用 `unlock()`；锁定期间读出的
+//! 敏感字段一律替换为占位符，且禁止写入供应商配置与 live 配置，避免占位符覆盖真实密钥。
+
+use crate::error::AppError;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use regex::Regex;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::{LazyLock, OnceLock, RwLock};
+
+/// 已加密字段的前缀
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// 锁定状态下敏感字段的占位符
+pub const REDACTED_PLACEHOLDER: &str = "<redacted>";
+
+const KEYRING_SERVICE: &str = "cc-switch";
+const KEYRING_USER: &str = "provider-secrets";
+const SALT_FILE: &str = "keystore.salt";
+const NONCE_LEN: usize = 12;
+
+#[derive(Default)]
+struct KeyState {
+    cipher: Option<Aes256Gcm>,
+    locked: bool,
+}
+
+static STATE: OnceLock<RwLock<KeyState>> = OnceLock::new();
+
+fn state_cell() -> &'static RwLock<KeyState> {
+    STATE.get_or_init(|| RwLock::new(KeyState::default()))
+}
+
+fn current_cipher() -> Option<Aes256Gcm> {
+    state_cell().read().ok().and_then(|s| s.cipher.clone())
+}
+
+/// 初始化加密密钥，返回系统密钥服务是否可用
+///
+/// 需在打开数据库前调用；未调用或失败时所有字段以明文读写。
+/// `require_unlock` 为 true 时仅进入锁定状态，不访问钥匙串。
+pub fn init(require_unlock: bool) -> bool {
+    if require_unlock {
+        if let Ok(mut state) = state_cell().write() {
+            state.cipher = None;
+            state.locked = true;
+        }
+        log::info!("已启用启动解锁保护，API Key 在解锁前不可见");
+        return true;
+    }
+
+    match load_cipher() {
+        Ok(cipher) => {
+            if let Ok(mut state) = state_cell().write() {
+                state.cipher = Some(cipher);
+                state.locked = false;
+            }
+            log::info!("✓ 已启用 API Key 加密存储");
+            true
+        }
+        Err(e) => {
+            log::warn!("系统密钥服务不可用，API Key 将以明文保存（未加密模式）: {e}");
+            false
+        }
+    }
+}
+
+/// 系统密钥服务是否可用（即 API Key 是否以加密形式保存）
+pub fn is_keystore_available() -> bool {
+    state_cell()
+        .read()
+        .map(|s| s.cipher.is_some() || s.locked)
+        .unwrap_or(false)
+}
+
+/// 存储是否处于锁定状态
+pub fn is_locked() -> bool {
+    state_cell().read().map(|s| s.locked).unwrap_or(false)
+}
+
+/// 从系统钥匙串重新读取密钥并解锁（系统可能按其策略弹出凭据确认）
+pub fn unlock() -> Result<(), String> {
+    let cipher = load_cipher()?;
+    let mut state = state_cell()
+        .write()
+        .map_err(|e| format!("获取密钥状态失败: {e}"))?;
+    state.cipher = Some(cipher);
+    state.locked = false;
+    log::info!("✓ 已解锁 API Key 存储");
+    Ok(())
+}
+
+/// 丢弃内存中的密钥并进入锁定状态
+pub fn lock() -> Result<(), String> {
+    let mut state = state_cell()
+        .write()
+        .map_err(|e| format!("获取密钥状态失败: {e}"))?;
+    if state.cipher.is_none() && !state.locked {
+        return Err("系统密钥服务不可用，无法锁定".to_string());
+    }
+    state.cipher = None;
+    state.locked = true;
+    log::info!("已锁定 API Key 存储");
+    Ok(())
+}
+
+/// 锁定状态下拒绝写入，避免占位符覆盖真实密钥
+pub fn ensure_unlocked() -> Result<(), AppError> {
+    if is_locked() {
+        return Err(AppError::Message(
+            "API Key 存储已锁定，请先解锁后再操作".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn load_cipher() -> Result<Aes256Gcm, String> {
+    let master = load_or_create_master_secret()?;
+    let salt = load_or_create_salt()?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&salt);
+    hasher.update(master.as_bytes());
+    let key: [u8; 32] = hasher.finalize().into();
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// 从系统钥匙串读取主密钥，不存在时生成并写入
+fn load_or_create_master_secret() -> Result<String, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("打开系统钥匙串失败: {e}"))?;
+
+    match entry.get_password() {
+        Ok(secret) => Ok(secret),
+        Err(keyring::Error::NoEntry) => {
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            let secret = hex::encode(bytes);
+            entry
+                .set_password(&secret)
+                .map_err(|e| format!("写入系统钥匙串失败: {e}"))?;
+            Ok(secret)
+        }
+        Err(e) => Err(format!("读取系统钥匙串失败: {e}")),
+    }
+}
+
+/// 读取本机随机盐（首次运行时生成）
+fn load_or_create_salt() -> Result<Vec<u8>, String> {
+    let path = crate::config::get_app_config_dir().join(SALT_FILE);
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        if let Ok(salt) = hex::decode(content.trim()) {
+            if !salt.is_empty() {
+                return Ok(salt);
+            }
+        }
+        log::warn!("密钥盐文件损坏，将重新生成: {}", path.display());
+    }
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    crate::config::atomic_write(&path, hex::encode(salt).as_bytes())
+        .map_err(|e| format!("保存密钥盐失败: {e}"))?;
+    Ok(salt.to_vec())
+}
+
+/// 判断字段名是否为敏感字段（API Key / Token / Secret / Password）
+///
+/// `token` 与 `apikey` 只匹配结尾（如 `ANTHROPIC_AUTH_TOKEN`、`apiKey`），
+/// 避免把 `CLAUDE_CODE_MAX_OUTPUT_TOKENS` 等数值上限误判为密钥。
+pub fn is_secret_key(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ["apikey", "token"]
+        .iter()
+        .any(|suffix| normalized.ends_with(suffix))
+        || ["secret", "password", "authorization"]
+            .iter()
+            .any(|needle| normalized.contains(needle))
+}
+
+/// 文本中常见密钥格式的匹配规则及替换值（按顺序应用）
+static SECRET_TEXT_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (r"\bsk-ant-[A-Za-z0-9_\-]+", "sk-****"),
+        (r"\bsk-[A-Za-z0-9_\-]{8,}", "sk-****"),
+        (r"(?i)\bbearer\s+[A-Za-z0-9._~+/\-]+=*", "Bearer ****"),
+        (r"\bAIza[0-9A-Za-z_\-]{20,}", "AIza****"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+    .collect()
+});
+
+/// 长 base64 / base64url 串（是否遮盖由 [`looks_like_random_token`] 决定）
+static LONG_TOKEN_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9+/_\-]{32,}={0,2}").unwrap());
+
+/// 同时含大写、小写字母与数字才视为随机令牌；纯十六进制串（提交哈希、sha256 摘要）保留
+fn looks_like_random_token(candidate: &str) -> bool {
+    let body = candidate.trim_end_matches('=');
+    if body.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    body.chars().any(|c| c.is_ascii_uppercase())
+        && body.chars().any(|c| c.is_ascii_lowercase())
+        && body.chars().any(|c| c.is_ascii_digit())
+}
+
+/// 遮盖文本中的密钥（`sk-ant-...`、`sk-...`、Bearer 令牌、长 base64 串等）
+///
+/// 用于返回给前端的错误信息与日志，避免密钥随报错泄露。
+pub fn redact_secrets(text: &str) -> String {
+    let mut result = text.to_string();
+    for (re, replacement) in SECRET_TEXT_PATTERNS.iter() {
+        if re.is_match(&result) {
+            result = re.replace_all(&result, *replacement).into_owned();
+        }
+    }
+    LONG_TOKEN_PATTERN
+        .replace_all(&result, |caps: &regex::Captures| {
+            if looks_like_random_token(&caps[0]) {
+                "****".to_string()
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// 将错误转换为已遮盖密钥的字符串，用于命令的 `map_err`
+pub fn redact_error(err: impl std::fmt::Display) -> String {
+    redact_secrets(&err.to_string())
+}
+
+fn encrypt_str(cipher: &Aes256Gcm, plaintext: &str) -> Option<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext.as_bytes()).ok()?;
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Some(format!("{ENCRYPTED_PREFIX}{}", BASE64.encode(payload)))
+}
+
+fn decrypt_str(cipher: &Aes256Gcm, value: &str) -> Option<String> {
+    let payload = BASE64.decode(value.strip_prefix(ENCRYPTED_PREFIX)?).ok()?;
+    if payload.len() <= NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+/// 递归处理敏感字段的字符串值
+fn visit_secrets(value: &mut Value, f: &mut dyn FnMut(&mut String)) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                match child {
+                    Value::String(s) if is_secret_key(key) => f(s),
+                    _ => visit_secrets(child, f),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| visit_secrets(item, f)),
+        _ => {}
+    }
+}
+
+fn encrypt_with(cipher: &Aes256Gcm, value: &Value) -> Value {
+    let mut value = value.clone();
+    visit_secrets(&mut value, &mut |s| {
+        if s.is_empty() || s.starts_with(ENCRYPTED_PREFIX) {
+            return;
+        }
+        if let Some(encrypted) = encrypt_str(cipher, s) {
+            *s = encrypted;
+        }
+    });
+    value
+}
+
+fn decrypt_with(cipher: Option<&Aes256Gcm>, locked: bool, value: &mut Value) {
+    visit_secrets(value, &mut |s| {
+        if locked {
+            if !s.is_empty() {
+                *s = REDACTED_PLACEHOLDER.to_string();
+            }
+            return;
+        }
+        if !s.starts_with(ENCRYPTED_PREFIX) {
+            return;
+        }
+        match cipher.and_then(|c| decrypt_str(c, s)) {
+            Some(plaintext) => *s = plaintext,
+            None => log::warn!("无法解密已加密的字段（系统钥匙串不可用或密钥已变更）"),
+        }
+    });
+}
+
+/// 返回敏感字段已加密的副本（未启用加密时原样返回）
+pub fn encrypt_secrets(value: &Value) -> Value {
+    match current_cipher() {
+        Some(cipher) => encrypt_with(&cipher, value),
+        None => value.clone(),
+    }
+}
+
+/// 就地解密敏感字段（锁定状态下替换为占位符）
+pub fn decrypt_secrets(value: &mut Value) {
+    decrypt_with(current_cipher().as_ref(), is_locked(), value);
+}
+
+/// 就地将敏感字段替换为占位符（用于导出与诊断信息）
+pub fn redact_json_secrets(value: &mut Value) {
+    visit_secrets(value, &mut |s| *s = REDACTED_PLACEHOLDER.to_string());
+}
+
+/// 就地清空敏感字段的值（用于可分享的导出），返回是否清除了非空字段
+pub fn strip_json_secrets(value: &mut Value) -> bool {
+    let mut stripped = false;
+    visit_secrets(value, &mut |s| {
+        if !s.is_empty() {
+            s.clear();
+            stripped = true;
+        }
+    });
+    stripped
+}
+
+/// 是否仍含有未加密的敏感字段
+pub fn contains_plaintext_secrets(value: &Value) -> bool {
+    let mut found = false;
+    let mut value = value.clone();
+    visit_secrets(&mut value, &mut |s| {
+        if !s.is_empty() && !s.starts_with(ENCRYPTED_PREFIX) {
+            found = true;
+        }
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_cipher() -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&[7u8; 32]))
+    }
+
+    #[test]
+    fn encrypts_only_secret_fields_and_roundtrips() {
+        let cipher = test_cipher();
+        let original = json!({
+            "env": {
+                "ANTHROPIC_AUTH_TOKEN": "sk-ant-secret",
+                "ANTHROPIC_BASE_URL": "https://api.example.com"
+            },
+            "auth": { "OPENAI_API_KEY": "sk-openai" }
+        });
+
+        let encrypted = encrypt_with(&cipher, &original);
+        let token = encrypted["env"]["ANTHROPIC_AUTH_TOKEN"].as_str().unwrap();
+        assert!(token.starts_with(ENCRYPTED_PREFIX));
+        assert!(!token.contains("sk-ant-secret"));
+        assert_eq!(
+            encrypted["env"]["ANTHROPIC_BASE_URL"],
+            "https://api.example.com"
+        );
+        assert!(!contains_plaintext_secrets(&encrypted));
+        assert!(contains_plaintext_secrets(&original));
+
+        // 重复加密不会二次包裹
+        assert_eq!(encrypt_with(&cipher, &encrypted), encrypted);
+
+        let mut decrypted = encrypted;
+        decrypt_with(Some(&cipher), false, &mut decrypted);
+        assert_eq!(decrypted, original);
+    }
+
+    #[test]
+    fn token_limits_are_not_secrets() {
+        for key in ["CLAUDE_CODE_MAX_OUTPUT_TOKENS", "MAX_THINKING_TOKENS"] {
+            assert!(!is_secret_key(key), "{key}");
+        }
+        for key in ["ANTHROPIC_AUTH_TOKEN", "access_token", "apiKey", "api_key"] {
+            assert!(is_secret_key(key), "{key}");
+        }
+
+        let original = json!({
+            "env": {
+                "ANTHROPIC_AUTH_TOKEN": "sk-ant-secret",
+                "CLAUDE_CODE_MAX_OUTPUT_TOKENS": "32000"
+            }
+        });
+        let encrypted = encrypt_with(&test_cipher(), &original);
+        assert_eq!(encrypted["env"]["CLAUDE_CODE_MAX_OUTPUT_TOKENS"], "32000");
+    }
+
+    #[test]
+    fn decrypt_without_key_leaves_value_untouched() {
+        let encrypted = encrypt_with(&test_cipher(), &json!({ "apiKey": "k" }));
+        let mut value = encrypted.clone();
+        decrypt_with(None, false, &mut value);
+        assert_eq!(value, encrypted);
+    }
+
+    #[test]
+    fn locked_state_redacts_secrets() {
+        let encrypted = encrypt_with(
+            &test_cipher(),
+            &json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "sk", "ANTHROPIC_BASE_URL": "u" } }),
+        );
+        let mut value = encrypted;
+        decrypt_with(None, true, &mut value);
+        assert_eq!(value["env"]["ANTHROPIC_AUTH_TOKEN"], REDACTED_PLACEHOLDER);
+        assert_eq!(value["env"]["ANTHROPIC_BASE_URL"], "u");
+    }
+
+    #[test]
+    fn redact_secrets_masks_keys_in_error_text() {
+        let message = "请求失败: invalid x-api-key sk-ant-REDACTED (status 401)";
+        let redacted = redact_secrets(message);
+        assert_eq!(redacted, "请求失败: invalid x-api-key sk-**** (status 401)");
+
+        let redacted = redact_secrets("OPENAI_API_KEY=sk-proj-abcdefghijkl rejected");
+        assert_eq!(redacted, "OPENAI_API_KEY=sk-**** rejected");
+
+        let redacted = redact_secrets("Authorization: Bearer abc.def-ghi_jkl");
+        assert_eq!(redacted, "Authorization: Bearer ****");
+
+        let token = "QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo0NTY3ODkw";
+        assert_eq!(redact_secrets(&format!("token {token}")), "token ****");
+
+        let token = "Zm9vYmFyYmF6/cXV4cXV1eA+QUJDREVGR0hJSks1234==";
+        assert_eq!(redact_secrets(&format!("token {token}")), "token ****");
+    }
+
+    #[test]
+    fn redact_secrets_keeps_hex_digests() {
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let message =
+            format!("sha256 校验通过: {sha256}, commit 3f2a9c1e8b7d6a5f4e3d2c1b0a9f8e7d6c5b4a39");
+        assert_eq!(redact_secrets(&message), message);
+    }
+
+    #[test]
+    fn redact_secrets_keeps_ordinary_text() {
+        let message =
+            "供应商 550e8400-e29b-41d4-a716-446655440000 不存在: /usr/local/lib/node_modules";
+        assert_eq!(redact_secrets(message), message);
+        assert_eq!(
+            redact_error(AppError::Message("task-runner failed".to_string())),
+            "task-runner failed"
+        );
+    }
+}
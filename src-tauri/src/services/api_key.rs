@@ -0,0 +1,124 @@
+//! API Key 格式校验（仅做本地的提示性检查，不发起网络请求）
+
+use crate::app_config::AppType;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyValidation {
+    pub valid: bool,
+    /// 去除首尾空白与引号后的 Key
+    pub normalized_key: String,
+    /// 不通过或可疑时的说明
+    pub reason: Option<String>,
+}
+
+/// 已知的 Key 前缀及其来源
+const KNOWN_PREFIXES: &[(&str, &str)] = &[
+    ("sk-ant-", "Anthropic"),
+    ("sk-proj-", "OpenAI"),
+    ("AIza", "Google Gemini"),
+];
+
+fn detect_vendor(key: &str) -> Option<&'static str> {
+    KNOWN_PREFIXES
+        .iter()
+        .find(|(prefix, _)| key.starts_with(prefix))
+        .map(|(_, vendor)| *vendor)
+}
+
+/// 各工具对应的官方 Key 来源
+fn expected_vendor(app_type: &AppType) -> &'static str {
+    match app_type {
+        AppType::Claude => "Anthropic",
+        AppType::Codex => "OpenAI",
+        AppType::Gemini => "Google Gemini",
+    }
+}
+
+/// 校验 API Key 格式
+///
+/// 中转服务的 Key 通常为 `sk-` 开头的自定义格式，因此只在识别出明确属于其它厂商的前缀时判为不匹配。
+pub fn validate_api_key(app_type: &AppType, key: &str) -> ApiKeyValidation {
+    let normalized = key
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .trim()
+        .to_string();
+
+    let invalid = |reason: String| ApiKeyValidation {
+        valid: false,
+        normalized_key: normalized.clone(),
+        reason: Some(reason),
+    };
+
+    if normalized.is_empty() {
+        return invalid("API Key 不能为空".to_string());
+    }
+    if normalized.chars().any(char::is_whitespace) {
+        return invalid("API Key 中包含空白字符，可能复制了多余内容".to_string());
+    }
+
+    let expected = expected_vendor(app_type);
+    if let Some(vendor) = detect_vendor(&normalized) {
+        if vendor != expected {
+            return invalid(format!(
+                "这看起来是 {vendor} 的 Key，与 {} 不匹配",
+                app_type.as_str()
+            ));
+        }
+    }
+
+    ApiKeyValidation {
+        valid: true,
+        normalized_key: normalized,
+        reason: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_matching_official_keys() {
+        for (app, key) in [
+            (AppType::Claude, "sk-ant-api03-abc"),
+            (AppType::Codex, "sk-proj-abc"),
+            (AppType::Gemini, "AIzaSyabc"),
+        ] {
+            let result = validate_api_key(&app, key);
+            assert!(result.valid, "{key} should be valid for {}", app.as_str());
+            assert_eq!(result.reason, None);
+        }
+    }
+
+    #[test]
+    fn accepts_generic_relay_keys() {
+        for app in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+            assert!(validate_api_key(&app, "sk-relay123").valid);
+        }
+    }
+
+    #[test]
+    fn flags_keys_from_other_vendors() {
+        assert!(!validate_api_key(&AppType::Claude, "sk-proj-abc").valid);
+        assert!(!validate_api_key(&AppType::Claude, "AIzaSyabc").valid);
+        assert!(!validate_api_key(&AppType::Codex, "sk-ant-api03-abc").valid);
+        assert!(!validate_api_key(&AppType::Gemini, "sk-ant-api03-abc").valid);
+    }
+
+    #[test]
+    fn trims_whitespace_and_quotes() {
+        let result = validate_api_key(&AppType::Claude, "  \"sk-ant-api03-abc\"\n");
+        assert!(result.valid);
+        assert_eq!(result.normalized_key, "sk-ant-api03-abc");
+    }
+
+    #[test]
+    fn rejects_empty_and_inner_whitespace() {
+        assert!(!validate_api_key(&AppType::Claude, "").valid);
+        assert!(!validate_api_key(&AppType::Claude, "   \t\n").valid);
+        assert!(!validate_api_key(&AppType::Codex, "sk-abc def").valid);
+    }
+}
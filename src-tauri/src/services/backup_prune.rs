@@ -0,0 +1,219 @@
+//! 备份清理
+//!
+//! 应用在写入配置、迁移与数据库快照时会不断生成备份文件。按“备份目标”（config.json、
+//! 数据库、环境变量、迁移前备份）分组，每组保留最新的若干个，其余超过保留天数的删除；
+//! 每组最新的一个备份无论如何都不会删除，以保证仍可撤销最近一次修改。
+//!
+//! CLI 配置旁的 `<文件名>.bak` 每个目标只有一份（即最新备份），因此不参与清理。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// 备份文件名前缀与扩展名 → 备份目标
+const BACKUP_KINDS: &[(&str, &str, &str)] = &[
+    ("backup_", "json", "config"),
+    ("db_backup_", "db", "database"),
+    ("env-backup-", "json", "env"),
+    ("config.backup-", "json", "migration"),
+];
+
+/// 清理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    pub files_removed: usize,
+    pub bytes_reclaimed: u64,
+}
+
+#[derive(Debug)]
+struct BackupFile {
+    target: &'static str,
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// 识别备份文件所属目标（非备份文件返回 `None`）
+fn backup_target(file_name: &str) -> Option<&'static str> {
+    BACKUP_KINDS
+        .iter()
+        .find(|(prefix, ext, _)| {
+            file_name.starts_with(prefix)
+                && Path::new(file_name).extension().is_some_and(|e| e == *ext)
+        })
+        .map(|(_, _, target)| *target)
+}
+
+fn collect_backups(dirs: &[PathBuf]) -> Vec<BackupFile> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(target) = backup_target(&file_name) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            files.push(BackupFile {
+                target,
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+    files
+}
+
+/// 挑出应删除的备份：每组按修改时间从新到旧，前 `keep_per_target` 个（至少 1 个）保留，
+/// 其余仅在早于 `now - max_age` 时删除
+fn select_prunable(
+    files: Vec<BackupFile>,
+    keep_per_target: usize,
+    max_age: Duration,
+    now: SystemTime,
+) -> Vec<BackupFile> {
+    let cutoff = now.checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut groups: HashMap<&'static str, Vec<BackupFile>> = HashMap::new();
+    for file in files {
+        groups.entry(file.target).or_default().push(file);
+    }
+
+    let mut prunable = Vec::new();
+    for mut group in groups.into_values() {
+        group.sort_by(|a, b| {
+            b.modified
+                .cmp(&a.modified)
+                .then_with(|| b.path.cmp(&a.path))
+        });
+        prunable.extend(
+            group
+                .into_iter()
+                .skip(keep_per_target.max(1))
+                .filter(|file| file.modified < cutoff),
+        );
+    }
+    prunable
+}
+
+/// 应用生成备份的目录（去重后）
+fn backup_dirs() -> Vec<PathBuf> {
+    let app_dir = crate::config::get_app_config_dir();
+    let mut dirs = vec![
+        app_dir.join("backups"),
+        app_dir,
+        crate::config::get_data_dir().join("backups"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        // 环境变量冲突备份固定写入 ~/.cc-switch/backups
+        dirs.push(home.join(".cc-switch").join("backups"));
+    }
+
+    let mut seen = Vec::new();
+    dirs.retain(|dir| {
+        let key = fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+        if seen.contains(&key) {
+            false
+        } else {
+            seen.push(key);
+            true
+        }
+    });
+    dirs
+}
+
+fn prune_in(
+    dirs: &[PathBuf],
+    keep_per_target: usize,
+    max_age_days: u64,
+    now: SystemTime,
+) -> Result<PruneReport, AppError> {
+    let max_age = Duration::from_secs(max_age_days.saturating_mul(24 * 60 * 60));
+    let mut report = PruneReport::default();
+    for file in select_prunable(collect_backups(dirs), keep_per_target, max_age, now) {
+        match fs::remove_file(&file.path) {
+            Ok(()) => {
+                report.files_removed += 1;
+                report.bytes_reclaimed += file.size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::io(&file.path, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// 清理应用数据目录中的旧备份
+pub fn prune_backups(keep_per_target: usize, max_age_days: u64) -> Result<PruneReport, AppError> {
+    let report = prune_in(
+        &backup_dirs(),
+        keep_per_target,
+        max_age_days,
+        SystemTime::now(),
+    )?;
+    log::info!(
+        "已清理 {} 个旧备份，释放 {} 字节",
+        report.files_removed,
+        report.bytes_reclaimed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn write_backup(dir: &Path, name: &str, age_days: u32, now: SystemTime) {
+        let path = dir.join(name);
+        fs::write(&path, "backup").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(now - DAY * age_days).unwrap();
+    }
+
+    #[test]
+    fn keeps_recent_backups_per_target() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let now = SystemTime::now();
+        for (i, age) in [1, 10, 20, 30].into_iter().enumerate() {
+            write_backup(dir.path(), &format!("backup_{i}.json"), age, now);
+        }
+        // 唯一的数据库备份即使过期也保留
+        write_backup(dir.path(), "db_backup_old.db", 90, now);
+        write_backup(dir.path(), "settings.json", 90, now);
+
+        let report = prune_in(&[dir.path().to_path_buf()], 2, 15, now).unwrap();
+        assert_eq!(
+            report,
+            PruneReport {
+                files_removed: 2,
+                bytes_reclaimed: 12
+            }
+        );
+        assert!(dir.path().join("backup_0.json").exists());
+        assert!(dir.path().join("backup_1.json").exists());
+        assert!(!dir.path().join("backup_2.json").exists());
+        assert!(dir.path().join("db_backup_old.db").exists());
+        assert!(dir.path().join("settings.json").exists());
+
+        // keep = 0 仍保留最新的一个
+        let report = prune_in(&[dir.path().to_path_buf()], 0, 0, now).unwrap();
+        assert_eq!(report.files_removed, 1);
+        assert!(dir.path().join("backup_0.json").exists());
+        assert!(dir.path().join("db_backup_old.db").exists());
+    }
+}
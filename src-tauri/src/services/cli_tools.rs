@@ -0,0 +1,1521 @@
+//! 受管理 CLI 工具（Claude Code / Codex / Gemini CLI）的版本检测
+//!
+//! 通过工具注册表描述每个 CLI 的命令名与 npm 包名，新增工具只需扩展注册表。
+
+use crate::database::Database;
+use crate::services::rate_limit;
+use crate::services::retry::{self, FailureKind, RequestError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// npm registry 单次版本查询的超时时间
+const NPM_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// 版本检测子进程的超时时间，超时即结束进程（如 CLI 等待输入、shell 配置异常）
+const VERSION_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// npm 精简元数据格式：仍包含 `dist-tags`，但体积远小于完整包文档
+const NPM_ABBREVIATED_ACCEPT: &str =
+    "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8";
+
+/// 受管理的 CLI 工具
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedTool {
+    /// 工具 ID（与前端展示、设置中的隐藏列表对应）
+    pub id: &'static str,
+    /// 可执行命令名
+    pub command: &'static str,
+    /// 对应的 npm 包名（用于查询最新版本）
+    pub npm_package: &'static str,
+    /// 用户是否在设置中隐藏了该工具
+    pub hidden: bool,
+}
+
+/// 内置工具注册表：(工具 ID, 命令名, npm 包名)
+const BUILTIN_TOOLS: &[(&str, &str, &str)] = &[
+    ("claude", "claude", "@anthropic-ai/claude-code"),
+    ("codex", "codex", "@openai/codex"),
+    ("gemini", "gemini", "@google/gemini-cli"),
+];
+
+/// 获取受管理的工具列表，并根据设置标记隐藏状态
+pub fn managed_tools() -> Vec<ManagedTool> {
+    let hidden_tools = crate::settings::get_settings().hidden_tools;
+    BUILTIN_TOOLS
+        .iter()
+        .map(|&(id, command, npm_package)| ManagedTool {
+            id,
+            command,
+            npm_package,
+            hidden: hidden_tools.iter().any(|h| h == id),
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize)]
+pub struct ToolVersion {
+    pub name: String,
+    pub version: Option<String>,
+    pub latest_version: Option<String>, // 新增字段：最新版本
+    /// 最新版本来自过期缓存（远程查询失败时回退）
+    pub latest_version_stale: bool,
+    /// npm registry 限流中，最新版本未能刷新
+    pub rate_limited: bool,
+    pub error: Option<String>,
+    /// 检测到版本的可执行文件路径（通过 shell PATH 检测时为 `"PATH"`）
+    pub path: Option<String>,
+    /// 本地版本是否落后于最新版本；任一版本无法按 semver 解析时为 `None`，
+    /// 本地版本为用户固定的版本时为 `Some(false)`
+    pub update_available: Option<bool>,
+    /// 本地版本与用户固定的版本一致（不提示更新）
+    pub pinned: bool,
+    /// 安装方式：`nvm` / `homebrew` / `npm-global` / `pnpm` / `yarn` / `n` / `local-bin` / `system`；
+    /// 通过 shell PATH 直接检测成功时无法判断，为 `None`
+    pub install_method: Option<String>,
+}
+
+/// 版本报告的导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionReportFormat {
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for VersionReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("不支持的导出格式: {other}（仅支持 json、csv）")),
+        }
+    }
+}
+
+const VERSION_REPORT_COLUMNS: [&str; 9] = [
+    "name",
+    "version",
+    "latest_version",
+    "update_available",
+    "pinned",
+    "install_method",
+    "path",
+    "latest_version_stale",
+    "error",
+];
+
+/// CSV 字段：含分隔符、引号或换行时加引号；以公式字符开头时加 `'`，避免表格软件执行
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// 生成版本报告（JSON 字段与 [`ToolVersion`] 序列化结果一致，CSV 每个工具一行）
+pub fn render_version_report(
+    versions: &[ToolVersion],
+    format: VersionReportFormat,
+) -> Result<String, String> {
+    match format {
+        VersionReportFormat::Json => {
+            serde_json::to_string_pretty(versions).map_err(|e| format!("序列化版本报告失败: {e}"))
+        }
+        VersionReportFormat::Csv => {
+            let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+            let mut csv = VERSION_REPORT_COLUMNS.join(",");
+            csv.push_str("\r\n");
+            for v in versions {
+                let row = [
+                    v.name.clone(),
+                    opt(&v.version),
+                    opt(&v.latest_version),
+                    v.update_available
+                        .map(|b| b.to_string())
+                        .unwrap_or_default(),
+                    v.pinned.to_string(),
+                    opt(&v.install_method),
+                    opt(&v.path),
+                    v.latest_version_stale.to_string(),
+                    opt(&v.error),
+                ];
+                let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                csv.push_str(&fields.join(","));
+                csv.push_str("\r\n");
+            }
+            Ok(csv)
+        }
+    }
+}
+
+/// 并发检测所有未隐藏工具的版本；`join_all` 按注册表顺序返回，保证输出顺序稳定
+#[tracing::instrument(skip(client, db))]
+pub async fn detect_tool_versions(
+    client: &reqwest::Client,
+    db: &Database,
+    force: bool,
+) -> Vec<ToolVersion> {
+    let tasks = managed_tools()
+        .into_iter()
+        .filter(|tool| !tool.hidden)
+        .map(|tool| detect_tool_version(client, db, tool, force));
+
+    futures::future::join_all(tasks).await
+}
+
+/// 检测单个工具：本地版本（阻塞线程）与远程最新版本并发获取
+#[tracing::instrument(skip(client, db, tool, force), fields(tool = tool.id))]
+async fn detect_tool_version(
+    client: &reqwest::Client,
+    db: &Database,
+    tool: ManagedTool,
+    force: bool,
+) -> ToolVersion {
+    let command = tool.command;
+
+    // 1. 获取本地版本 - 先尝试直接执行，失败则扫描常见路径
+    let span = tracing::Span::current();
+    let local = tauri::async_runtime::spawn_blocking(move || {
+        let _span = span.entered();
+        detect_local_version(command)
+    });
+
+    // 2. 获取远程最新版本
+    let remote = fetch_npm_latest_version(client, db, tool.npm_package, force);
+
+    let (local, latest) = tokio::join!(local, remote);
+    let latest_version = latest.version;
+    let local = local.unwrap_or_else(|e| LocalToolVersion::failed(e.to_string()));
+
+    let pin = crate::settings::get_settings()
+        .pinned_tool_versions
+        .remove(tool.id);
+    let pinned = matches_pin(local.version.as_deref(), pin.as_deref());
+    let update_available = if pinned {
+        Some(false)
+    } else {
+        compute_update_available(local.version.as_deref(), latest_version.as_deref())
+    };
+    tracing::debug!(
+        version = ?local.version,
+        latest = ?latest_version,
+        error = ?local.error,
+        "版本检测完成"
+    );
+
+    ToolVersion {
+        name: tool.id.to_string(),
+        update_available,
+        pinned,
+        version: local.version,
+        latest_version,
+        latest_version_stale: latest.stale,
+        rate_limited: latest.rate_limited,
+        error: local.error,
+        path: local.path,
+        install_method: local.install_method,
+    }
+}
+
+/// 工具在 npm 上的最新版本（字段名与 [`ToolVersion`] 相同，便于前端合并到已检测的本地版本）
+#[derive(Clone, Serialize)]
+pub struct LatestToolVersion {
+    pub latest_version: Option<String>,
+    pub latest_version_stale: bool,
+    pub rate_limited: bool,
+}
+
+/// 并发查询所有未隐藏工具的最新版本（遵循缓存有效期），不检测本地版本
+#[tracing::instrument(skip(client, db))]
+pub async fn fetch_latest_versions(
+    client: &reqwest::Client,
+    db: &Database,
+) -> BTreeMap<String, LatestToolVersion> {
+    let tools: Vec<ManagedTool> = managed_tools()
+        .into_iter()
+        .filter(|tool| !tool.hidden)
+        .collect();
+    let latest = futures::future::join_all(
+        tools
+            .iter()
+            .map(|tool| fetch_npm_latest_version(client, db, tool.npm_package, false)),
+    )
+    .await;
+
+    tools
+        .iter()
+        .zip(latest)
+        .map(|(tool, latest)| {
+            (
+                tool.id.to_string(),
+                LatestToolVersion {
+                    latest_version: latest.version,
+                    latest_version_stale: latest.stale,
+                    rate_limited: latest.rate_limited,
+                },
+            )
+        })
+        .collect()
+}
+
+/// npm 最新版本的查询结果
+struct LatestVersion {
+    version: Option<String>,
+    /// 来自过期缓存（远程查询失败时回退）
+    stale: bool,
+    /// npm registry 限流中，本次未发起远程查询或查询被拒绝
+    rate_limited: bool,
+}
+
+/// 获取 npm 包的最新版本（带缓存）。
+///
+/// 缓存未过期且未强制刷新时直接返回缓存值；远程查询失败（含限流退避期）时回退到
+/// 上次缓存的值并标记为过期。
+async fn fetch_npm_latest_version(
+    client: &reqwest::Client,
+    db: &Database,
+    package: &str,
+    force: bool,
+) -> LatestVersion {
+    let cached = match db.get_npm_version_cache(package) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("读取 npm 版本缓存失败 ({package}): {e}");
+            None
+        }
+    };
+
+    if !force {
+        if let Some(entry) = &cached {
+            let ttl = crate::settings::get_settings().npm_version_cache_ttl_secs as i64;
+            let age = chrono::Utc::now().timestamp() - entry.fetched_at;
+            if (0..ttl).contains(&age) {
+                return LatestVersion {
+                    version: Some(entry.version.clone()),
+                    stale: false,
+                    rate_limited: false,
+                };
+            }
+        }
+    }
+
+    match query_npm_latest_version(client, package).await {
+        Ok(version) => {
+            if let Err(e) = db.set_npm_version_cache(package, &version) {
+                log::warn!("写入 npm 版本缓存失败 ({package}): {e}");
+            }
+            LatestVersion {
+                version: Some(version),
+                stale: false,
+                rate_limited: false,
+            }
+        }
+        Err(e) => {
+            log::warn!("获取 npm 最新版本失败 ({package}): {e}");
+            LatestVersion {
+                stale: cached.is_some(),
+                version: cached.map(|entry| entry.version),
+                rate_limited: rate_limit::limited_until(rate_limit::NPM).is_some(),
+            }
+        }
+    }
+}
+
+/// 从 npm 包文档（完整或精简格式）中读取 `dist-tags.latest`
+fn parse_npm_latest_version(json: &serde_json::Value) -> Result<String, String> {
+    json.get("dist-tags")
+        .and_then(|tags| tags.get("latest"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| "响应中缺少 dist-tags.latest".to_string())
+}
+
+/// 直接请求 npm registry 查询最新版本（不读写缓存），失败时返回原因
+///
+/// 限流退避期内不发起请求，直接返回错误。
+pub(crate) async fn query_npm_latest_version(
+    client: &reqwest::Client,
+    package: &str,
+) -> Result<String, String> {
+    rate_limit::ensure_allowed(rate_limit::NPM)?;
+    let registry = crate::settings::get_npm_registry();
+    let url = format!("{registry}/{package}");
+    let attempt = || async {
+        let request = async {
+            let resp = client
+                .get(&url)
+                .header(reqwest::header::ACCEPT, NPM_ABBREVIATED_ACCEPT)
+                .send()
+                .await
+                .map_err(|e| RequestError::from_reqwest("请求失败", &e))?;
+            rate_limit::check_response(rate_limit::NPM, &resp)
+                .map_err(|e| RequestError::new(FailureKind::ClientError, e))?;
+            let resp = RequestError::check_server_error("请求失败", resp)?;
+            let json = resp
+                .json::<serde_json::Value>()
+                .await
+                .map_err(|e| RequestError::other(format!("解析响应失败: {e}")))?;
+            parse_npm_latest_version(&json).map_err(RequestError::other)
+        };
+        tokio::time::timeout(NPM_REQUEST_TIMEOUT, request)
+            .await
+            .unwrap_or_else(|_| {
+                Err(RequestError::new(
+                    FailureKind::Timeout,
+                    format!("请求超时: 超过 {}s", NPM_REQUEST_TIMEOUT.as_secs()),
+                ))
+            })
+    };
+
+    Ok(retry::retry_with_backoff("查询 npm 最新版本", &retry::DEFAULT_POLICY, attempt).await?)
+}
+
+/// npm registry 连通性检查超时时间
+const REGISTRY_PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// npm registry 连通性检查结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryStatus {
+    pub registry: String,
+    pub reachable: bool,
+    /// 请求耗时（毫秒），无响应时为 `None`
+    pub latency_ms: Option<u64>,
+    /// 正处于限流退避期，或本次请求被限流
+    pub rate_limited: bool,
+    pub error: Option<String>,
+}
+
+/// 请求 registry 的 `/-/ping` 检查连通性
+///
+/// 收到任何非 5xx 响应即视为可达（部分镜像未实现 ping 接口会返回 404）。
+/// 被限流时记录退避，后续版本查询会跳过远程请求。
+pub(crate) async fn check_registry_status(
+    client: &reqwest::Client,
+    registry: &str,
+) -> RegistryStatus {
+    let registry = registry.trim().trim_end_matches('/').to_string();
+    let mut status = RegistryStatus {
+        rate_limited: rate_limit::limited_until(rate_limit::NPM).is_some(),
+        registry,
+        reachable: false,
+        latency_ms: None,
+        error: None,
+    };
+
+    let started = std::time::Instant::now();
+    let result = client
+        .get(format!("{}/-/ping", status.registry))
+        .timeout(REGISTRY_PING_TIMEOUT)
+        .send()
+        .await;
+    match result {
+        Ok(resp) => {
+            status.latency_ms = Some(started.elapsed().as_millis() as u64);
+            if let Err(e) = rate_limit::check_response(rate_limit::NPM, &resp) {
+                status.rate_limited = true;
+                status.error = Some(e);
+            }
+            status.reachable = !resp.status().is_server_error();
+            if !status.reachable {
+                status.error = Some(format!("HTTP {}", resp.status()));
+            }
+        }
+        Err(e) if e.is_timeout() => {
+            status.error = Some(format!(
+                "请求超时: 超过 {}s",
+                REGISTRY_PING_TIMEOUT.as_secs()
+            ));
+        }
+        Err(e) => status.error = Some(format!("请求失败: {e}")),
+    }
+    status
+}
+
+/// 从 npm 包文档的 `versions` 对象中读取已发布版本，按 semver 从新到旧排序
+fn parse_npm_versions(json: &serde_json::Value) -> Result<Vec<String>, String> {
+    let versions = json
+        .get("versions")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| "响应中缺少 versions".to_string())?;
+    let mut parsed: Vec<semver::Version> = versions
+        .keys()
+        .filter_map(|v| semver::Version::parse(v).ok())
+        .collect();
+    parsed.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(parsed.into_iter().map(|v| v.to_string()).collect())
+}
+
+/// 请求 npm 包文档（精简格式），限流退避期内不发起请求
+async fn fetch_npm_package(
+    client: &reqwest::Client,
+    package: &str,
+) -> Result<serde_json::Value, String> {
+    rate_limit::ensure_allowed(rate_limit::NPM)?;
+    let registry = crate::settings::get_npm_registry();
+    let url = format!("{registry}/{package}");
+    let request = async {
+        let resp = client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, NPM_ABBREVIATED_ACCEPT)
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {e}"))?;
+        rate_limit::check_response(rate_limit::NPM, &resp)?;
+        resp.json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("解析响应失败: {e}"))
+    };
+
+    tokio::time::timeout(NPM_REQUEST_TIMEOUT, request)
+        .await
+        .unwrap_or_else(|_| Err(format!("请求超时: 超过 {}s", NPM_REQUEST_TIMEOUT.as_secs())))
+}
+
+/// 查询 npm 包的全部已发布版本（从新到旧），用于回退到旧版本
+pub(crate) async fn query_npm_versions(
+    client: &reqwest::Client,
+    package: &str,
+) -> Result<Vec<String>, String> {
+    parse_npm_versions(&fetch_npm_package(client, package).await?)
+}
+
+/// 单页最多返回的版本数
+pub const MAX_VERSION_PAGE_SIZE: usize = 50;
+
+/// npm 上已发布版本的一页（从新到旧）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolVersionPage {
+    pub versions: Vec<String>,
+    /// `dist-tags.latest`
+    pub latest: Option<String>,
+    /// 全部版本数
+    pub total: usize,
+    pub offset: usize,
+}
+
+fn version_page(
+    json: &serde_json::Value,
+    offset: usize,
+    limit: usize,
+) -> Result<ToolVersionPage, String> {
+    let versions = parse_npm_versions(json)?;
+    Ok(ToolVersionPage {
+        total: versions.len(),
+        versions: versions
+            .into_iter()
+            .skip(offset)
+            .take(limit.clamp(1, MAX_VERSION_PAGE_SIZE))
+            .collect(),
+        latest: parse_npm_latest_version(json).ok(),
+        offset,
+    })
+}
+
+/// 分页查询 npm 包的已发布版本，同时刷新最新版本缓存
+pub(crate) async fn list_npm_versions(
+    client: &reqwest::Client,
+    db: &Database,
+    package: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<ToolVersionPage, String> {
+    let page = version_page(&fetch_npm_package(client, package).await?, offset, limit)?;
+    if let Some(latest) = &page.latest {
+        if let Err(e) = db.set_npm_version_cache(package, latest) {
+            log::warn!("写入 npm 版本缓存失败 ({package}): {e}");
+        }
+    }
+    Ok(page)
+}
+
+/// 本机 Node.js 版本与工具声明的 `engines.node` 是否匹配
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCompatibility {
+    pub tool: String,
+    /// `node --version`，未找到 Node.js 时为空
+    pub node_version: Option<String>,
+    /// 工具声明的 `engines.node` 范围，未声明时为空
+    pub required: Option<String>,
+    /// 未找到 Node.js 时为 false；未声明或范围无法解析时视为满足
+    pub ok: bool,
+}
+
+/// 指定版本（不在 `versions` 中时取 `dist-tags.latest`）声明的 `engines.node`
+fn engines_node(json: &serde_json::Value, version: Option<&str>) -> Option<String> {
+    let versions = json.get("versions")?;
+    let version = version
+        .filter(|v| versions.get(*v).is_some())
+        .map(str::to_string)
+        .or_else(|| parse_npm_latest_version(json).ok())?;
+    versions
+        .get(&version)?
+        .get("engines")?
+        .get("node")?
+        .as_str()
+        .map(|range| range.trim().to_string())
+        .filter(|range| !range.is_empty())
+}
+
+/// 判断 Node.js 版本是否满足 npm 版本范围（支持 `||` 与空格分隔的比较式），范围无法解析时返回 `None`
+fn node_satisfies(node_version: &str, range: &str) -> Option<bool> {
+    let version = semver::Version::parse(node_version.trim().trim_start_matches('v')).ok()?;
+    let mut any_parsed = false;
+    for alternative in range.split("||") {
+        let comparators: Vec<&str> = alternative.split_whitespace().collect();
+        if comparators.is_empty() || comparators == ["*"] {
+            return Some(true);
+        }
+        let Ok(req) = semver::VersionReq::parse(&comparators.join(", ")) else {
+            continue;
+        };
+        any_parsed = true;
+        if req.matches(&version) {
+            return Some(true);
+        }
+    }
+    any_parsed.then_some(false)
+}
+
+/// 检测本机 Node.js 是否满足工具（已安装版本，未安装时为最新版）声明的 `engines.node`
+pub async fn check_node_compatibility(
+    client: &reqwest::Client,
+    tool: &ManagedTool,
+) -> Result<NodeCompatibility, String> {
+    let command = tool.command;
+    let (node, local) = tauri::async_runtime::spawn_blocking(move || {
+        (
+            try_get_version("node").version,
+            detect_local_version(command).version,
+        )
+    })
+    .await
+    .map_err(|e| format!("检测 Node.js 版本失败: {e}"))?;
+
+    let json = fetch_npm_package(client, tool.npm_package).await?;
+    let required = engines_node(&json, local.as_deref());
+    let ok = match (&node, &required) {
+        (None, _) => false,
+        (Some(node), Some(range)) => node_satisfies(node, range).unwrap_or(true),
+        (Some(_), None) => true,
+    };
+    Ok(NodeCompatibility {
+        tool: tool.id.to_string(),
+        node_version: node,
+        required,
+        ok,
+    })
+}
+
+/// 本地 CLI 版本检测结果
+#[derive(Debug, Default)]
+pub(crate) struct LocalToolVersion {
+    pub version: Option<String>,
+    pub error: Option<String>,
+    /// 产生版本号的可执行文件路径；通过 shell 的 PATH 直接执行成功时为 `"PATH"`
+    pub path: Option<String>,
+    /// 安装方式（由匹配到的搜索目录推断）
+    pub install_method: Option<String>,
+}
+
+impl LocalToolVersion {
+    fn failed(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 按 semver 比较本地版本与最新版本（预发布版本低于同号正式版，如 `1.2.0-beta.1 < 1.2.0`）
+pub(crate) fn compute_update_available(local: Option<&str>, latest: Option<&str>) -> Option<bool> {
+    let parse = |v: &str| semver::Version::parse(v.trim().trim_start_matches('v')).ok();
+    let local = parse(local?)?;
+    let latest = parse(latest?)?;
+    Some(local < latest)
+}
+
+/// 本地版本是否为用户固定的版本（忽略前缀 `v`，均可按 semver 解析时按 semver 比较）
+pub(crate) fn matches_pin(local: Option<&str>, pin: Option<&str>) -> bool {
+    let (Some(local), Some(pin)) = (local, pin) else {
+        return false;
+    };
+    let normalize = |v: &str| v.trim().trim_start_matches('v').to_string();
+    let (local, pin) = (normalize(local), normalize(pin));
+    match (semver::Version::parse(&local), semver::Version::parse(&pin)) {
+        (Ok(local), Ok(pin)) => local == pin,
+        _ => local == pin,
+    }
+}
+
+/// 从版本输出中提取纯版本号
+fn extract_version(raw: &str) -> String {
+    // 匹配 semver 格式: x.y.z 或 x.y.z-xxx
+    let re = regex::Regex::new(r"\d+\.\d+\.\d+(-[\w.]+)?").unwrap();
+    re.find(raw)
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_else(|| raw.to_string())
+}
+
+/// shell 报告命令不存在时 [`try_get_version`] 给出的错误
+const NOT_IN_PATH: &str = "PATH 中未找到该命令";
+
+/// shell 的“命令不存在”退出码：sh 为 127（登录 shell），cmd 为 9009
+fn is_command_not_found(code: Option<i32>) -> bool {
+    if cfg!(target_os = "windows") {
+        code == Some(9009)
+    } else {
+        code == Some(127)
+    }
+}
+
+/// 尝试直接执行命令获取版本
+fn try_get_version(tool: &str) -> LocalToolVersion {
+    use std::process::Command;
+
+    // `.cmd` shim 须经 cmd 执行，工具名拼入命令行前先校验
+    #[cfg(target_os = "windows")]
+    let output = match crate::shell_args::ensure_safe_shell_arg(tool) {
+        Ok(()) => output_with_timeout(
+            Command::new("cmd")
+                .arg("/C")
+                .arg(tool)
+                .arg("--version")
+                .creation_flags(CREATE_NO_WINDOW),
+            VERSION_COMMAND_TIMEOUT,
+        ),
+        Err(e) => return LocalToolVersion::failed(e),
+    };
+
+    // macOS 上 GUI 应用继承的 PATH 不含 shell 配置中的 nvm/brew 目录，优先使用登录 shell；
+    // 否则不经 shell 直接按 PATH 执行
+    #[cfg(not(target_os = "windows"))]
+    let output = match login_shell_version_output(tool) {
+        Some(out) => Ok(out),
+        None => output_with_timeout(Command::new(tool).arg("--version"), VERSION_COMMAND_TIMEOUT),
+    };
+
+    match output {
+        Ok(out) => {
+            let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            if out.status.success() {
+                let raw = if stdout.is_empty() { &stderr } else { &stdout };
+                if raw.is_empty() {
+                    LocalToolVersion::failed("未安装或无法执行")
+                } else {
+                    LocalToolVersion {
+                        version: Some(extract_version(raw)),
+                        error: None,
+                        path: Some("PATH".to_string()),
+                        install_method: None,
+                    }
+                }
+            } else if is_command_not_found(out.status.code()) {
+                LocalToolVersion::failed(NOT_IN_PATH)
+            } else {
+                let err = if stderr.is_empty() { stdout } else { stderr };
+                LocalToolVersion::failed(if err.is_empty() {
+                    "未安装或无法执行".to_string()
+                } else {
+                    err
+                })
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            LocalToolVersion::failed(format!("版本检测超时: {e}"))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => LocalToolVersion::failed(NOT_IN_PATH),
+        Err(e) => LocalToolVersion::failed(e.to_string()),
+    }
+}
+
+/// 通过用户登录 shell（`$SHELL -lic`）执行 `<tool> --version`，仅在 macOS 上启用
+///
+/// 执行失败、超时或无输出时返回 None，由调用方不经 shell 直接执行 `<tool> --version`。
+#[cfg(not(target_os = "windows"))]
+fn login_shell_version_output(tool: &str) -> Option<std::process::Output> {
+    if !cfg!(target_os = "macos") || !crate::shell_args::is_safe_shell_arg(tool) {
+        return None;
+    }
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())?;
+    let mut cmd = std::process::Command::new(&shell);
+    cmd.arg("-lic").arg(format!("{tool} --version"));
+    match output_with_timeout(&mut cmd, VERSION_COMMAND_TIMEOUT) {
+        Ok(out) if out.status.success() && !out.stdout.trim_ascii().is_empty() => Some(out),
+        Ok(out) => {
+            tracing::debug!(tool, shell, status = %out.status, "登录 shell 执行版本命令失败");
+            None
+        }
+        Err(e) => {
+            tracing::warn!(tool, shell, error = %e, "登录 shell 执行版本命令失败");
+            None
+        }
+    }
+}
+
+/// 执行命令并等待输出，超时则结束进程，避免阻塞版本检测
+fn output_with_timeout(
+    cmd: &mut std::process::Command,
+    timeout: std::time::Duration,
+) -> std::io::Result<std::process::Output> {
+    use std::process::Stdio;
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let deadline = std::time::Instant::now() + timeout;
+    while child.try_wait()?.is_none() {
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("超过 {}s 未返回", timeout.as_secs()),
+            ));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    child.wait_with_output()
+}
+
+/// 常见的 CLI 安装目录及其对应的安装方式
+fn cli_search_paths() -> Vec<(std::path::PathBuf, &'static str)> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let pnpm_home = std::env::var_os("PNPM_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from);
+    cli_search_paths_in(&home, pnpm_home, yarn_global_bin())
+}
+
+/// 基于给定 HOME 构建搜索目录（便于测试注入临时目录）
+fn cli_search_paths_in(
+    home: &std::path::Path,
+    pnpm_home: Option<std::path::PathBuf>,
+    yarn_bin: Option<std::path::PathBuf>,
+) -> Vec<(std::path::PathBuf, &'static str)> {
+    // 常见的 npm 全局安装路径
+    let mut search_paths: Vec<(std::path::PathBuf, &'static str)> = vec![
+        (home.join(".npm-global/bin"), "npm-global"),
+        (home.join(".local/bin"), "local-bin"),
+        (home.join("n/bin"), "n"), // n version manager
+    ];
+
+    // pnpm 全局 bin 目录：优先使用 PNPM_HOME
+    if let Some(pnpm_home) = pnpm_home {
+        search_paths.push((pnpm_home, "pnpm"));
+    }
+    #[cfg(not(target_os = "windows"))]
+    search_paths.push((home.join(".local/share/pnpm"), "pnpm"));
+
+    // yarn 全局 bin 目录：优先使用 `yarn global bin` 的输出
+    if let Some(yarn_bin) = yarn_bin {
+        search_paths.push((yarn_bin, "yarn"));
+    }
+    #[cfg(not(target_os = "windows"))]
+    search_paths.push((home.join(".config/yarn/global/node_modules/.bin"), "yarn"));
+
+    #[cfg(target_os = "macos")]
+    {
+        search_paths.push((std::path::PathBuf::from("/opt/homebrew/bin"), "homebrew"));
+        search_paths.push((std::path::PathBuf::from("/usr/local/bin"), "homebrew"));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        search_paths.push((std::path::PathBuf::from("/usr/local/bin"), "system"));
+        search_paths.push((std::path::PathBuf::from("/usr/bin"), "system"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(appdata) = dirs::data_dir() {
+            search_paths.push((appdata.join("npm"), "npm-global"));
+        }
+        search_paths.push((
+            std::path::PathBuf::from("C:\\Program Files\\nodejs"),
+            "system",
+        ));
+    }
+
+    // 扫描 nvm 目录下的所有 node 版本
+    let nvm_base = home.join(".nvm/versions/node");
+    if nvm_base.exists() {
+        if let Ok(entries) = std::fs::read_dir(&nvm_base) {
+            for entry in entries.flatten() {
+                let bin_path = entry.path().join("bin");
+                if bin_path.exists() {
+                    search_paths.push((bin_path, "nvm"));
+                }
+            }
+        }
+    }
+
+    search_paths
+}
+
+/// 查询 `yarn global bin`，未安装 yarn 时返回 None
+fn yarn_global_bin() -> Option<std::path::PathBuf> {
+    use std::process::Command;
+
+    #[cfg(target_os = "windows")]
+    let output = output_with_timeout(
+        Command::new("cmd")
+            .args(["/C", "yarn global bin"])
+            .creation_flags(CREATE_NO_WINDOW),
+        VERSION_COMMAND_TIMEOUT,
+    );
+
+    #[cfg(not(target_os = "windows"))]
+    let output = output_with_timeout(
+        Command::new("yarn").args(["global", "bin"]),
+        VERSION_COMMAND_TIMEOUT,
+    );
+
+    let out = output.ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let dir = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!dir.is_empty()).then(|| std::path::PathBuf::from(dir))
+}
+
+/// 检测本地 CLI 版本：先直接执行，失败则扫描常见的 npm 全局安装路径（阻塞调用）
+///
+/// 冷启动时 shell 的 PATH 可能尚未初始化完成（常见于 macOS 登录 shell），扫描也失败时
+/// 再直接执行一次；两种方式都失败才报告错误。找到 CLI 却无法执行且 `node` 也不可用时，
+/// 在错误中注明缺少 Node.js。
+pub(crate) fn detect_local_version(command: &str) -> LocalToolVersion {
+    let direct_result = try_get_version(command);
+    if direct_result.version.is_some() {
+        return direct_result;
+    }
+    let scanned = scan_cli_version(command);
+    if scanned.version.is_some() {
+        return scanned;
+    }
+    let retried = try_get_version(command);
+    if retried.version.is_some() {
+        return retried;
+    }
+
+    // 找到了 CLI 却无法执行时，最常见的原因是缺少 Node.js 运行时
+    let found = scanned.path.is_some() || retried.error.as_deref() != Some(NOT_IN_PATH);
+    let mut result = combine_detection_failures(command, retried, scanned);
+    if found && !node_available() {
+        if let Some(error) = &mut result.error {
+            error.push_str(&format!("；{NODE_MISSING}"));
+        }
+    }
+    result
+}
+
+/// CLI 已安装但缺少运行时时附加到错误信息中的提示
+pub(crate) const NODE_MISSING: &str = "Node.js 不在 PATH 中";
+
+/// `node --version` 能否执行成功
+fn node_available() -> bool {
+    try_get_version("node").version.is_some()
+}
+
+/// 合并直接执行与扫描两次失败的结果，区分“未安装”与“已找到但无法执行”
+fn combine_detection_failures(
+    command: &str,
+    direct: LocalToolVersion,
+    scanned: LocalToolVersion,
+) -> LocalToolVersion {
+    let direct_error = direct
+        .error
+        .filter(|e| e != NOT_IN_PATH)
+        .map(|e| format!("已在 PATH 中找到 {command}，但无法执行: {e}"));
+    match scanned.path {
+        Some(path) => LocalToolVersion {
+            error: Some(direct_error.unwrap_or_else(|| {
+                format!("已找到 {path}，但执行 `{command} --version` 失败或无输出")
+            })),
+            path: Some(path),
+            install_method: scanned.install_method,
+            ..Default::default()
+        },
+        None => LocalToolVersion::failed(
+            direct_error
+                .unwrap_or_else(|| format!("未安装：PATH 及常见安装目录中均未找到 {command}")),
+        ),
+    }
+}
+
+/// 扫描常见路径查找 CLI
+fn scan_cli_version(tool: &str) -> LocalToolVersion {
+    let result = scan_cli_version_in(tool, &cli_search_paths());
+    if let Some(error) = &result.error {
+        tracing::warn!(tool, error = %error, "扫描 CLI 安装路径未找到可用版本");
+    }
+    result
+}
+
+/// 在给定目录列表中查找 CLI，返回第一个能输出版本号的结果
+fn scan_cli_version_in(
+    tool: &str,
+    search_paths: &[(std::path::PathBuf, &'static str)],
+) -> LocalToolVersion {
+    match find_installations(tool, search_paths).next() {
+        Some(found) => LocalToolVersion {
+            version: Some(found.version),
+            error: None,
+            path: Some(found.path),
+            install_method: Some(found.install_method),
+        },
+        // 文件存在但无法输出版本号时保留其路径，便于区分“已安装但无法执行”
+        None => match first_existing_candidate(tool, search_paths) {
+            Some((tool_path, install_method)) => LocalToolVersion {
+                error: Some(format!("{} 无法执行", tool_path.display())),
+                path: Some(tool_path.to_string_lossy().to_string()),
+                install_method: Some(install_method.to_string()),
+                ..Default::default()
+            },
+            None => LocalToolVersion::failed("未安装或无法执行"),
+        },
+    }
+}
+
+/// 按搜索顺序返回第一个存在的 CLI 文件（不检查能否执行）
+fn first_existing_candidate(
+    tool: &str,
+    search_paths: &[(std::path::PathBuf, &'static str)],
+) -> Option<(std::path::PathBuf, &'static str)> {
+    search_paths.iter().find_map(|(path, install_method)| {
+        tool_candidates(path, tool)
+            .into_iter()
+            .find(|tool_path| tool_path.exists())
+            .map(|tool_path| (tool_path, *install_method))
+    })
+}
+
+/// 一处检测到的 CLI 安装
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInstallation {
+    pub path: String,
+    pub version: String,
+    pub install_method: String,
+}
+
+/// 扫描所有搜索目录，收集 CLI 的全部安装（用于提示多处安装冲突）
+///
+/// 指向同一文件的符号链接只保留第一次出现的路径。
+pub fn detect_tool_conflicts(tool: &str) -> Vec<ToolInstallation> {
+    let mut seen = std::collections::HashSet::new();
+    find_installations(tool, &cli_search_paths())
+        .filter(|found| {
+            let canonical = std::fs::canonicalize(&found.path)
+                .unwrap_or_else(|_| std::path::PathBuf::from(&found.path));
+            seen.insert(canonical)
+        })
+        .collect()
+}
+
+/// PATH 或常见安装目录中找到的一个 CLI 可执行文件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolBinary {
+    pub path: String,
+    /// 符号链接解析后的实际文件
+    pub resolved_path: String,
+    /// `--version` 输出的版本号；无法执行时为 `None`
+    pub version: Option<String>,
+    /// 所在目录对应的安装方式；不属于已知安装目录的 PATH 目录为 `path`
+    pub install_method: String,
+    /// 所在目录是否在 PATH 中
+    pub in_path: bool,
+    /// 版本检测实际会使用的文件（PATH 中第一个可执行的，PATH 中没有时为扫描到的第一个）
+    pub active: bool,
+}
+
+/// 列出 PATH 与常见安装目录中的全部 CLI 可执行文件（含无法执行的），用于排查遮蔽与冲突
+///
+/// 指向同一文件的路径只保留第一次出现的。
+pub fn list_tool_binaries(tool: &str) -> Vec<ToolBinary> {
+    let path_dirs: Vec<std::path::PathBuf> = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    list_binaries_in(tool, &path_dirs, &cli_search_paths())
+}
+
+fn list_binaries_in(
+    tool: &str,
+    path_dirs: &[std::path::PathBuf],
+    search_paths: &[(std::path::PathBuf, &'static str)],
+) -> Vec<ToolBinary> {
+    let method_of = |dir: &std::path::Path| {
+        search_paths
+            .iter()
+            .find(|(path, _)| path == dir)
+            .map_or("path", |(_, method)| *method)
+    };
+    let dirs = path_dirs
+        .iter()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| (dir.clone(), method_of(dir), true))
+        .chain(
+            search_paths
+                .iter()
+                .filter(|(path, _)| !path_dirs.contains(path))
+                .map(|(path, method)| (path.clone(), *method, false)),
+        );
+
+    let mut seen = std::collections::HashSet::new();
+    let mut binaries: Vec<ToolBinary> = Vec::new();
+    for (dir, install_method, in_path) in dirs {
+        for tool_path in tool_candidates(&dir, tool) {
+            if !tool_path.is_file() {
+                continue;
+            }
+            let resolved = std::fs::canonicalize(&tool_path).unwrap_or_else(|_| tool_path.clone());
+            if !seen.insert(resolved.clone()) {
+                continue;
+            }
+            binaries.push(ToolBinary {
+                path: tool_path.to_string_lossy().to_string(),
+                resolved_path: resolved.to_string_lossy().to_string(),
+                version: run_version_command(&tool_path, &dir).map(|raw| extract_version(&raw)),
+                install_method: install_method.to_string(),
+                in_path,
+                active: false,
+            });
+        }
+    }
+
+    let active = binaries
+        .iter()
+        .position(|b| b.in_path && b.version.is_some())
+        .or_else(|| binaries.iter().position(|b| b.version.is_some()));
+    if let Some(index) = active {
+        binaries[index].active = true;
+    }
+    binaries
+}
+
+/// 按搜索顺序惰性地查找能输出版本号的 CLI 安装
+fn find_installations<'a>(
+    tool: &'a str,
+    search_paths: &'a [(std::path::PathBuf, &'static str)],
+) -> impl Iterator<Item = ToolInstallation> + 'a {
+    search_paths.iter().flat_map(move |(path, install_method)| {
+        tool_candidates(path, tool)
+            .into_iter()
+            .filter(|tool_path| tool_path.exists())
+            .filter_map(move |tool_path| {
+                let raw = run_version_command(&tool_path, path)?;
+                Some(ToolInstallation {
+                    path: tool_path.to_string_lossy().to_string(),
+                    version: extract_version(&raw),
+                    install_method: install_method.to_string(),
+                })
+            })
+    })
+}
+
+/// 目录下可能的 CLI 可执行文件（Windows 上 npm/pnpm 可能生成 .cmd / .exe / .ps1 shim）
+fn tool_candidates(dir: &std::path::Path, tool: &str) -> Vec<std::path::PathBuf> {
+    if cfg!(target_os = "windows") {
+        ["cmd", "exe", "ps1"]
+            .iter()
+            .map(|ext| dir.join(format!("{tool}.{ext}")))
+            .collect()
+    } else {
+        vec![dir.join(tool)]
+    }
+}
+
+/// 执行 `<tool> --version`，成功且有输出时返回原始输出
+fn run_version_command(tool_path: &std::path::Path, dir: &std::path::Path) -> Option<String> {
+    use std::process::Command;
+
+    // 构建 PATH 环境变量，确保 node 可被找到
+    let current_path = std::env::var("PATH").unwrap_or_default();
+    let separator = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+    let new_path = format!("{}{separator}{}", dir.display(), current_path);
+
+    #[cfg(target_os = "windows")]
+    let output = {
+        let is_ps1 = tool_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ps1"));
+        let mut cmd = if is_ps1 {
+            let mut cmd = Command::new("powershell");
+            cmd.args(["-NoProfile", "-File"]).arg(tool_path);
+            cmd
+        } else {
+            Command::new(tool_path)
+        };
+        output_with_timeout(
+            cmd.arg("--version")
+                .env("PATH", &new_path)
+                .creation_flags(CREATE_NO_WINDOW),
+            VERSION_COMMAND_TIMEOUT,
+        )
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let output = output_with_timeout(
+        Command::new(tool_path)
+            .arg("--version")
+            .env("PATH", &new_path),
+        VERSION_COMMAND_TIMEOUT,
+    );
+
+    let out = match output {
+        Ok(out) => out,
+        Err(e) => {
+            tracing::warn!(path = %tool_path.display(), error = %e, "执行 CLI 版本命令失败");
+            return None;
+        }
+    };
+    if !out.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+    let raw = if stdout.is_empty() { stderr } else { stdout };
+    (!raw.is_empty()).then_some(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_latest_from_abbreviated_npm_metadata() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/npm_abbreviated_claude_code.json"
+        ))
+        .expect("fixture should be valid JSON");
+        assert_eq!(parse_npm_latest_version(&fixture).as_deref(), Ok("2.0.14"));
+        assert!(parse_npm_latest_version(&serde_json::json!({ "name": "x" })).is_err());
+    }
+
+    #[test]
+    fn lists_npm_versions_newest_first() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/npm_abbreviated_claude_code.json"
+        ))
+        .expect("fixture should be valid JSON");
+        assert_eq!(parse_npm_versions(&fixture).unwrap(), ["2.0.14", "2.0.13"]);
+
+        let json = serde_json::json!({ "versions": {
+            "1.2.0-beta.1": {}, "1.10.0": {}, "1.2.0": {}, "not-semver": {}
+        } });
+        assert_eq!(
+            parse_npm_versions(&json).unwrap(),
+            ["1.10.0", "1.2.0", "1.2.0-beta.1"]
+        );
+        assert!(parse_npm_versions(&serde_json::json!({ "name": "x" })).is_err());
+    }
+
+    #[test]
+    fn version_page_limits_and_reports_latest() {
+        let versions: serde_json::Map<String, serde_json::Value> = (0..80)
+            .map(|minor| (format!("1.{minor}.0"), serde_json::json!({})))
+            .collect();
+        let json = serde_json::json!({
+            "dist-tags": { "latest": "1.78.0" },
+            "versions": versions
+        });
+
+        let page = version_page(&json, 0, 500).unwrap();
+        assert_eq!(page.total, 80);
+        assert_eq!(page.versions.len(), MAX_VERSION_PAGE_SIZE);
+        assert_eq!(page.versions[0], "1.79.0");
+        assert_eq!(page.latest.as_deref(), Some("1.78.0"));
+
+        let page = version_page(&json, 75, 10).unwrap();
+        assert_eq!(page.versions, ["1.4.0", "1.3.0", "1.2.0", "1.1.0", "1.0.0"]);
+        assert_eq!(page.offset, 75);
+    }
+
+    #[test]
+    fn version_report_renders_csv_and_json() {
+        let versions = vec![ToolVersion {
+            name: "claude".to_string(),
+            version: Some("2.0.13".to_string()),
+            latest_version: Some("2.0.14".to_string()),
+            latest_version_stale: false,
+            rate_limited: false,
+            error: Some("warn, \"quoted\"".to_string()),
+            path: Some("/usr/local/bin/claude".to_string()),
+            update_available: Some(true),
+            pinned: false,
+            install_method: Some("npm-global".to_string()),
+        }];
+
+        let csv = render_version_report(&versions, VersionReportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], VERSION_REPORT_COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "claude,2.0.13,2.0.14,true,false,npm-global,/usr/local/bin/claude,false,\"warn, \"\"quoted\"\"\""
+        );
+        assert_eq!(csv_field("=cmd()"), "'=cmd()");
+
+        let json = render_version_report(&versions, VersionReportFormat::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0]["update_available"], true);
+        assert_eq!(parsed[0]["install_method"], "npm-global");
+
+        assert!("CSV".parse::<VersionReportFormat>().is_ok());
+        assert!("xml".parse::<VersionReportFormat>().is_err());
+    }
+
+    #[test]
+    fn node_engine_ranges_are_checked() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/npm_abbreviated_claude_code.json"
+        ))
+        .expect("fixture should be valid JSON");
+        assert_eq!(engines_node(&fixture, None).as_deref(), Some(">=18.0.0"));
+        assert_eq!(
+            engines_node(&fixture, Some("2.0.13")).as_deref(),
+            Some(">=18.0.0")
+        );
+
+        assert_eq!(node_satisfies("v20.11.1", ">=18.0.0"), Some(true));
+        assert_eq!(node_satisfies("16.20.2", ">=18.0.0"), Some(false));
+        assert_eq!(node_satisfies("18.19.0", "^16.14 || >=18"), Some(true));
+        assert_eq!(node_satisfies("17.0.0", "^16.14 || >=18"), Some(false));
+        assert_eq!(node_satisfies("20.0.0", ">=18 <22"), Some(true));
+        assert_eq!(node_satisfies("22.1.0", ">=18 <22"), Some(false));
+        assert_eq!(node_satisfies("20.0.0", "not a range"), None);
+    }
+
+    #[test]
+    fn update_available_compares_semver() {
+        assert_eq!(
+            compute_update_available(Some("1.2.0"), Some("1.4.1")),
+            Some(true)
+        );
+        assert_eq!(
+            compute_update_available(Some("1.4.1"), Some("1.4.1")),
+            Some(false)
+        );
+        assert_eq!(
+            compute_update_available(Some("2.0.0"), Some("1.9.9")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn update_available_orders_prerelease_before_release() {
+        assert_eq!(
+            compute_update_available(Some("1.2.0-beta.1"), Some("1.2.0")),
+            Some(true)
+        );
+        assert_eq!(
+            compute_update_available(Some("1.2.0"), Some("1.2.0-beta.1")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn pin_matches_normalized_local_version() {
+        assert!(matches_pin(Some("1.2.0"), Some("v1.2.0")));
+        assert!(matches_pin(Some("nightly"), Some("nightly")));
+        assert!(!matches_pin(Some("1.2.1"), Some("1.2.0")));
+        assert!(!matches_pin(Some("1.2.0"), None));
+        assert!(!matches_pin(None, Some("1.2.0")));
+    }
+
+    #[test]
+    fn update_available_is_none_for_unparsable_versions() {
+        assert_eq!(
+            compute_update_available(Some("unknown"), Some("1.0.0")),
+            None
+        );
+        assert_eq!(compute_update_available(Some("1.0.0"), None), None);
+        assert_eq!(compute_update_available(None, Some("1.0.0")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_finds_cli_in_pnpm_home_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().expect("create temp home");
+        let pnpm_dir = home.path().join(".local/share/pnpm");
+        std::fs::create_dir_all(&pnpm_dir).expect("create pnpm dir");
+        let tool = "cc-switch-fake-cli";
+        let tool_path = pnpm_dir.join(tool);
+        std::fs::write(&tool_path, "#!/bin/sh\necho '1.2.3 (Fake CLI)'\n").expect("write shim");
+        std::fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod shim");
+
+        let search_paths = cli_search_paths_in(home.path(), None, None);
+        let result = scan_cli_version_in(tool, &search_paths);
+
+        assert_eq!(result.version.as_deref(), Some("1.2.3"));
+        assert_eq!(result.install_method.as_deref(), Some("pnpm"));
+        assert_eq!(
+            result.path.as_deref(),
+            Some(tool_path.to_string_lossy().as_ref())
+        );
+    }
+    #[cfg(unix)]
+    #[test]
+    fn scan_distinguishes_broken_install_from_missing() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().expect("create temp home");
+        let tool = "cc-switch-fake-cli";
+        let search_paths = cli_search_paths_in(home.path(), None, None);
+
+        let missing = scan_cli_version_in(tool, &search_paths);
+        assert!(missing.path.is_none());
+        let combined =
+            combine_detection_failures(tool, LocalToolVersion::failed(NOT_IN_PATH), missing);
+        assert!(combined.error.unwrap().starts_with("未安装"));
+
+        let bin_dir = home.path().join(".local/bin");
+        std::fs::create_dir_all(&bin_dir).expect("create bin dir");
+        let tool_path = bin_dir.join(tool);
+        std::fs::write(&tool_path, "#!/bin/sh\nexit 1\n").expect("write shim");
+        std::fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod shim");
+
+        let broken = scan_cli_version_in(tool, &search_paths);
+        assert!(broken.version.is_none());
+        assert_eq!(
+            broken.path.as_deref(),
+            Some(tool_path.to_string_lossy().as_ref())
+        );
+        let combined =
+            combine_detection_failures(tool, LocalToolVersion::failed(NOT_IN_PATH), broken);
+        let error = combined.error.unwrap();
+        assert!(error.starts_with("已找到"), "{error}");
+        assert_eq!(combined.install_method.as_deref(), Some("local-bin"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_with_timeout_kills_hanging_command() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg("echo 1.2.3");
+        let out = output_with_timeout(&mut cmd, std::time::Duration::from_secs(5))
+            .expect("command finishes");
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "1.2.3");
+
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg("sleep 10");
+        let started = std::time::Instant::now();
+        let err = output_with_timeout(&mut cmd, std::time::Duration::from_millis(200))
+            .expect_err("command times out");
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_installations_collects_every_match() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().expect("create temp home");
+        let tool = "cc-switch-fake-cli";
+        let mut paths = Vec::new();
+        for (dir, version) in [(".npm-global/bin", "1.2.0"), (".local/bin", "1.4.1")] {
+            let dir = home.path().join(dir);
+            std::fs::create_dir_all(&dir).expect("create bin dir");
+            let tool_path = dir.join(tool);
+            std::fs::write(&tool_path, format!("#!/bin/sh\necho '{version}'\n"))
+                .expect("write shim");
+            std::fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+                .expect("chmod shim");
+            paths.push(tool_path.to_string_lossy().to_string());
+        }
+
+        let search_paths = cli_search_paths_in(home.path(), None, None);
+        let found: Vec<_> = find_installations(tool, &search_paths).collect();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path, paths[0]);
+        assert_eq!(found[0].version, "1.2.0");
+        assert_eq!(found[0].install_method, "npm-global");
+        assert_eq!(found[1].version, "1.4.1");
+        assert_eq!(found[1].install_method, "local-bin");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn list_binaries_marks_the_path_binary_as_active() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = tempfile::tempdir().expect("create temp home");
+        let tool = "cc-switch-fake-cli";
+        let write_tool = |dir: &std::path::Path, script: &str| {
+            std::fs::create_dir_all(dir).expect("create bin dir");
+            let tool_path = dir.join(tool);
+            std::fs::write(&tool_path, script).expect("write shim");
+            std::fs::set_permissions(&tool_path, std::fs::Permissions::from_mode(0o755))
+                .expect("chmod shim");
+            tool_path
+        };
+        let custom = home.path().join("custom/bin");
+        write_tool(&custom, "#!/bin/sh\nexit 1\n");
+        let npm_global = home.path().join(".npm-global/bin");
+        write_tool(&npm_global, "#!/bin/sh\necho '1.2.0'\n");
+        let local_bin = home.path().join(".local/bin");
+        let local = write_tool(&local_bin, "#!/bin/sh\necho '1.4.1'\n");
+        let linked = home.path().join("linked");
+        std::fs::create_dir_all(&linked).unwrap();
+        std::os::unix::fs::symlink(&local, linked.join(tool)).unwrap();
+
+        let search_paths = cli_search_paths_in(home.path(), None, None);
+        let path_dirs = vec![custom.clone(), local_bin.clone(), linked];
+        let found = list_binaries_in(tool, &path_dirs, &search_paths);
+
+        assert_eq!(found.len(), 3, "{found:?}");
+        assert_eq!(found[0].install_method, "path");
+        assert_eq!(found[0].version, None);
+        assert!(!found[0].active);
+        assert_eq!(found[1].version.as_deref(), Some("1.4.1"));
+        assert_eq!(found[1].install_method, "local-bin");
+        assert!(found[1].in_path && found[1].active);
+        assert_eq!(found[2].install_method, "npm-global");
+        assert!(!found[2].in_path && !found[2].active);
+    }
+
+    #[tokio::test]
+    async fn unreachable_registry_reports_error() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("reserve a local port")
+            .port();
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let status = check_registry_status(&client, &format!("http://127.0.0.1:{port}/")).await;
+        assert_eq!(status.registry, format!("http://127.0.0.1:{port}"));
+        assert!(!status.reachable);
+        assert_eq!(status.latency_ms, None);
+        assert!(status.error.is_some());
+    }
+}
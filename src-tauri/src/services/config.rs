@@ -1,14 +1,31 @@
 use super::provider::ProviderService;
 use crate::app_config::{AppType, MultiAppConfig};
+use crate::database::Database;
 use crate::error::AppError;
+use crate::init_status::MigrationErrorPayload;
 use crate::provider::Provider;
 use chrono::Utc;
+use serde::Serialize;
 use serde_json::Value;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 const MAX_BACKUPS: usize = 10;
 
+/// 迁移前备份文件名前缀（`config.backup-<ts>.json`）
+const MIGRATION_BACKUP_PREFIX: &str = "config.backup-";
+
+/// 迁移前生成的 config.json 备份
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBackupEntry {
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// 修改时间（Unix 秒）
+    pub modified_at: Option<i64>,
+}
+
 /// 配置导入导出相关业务逻辑
 pub struct ConfigService;
 
@@ -38,6 +55,139 @@ impl ConfigService {
         Ok(backup_id)
     }
 
+    /// JSON → SQLite 迁移前，将 config.json 复制为同目录下的 `config.backup-<ts>.json`。
+    ///
+    /// 迁移备份不参与 `backups/` 目录的数量清理，由用户手动恢复或删除。
+    pub fn create_migration_backup(config_path: &Path) -> Result<PathBuf, AppError> {
+        let dir = config_path
+            .parent()
+            .ok_or_else(|| AppError::Config("Invalid config path".into()))?;
+
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let mut backup_path = dir.join(format!("{MIGRATION_BACKUP_PREFIX}{timestamp}.json"));
+        let mut counter = 1;
+        while backup_path.exists() {
+            backup_path = dir.join(format!(
+                "{MIGRATION_BACKUP_PREFIX}{timestamp}_{counter}.json"
+            ));
+            counter += 1;
+        }
+
+        fs::copy(config_path, &backup_path).map_err(|e| AppError::io(&backup_path, e))?;
+        Ok(backup_path)
+    }
+
+    /// 列出目录中的迁移前备份，按修改时间从新到旧排序。
+    pub fn list_migration_backups(dir: &Path) -> Result<Vec<ConfigBackupEntry>, AppError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(iter) => iter,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::io(dir, e)),
+        };
+
+        let mut backups: Vec<ConfigBackupEntry> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if !file_name.starts_with(MIGRATION_BACKUP_PREFIX) || !file_name.ends_with(".json")
+                {
+                    return None;
+                }
+                let metadata = entry.metadata().ok()?;
+                if !metadata.is_file() {
+                    return None;
+                }
+                let modified_at = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64);
+                Some(ConfigBackupEntry {
+                    path: entry.path().to_string_lossy().to_string(),
+                    file_name,
+                    size_bytes: metadata.len(),
+                    modified_at,
+                })
+            })
+            .collect();
+
+        backups.sort_by(|a, b| {
+            b.modified_at
+                .cmp(&a.modified_at)
+                .then_with(|| b.file_name.cmp(&a.file_name))
+        });
+        Ok(backups)
+    }
+
+    /// 手动重新执行 JSON → SQLite 迁移，返回使用的源文件路径。
+    ///
+    /// 源文件依次取 `config.json`、已归档的 `config.json.migrated`、最新的迁移前备份。
+    /// 数据库中已有供应商时，仅在 `force` 为 true 时覆盖。
+    #[tracing::instrument(skip(db))]
+    pub fn rerun_migration(db: &Database, force: bool) -> Result<PathBuf, MigrationErrorPayload> {
+        let dir = crate::config::get_app_config_dir();
+        let source = Self::find_migration_source(&dir).ok_or_else(|| MigrationErrorPayload {
+            stage: "source".to_string(),
+            message: format!("未找到可用于迁移的配置文件: {}", dir.display()),
+            backup_path: None,
+        })?;
+        let fail = |stage: &str, message: String| MigrationErrorPayload {
+            stage: stage.to_string(),
+            message,
+            backup_path: Some(source.to_string_lossy().to_string()),
+        };
+
+        let config =
+            MultiAppConfig::load_from_file(&source).map_err(|e| fail("load", e.to_string()))?;
+
+        let has_data = !db
+            .is_providers_table_empty()
+            .map_err(|e| fail("check", e.to_string()))?;
+        if has_data && !force {
+            return Err(fail(
+                "check",
+                "数据库中已有供应商数据，如需覆盖请使用强制模式重新迁移".to_string(),
+            ));
+        }
+
+        db.remigrate_from_json(&config)
+            .map_err(|e| fail("migrate", e.to_string()))?;
+        Ok(source)
+    }
+
+    fn find_migration_source(dir: &Path) -> Option<PathBuf> {
+        let candidates = [dir.join("config.json"), dir.join("config.json.migrated")];
+        if let Some(path) = candidates.into_iter().find(|p| p.is_file()) {
+            return Some(path);
+        }
+        Self::list_migration_backups(dir)
+            .ok()?
+            .into_iter()
+            .next()
+            .map(|entry| PathBuf::from(entry.path))
+    }
+
+    /// 将数据库中的完整配置（供应商、当前选中项、MCP、提示词、Skills）与设备设置导出为格式化 JSON。
+    ///
+    /// 导出结果可作为 `config.json` 通过 [`Self::rerun_migration`] 重新导入。
+    /// `include_secrets` 为 false 时，API Key / Token / 密码等字段会被替换为占位符。
+    pub fn export_config_json(db: &Database, include_secrets: bool) -> Result<String, AppError> {
+        let config = db.export_to_multi_app_config()?;
+        let mut value =
+            serde_json::to_value(&config).map_err(|e| AppError::JsonSerialize { source: e })?;
+        if let Some(map) = value.as_object_mut() {
+            let settings = serde_json::to_value(crate::settings::get_settings())
+                .map_err(|e| AppError::JsonSerialize { source: e })?;
+            map.insert("settings".to_string(), settings);
+        }
+
+        if !include_secrets {
+            crate::secret_store::redact_json_secrets(&mut value);
+        }
+
+        serde_json::to_string_pretty(&value).map_err(|e| AppError::JsonSerialize { source: e })
+    }
+
     fn cleanup_old_backups(backup_dir: &Path, retain: usize) -> Result<(), AppError> {
         if retain == 0 {
             return Ok(());
@@ -218,3 +368,40 @@ impl ConfigService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::secret_store::{redact_json_secrets, REDACTED_PLACEHOLDER};
+    use serde_json::json;
+
+    #[test]
+    fn redact_secrets_masks_nested_credentials() {
+        let mut value = json!({
+            "settingsConfig": {
+                "env": {
+                    "ANTHROPIC_AUTH_TOKEN": "sk-live",
+                    "ANTHROPIC_BASE_URL": "https://api.example.com"
+                }
+            },
+            "auth": { "OPENAI_API_KEY": "sk-openai" },
+            "servers": [{ "env": { "GITHUB_TOKEN": "ghp" }, "args": ["--token"] }]
+        });
+
+        redact_json_secrets(&mut value);
+
+        assert_eq!(
+            value["settingsConfig"]["env"]["ANTHROPIC_AUTH_TOKEN"],
+            REDACTED_PLACEHOLDER
+        );
+        assert_eq!(
+            value["settingsConfig"]["env"]["ANTHROPIC_BASE_URL"],
+            "https://api.example.com"
+        );
+        assert_eq!(value["auth"]["OPENAI_API_KEY"], REDACTED_PLACEHOLDER);
+        assert_eq!(
+            value["servers"][0]["env"]["GITHUB_TOKEN"],
+            REDACTED_PLACEHOLDER
+        );
+        assert_eq!(value["servers"][0]["args"][0], "--token");
+    }
+}
@@ -0,0 +1,155 @@
+//! 受管理 CLI 配置文件的外部修改监听
+//!
+//! 用户手动编辑或其它工具改写 `~/.claude/settings.json` 等文件时，向前端发送
+//! `config-changed-externally` 事件，提示重新加载，避免之后切换供应商时覆盖用户修改。
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 外部修改事件名
+pub const CONFIG_CHANGED_EVENT: &str = "config-changed-externally";
+
+/// 合并短时间内多次写入的防抖间隔
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChangedPayload {
+    pub path: String,
+}
+
+static WATCHER: OnceLock<Mutex<Option<Debouncer<RecommendedWatcher>>>> = OnceLock::new();
+
+/// 应用自身最近一次写入各文件的内容哈希
+static SELF_WRITES: OnceLock<Mutex<HashMap<PathBuf, [u8; 32]>>> = OnceLock::new();
+
+fn watcher_cell() -> &'static Mutex<Option<Debouncer<RecommendedWatcher>>> {
+    WATCHER.get_or_init(|| Mutex::new(None))
+}
+
+fn self_writes() -> &'static Mutex<HashMap<PathBuf, [u8; 32]>> {
+    SELF_WRITES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn hash_bytes(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// 记录应用自身写入的内容，监听到该内容时不视为外部修改
+pub fn note_self_write(path: &Path, data: &[u8]) {
+    if let Ok(mut map) = self_writes().lock() {
+        map.insert(path.to_path_buf(), hash_bytes(data));
+    }
+}
+
+/// 文件当前内容是否与应用最近一次写入一致
+fn is_self_write(path: &Path) -> bool {
+    let Ok(map) = self_writes().lock() else {
+        return false;
+    };
+    let Some(expected) = map.get(path) else {
+        return false;
+    };
+    std::fs::read(path)
+        .map(|data| hash_bytes(&data) == *expected)
+        .unwrap_or(false)
+}
+
+/// 需要监听的 CLI 配置文件
+fn managed_config_paths() -> Vec<PathBuf> {
+    vec![
+        crate::config::get_claude_settings_path(),
+        crate::codex_config::get_codex_auth_path(),
+        crate::codex_config::get_codex_config_path(),
+        crate::gemini_config::get_gemini_env_path(),
+        crate::gemini_config::get_gemini_settings_path(),
+    ]
+}
+
+/// 启动监听（已在运行时先停止再按当前目录重新启动）
+///
+/// 监听配置文件所在目录而非文件本身，以便捕获"写临时文件再 rename"式的替换。
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let files: HashSet<PathBuf> = managed_config_paths().into_iter().collect();
+    let dirs: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|p| p.parent().map(Path::to_path_buf))
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    let watched_files = files.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE_INTERVAL, move |res: DebounceEventResult| {
+        let events = match res {
+            Ok(events) => events,
+            Err(e) => {
+                log::warn!("配置文件监听出错: {e}");
+                return;
+            }
+        };
+
+        let changed: HashSet<&PathBuf> = events
+            .iter()
+            .map(|event| &event.path)
+            .filter(|path| watched_files.contains(*path))
+            .collect();
+        for path in changed {
+            if is_self_write(path) {
+                continue;
+            }
+            log::info!("检测到配置文件被外部修改: {}", path.display());
+            let payload = ConfigChangedPayload {
+                path: path.to_string_lossy().to_string(),
+            };
+            if let Err(e) = app.emit(CONFIG_CHANGED_EVENT, payload) {
+                log::warn!("发送配置变更事件失败: {e}");
+            }
+        }
+    })
+    .map_err(|e| format!("创建配置文件监听失败: {e}"))?;
+
+    for dir in &dirs {
+        debouncer
+            .watcher()
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("监听目录失败 {}: {e}", dir.display()))?;
+    }
+
+    let mut guard = watcher_cell()
+        .lock()
+        .map_err(|e| format!("获取监听状态失败: {e}"))?;
+    *guard = Some(debouncer);
+    log::info!("已启动配置文件监听（{} 个目录）", dirs.len());
+    Ok(())
+}
+
+/// 停止监听，返回此前是否在运行
+pub fn stop() -> Result<bool, String> {
+    let mut guard = watcher_cell()
+        .lock()
+        .map_err(|e| format!("获取监听状态失败: {e}"))?;
+    Ok(guard.take().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_write_is_recognized_until_content_changes() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, b"{}").expect("write file");
+
+        note_self_write(&path, b"{}");
+        assert!(is_self_write(&path));
+
+        std::fs::write(&path, b"{\"edited\":true}").expect("external edit");
+        assert!(!is_self_write(&path));
+    }
+}
@@ -0,0 +1,268 @@
+//! 供应商 API 连通性测试
+//!
+//! 通过请求模型列表验证 Base URL 与 API Key 是否可用，不产生 token 消耗。
+
+use crate::app_config::AppType;
+use futures::StreamExt;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// 连通性测试的请求超时时间
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 批量测试中单个供应商的总超时（含连接建立与响应读取）
+const BATCH_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// 批量测试的最大并发数
+const BATCH_CONCURRENCY: usize = 4;
+
+/// Anthropic API 版本头
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTestResult {
+    pub ok: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub message: String,
+}
+
+/// 按工具类型拼接模型列表地址（兼容 Base URL 是否已带版本前缀）
+fn models_url(app_type: &AppType, base_url: &str) -> String {
+    let base = base_url.trim().trim_end_matches('/');
+    match app_type {
+        AppType::Claude | AppType::Codex => {
+            if base.ends_with("/v1") {
+                format!("{base}/models")
+            } else {
+                format!("{base}/v1/models")
+            }
+        }
+        AppType::Gemini => {
+            if base.ends_with("/v1beta") || base.ends_with("/v1") {
+                format!("{base}/models")
+            } else {
+                format!("{base}/v1beta/models")
+            }
+        }
+    }
+}
+
+/// 发起一次带鉴权的模型列表请求，返回状态码与耗时
+///
+/// API Key 只出现在请求头中，不写入日志或返回值。
+pub async fn test_connection(
+    client: &reqwest::Client,
+    app_type: &AppType,
+    base_url: &str,
+    api_key: &str,
+) -> Result<ConnectionTestResult, String> {
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err("API Key 不能为空".to_string());
+    }
+
+    let url = models_url(app_type, base_url);
+    let parsed = url::Url::parse(&url).map_err(|e| format!("无效的 Base URL: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Base URL 仅支持 http/https 协议".to_string());
+    }
+
+    let request = client.get(parsed).timeout(CONNECTION_TEST_TIMEOUT);
+    let request = match app_type {
+        // 第三方中转通常只认 Bearer，官方 API 认 x-api-key，两者都带上
+        AppType::Claude => request
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .bearer_auth(api_key),
+        AppType::Codex => request.bearer_auth(api_key),
+        AppType::Gemini => request.header("x-goog-api-key", api_key),
+    };
+
+    let started_at = Instant::now();
+    let result = request.send().await;
+    let latency_ms = started_at.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(response) => {
+            let status = response.status();
+            let message = if status.is_success() {
+                "连接成功".to_string()
+            } else if status == reqwest::StatusCode::UNAUTHORIZED
+                || status == reqwest::StatusCode::FORBIDDEN
+            {
+                "API Key 无效或无权限".to_string()
+            } else if status == reqwest::StatusCode::NOT_FOUND {
+                "接口地址不存在，请检查 Base URL".to_string()
+            } else {
+                format!("请求失败: HTTP {status}")
+            };
+            ConnectionTestResult {
+                ok: status.is_success(),
+                status_code: Some(status.as_u16()),
+                latency_ms,
+                message,
+            }
+        }
+        Err(e) => {
+            let message = if e.is_timeout() {
+                "连接超时".to_string()
+            } else if e.is_connect() {
+                "无法连接到服务器".to_string()
+            } else {
+                "请求失败".to_string()
+            };
+            log::warn!("供应商连通性测试失败: {message} ({})", e.without_url());
+            ConnectionTestResult {
+                ok: false,
+                status_code: None,
+                latency_ms,
+                message,
+            }
+        }
+    })
+}
+
+/// 批量测试中的一个供应商
+#[derive(Debug, Clone)]
+pub struct ConnectionTarget {
+    pub app_type: AppType,
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 为空时使用官方默认地址
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+}
+
+/// 批量测试中单个供应商的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderTestResult {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    #[serde(flatten)]
+    pub result: ConnectionTestResult,
+}
+
+/// 未配置 Base URL 时使用的官方地址
+fn default_base_url(app_type: &AppType) -> &'static str {
+    match app_type {
+        AppType::Claude => "https://api.anthropic.com",
+        AppType::Codex => "https://api.openai.com/v1",
+        AppType::Gemini => "https://generativelanguage.googleapis.com",
+    }
+}
+
+fn failed(message: impl Into<String>, latency_ms: u64) -> ConnectionTestResult {
+    ConnectionTestResult {
+        ok: false,
+        status_code: None,
+        latency_ms,
+        message: message.into(),
+    }
+}
+
+async fn test_target(client: &reqwest::Client, target: &ConnectionTarget) -> ConnectionTestResult {
+    let Some(api_key) = target.api_key.as_deref() else {
+        return failed("未配置 API Key", 0);
+    };
+    let base_url = target
+        .base_url
+        .as_deref()
+        .unwrap_or_else(|| default_base_url(&target.app_type));
+    let started_at = Instant::now();
+    match tokio::time::timeout(
+        BATCH_TEST_TIMEOUT,
+        test_connection(client, &target.app_type, base_url, api_key),
+    )
+    .await
+    {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => failed(crate::secret_store::redact_secrets(&e), 0),
+        Err(_) => failed("连接超时", started_at.elapsed().as_millis() as u64),
+    }
+}
+
+/// 并发测试多个供应商（最多同时 4 个），每完成一个调用一次 `on_result`
+///
+/// 返回结果按 `targets` 的顺序排列。
+pub async fn test_all(
+    client: &reqwest::Client,
+    targets: Vec<ConnectionTarget>,
+    mut on_result: impl FnMut(&ProviderTestResult),
+) -> Vec<ProviderTestResult> {
+    let mut results: Vec<(usize, ProviderTestResult)> = Vec::with_capacity(targets.len());
+    let mut stream = futures::stream::iter(targets.into_iter().enumerate())
+        .map(|(index, target)| async move {
+            let result = test_target(client, &target).await;
+            (
+                index,
+                ProviderTestResult {
+                    app_type: target.app_type.as_str().to_string(),
+                    provider_id: target.provider_id,
+                    provider_name: target.provider_name,
+                    result,
+                },
+            )
+        })
+        .buffer_unordered(BATCH_CONCURRENCY);
+    while let Some((index, result)) = stream.next().await {
+        on_result(&result);
+        results.push((index, result));
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_all_reports_in_input_order_without_network() {
+        let client = reqwest::Client::new();
+        let target = |id: &str, base_url: &str, api_key: Option<&str>| ConnectionTarget {
+            app_type: AppType::Claude,
+            provider_id: id.to_string(),
+            provider_name: id.to_uppercase(),
+            base_url: Some(base_url.to_string()),
+            api_key: api_key.map(str::to_string),
+        };
+        let targets = vec![
+            target("a", "https://relay.example", None),
+            target("b", "ftp://relay.example", Some("sk-test")),
+        ];
+
+        let mut progress = 0;
+        let results = test_all(&client, targets, |_| progress += 1).await;
+        assert_eq!(progress, 2);
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| r.provider_id.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert!(results.iter().all(|r| !r.result.ok));
+        assert_eq!(results[0].result.message, "未配置 API Key");
+    }
+
+    #[test]
+    fn models_url_handles_version_suffix() {
+        assert_eq!(
+            models_url(&AppType::Claude, "https://api.anthropic.com/"),
+            "https://api.anthropic.com/v1/models"
+        );
+        assert_eq!(
+            models_url(&AppType::Codex, "https://api.openai.com/v1"),
+            "https://api.openai.com/v1/models"
+        );
+        assert_eq!(
+            models_url(
+                &AppType::Gemini,
+                "https://generativelanguage.googleapis.com"
+            ),
+            "https://generativelanguage.googleapis.com/v1beta/models"
+        );
+    }
+}
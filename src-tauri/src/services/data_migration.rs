@@ -0,0 +1,363 @@
+//! 便携目录与安装目录之间的数据迁移
+//!
+//! 便携模式的数据位于可执行文件旁的 `data/`，安装模式位于 `~/.cc-switch`，切换安装方式后
+//! 原有数据会留在另一处。迁移时先把数据库、设置、密钥盐与备份复制到目标旁的临时目录并逐一
+//! 校验（数据库跑完整性检查，其余文件逐字节比对），全部通过后才移入目标目录；目标不存在时
+//! 整个临时目录一次性重命名为目标目录。
+//!
+//! 当前正在使用的数据目录不能作为迁移目标：运行中的数据库连接会与覆盖写入冲突。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::error::AppError;
+
+const DB_FILE: &str = "cc-switch.db";
+/// 随数据库一起迁移的文件（`keystore.salt` 用于解密已加密的 API Key）
+const DATA_FILES: &[&str] = &["settings.json", "keystore.salt"];
+const BACKUP_DIR: &str = "backups";
+/// 判断目标是否为空时忽略的文件
+const IGNORED_ENTRIES: &[&str] = &[".instance.lock", ".write-test"];
+
+/// 迁移的单个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigratedFile {
+    /// 相对数据目录的路径（如 `cc-switch.db`、`backups/db_backup_x.db`）
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// 迁移结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataMigrationReport {
+    pub from: String,
+    pub to: String,
+    pub files: Vec<MigratedFile>,
+    pub total_bytes: u64,
+}
+
+/// 解析迁移位置：`portable`、`installed` 或绝对路径
+fn resolve_location(location: &str) -> Result<PathBuf, AppError> {
+    match location.trim() {
+        "portable" => crate::config::portable_data_dir().ok_or_else(|| {
+            AppError::localized(
+                "data.migrate.portable_unavailable",
+                "无法确定便携模式数据目录",
+                "Unable to determine the portable data directory",
+            )
+        }),
+        "installed" => Ok(crate::config::installed_data_dir()),
+        other if Path::new(other).is_absolute() => Ok(PathBuf::from(other)),
+        other => Err(AppError::localized(
+            "data.migrate.invalid_location",
+            format!("无效的数据位置: {other}（应为 portable、installed 或绝对路径）"),
+            format!(
+                "Invalid data location: {other} (expected portable, installed or an absolute path)"
+            ),
+        )),
+    }
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    canonical(a) == canonical(b)
+}
+
+/// 目录不存在或只包含可忽略的文件
+fn is_empty_dir(dir: &Path) -> Result<bool, AppError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(AppError::io(dir, e)),
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .all(|entry| IGNORED_ENTRIES.contains(&entry.file_name().to_string_lossy().as_ref())))
+}
+
+/// 源目录中需要迁移的文件（相对路径）
+fn source_files(from: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut files: Vec<PathBuf> = std::iter::once(DB_FILE)
+        .chain(DATA_FILES.iter().copied())
+        .map(PathBuf::from)
+        .filter(|name| from.join(name).is_file())
+        .collect();
+
+    let backup_dir = from.join(BACKUP_DIR);
+    if backup_dir.is_dir() {
+        let mut backups: Vec<PathBuf> = fs::read_dir(&backup_dir)
+            .map_err(|e| AppError::io(&backup_dir, e))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .map(|entry| Path::new(BACKUP_DIR).join(entry.file_name()))
+            .collect();
+        backups.sort();
+        files.extend(backups);
+    }
+    Ok(files)
+}
+
+/// 用 SQLite 在线备份复制数据库，得到一致的快照（源库可能正在被使用）
+fn copy_database(source: &Path, dest: &Path) -> Result<(), AppError> {
+    let db_err = |e: rusqlite::Error| AppError::Database(e.to_string());
+    let src =
+        Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(db_err)?;
+    let mut dst = Connection::open(dest).map_err(db_err)?;
+    Backup::new(&src, &mut dst)
+        .map_err(db_err)?
+        .step(-1)
+        .map_err(db_err)?;
+    Ok(())
+}
+
+fn verify_database(path: &Path) -> Result<(), AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(AppError::Database(format!(
+            "迁移后的数据库完整性检查失败: {result}"
+        )))
+    }
+}
+
+fn verify_copy(source: &Path, copy: &Path) -> Result<(), AppError> {
+    let original = fs::read(source).map_err(|e| AppError::io(source, e))?;
+    let copied = fs::read(copy).map_err(|e| AppError::io(copy, e))?;
+    if original == copied {
+        Ok(())
+    } else {
+        Err(AppError::Message(format!(
+            "复制后的文件与源文件不一致: {}",
+            copy.display()
+        )))
+    }
+}
+
+/// 把所有文件复制到临时目录并校验
+fn stage(from: &Path, staging: &Path, files: &[PathBuf]) -> Result<Vec<MigratedFile>, AppError> {
+    let mut migrated = Vec::with_capacity(files.len());
+    for name in files {
+        let source = from.join(name);
+        let dest = staging.join(name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+        }
+        if name.as_os_str() == DB_FILE {
+            copy_database(&source, &dest)?;
+            verify_database(&dest)?;
+        } else {
+            fs::copy(&source, &dest).map_err(|e| AppError::io(&dest, e))?;
+            verify_copy(&source, &dest)?;
+        }
+        let bytes = fs::metadata(&dest)
+            .map_err(|e| AppError::io(&dest, e))?
+            .len();
+        migrated.push(MigratedFile {
+            path: name.to_string_lossy().replace('\\', "/"),
+            bytes,
+        });
+    }
+    Ok(migrated)
+}
+
+/// 将校验通过的临时目录内容移入目标目录
+fn commit(staging: &Path, to: &Path, files: &[PathBuf]) -> Result<(), AppError> {
+    if !to.exists() {
+        return fs::rename(staging, to).map_err(|e| AppError::io(to, e));
+    }
+    // 目标中旧数据库遗留的 WAL 会被 SQLite 套用到新数据库上，须一并移除
+    for suffix in ["-wal", "-shm"] {
+        let stale = to.join(format!("{DB_FILE}{suffix}"));
+        match fs::remove_file(&stale) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(AppError::io(&stale, e))
+            }
+            _ => {}
+        }
+    }
+    for name in files {
+        let dest = to.join(name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+        }
+        fs::rename(staging.join(name), &dest).map_err(|e| AppError::io(&dest, e))?;
+    }
+    let _ = fs::remove_dir_all(staging);
+    Ok(())
+}
+
+fn migrate_between(from: &Path, to: &Path, force: bool) -> Result<DataMigrationReport, AppError> {
+    if same_dir(from, to) {
+        return Err(AppError::localized(
+            "data.migrate.same_location",
+            "源目录与目标目录相同",
+            "Source and target directories are the same",
+        ));
+    }
+    let files = source_files(from)?;
+    if files.is_empty() {
+        return Err(AppError::localized(
+            "data.migrate.source_empty",
+            format!("源目录中没有可迁移的数据: {}", from.display()),
+            format!("No data to migrate in {}", from.display()),
+        ));
+    }
+    if !force && !is_empty_dir(to)? {
+        return Err(AppError::localized(
+            "data.migrate.target_not_empty",
+            format!("目标目录已有数据: {}，如需覆盖请强制迁移", to.display()),
+            format!(
+                "Target directory is not empty: {}; force the migration to overwrite it",
+                to.display()
+            ),
+        ));
+    }
+
+    // 临时目录与目标位于同一父目录，保证最后的重命名不跨文件系统
+    let parent = to
+        .parent()
+        .ok_or_else(|| AppError::Config(format!("无效的目标目录: {}", to.display())))?;
+    fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    let dir_name = to
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let staging = parent.join(format!(
+        ".{dir_name}.migrating-{}",
+        Utc::now().format("%Y%m%d_%H%M%S")
+    ));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| AppError::io(&staging, e))?;
+    }
+
+    let migrated = stage(from, &staging, &files).and_then(|migrated| {
+        commit(&staging, to, &files)?;
+        Ok(migrated)
+    });
+    let migrated = match migrated {
+        Ok(migrated) => migrated,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    let total_bytes = migrated.iter().map(|file| file.bytes).sum();
+    log::info!(
+        "已将 {} 个文件（{total_bytes} 字节）从 {} 迁移到 {}",
+        migrated.len(),
+        from.display(),
+        to.display()
+    );
+    Ok(DataMigrationReport {
+        from: from.to_string_lossy().to_string(),
+        to: to.to_string_lossy().to_string(),
+        files: migrated,
+        total_bytes,
+    })
+}
+
+/// 在便携目录与安装目录（或任意绝对路径）之间复制应用数据
+///
+/// 目标已有数据时需 `force` 才会覆盖；当前正在使用的数据目录不能作为目标。
+/// 调用前应先写入内存中的待保存设置。
+pub fn migrate_data_location(
+    from: &str,
+    to: &str,
+    force: bool,
+) -> Result<DataMigrationReport, AppError> {
+    let from = resolve_location(from)?;
+    let to = resolve_location(to)?;
+    if same_dir(&to, &crate::config::get_app_config_dir())
+        || same_dir(&to, &crate::config::get_data_dir())
+    {
+        return Err(AppError::localized(
+            "data.migrate.target_in_use",
+            "不能迁移到当前正在使用的数据目录，请在另一种安装方式下运行后再迁移",
+            "Cannot migrate into the data directory currently in use; run the other installation and migrate from there",
+        ));
+    }
+    migrate_between(&from, &to, force)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(dir: &Path) {
+        let conn = Connection::open(dir.join(DB_FILE)).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE providers (id TEXT PRIMARY KEY, name TEXT);
+             INSERT INTO providers VALUES ('p1', 'Provider 1');",
+        )
+        .unwrap();
+        fs::write(dir.join("settings.json"), r#"{"language":"zh"}"#).unwrap();
+        fs::write(dir.join(".instance.lock"), "").unwrap();
+        fs::create_dir_all(dir.join(BACKUP_DIR)).unwrap();
+        fs::write(dir.join(BACKUP_DIR).join("backup_1.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn copies_data_into_new_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let from = root.path().join("data");
+        let to = root.path().join("installed");
+        fs::create_dir_all(&from).unwrap();
+        seed(&from);
+
+        let report = migrate_between(&from, &to, false).unwrap();
+        let paths: Vec<&str> = report.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["cc-switch.db", "settings.json", "backups/backup_1.json"]
+        );
+        assert!(!to.join(".instance.lock").exists());
+        assert_eq!(
+            fs::read_to_string(to.join("settings.json")).unwrap(),
+            r#"{"language":"zh"}"#
+        );
+        let name: String = Connection::open(to.join(DB_FILE))
+            .unwrap()
+            .query_row("SELECT name FROM providers WHERE id = 'p1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(name, "Provider 1");
+        // 临时目录不残留
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn refuses_non_empty_target_unless_forced() {
+        let root = tempfile::tempdir().unwrap();
+        let from = root.path().join("a");
+        let to = root.path().join("b");
+        fs::create_dir_all(&from).unwrap();
+        fs::create_dir_all(&to).unwrap();
+        seed(&from);
+        fs::write(to.join("settings.json"), "{}").unwrap();
+
+        assert!(migrate_between(&from, &to, false).is_err());
+        assert_eq!(fs::read_to_string(to.join("settings.json")).unwrap(), "{}");
+
+        migrate_between(&from, &to, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(to.join("settings.json")).unwrap(),
+            r#"{"language":"zh"}"#
+        );
+        assert!(to.join(BACKUP_DIR).join("backup_1.json").exists());
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 2);
+    }
+}
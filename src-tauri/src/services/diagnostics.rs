@@ -0,0 +1,280 @@
+//! 诊断包导出
+//!
+//! 将系统信息、CLI 版本、去除密钥的数据库导出与最近的日志打包为单个 zip，便于反馈问题；
+//! 也可生成 Markdown 格式的摘要，直接粘贴到 GitHub issue 中。
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::init_status::{InitErrorPayload, MigrationErrorPayload};
+use crate::services::cli_tools::ToolVersion;
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+/// 打包的日志文件数量（按修改时间取最新）
+const MAX_LOG_FILES: usize = 3;
+
+/// 诊断包中的系统信息
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsInfo {
+    pub app_version: String,
+    pub os_version: String,
+    pub arch: String,
+    pub portable: bool,
+    pub generated_at: String,
+    pub tool_versions: Vec<ToolVersion>,
+}
+
+/// 生成诊断包，返回写入的 zip 路径（未带 `.zip` 扩展名时自动补全）
+pub fn export_bundle(
+    db: &Database,
+    info: &DiagnosticsInfo,
+    log_dir: &Path,
+    target: &Path,
+) -> Result<PathBuf, AppError> {
+    let target = if target
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+    {
+        target.to_path_buf()
+    } else {
+        target.with_extension("zip")
+    };
+
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+
+    let system =
+        serde_json::to_string_pretty(info).map_err(|e| AppError::JsonSerialize { source: e })?;
+    entries.push((
+        "system.json".to_string(),
+        crate::secret_store::redact_secrets(&system).into_bytes(),
+    ));
+    entries.push((
+        "database.sql".to_string(),
+        db.export_sql_redacted()?.into_bytes(),
+    ));
+
+    for path in recent_log_files(log_dir) {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let text = String::from_utf8_lossy(&bytes);
+                entries.push((
+                    format!("logs/{name}"),
+                    crate::secret_store::redact_secrets(&text).into_bytes(),
+                ));
+            }
+            Err(e) => log::warn!("读取日志文件失败 {}: {e}", path.display()),
+        }
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    let file = fs::File::create(&target).map_err(|e| AppError::io(&target, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, bytes) in entries {
+        zip.start_file(name, options)
+            .map_err(|e| AppError::Message(format!("写入诊断包失败: {e}")))?;
+        zip.write_all(&bytes)
+            .map_err(|e| AppError::io(&target, e))?;
+    }
+    zip.finish()
+        .map_err(|e| AppError::Message(format!("写入诊断包失败: {e}")))?;
+
+    Ok(target)
+}
+
+/// 生成 Markdown 格式的诊断摘要（已去除密钥），供用户粘贴到 issue
+pub fn render_markdown(
+    info: &DiagnosticsInfo,
+    init_error: Option<&InitErrorPayload>,
+    migration_error: Option<&MigrationErrorPayload>,
+) -> String {
+    let mut md = String::from("## CC Switch 诊断信息\n\n");
+    md.push_str(&format!("- 生成时间: {}\n", info.generated_at));
+    md.push_str(&format!("- 应用版本: {}\n", info.app_version));
+    md.push_str(&format!(
+        "- 操作系统: {} ({})\n",
+        info.os_version, info.arch
+    ));
+    md.push_str(&format!(
+        "- 便携模式: {}\n",
+        if info.portable { "是" } else { "否" }
+    ));
+
+    md.push_str("\n### CLI 工具\n\n");
+    if info.tool_versions.is_empty() {
+        md.push_str("未检测任何工具\n");
+    } else {
+        md.push_str("| 工具 | 本地版本 | 最新版本 | 安装方式 | 错误 |\n");
+        md.push_str("| --- | --- | --- | --- | --- |\n");
+        for tool in &info.tool_versions {
+            let cells = [
+                tool.name.as_str(),
+                tool.version.as_deref().unwrap_or("-"),
+                tool.latest_version.as_deref().unwrap_or("-"),
+                tool.install_method.as_deref().unwrap_or("-"),
+                tool.error.as_deref().unwrap_or(""),
+            ]
+            .map(table_cell);
+            md.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+
+    md.push_str("\n### 启动状态\n\n");
+    match init_error {
+        Some(err) => md.push_str(&format!(
+            "- 初始化错误: {:?}（{}）: {}\n",
+            err.kind,
+            err.path,
+            err.error.replace('\n', " ")
+        )),
+        None => md.push_str("- 初始化错误: 无\n"),
+    }
+    match migration_error {
+        Some(err) => md.push_str(&format!(
+            "- 迁移错误: 阶段 {}: {}\n",
+            err.stage,
+            err.message.replace('\n', " ")
+        )),
+        None => md.push_str("- 迁移错误: 无\n"),
+    }
+
+    crate::secret_store::redact_secrets(&md)
+}
+
+/// 转义 Markdown 表格单元格中的 `|` 与换行
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// 最近修改的日志文件（最新在前）
+fn recent_log_files(log_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(log_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files
+        .into_iter()
+        .take(MAX_LOG_FILES)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Provider;
+    use serde_json::json;
+    use std::io::Read;
+
+    #[test]
+    fn bundle_contains_expected_entries_without_keys() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let log_dir = dir.path().join("logs");
+        fs::create_dir_all(&log_dir).expect("create log dir");
+        fs::write(
+            log_dir.join("cc-switch.2026-01-01.log"),
+            "auth failed for sk-ant-api03-leaked\n",
+        )
+        .expect("write log");
+
+        let db = Database::memory().expect("create memory db");
+        db.save_provider(
+            "claude",
+            &Provider::with_id(
+                "p1".to_string(),
+                "P1".to_string(),
+                json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "sk-ant-api03-stored" } }),
+                None,
+            ),
+        )
+        .expect("save provider");
+
+        let info = DiagnosticsInfo {
+            app_version: "1.0.0".to_string(),
+            os_version: "test".to_string(),
+            arch: "x64".to_string(),
+            portable: false,
+            generated_at: "now".to_string(),
+            tool_versions: Vec::new(),
+        };
+        let path =
+            export_bundle(&db, &info, &log_dir, &dir.path().join("diag")).expect("export bundle");
+        assert_eq!(path.extension().and_then(|e| e.to_str()), Some("zip"));
+
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(&path).expect("open zip")).expect("read zip");
+        let mut names = Vec::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).expect("zip entry");
+            names.push(entry.name().to_string());
+            let mut content = String::new();
+            entry.read_to_string(&mut content).expect("read entry");
+            assert!(
+                !content.contains("sk-ant-api03"),
+                "{} leaks a key",
+                entry.name()
+            );
+        }
+        assert_eq!(
+            names,
+            vec![
+                "system.json",
+                "database.sql",
+                "logs/cc-switch.2026-01-01.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn markdown_lists_tools_and_errors_without_keys() {
+        let info = DiagnosticsInfo {
+            app_version: "1.0.0".to_string(),
+            os_version: "macOS 14.5".to_string(),
+            arch: "arm64".to_string(),
+            portable: true,
+            generated_at: "2026-01-01T00:00:00+08:00".to_string(),
+            tool_versions: vec![ToolVersion {
+                name: "claude".to_string(),
+                version: None,
+                latest_version: Some("2.0.14".to_string()),
+                latest_version_stale: false,
+                rate_limited: false,
+                error: Some("未安装 | token sk-ant-api03-leaked".to_string()),
+                path: None,
+                update_available: None,
+                pinned: false,
+                install_method: None,
+            }],
+        };
+        let migration_error = MigrationErrorPayload {
+            stage: "migrate".to_string(),
+            message: "写入失败\n第二行".to_string(),
+            backup_path: None,
+        };
+
+        let md = render_markdown(&info, None, Some(&migration_error));
+        assert!(md.contains("- 应用版本: 1.0.0"));
+        assert!(md.contains("- 便携模式: 是"));
+        assert!(md.contains("| claude | - | 2.0.14 | - | 未安装 \\| token"));
+        assert!(md.contains("- 初始化错误: 无"));
+        assert!(md.contains("- 迁移错误: 阶段 migrate: 写入失败 第二行"));
+        assert!(!md.contains("sk-ant-api03"), "{md}");
+    }
+}
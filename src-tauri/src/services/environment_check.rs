@@ -0,0 +1,350 @@
+//! 运行环境自检
+//!
+//! 汇总 CLI 安装、Node.js 版本要求、配置文件、当前供应商与网络连通性等检查项，每项给出状态与修复建议，
+//! 供前端诊断面板展示，帮助用户定位工具无法启动的原因。
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::services::cli_tools;
+
+/// 网络连通性探测地址（任一可达即视为网络正常）
+const NETWORK_PROBE_URLS: &[&str] = &["https://www.baidu.com", "https://www.bing.com"];
+/// 网络探测的超时时间
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// 单个检查项的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentCheck {
+    /// 检查项 ID，如 `cli:claude`、`config:codex`、`network`
+    pub id: String,
+    pub label: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// 修复建议（状态为 ok 时为空）
+    pub hint: Option<String>,
+}
+
+impl EnvironmentCheck {
+    fn new(id: impl Into<String>, label: impl Into<String>, status: CheckStatus) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            status,
+            message: String::new(),
+            hint: None,
+        }
+    }
+
+    fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// 执行全部检查；用户隐藏的工具不参与 CLI、配置与供应商检查
+pub async fn run_environment_check(
+    client: &reqwest::Client,
+    db: &Database,
+) -> Vec<EnvironmentCheck> {
+    let tools: Vec<_> = cli_tools::managed_tools()
+        .into_iter()
+        .filter(|tool| !tool.hidden)
+        .collect();
+
+    let commands: Vec<_> = tools.iter().map(|tool| (tool.id, tool.command)).collect();
+    let local = tauri::async_runtime::spawn_blocking(move || {
+        commands
+            .into_iter()
+            .map(|(id, command)| check_cli(id, command))
+            .collect::<Vec<_>>()
+    });
+    let npm_package = tools
+        .first()
+        .map(|tool| tool.npm_package)
+        .unwrap_or("@anthropic-ai/claude-code");
+    let node = futures::future::join_all(tools.iter().map(|tool| check_node(client, tool)));
+    let (local, network, registry, node) = tokio::join!(
+        local,
+        check_network(client),
+        check_npm_registry(client, npm_package),
+        node
+    );
+
+    let mut checks = local.unwrap_or_else(|e| {
+        vec![EnvironmentCheck::new("cli", "CLI 工具", CheckStatus::Fail)
+            .message(format!("检测 CLI 失败: {e}"))]
+    });
+    checks.extend(node.into_iter().flatten());
+    for tool in &tools {
+        let Ok(app_type) = tool.id.parse::<AppType>() else {
+            continue;
+        };
+        checks.push(check_config_dir(&app_type));
+        checks.extend(check_live_config(&app_type));
+        checks.push(check_current_provider(db, &app_type));
+    }
+
+    // 网络不通时 registry 必然不可达，修复建议以网络为准
+    let registry = if network.status == CheckStatus::Fail && registry.status == CheckStatus::Fail {
+        registry.hint("请先排查网络连接")
+    } else {
+        registry
+    };
+    checks.push(network);
+    checks.push(registry);
+    checks
+}
+
+fn check_cli(id: &str, command: &str) -> EnvironmentCheck {
+    let label = format!("{command} 命令行工具");
+    let local = cli_tools::detect_local_version(command);
+    match local.version {
+        Some(version) => {
+            let location = local.path.unwrap_or_else(|| "PATH".to_string());
+            EnvironmentCheck::new(format!("cli:{id}"), label, CheckStatus::Ok)
+                .message(format!("已安装 {version}（{location}）"))
+        }
+        None => {
+            let error = local
+                .error
+                .unwrap_or_else(|| "未安装或无法执行".to_string());
+            let hint = if error.contains(cli_tools::NODE_MISSING) {
+                format!("{command} 依赖 Node.js，请安装 Node.js 并确认 node 所在目录已加入 PATH")
+            } else {
+                format!(
+                    "请安装 {command}，并确认其所在目录已加入 PATH；如已安装，可重新打开终端后再试"
+                )
+            };
+            EnvironmentCheck::new(format!("cli:{id}"), label, CheckStatus::Fail)
+                .message(error)
+                .hint(hint)
+        }
+    }
+}
+
+/// 检查 Node.js 版本是否满足工具的 `engines.node`；未找到 Node.js（由 CLI 检查提示）
+/// 或查询 npm 元数据失败（由 registry 检查提示）时不生成检查项
+async fn check_node(
+    client: &reqwest::Client,
+    tool: &cli_tools::ManagedTool,
+) -> Option<EnvironmentCheck> {
+    let compat = cli_tools::check_node_compatibility(client, tool)
+        .await
+        .ok()?;
+    let node_version = compat.node_version?;
+    let required = compat.required.unwrap_or_else(|| "未声明".to_string());
+    let check = EnvironmentCheck::new(
+        format!("node:{}", tool.id),
+        format!("{} 的 Node.js 版本要求", tool.command),
+        CheckStatus::Ok,
+    );
+    Some(if compat.ok {
+        check.message(format!("Node.js {node_version} 满足要求（{required}）"))
+    } else {
+        EnvironmentCheck {
+            status: CheckStatus::Fail,
+            ..check
+        }
+        .message(format!(
+            "Node.js {node_version} 不满足 {} 的要求（{required}）",
+            tool.command
+        ))
+        .hint(format!(
+            "请将 Node.js 升级到满足 {required} 的版本，否则 {} 可能在运行时崩溃",
+            tool.command
+        ))
+    })
+}
+
+/// 检查工具的 live 配置文件：JSON/TOML 需可解析，`.env` 仅检查是否存在
+fn check_live_config(app_type: &AppType) -> Vec<EnvironmentCheck> {
+    crate::services::provider::live_config_paths(app_type)
+        .into_iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let check = EnvironmentCheck::new(
+                format!("config:{}:{name}", app_type.as_str()),
+                format!("{} 配置文件 {name}", app_type.as_str()),
+                CheckStatus::Ok,
+            );
+            match validate_config_file(&path) {
+                Ok(()) => check.message(path.display().to_string()),
+                Err(None) => EnvironmentCheck {
+                    status: CheckStatus::Warn,
+                    ..check
+                }
+                .message(format!("文件不存在: {}", path.display()))
+                .hint("在应用中选择并切换一次供应商即可生成该文件"),
+                Err(Some(e)) => EnvironmentCheck {
+                    status: CheckStatus::Fail,
+                    ..check
+                }
+                .message(e)
+                .hint("请修正文件格式，或重新切换供应商以覆盖写入（原文件会自动备份）"),
+            }
+        })
+        .collect()
+}
+
+/// 检查工具的配置目录是否可写（只读目录下无法切换供应商）
+fn check_config_dir(app_type: &AppType) -> EnvironmentCheck {
+    let check = EnvironmentCheck::new(
+        format!("config-dir:{}", app_type.as_str()),
+        format!("{} 配置目录", app_type.as_str()),
+        CheckStatus::Ok,
+    );
+    match crate::services::provider::check_live_writable(app_type) {
+        Ok(()) => check.message("可写"),
+        Err(e) => EnvironmentCheck {
+            status: CheckStatus::Fail,
+            ..check
+        }
+        .message(e.to_string())
+        .hint("这是目录权限问题：请为该目录授予当前用户写入权限，或联系管理员解除只读限制"),
+    }
+}
+
+/// 校验配置文件；`Err(None)` 表示文件不存在，`Err(Some(_))` 为格式错误
+fn validate_config_file(path: &Path) -> Result<(), Option<String>> {
+    if !path.is_file() {
+        return Err(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Some(format!("读取失败 {}: {e}", path.display())))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str::<serde_json::Value>(&content)
+            .map(|_| ())
+            .map_err(|e| Some(format!("JSON 格式错误 {}: {e}", path.display()))),
+        Some("toml") => toml::from_str::<toml::Table>(&content)
+            .map(|_| ())
+            .map_err(|e| Some(format!("TOML 格式错误 {}: {e}", path.display()))),
+        _ => Ok(()),
+    }
+}
+
+fn check_current_provider(db: &Database, app_type: &AppType) -> EnvironmentCheck {
+    let check = EnvironmentCheck::new(
+        format!("provider:{}", app_type.as_str()),
+        format!("{} 当前供应商", app_type.as_str()),
+        CheckStatus::Ok,
+    );
+    let current = match crate::settings::get_effective_current_provider(db, app_type) {
+        Ok(current) => current,
+        Err(e) => {
+            return EnvironmentCheck {
+                status: CheckStatus::Fail,
+                ..check
+            }
+            .message(format!("读取当前供应商失败: {e}"))
+        }
+    };
+    let Some(id) = current else {
+        return EnvironmentCheck {
+            status: CheckStatus::Warn,
+            ..check
+        }
+        .message("尚未选择供应商")
+        .hint("在供应商列表中添加并启用一个供应商");
+    };
+    match db.get_provider_by_id(&id, app_type.as_str()) {
+        Ok(Some(provider)) => check.message(provider.name),
+        Ok(None) => EnvironmentCheck {
+            status: CheckStatus::Fail,
+            ..check
+        }
+        .message(format!("当前供应商 {id} 已不存在"))
+        .hint("在供应商列表中重新选择一个供应商"),
+        Err(e) => EnvironmentCheck {
+            status: CheckStatus::Fail,
+            ..check
+        }
+        .message(format!("读取供应商失败: {e}")),
+    }
+}
+
+async fn check_network(client: &reqwest::Client) -> EnvironmentCheck {
+    let probes = NETWORK_PROBE_URLS.iter().map(|url| async move {
+        // 任何 HTTP 响应都说明网络可达，不关心状态码
+        tokio::time::timeout(NETWORK_PROBE_TIMEOUT, client.head(*url).send())
+            .await
+            .map_err(|_| format!("{url}: 请求超时"))?
+            .map(|_| *url)
+            .map_err(|e| format!("{url}: {e}"))
+    });
+    let results = futures::future::join_all(probes).await;
+
+    let check = EnvironmentCheck::new("network", "网络连接", CheckStatus::Ok);
+    if let Some(url) = results.iter().find_map(|r| r.as_ref().ok()) {
+        return check.message(format!("可访问 {url}"));
+    }
+    let errors: Vec<_> = results.into_iter().filter_map(Result::err).collect();
+    EnvironmentCheck {
+        status: CheckStatus::Fail,
+        ..check
+    }
+    .message(errors.join("；"))
+    .hint("请检查网络连接；如需通过代理上网，请在设置中配置网络代理")
+}
+
+async fn check_npm_registry(client: &reqwest::Client, package: &str) -> EnvironmentCheck {
+    let registry = crate::settings::get_npm_registry();
+    let check = EnvironmentCheck::new("npm-registry", "npm registry", CheckStatus::Ok);
+    match cli_tools::query_npm_latest_version(client, package).await {
+        Ok(version) => check.message(format!("{registry} 可访问（{package} 最新版本 {version}）")),
+        Err(e) => EnvironmentCheck {
+            status: CheckStatus::Fail,
+            ..check
+        }
+        .message(format!("{registry}: {e}"))
+        .hint("无法访问 npm registry 时无法检查与安装 CLI 更新，可在设置中切换为国内镜像"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_config_file_formats() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let json = dir.path().join("settings.json");
+        let toml = dir.path().join("config.toml");
+        let env = dir.path().join(".env");
+
+        assert_eq!(validate_config_file(&json), Err(None));
+
+        std::fs::write(&json, "{\"env\": {}}").unwrap();
+        std::fs::write(&toml, "model = \"gpt-5\"\n").unwrap();
+        std::fs::write(&env, "GEMINI_API_KEY=\n").unwrap();
+        assert!(validate_config_file(&json).is_ok());
+        assert!(validate_config_file(&toml).is_ok());
+        assert!(validate_config_file(&env).is_ok());
+
+        std::fs::write(&json, "{\"env\": ").unwrap();
+        std::fs::write(&toml, "model = ").unwrap();
+        assert!(matches!(validate_config_file(&json), Err(Some(_))));
+        assert!(matches!(validate_config_file(&toml), Err(Some(_))));
+    }
+}
@@ -0,0 +1,185 @@
+//! JSON → SQLite 迁移锁
+//!
+//! 迁移开始前在配置目录写入 `migration.lock`，结束后删除。应用在迁移中途崩溃时锁会残留，
+//! 此时数据库可能只建了一半，启动不会再次迁移。[`reset`] 清理锁与临时文件，
+//! 并安排下次启动时将半成品数据库移到一旁，从 config.json 重新迁移。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::Utc;
+
+use crate::error::AppError;
+
+const LOCK_FILE: &str = "migration.lock";
+const RETRY_MARKER: &str = "migration.retry";
+
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// 迁移进行期间持有，释放时删除锁文件
+pub struct MigrationGuard {
+    lock_path: PathBuf,
+}
+
+impl Drop for MigrationGuard {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.lock_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("删除迁移锁失败 {}: {e}", self.lock_path.display());
+            }
+        }
+        IN_PROGRESS.store(false, Ordering::SeqCst);
+    }
+}
+
+/// 标记迁移开始并写入锁文件（写入失败仅记录日志，不阻止迁移）
+pub fn acquire(dir: &Path) -> Result<MigrationGuard, AppError> {
+    if IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Message("迁移正在进行中".to_string()));
+    }
+    let lock_path = dir.join(LOCK_FILE);
+    let content = format!("pid={}\nstarted_at={}\n", std::process::id(), Utc::now());
+    if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&lock_path, content)) {
+        log::warn!("写入迁移锁失败 {}: {e}", lock_path.display());
+    }
+    Ok(MigrationGuard { lock_path })
+}
+
+/// 当前进程是否正在迁移
+pub fn is_in_progress() -> bool {
+    IN_PROGRESS.load(Ordering::SeqCst)
+}
+
+/// 上次迁移残留的锁（当前进程未在迁移时才视为残留）
+pub fn stale_lock(dir: &Path) -> Option<PathBuf> {
+    let lock_path = dir.join(LOCK_FILE);
+    (!is_in_progress() && lock_path.is_file()).then_some(lock_path)
+}
+
+/// 清理迁移锁与原子写入残留的临时文件，返回被清理的项目
+///
+/// 存在残留锁且 config.json 尚未归档时，安排下次启动重新迁移。迁移进行中时拒绝执行。
+pub fn reset(dir: &Path) -> Result<Vec<String>, AppError> {
+    if is_in_progress() {
+        return Err(AppError::Message(
+            "迁移正在进行中，无法重置迁移状态".to_string(),
+        ));
+    }
+
+    let mut cleared = Vec::new();
+    let had_lock = match stale_lock(dir) {
+        Some(lock_path) => {
+            fs::remove_file(&lock_path).map_err(|e| AppError::io(&lock_path, e))?;
+            cleared.push(lock_path.to_string_lossy().to_string());
+            true
+        }
+        None => false,
+    };
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let is_temp = entry.file_name().to_str().is_some_and(is_atomic_write_temp);
+            if is_temp && path.is_file() {
+                fs::remove_file(&path).map_err(|e| AppError::io(&path, e))?;
+                cleared.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    if had_lock && dir.join("config.json").is_file() {
+        let marker = dir.join(RETRY_MARKER);
+        fs::write(&marker, Utc::now().to_rfc3339()).map_err(|e| AppError::io(&marker, e))?;
+        cleared.push(format!(
+            "{}（下次启动时重新迁移）",
+            marker.to_string_lossy()
+        ));
+    }
+
+    Ok(cleared)
+}
+
+/// 启动时处理重置安排：将未完成迁移留下的数据库移到一旁，返回被移动的文件
+///
+/// 须在打开数据库之前调用。
+pub fn apply_pending_retry(dir: &Path, db_path: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let marker = dir.join(RETRY_MARKER);
+    if !marker.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let suffix = format!("interrupted-{}", Utc::now().format("%Y%m%d_%H%M%S"));
+    let mut moved = Vec::new();
+    for ext in ["", "-wal", "-shm", "-journal"] {
+        let path = PathBuf::from(format!("{}{ext}", db_path.display()));
+        if !path.exists() {
+            continue;
+        }
+        let target = PathBuf::from(format!("{}.{suffix}", path.display()));
+        fs::rename(&path, &target).map_err(|e| AppError::IoContext {
+            context: format!("移动未完成迁移的数据库失败: {}", path.display()),
+            source: e,
+        })?;
+        moved.push(target);
+    }
+    fs::remove_file(&marker).map_err(|e| AppError::io(&marker, e))?;
+    Ok(moved)
+}
+
+/// `atomic_write` 使用的临时文件名：`<文件名>.tmp.<纳秒时间戳>`
+fn is_atomic_write_temp(name: &str) -> bool {
+    name.rsplit_once(".tmp.")
+        .is_some_and(|(stem, ts)| !stem.is_empty() && ts.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_clears_stale_lock_and_schedules_retry() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let db_path = dir.path().join("cc-switch.db");
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+        fs::write(&db_path, "half-migrated").unwrap();
+        fs::write(dir.path().join("config.json.tmp.1700000000"), "").unwrap();
+
+        {
+            let guard = acquire(dir.path()).expect("acquire migration lock");
+            assert!(reset(dir.path()).is_err(), "reset refused while migrating");
+            // 模拟崩溃：锁文件残留
+            std::mem::forget(guard);
+        }
+        IN_PROGRESS.store(false, Ordering::SeqCst);
+
+        let cleared = reset(dir.path()).expect("reset migration state");
+        assert_eq!(
+            cleared.len(),
+            3,
+            "lock, temp file and retry marker: {cleared:?}"
+        );
+        assert!(!dir.path().join(LOCK_FILE).exists());
+        assert!(!dir.path().join("config.json.tmp.1700000000").exists());
+
+        let moved = apply_pending_retry(dir.path(), &db_path).expect("apply retry");
+        assert_eq!(moved.len(), 1);
+        assert!(
+            !db_path.exists(),
+            "database moved aside so migration reruns"
+        );
+        assert!(!dir.path().join(RETRY_MARKER).exists());
+        assert!(apply_pending_retry(dir.path(), &db_path)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn recognizes_atomic_write_temp_names() {
+        assert!(is_atomic_write_temp(
+            "settings.json.tmp.1712345678901234567"
+        ));
+        assert!(!is_atomic_write_temp("settings.json"));
+        assert!(!is_atomic_write_temp("notes.tmp.txt"));
+    }
+}
@@ -1,19 +1,36 @@
+pub mod api_key;
+pub mod backup_prune;
+pub mod cli_tools;
 pub mod config;
+pub mod config_watcher;
+pub mod connection_test;
+pub mod data_migration;
+pub mod diagnostics;
 pub mod env_checker;
 pub mod env_manager;
+pub mod environment_check;
 pub mod mcp;
+pub mod migration_lock;
+pub mod profile;
 pub mod prompt;
 pub mod provider;
 pub mod proxy;
+pub mod rate_limit;
+pub mod release;
+pub mod retry;
+pub mod settings_transfer;
+pub mod shell_profile;
 pub mod skill;
 pub mod speedtest;
 pub mod stream_check;
+pub mod tool_install;
 pub mod usage_stats;
 
-pub use config::ConfigService;
+pub use config::{ConfigBackupEntry, ConfigService};
 pub use mcp::McpService;
+pub use profile::ProfileService;
 pub use prompt::PromptService;
-pub use provider::{ProviderService, ProviderSortUpdate};
+pub use provider::{ImportStrategy, ProviderImportStatus, ProviderService, ProviderSortUpdate};
 pub use proxy::ProxyService;
 pub use skill::{Skill, SkillRepo, SkillService};
 pub use speedtest::{EndpointLatency, SpeedtestService};
@@ -0,0 +1,149 @@
+//! 配置档案（如工作 / 个人）
+//!
+//! 每个档案拥有独立的供应商列表与各应用的当前供应商。切换档案时先将 Live 配置回填到
+//! 当前供应商，再互换数据库中的供应商集合，最后按新档案的当前供应商重写 CLI 配置文件。
+//! 新档案中某应用没有当前供应商时，该应用的配置文件保持不变。
+
+use crate::app_config::AppType;
+use crate::database::{AppProfileSnapshot, Profile, ProfileSnapshot, DEFAULT_PROFILE_ID};
+use crate::error::AppError;
+use crate::services::provider;
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+/// 档案名称的最大长度（字符数）
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+const APP_TYPES: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+
+pub struct ProfileService;
+
+impl ProfileService {
+    pub fn list(state: &AppState) -> Result<Vec<Profile>, AppError> {
+        state.db.list_profiles()
+    }
+
+    /// 新建空档案（不切换到该档案）
+    pub fn create(state: &AppState, name: &str) -> Result<Profile, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::localized(
+                "profile.name.empty",
+                "档案名称不能为空",
+                "Profile name must not be empty",
+            ));
+        }
+        if name.chars().count() > MAX_PROFILE_NAME_LEN {
+            return Err(AppError::localized(
+                "profile.name.too_long",
+                format!("档案名称不能超过 {MAX_PROFILE_NAME_LEN} 个字符"),
+                format!("Profile name must be at most {MAX_PROFILE_NAME_LEN} characters"),
+            ));
+        }
+        if state.db.list_profiles()?.iter().any(|p| p.name == name) {
+            return Err(AppError::localized(
+                "profile.name.duplicate",
+                format!("档案“{name}”已存在"),
+                format!("Profile \"{name}\" already exists"),
+            ));
+        }
+
+        let profile = Profile {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+            active: false,
+        };
+        state.db.create_profile(&profile)?;
+        Ok(profile)
+    }
+
+    /// 切换到指定档案并重写 CLI 配置文件，返回切换后的档案
+    ///
+    /// 代理接管期间 Live 配置指向本地代理，无法回填，因此拒绝切换。
+    pub fn switch(state: &AppState, id: &str) -> Result<Profile, AppError> {
+        let profiles = state.db.list_profiles()?;
+        let target = profiles
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::Message(format!("配置档案 {id} 不存在")))?;
+        if target.active {
+            return Ok(target);
+        }
+        for app_type in &APP_TYPES {
+            if futures::executor::block_on(state.db.get_live_backup(app_type.as_str()))?.is_some() {
+                return Err(AppError::localized(
+                    "profile.switch.takeover",
+                    "代理接管期间无法切换配置档案，请先关闭代理接管",
+                    "Cannot switch profiles while the proxy takeover is active",
+                ));
+            }
+        }
+
+        let mut outgoing = ProfileSnapshot::default();
+        for app_type in &APP_TYPES {
+            let current = crate::settings::get_effective_current_provider(&state.db, app_type)?;
+            if let Some(provider) = current.as_ref().and_then(|current| {
+                state
+                    .db
+                    .get_provider_by_id(current, app_type.as_str())
+                    .ok()
+                    .flatten()
+            }) {
+                ProviderService::backfill_from_live(state, app_type, &provider);
+            }
+            let providers = state.db.get_all_providers(app_type.as_str())?;
+            outgoing.apps.insert(
+                app_type.as_str().to_string(),
+                AppProfileSnapshot {
+                    current,
+                    providers: providers.into_values().collect(),
+                },
+            );
+        }
+
+        let incoming = state.db.swap_profile(&outgoing, id)?;
+        for app_type in &APP_TYPES {
+            let current = incoming
+                .apps
+                .get(app_type.as_str())
+                .and_then(|app| app.current.as_deref());
+            crate::settings::set_current_provider(app_type, current)?;
+        }
+        provider::forget_switch();
+        ProviderService::sync_current_to_live(state)?;
+
+        log::info!("已切换到配置档案 {}（{}）", target.name, target.id);
+        Ok(Profile {
+            active: true,
+            ..target
+        })
+    }
+
+    /// 删除档案；默认档案与当前档案不可删除
+    pub fn delete(state: &AppState, id: &str) -> Result<(), AppError> {
+        if id == DEFAULT_PROFILE_ID {
+            return Err(AppError::localized(
+                "profile.delete.default",
+                "默认档案不可删除",
+                "The default profile cannot be deleted",
+            ));
+        }
+        let profile = state
+            .db
+            .list_profiles()?
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| AppError::Message(format!("配置档案 {id} 不存在")))?;
+        if profile.active {
+            return Err(AppError::localized(
+                "profile.delete.active",
+                "不能删除当前档案，请先切换到其它档案",
+                "Switch to another profile before deleting the current one",
+            ));
+        }
+        state.db.delete_profile(id)?;
+        Ok(())
+    }
+}
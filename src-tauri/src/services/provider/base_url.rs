@@ -0,0 +1,231 @@
+//! Base URL normalization
+//!
+//! Cleans up pasted base URLs (missing scheme, trailing slashes) before a provider is saved,
+//! so the CLI tools do not build malformed request URLs from them.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+
+/// Result of normalizing a base URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizedBaseUrl {
+    pub url: String,
+    /// Whether `url` differs from the input
+    pub changed: bool,
+    /// Warnings for the user (the URL is still accepted)
+    pub notes: Vec<String>,
+}
+
+/// Enforce an http/https scheme and strip trailing slashes
+///
+/// A URL without a scheme gets `https://`. A trailing version segment such as `/v1`
+/// is kept but reported in `notes`, since some tools append it themselves.
+pub fn normalize_base_url(url: String) -> Result<NormalizedBaseUrl, AppError> {
+    let trimmed = url.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::InvalidInput("Base URL 不能为空".to_string()));
+    }
+
+    let mut notes = Vec::new();
+    let mut normalized = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        notes.push(format!("未指定协议，已补全为 https://{trimmed}"));
+        format!("https://{trimmed}")
+    };
+
+    let parsed = url::Url::parse(&normalized)
+        .map_err(|e| AppError::InvalidInput(format!("无效的 Base URL '{trimmed}': {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::InvalidInput(format!(
+            "Base URL 仅支持 http/https 协议: {trimmed}"
+        )));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(AppError::InvalidInput(format!(
+            "Base URL 缺少主机名: {trimmed}"
+        )));
+    }
+
+    let without_slash = normalized.trim_end_matches('/');
+    if without_slash.len() != normalized.len() {
+        normalized = without_slash.to_string();
+    }
+
+    if let Some(segment) = trailing_version_segment(&parsed) {
+        notes.push(format!(
+            "Base URL 以 /{segment} 结尾，部分工具会自动追加该路径，可能导致请求地址重复"
+        ));
+    }
+
+    Ok(NormalizedBaseUrl {
+        changed: normalized != url,
+        url: normalized,
+        notes,
+    })
+}
+
+/// Last path segment when it looks like an API version (`v1`, `v1beta`, ...)
+fn trailing_version_segment(url: &url::Url) -> Option<String> {
+    let segment = url.path_segments()?.rev().find(|s| !s.is_empty())?;
+    let rest = segment.strip_prefix('v')?;
+    rest.starts_with(|c: char| c.is_ascii_digit())
+        .then(|| segment.to_string())
+}
+
+/// Normalize the base URL(s) stored in a provider's settings in place
+///
+/// Returns the notes collected for the user; providers without a base URL are left untouched.
+pub(crate) fn normalize_provider_base_urls(
+    app_type: &AppType,
+    provider: &mut Provider,
+) -> Result<Vec<String>, AppError> {
+    match app_type {
+        AppType::Claude => normalize_env_base_url(provider, "ANTHROPIC_BASE_URL"),
+        AppType::Gemini => normalize_env_base_url(provider, "GOOGLE_GEMINI_BASE_URL"),
+        AppType::Codex => normalize_codex_base_urls(provider),
+    }
+}
+
+fn normalize_env_base_url(provider: &mut Provider, key: &str) -> Result<Vec<String>, AppError> {
+    let Some(slot) = provider
+        .settings_config
+        .get_mut("env")
+        .and_then(|env| env.get_mut(key))
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(raw) = slot.as_str().filter(|s| !s.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let normalized = normalize_base_url(raw.to_string())?;
+    if normalized.changed {
+        *slot = Value::String(normalized.url);
+    }
+    Ok(normalized.notes)
+}
+
+fn normalize_codex_base_urls(provider: &mut Provider) -> Result<Vec<String>, AppError> {
+    let Some(config) = provider
+        .settings_config
+        .get("config")
+        .and_then(|v| v.as_str())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let re = Regex::new(r#"(base_url\s*=\s*)(["'])([^"']*)(["'])"#)
+        .map_err(|e| AppError::Message(format!("正则初始化失败: {e}")))?;
+    let mut notes = Vec::new();
+    let mut rewritten = String::with_capacity(config.len());
+    let mut last = 0;
+    for caps in re.captures_iter(config) {
+        let (Some(whole), Some(value)) = (caps.get(0), caps.get(3)) else {
+            continue;
+        };
+        if value.as_str().trim().is_empty() {
+            continue;
+        }
+        let normalized = normalize_base_url(value.as_str().to_string())?;
+        notes.extend(normalized.notes);
+        rewritten.push_str(&config[last..whole.start()]);
+        rewritten.push_str(&caps[1]);
+        rewritten.push_str(&caps[2]);
+        rewritten.push_str(&normalized.url);
+        rewritten.push_str(&caps[4]);
+        last = whole.end();
+    }
+    rewritten.push_str(&config[last..]);
+
+    if rewritten != config {
+        provider.settings_config["config"] = Value::String(rewritten);
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn adds_missing_scheme() {
+        let result = normalize_base_url("api.example.com".to_string()).unwrap();
+        assert_eq!(result.url, "https://api.example.com");
+        assert!(result.changed);
+        assert_eq!(result.notes.len(), 1);
+    }
+
+    #[test]
+    fn strips_trailing_slashes_and_whitespace() {
+        let result = normalize_base_url("  https://api.example.com/api//  ".to_string()).unwrap();
+        assert_eq!(result.url, "https://api.example.com/api");
+        assert!(result.changed);
+        assert!(result.notes.is_empty());
+    }
+
+    #[test]
+    fn keeps_clean_url_unchanged() {
+        let result = normalize_base_url("http://localhost:8080".to_string()).unwrap();
+        assert_eq!(result.url, "http://localhost:8080");
+        assert!(!result.changed);
+        assert!(result.notes.is_empty());
+    }
+
+    #[test]
+    fn warns_about_trailing_version_segment() {
+        let result = normalize_base_url("https://api.example.com/v1/".to_string()).unwrap();
+        assert_eq!(result.url, "https://api.example.com/v1");
+        assert!(result.notes.iter().any(|n| n.contains("/v1")));
+
+        let result = normalize_base_url("https://api.example.com/v1beta".to_string()).unwrap();
+        assert!(result.notes.iter().any(|n| n.contains("/v1beta")));
+
+        let result = normalize_base_url("https://api.example.com/vendor".to_string()).unwrap();
+        assert!(result.notes.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        assert!(normalize_base_url("   ".to_string()).is_err());
+        assert!(normalize_base_url("ftp://api.example.com".to_string()).is_err());
+        assert!(normalize_base_url("https://".to_string()).is_err());
+    }
+
+    #[test]
+    fn normalizes_provider_settings_in_place() {
+        let mut claude = Provider::with_id(
+            "claude".into(),
+            "Claude".into(),
+            json!({ "env": { "ANTHROPIC_BASE_URL": "claude.example.com/" } }),
+            None,
+        );
+        normalize_provider_base_urls(&AppType::Claude, &mut claude).unwrap();
+        assert_eq!(
+            claude.settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://claude.example.com"
+        );
+
+        let mut codex = Provider::with_id(
+            "codex".into(),
+            "Codex".into(),
+            json!({
+                "auth": {},
+                "config": "model = \"gpt\"\nbase_url = \"https://codex.example.com/v1/\"\n"
+            }),
+            None,
+        );
+        let notes = normalize_provider_base_urls(&AppType::Codex, &mut codex).unwrap();
+        assert_eq!(
+            codex.settings_config["config"],
+            "model = \"gpt\"\nbase_url = \"https://codex.example.com/v1\"\n"
+        );
+        assert_eq!(notes.len(), 1);
+    }
+}
@@ -0,0 +1,258 @@
+//! Bulk provider import
+//!
+//! Reads a JSON array of provider definitions (each a provider object plus an `app` field)
+//! and merges them into the store in a single transaction. Entries from a shareable export
+//! (`secretRequired: true`) are flagged so the user is asked for their own key.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::secret_store::REDACTED_PLACEHOLDER;
+use crate::store::AppState;
+
+use super::share::SECRET_REQUIRED_KEY;
+use super::ProviderService;
+
+/// How to resolve an imported provider whose id or name already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStrategy {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+impl FromStr for ImportStrategy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "rename" => Ok(Self::Rename),
+            other => Err(AppError::InvalidInput(format!(
+                "不支持的导入策略: '{other}'。可选值: skip, overwrite, rename。"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderImportStatus {
+    Added,
+    Overwritten,
+    Renamed,
+    Skipped,
+    /// The entry failed validation; nothing was imported
+    Invalid,
+    /// The entry was valid but another entry failed validation, so nothing was imported
+    Aborted,
+}
+
+/// Outcome for one entry of the import file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderImportResult {
+    pub index: usize,
+    pub app: Option<String>,
+    /// Provider id as stored (differs from the file when renamed or overwriting by name)
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub status: ProviderImportStatus,
+    pub message: Option<String>,
+    /// The entry came from a shareable export without secrets; the user must fill in a key
+    pub secret_required: bool,
+}
+
+/// Import providers from a JSON file
+///
+/// All entries are validated first; if any is invalid nothing is written.
+pub(crate) fn import_providers(
+    state: &AppState,
+    path: &Path,
+    strategy: ImportStrategy,
+) -> Result<Vec<ProviderImportResult>, AppError> {
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    let entries: Vec<Value> = crate::config::parse_json_content(path, &content)?;
+
+    let mut existing: HashMap<String, IndexMap<String, Provider>> = HashMap::new();
+    let mut results = Vec::with_capacity(entries.len());
+    let mut batch: Vec<(String, Provider)> = Vec::new();
+    let mut overwritten: Vec<(AppType, String)> = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let (app_type, mut provider, secret_required) = match parse_entry(entry) {
+            Ok(parsed) => parsed,
+            Err((app, id, message)) => {
+                results.push(ProviderImportResult {
+                    index,
+                    app,
+                    id,
+                    name: None,
+                    status: ProviderImportStatus::Invalid,
+                    message: Some(message),
+                    secret_required: false,
+                });
+                continue;
+            }
+        };
+
+        let providers = match existing.entry(app_type.as_str().to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(state.db.get_all_providers(app_type.as_str())?),
+        };
+
+        let collision = providers
+            .values()
+            .find(|p| p.id == provider.id || p.name == provider.name)
+            .cloned();
+        let status = match (collision, strategy) {
+            (None, _) => {
+                if provider.sort_index.is_none() {
+                    provider.sort_index = Some(next_sort_index(providers));
+                }
+                ProviderImportStatus::Added
+            }
+            (Some(_), ImportStrategy::Skip) => ProviderImportStatus::Skipped,
+            (Some(target), ImportStrategy::Overwrite) => {
+                provider.id = target.id.clone();
+                provider.sort_index = provider.sort_index.or(target.sort_index);
+                provider.created_at = provider.created_at.or(target.created_at);
+                overwritten.push((app_type.clone(), target.id));
+                ProviderImportStatus::Overwritten
+            }
+            (Some(_), ImportStrategy::Rename) => {
+                provider.id = uuid::Uuid::new_v4().to_string();
+                provider.name = unique_name(providers, &provider.name);
+                provider.sort_index = Some(next_sort_index(providers));
+                ProviderImportStatus::Renamed
+            }
+        };
+
+        results.push(ProviderImportResult {
+            index,
+            app: Some(app_type.as_str().to_string()),
+            id: Some(provider.id.clone()),
+            name: Some(provider.name.clone()),
+            status,
+            message: secret_required
+                .then(|| "该供应商未包含密钥，请填写你自己的 API Key".to_string()),
+            secret_required,
+        });
+        if status != ProviderImportStatus::Skipped {
+            providers.insert(provider.id.clone(), provider.clone());
+            batch.push((app_type.as_str().to_string(), provider));
+        }
+    }
+
+    if results
+        .iter()
+        .any(|r| r.status == ProviderImportStatus::Invalid)
+    {
+        for result in results
+            .iter_mut()
+            .filter(|r| r.status != ProviderImportStatus::Invalid)
+        {
+            result.status = ProviderImportStatus::Aborted;
+        }
+        return Ok(results);
+    }
+
+    state.db.save_providers(&batch)?;
+    log::info!("已从 {} 导入 {} 个供应商", path.display(), batch.len());
+
+    // Overwriting the active provider must reach the Live config too
+    let touches_current = overwritten.iter().any(|(app_type, id)| {
+        crate::settings::get_effective_current_provider(&state.db, app_type)
+            .ok()
+            .flatten()
+            .as_deref()
+            == Some(id.as_str())
+    });
+    if touches_current {
+        if let Err(e) = ProviderService::sync_current_to_live(state) {
+            log::warn!("导入后同步 live 配置失败: {e}");
+        }
+    }
+
+    Ok(results)
+}
+
+type EntryError = (Option<String>, Option<String>, String);
+
+/// Parse and validate one entry; errors carry the app/id found so far for reporting
+fn parse_entry(mut entry: Value) -> Result<(AppType, Provider, bool), EntryError> {
+    let id = entry.get("id").and_then(|v| v.as_str()).map(str::to_string);
+    let obj = entry
+        .as_object_mut()
+        .ok_or_else(|| (None, None, "条目必须是 JSON 对象".to_string()))?;
+    let app = obj
+        .remove("app")
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or_else(|| (None, id.clone(), "缺少 app 字段".to_string()))?;
+    let secret_required = obj
+        .remove(SECRET_REQUIRED_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let fail = |message: String| (Some(app.clone()), id.clone(), message);
+
+    let app_type = AppType::from_str(&app).map_err(|e| fail(e.to_string()))?;
+    let mut provider: Provider =
+        serde_json::from_value(entry).map_err(|e| fail(format!("供应商格式错误: {e}")))?;
+    if provider.id.trim().is_empty() {
+        return Err(fail("供应商 id 不能为空".to_string()));
+    }
+    if provider.name.trim().is_empty() {
+        return Err(fail("供应商名称不能为空".to_string()));
+    }
+    if contains_redacted(&provider.settings_config)
+        || provider.env.values().any(|v| v == REDACTED_PLACEHOLDER)
+    {
+        return Err(fail(
+            "配置包含已隐藏的密钥，请使用包含密钥的导出文件".to_string(),
+        ));
+    }
+
+    ProviderService::normalize_provider_if_claude(&app_type, &mut provider);
+    ProviderService::normalize_base_urls(&app_type, &mut provider)
+        .map_err(|e| fail(e.to_string()))?;
+    ProviderService::validate_provider_settings(&app_type, &provider)
+        .map_err(|e| fail(e.to_string()))?;
+    Ok((app_type, provider, secret_required))
+}
+
+fn contains_redacted(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s == REDACTED_PLACEHOLDER,
+        Value::Array(items) => items.iter().any(contains_redacted),
+        Value::Object(map) => map.values().any(contains_redacted),
+        _ => false,
+    }
+}
+
+fn next_sort_index(providers: &IndexMap<String, Provider>) -> usize {
+    providers
+        .values()
+        .filter_map(|p| p.sort_index)
+        .max()
+        .map_or(providers.len(), |max| max + 1)
+}
+
+fn unique_name(providers: &IndexMap<String, Provider>, name: &str) -> String {
+    let mut n = 2;
+    let mut candidate = format!("{name} ({n})");
+    while providers.values().any(|p| p.name == candidate) {
+        n += 1;
+        candidate = format!("{name} ({n})");
+    }
+    candidate
+}
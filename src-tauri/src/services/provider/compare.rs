@@ -0,0 +1,192 @@
+//! Side-by-side comparison of two providers
+//!
+//! Compares what a switch to each provider would write to the Live config: the effective
+//! base URL and model, then every config entry flattened to a dotted path. Secrets are only
+//! reported as `set` / `unset`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::secret_store::{is_secret_key, redact_secrets};
+
+use super::verify::{config_credentials, expected_live};
+
+/// One compared field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderFieldDiff {
+    /// `baseUrl`, `model`, `apiKey`, or a dotted config path such as `env.ANTHROPIC_MODEL`
+    pub field: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+    pub differs: bool,
+}
+
+/// Field-by-field comparison of two providers of the same app
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderDiff {
+    pub provider_a: String,
+    pub provider_b: String,
+    /// Summary fields first, then config paths in alphabetical order
+    pub fields: Vec<ProviderFieldDiff>,
+}
+
+fn presence(value: Option<&str>) -> String {
+    match value {
+        Some(v) if !v.trim().is_empty() => "set".to_string(),
+        _ => "unset".to_string(),
+    }
+}
+
+/// Flatten `value` into dotted paths; secret leaves become `set` / `unset`
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                if is_secret_key(key) && !child.is_object() {
+                    out.insert(path, presence(child.as_str()));
+                } else {
+                    flatten(&path, child, out);
+                }
+            }
+        }
+        Value::String(s) => {
+            out.insert(prefix.to_string(), redact_secrets(s));
+        }
+        Value::Null => {}
+        other => {
+            out.insert(prefix.to_string(), redact_secrets(&other.to_string()));
+        }
+    }
+}
+
+/// Live config entries, with Codex's `config.toml` expanded into its keys
+fn config_entries(live: &Value) -> BTreeMap<String, String> {
+    let mut live = live.clone();
+    if let Some(config) = live.get_mut("config") {
+        if let Some(parsed) = config
+            .as_str()
+            .and_then(|text| toml::from_str::<toml::Value>(text).ok())
+            .and_then(|toml| serde_json::to_value(toml).ok())
+        {
+            *config = parsed;
+        }
+    }
+    let mut entries = BTreeMap::new();
+    flatten("", &live, &mut entries);
+    entries
+}
+
+fn summary(
+    app_type: &AppType,
+    provider: &Provider,
+    live: &Value,
+) -> [(&'static str, Option<String>); 3] {
+    let (model, _) = super::overrides::effective_settings(app_type, provider);
+    let (base_url, api_key) = config_credentials(app_type, live);
+    [
+        ("baseUrl", base_url),
+        ("model", model.value),
+        ("apiKey", Some(presence(api_key.as_deref()))),
+    ]
+}
+
+/// Compare the effective configs of two providers
+pub(crate) fn diff_providers(
+    app_type: &AppType,
+    a: &Provider,
+    b: &Provider,
+) -> Result<ProviderDiff, AppError> {
+    let live_a = expected_live(app_type, a)?;
+    let live_b = expected_live(app_type, b)?;
+
+    let mut fields: Vec<ProviderFieldDiff> = summary(app_type, a, &live_a)
+        .into_iter()
+        .zip(summary(app_type, b, &live_b))
+        .map(|((field, a), (_, b))| ProviderFieldDiff {
+            field: field.to_string(),
+            differs: a != b,
+            a,
+            b,
+        })
+        .collect();
+
+    let mut entries_a = config_entries(&live_a);
+    let mut entries_b = config_entries(&live_b);
+    let paths: BTreeSet<String> = entries_a.keys().chain(entries_b.keys()).cloned().collect();
+    fields.extend(paths.into_iter().map(|path| {
+        let a = entries_a.remove(&path);
+        let b = entries_b.remove(&path);
+        ProviderFieldDiff {
+            field: path,
+            differs: a != b,
+            a,
+            b,
+        }
+    }));
+
+    Ok(ProviderDiff {
+        provider_a: a.id.clone(),
+        provider_b: b.id.clone(),
+        fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude(id: &str, base_url: &str, token: &str, model: &str) -> Provider {
+        Provider::with_id(
+            id.to_string(),
+            id.to_string(),
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": base_url,
+                    "ANTHROPIC_AUTH_TOKEN": token,
+                    "ANTHROPIC_MODEL": model
+                }
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn reports_differences_without_exposing_secrets() {
+        let a = claude("a", "https://a.example", "sk-aaaaaaaaaaaa", "opus");
+        let b = claude("b", "https://b.example/", "", "opus");
+        let diff = diff_providers(&AppType::Claude, &a, &b).unwrap();
+
+        let field = |name: &str| {
+            diff.fields
+                .iter()
+                .find(|f| f.field == name)
+                .unwrap_or_else(|| panic!("missing field {name}"))
+        };
+        assert_eq!(diff.fields[0].field, "baseUrl");
+        assert!(field("baseUrl").differs);
+        assert_eq!(field("baseUrl").b.as_deref(), Some("https://b.example"));
+        assert!(!field("model").differs);
+
+        let key = field("env.ANTHROPIC_AUTH_TOKEN");
+        assert_eq!(key.a.as_deref(), Some("set"));
+        assert_eq!(key.b.as_deref(), Some("unset"));
+        assert!(diff
+            .fields
+            .iter()
+            .all(|f| f.a.as_deref() != Some("sk-aaaaaaaaaaaa")));
+        assert!(!field("env.ANTHROPIC_MODEL").differs);
+    }
+}
@@ -0,0 +1,87 @@
+//! Live config parse checks
+//!
+//! Hand-edited Live config files that no longer parse make verify / preview / import fail.
+//! These helpers locate the parse errors and, on request, back up the broken files and
+//! replace them with a valid skeleton.
+
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::app_config::AppType;
+use crate::config::{atomic_write, copy_file, ConfigParseError};
+use crate::error::AppError;
+
+use super::live::{live_config_paths, stub_contents};
+
+/// A broken Live config file that was reset to a valid skeleton
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigResetEntry {
+    pub path: String,
+    /// Copy of the broken file, kept for manual recovery
+    pub backup_path: String,
+}
+
+/// Parse error of a JSON Live config file (`None` if missing, not JSON, or valid)
+fn parse_error(path: &Path) -> Result<Option<ConfigParseError>, AppError> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("json") || !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    Ok(serde_json::from_str::<serde_json::Value>(&content)
+        .err()
+        .map(|e| ConfigParseError::new(path, &content, &e)))
+}
+
+/// Parse errors in the app's JSON Live config files
+pub(crate) fn invalid_live_configs(app_type: &AppType) -> Result<Vec<ConfigParseError>, AppError> {
+    live_config_paths(app_type)
+        .iter()
+        .filter_map(|path| parse_error(path).transpose())
+        .collect()
+}
+
+/// `<file>.invalid-<timestamp>` next to the broken file, unique within the directory
+fn backup_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+    let mut backup = path.with_file_name(format!("{file_name}.invalid-{timestamp}"));
+    let mut counter = 1;
+    while backup.exists() {
+        backup = path.with_file_name(format!("{file_name}.invalid-{timestamp}_{counter}"));
+        counter += 1;
+    }
+    backup
+}
+
+/// Back up each JSON Live config file that fails to parse and reset it to `{}`
+///
+/// Files that parse are left untouched.
+pub(crate) fn reset_invalid_live_configs(
+    app_type: &AppType,
+) -> Result<Vec<ConfigResetEntry>, AppError> {
+    let mut reset = Vec::new();
+    for path in live_config_paths(app_type) {
+        if parse_error(&path)?.is_none() {
+            continue;
+        }
+        let backup = backup_path_for(&path);
+        copy_file(&path, &backup)?;
+        atomic_write(&path, stub_contents(&path).as_bytes())?;
+        log::warn!(
+            "配置文件 {} 格式无效，已备份到 {} 并重置",
+            path.display(),
+            backup.display()
+        );
+        reset.push(ConfigResetEntry {
+            path: path.to_string_lossy().to_string(),
+            backup_path: backup.to_string_lossy().to_string(),
+        });
+    }
+    Ok(reset)
+}
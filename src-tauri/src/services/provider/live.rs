@@ -3,12 +3,16 @@
 //! Handles reading and writing live configuration files for Claude, Codex, and Gemini.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use serde_json::{json, Value};
 
 use crate::app_config::AppType;
 use crate::codex_config::{get_codex_auth_path, get_codex_config_path};
-use crate::config::{delete_file, get_claude_settings_path, read_json_file, write_json_file};
+use crate::config::{
+    atomic_write, delete_file, get_claude_settings_path, read_json_file, write_config_atomic,
+    write_json_file,
+};
 use crate::error::AppError;
 use crate::provider::Provider;
 use crate::services::mcp::McpService;
@@ -18,6 +22,7 @@ use super::gemini_auth::{
     detect_gemini_auth_type, ensure_google_oauth_security_flag, GeminiAuthType,
 };
 use super::normalize_claude_models_in_value;
+use super::overrides::{apply_codex_overrides, override_env};
 
 /// Live configuration snapshot for backup/restore
 #[derive(Clone)]
@@ -92,29 +97,297 @@ impl LiveSnapshot {
     }
 }
 
+/// Split a Codex provider config into the `auth.json` value and `config.toml` text
+///
+/// The model / base URL overrides are applied to the returned `config.toml` text.
+pub(crate) fn codex_live_parts(provider: &Provider) -> Result<(&Value, String), AppError> {
+    let obj = provider
+        .settings_config
+        .as_object()
+        .ok_or_else(|| AppError::Config("Codex 供应商配置必须是 JSON 对象".to_string()))?;
+    let auth = obj
+        .get("auth")
+        .ok_or_else(|| AppError::Config("Codex 供应商配置缺少 'auth' 字段".to_string()))?;
+    let config_str = obj.get("config").and_then(|v| v.as_str()).ok_or_else(|| {
+        AppError::Config("Codex 供应商配置缺少 'config' 字段或不是字符串".to_string())
+    })?;
+    Ok((auth, apply_codex_overrides(provider, config_str)))
+}
+
+/// Env vars written on top of `settingsConfig`: the provider's extra env vars, then the
+/// model / base URL overrides
+fn injected_env(app_type: &AppType, provider: &Provider) -> Vec<(String, String)> {
+    let mut entries: Vec<_> = sorted_env(&provider.env)
+        .into_iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    entries.extend(override_env(app_type, provider));
+    entries
+}
+
+/// Claude `settings.json` content for a provider, with the injected env vars merged into `env`
+pub(crate) fn claude_live_settings(provider: &Provider) -> Value {
+    let mut settings = provider.settings_config.clone();
+    let injected = injected_env(&AppType::Claude, provider);
+    if injected.is_empty() {
+        return settings;
+    }
+    if let Some(obj) = settings.as_object_mut() {
+        let env = obj.entry("env").or_insert_with(|| json!({}));
+        if !env.is_object() {
+            *env = json!({});
+        }
+        if let Some(env) = env.as_object_mut() {
+            for (key, value) in injected {
+                env.insert(key, Value::String(value));
+            }
+        }
+    }
+    settings
+}
+
+/// Validate provider-level env vars: names must be valid env var names, values single-line
+///
+/// Codex reads no env block from its config files, so extra env vars are rejected there.
+pub(crate) fn validate_provider_env(
+    app_type: &AppType,
+    env: &HashMap<String, String>,
+) -> Result<(), AppError> {
+    if env.is_empty() {
+        return Ok(());
+    }
+    if matches!(app_type, AppType::Codex) {
+        return Err(AppError::localized(
+            "provider.env.unsupported",
+            "Codex 不支持为供应商注入自定义环境变量",
+            "Codex does not support custom provider environment variables",
+        ));
+    }
+    for (key, value) in sorted_env(env) {
+        let valid_name = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(AppError::localized(
+                "provider.env.invalid_name",
+                format!("无效的环境变量名: '{key}'（仅支持字母、数字和下划线，且不能以数字开头）"),
+                format!("Invalid environment variable name: '{key}'"),
+            ));
+        }
+        if value.contains(['\n', '\r', '\0']) {
+            return Err(AppError::localized(
+                "provider.env.invalid_value",
+                format!("环境变量 {key} 的值不能包含换行符"),
+                format!("Value of environment variable {key} must not contain line breaks"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Undo what the switch injected into a Live config read back for backfill, so extra env
+/// vars and overrides stay on the provider instead of being copied into `settings_config`
+///
+/// Values the user changed in the Live config since the switch are kept.
+pub(crate) fn restore_injected_values(
+    app_type: &AppType,
+    live_config: &mut Value,
+    provider: &Provider,
+) {
+    if let AppType::Codex = app_type {
+        let Some(original) = provider
+            .settings_config
+            .get("config")
+            .and_then(|v| v.as_str())
+        else {
+            return;
+        };
+        if live_config.get("config").and_then(|v| v.as_str())
+            == Some(apply_codex_overrides(provider, original).as_str())
+        {
+            live_config["config"] = Value::String(original.to_string());
+        }
+        return;
+    }
+
+    let original_env = provider.settings_config.get("env");
+    let Some(live_env) = live_config.get_mut("env").and_then(|v| v.as_object_mut()) else {
+        return;
+    };
+    for (key, value) in injected_env(app_type, provider) {
+        if live_env.get(&key).and_then(|v| v.as_str()) != Some(value.as_str()) {
+            continue;
+        }
+        match original_env.and_then(|env| env.get(&key)) {
+            Some(original) => {
+                live_env.insert(key, original.clone());
+            }
+            None => {
+                live_env.remove(&key);
+            }
+        }
+    }
+}
+
+fn sorted_env(env: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = env.iter().collect();
+    entries.sort();
+    entries
+}
+
+/// Live config files written by `write_live_snapshot` for an app
+pub(crate) fn live_config_paths(app_type: &AppType) -> Vec<PathBuf> {
+    match app_type {
+        AppType::Claude => vec![get_claude_settings_path()],
+        AppType::Codex => vec![get_codex_auth_path(), get_codex_config_path()],
+        AppType::Gemini => vec![
+            crate::gemini_config::get_gemini_env_path(),
+            crate::gemini_config::get_gemini_settings_path(),
+        ],
+    }
+}
+
+/// Minimal valid contents for a missing Live config file
+pub(super) fn stub_contents(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => "{}\n",
+        _ => "",
+    }
+}
+
+/// `配置目录不可写` error naming the directory and the OS error kind
+fn dir_not_writable(dir: &Path, err: &std::io::Error) -> AppError {
+    let kind = err.kind();
+    AppError::localized(
+        "live.dir.not_writable",
+        format!("配置目录不可写: {}（{kind:?}）", dir.display()),
+        format!(
+            "Config directory is not writable: {} ({kind:?})",
+            dir.display()
+        ),
+    )
+}
+
+/// Check that `dir` accepts new files by creating and removing a temp file
+///
+/// Atomic writes create a temp file next to the target, so a read-only directory fails
+/// even when the config file itself is writable.
+fn probe_dir_writable(dir: &Path) -> Result<(), AppError> {
+    match tempfile::Builder::new()
+        .prefix(".cc-switch-probe")
+        .tempfile_in(dir)
+    {
+        Ok(_) => Ok(()),
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem
+            ) =>
+        {
+            Err(dir_not_writable(dir, &e))
+        }
+        Err(e) => Err(AppError::io(dir, e)),
+    }
+}
+
+/// Preflight check that the Live config directories are writable
+///
+/// Missing directories are checked at their nearest existing ancestor, where they would be
+/// created.
+pub fn check_live_writable(app_type: &AppType) -> Result<(), AppError> {
+    let mut dirs: Vec<PathBuf> = live_config_paths(app_type)
+        .iter()
+        .filter_map(|path| path.parent()?.ancestors().find(|dir| dir.is_dir()))
+        .map(Path::to_path_buf)
+        .collect();
+    dirs.dedup();
+    dirs.iter().try_for_each(|dir| probe_dir_writable(dir))
+}
+
+/// Reject a config path that can't be written as a regular file
+fn check_config_path(path: &Path) -> Result<bool, AppError> {
+    let display = path.display();
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(AppError::io(path, e)),
+    };
+    if meta.file_type().is_symlink() {
+        return match std::fs::metadata(path) {
+            Ok(target) if target.is_file() => Ok(true),
+            Ok(_) => Err(AppError::localized(
+                "live.path.symlink_not_file",
+                format!("配置路径 {display} 是指向目录的符号链接，无法写入配置"),
+                format!("Config path {display} is a symlink to a directory"),
+            )),
+            Err(_) => Err(AppError::localized(
+                "live.path.broken_symlink",
+                format!("配置路径 {display} 是失效的符号链接，请修复或删除后重试"),
+                format!("Config path {display} is a broken symlink; fix or remove it"),
+            )),
+        };
+    }
+    if !meta.is_file() {
+        return Err(AppError::localized(
+            "live.path.not_file",
+            format!("配置路径 {display} 是目录而不是文件，请移走后重试"),
+            format!("Config path {display} is a directory, not a file"),
+        ));
+    }
+    Ok(true)
+}
+
+/// Create the app's config directory and minimal Live config files if missing
+///
+/// Idempotent; returns whether anything was created. Fails with a clear error when a
+/// config path (or its directory) exists but isn't usable.
+pub fn ensure_live_config(app_type: &AppType) -> Result<bool, AppError> {
+    check_live_writable(app_type)?;
+    let mut created = false;
+    for path in live_config_paths(app_type) {
+        if let Some(dir) = path.parent() {
+            match std::fs::metadata(dir) {
+                Ok(meta) if !meta.is_dir() => {
+                    return Err(AppError::localized(
+                        "live.dir.not_dir",
+                        format!("配置目录 {} 是文件而不是目录", dir.display()),
+                        format!("Config directory {} is a file", dir.display()),
+                    ));
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    std::fs::create_dir_all(dir).map_err(|e| AppError::io(dir, e))?;
+                    created = true;
+                }
+            }
+        }
+        if !check_config_path(&path)? {
+            atomic_write(&path, stub_contents(&path).as_bytes())?;
+            log::info!("已创建 {} 配置文件 {}", app_type.as_str(), path.display());
+            created = true;
+        }
+    }
+    Ok(created)
+}
+
 /// Write live configuration snapshot for a provider
 pub(crate) fn write_live_snapshot(app_type: &AppType, provider: &Provider) -> Result<(), AppError> {
+    crate::secret_store::ensure_unlocked()?;
     match app_type {
         AppType::Claude => {
             let path = get_claude_settings_path();
-            write_json_file(&path, &provider.settings_config)?;
+            let json = serde_json::to_string_pretty(&claude_live_settings(provider))
+                .map_err(|e| AppError::JsonSerialize { source: e })?;
+            write_config_atomic(&path, json.as_bytes())?;
         }
         AppType::Codex => {
-            let obj = provider
-                .settings_config
-                .as_object()
-                .ok_or_else(|| AppError::Config("Codex 供应商配置必须是 JSON 对象".to_string()))?;
-            let auth = obj
-                .get("auth")
-                .ok_or_else(|| AppError::Config("Codex 供应商配置缺少 'auth' 字段".to_string()))?;
-            let config_str = obj.get("config").and_then(|v| v.as_str()).ok_or_else(|| {
-                AppError::Config("Codex 供应商配置缺少 'config' 字段或不是字符串".to_string())
-            })?;
+            let (auth, config_str) = codex_live_parts(provider)?;
 
             let auth_path = get_codex_auth_path();
-            write_json_file(&auth_path, auth)?;
+            let auth_json = serde_json::to_string_pretty(auth)
+                .map_err(|e| AppError::JsonSerialize { source: e })?;
+            write_config_atomic(&auth_path, auth_json.as_bytes())?;
             let config_path = get_codex_config_path();
-            std::fs::write(&config_path, config_str).map_err(|e| AppError::io(&config_path, e))?;
+            write_config_atomic(&config_path, config_str.as_bytes())?;
         }
         AppType::Gemini => {
             // Delegate to write_gemini_live which handles env file writing correctly
@@ -304,22 +577,52 @@ pub fn import_default_config(state: &AppState, app_type: AppType) -> Result<bool
     Ok(true) // 真正导入了
 }
 
-/// Write Gemini live configuration with authentication handling
-pub(crate) fn write_gemini_live(provider: &Provider) -> Result<(), AppError> {
-    use crate::gemini_config::{
-        get_gemini_settings_path, json_to_env, validate_gemini_settings_strict,
-        write_gemini_env_atomic,
+/// Provider settings extracted from the tool's Live config
+///
+/// Keeps only what a provider owns: Claude `env`, Codex `auth` + `config.toml`, Gemini `.env`.
+/// Other Live settings (permissions, hooks, MCP servers, ...) are left out.
+pub(crate) fn settings_from_live(app_type: &AppType) -> Result<Value, AppError> {
+    let live = read_live_settings(app_type.clone())?;
+    let mut settings = match app_type {
+        AppType::Claude | AppType::Gemini => json!({
+            "env": live.get("env").cloned().unwrap_or_else(|| json!({})),
+        }),
+        AppType::Codex => live,
     };
+    if matches!(app_type, AppType::Claude) {
+        let _ = normalize_claude_models_in_value(&mut settings);
+    }
+    Ok(settings)
+}
 
-    // One-time auth type detection to avoid repeated detection
-    let auth_type = detect_gemini_auth_type(provider);
+/// Gemini `.env` content to write for a provider (cleared for Google OAuth)
+pub(crate) fn gemini_env_for_provider(
+    provider: &Provider,
+    auth_type: GeminiAuthType,
+) -> Result<HashMap<String, String>, AppError> {
+    use crate::gemini_config::{json_to_env, validate_gemini_settings_strict};
+
+    let mut env_map = match auth_type {
+        // Google official uses OAuth, clear env
+        GeminiAuthType::GoogleOfficial => HashMap::new(),
+        // PackyCode / generic providers use API Key (strict validation on switch)
+        GeminiAuthType::Packycode | GeminiAuthType::Generic => {
+            validate_gemini_settings_strict(&provider.settings_config)?;
+            json_to_env(&provider.settings_config)?
+        }
+    };
+    // Provider-level env vars and overrides take precedence over settingsConfig
+    env_map.extend(injected_env(&AppType::Gemini, provider));
+    Ok(env_map)
+}
 
-    let mut env_map = json_to_env(&provider.settings_config)?;
+/// Gemini `settings.json` content to write for a provider, before `selectedType` is applied
+///
+/// - config is object: merge with existing to preserve mcpServers etc.
+/// - config is null or absent: preserve existing file content
+pub(crate) fn merged_gemini_settings(provider: &Provider) -> Result<Option<Value>, AppError> {
+    use crate::gemini_config::get_gemini_settings_path;
 
-    // Prepare config to write to ~/.gemini/settings.json
-    // Behavior:
-    // - config is object: use it (merge with existing to preserve mcpServers etc.)
-    // - config is null or absent: preserve existing file content
     let settings_path = get_gemini_settings_path();
     let mut config_to_write: Option<Value> = None;
 
@@ -356,26 +659,22 @@ pub(crate) fn write_gemini_live(provider: &Provider) -> Result<(), AppError> {
         config_to_write = Some(read_json_file(&settings_path)?);
     }
 
-    match auth_type {
-        GeminiAuthType::GoogleOfficial => {
-            // Google official uses OAuth, clear env
-            env_map.clear();
-            write_gemini_env_atomic(&env_map)?;
-        }
-        GeminiAuthType::Packycode => {
-            // PackyCode provider, uses API Key (strict validation on switch)
-            validate_gemini_settings_strict(&provider.settings_config)?;
-            write_gemini_env_atomic(&env_map)?;
-        }
-        GeminiAuthType::Generic => {
-            // Generic provider, uses API Key (strict validation on switch)
-            validate_gemini_settings_strict(&provider.settings_config)?;
-            write_gemini_env_atomic(&env_map)?;
-        }
-    }
+    Ok(config_to_write)
+}
+
+/// Write Gemini live configuration with authentication handling
+pub(crate) fn write_gemini_live(provider: &Provider) -> Result<(), AppError> {
+    use crate::gemini_config::{get_gemini_settings_path, write_gemini_env_atomic};
+
+    // One-time auth type detection to avoid repeated detection
+    let auth_type = detect_gemini_auth_type(provider);
+
+    let env_map = gemini_env_for_provider(provider, auth_type)?;
+    let config_to_write = merged_gemini_settings(provider)?;
+    write_gemini_env_atomic(&env_map)?;
 
     if let Some(config_value) = config_to_write {
-        write_json_file(&settings_path, &config_value)?;
+        write_json_file(&get_gemini_settings_path(), &config_value)?;
     }
 
     // Set security.auth.selectedType based on auth type
@@ -390,3 +689,26 @@ pub(crate) fn write_gemini_live(provider: &Provider) -> Result<(), AppError> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_leaves_no_files_and_reports_read_only_dirs() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        probe_dir_writable(dir.path()).expect("temp dir is writable");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let err = dir_not_writable(
+            dir.path(),
+            &std::io::Error::from(std::io::ErrorKind::ReadOnlyFilesystem),
+        )
+        .to_string();
+        assert!(
+            err.starts_with(&format!("配置目录不可写: {}", dir.path().display())),
+            "{err}"
+        );
+        assert!(err.contains("ReadOnlyFilesystem"), "{err}");
+    }
+}
@@ -2,31 +2,63 @@
 //!
 //! Handles provider CRUD operations, switching, and configuration management.
 
+mod base_url;
+mod bulk_import;
+mod compare;
+mod config_check;
 mod endpoints;
 mod gemini_auth;
 mod live;
+mod overrides;
+mod preview;
+mod search;
+mod share;
+mod shared_config;
+mod sort;
+mod switch_all;
+mod undo;
 mod usage;
+mod validate;
+mod verify;
 
 use indexmap::IndexMap;
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::app_config::AppType;
 use crate::error::AppError;
 use crate::provider::{Provider, UsageResult};
+use crate::services::connection_test::ConnectionTarget;
 use crate::services::mcp::McpService;
 use crate::settings::CustomEndpoint;
 use crate::store::AppState;
 
 // Re-export sub-module functions for external access
-pub use live::{import_default_config, read_live_settings, sync_current_to_live};
+pub use live::{
+    check_live_writable, ensure_live_config, import_default_config, read_live_settings,
+    sync_current_to_live,
+};
 
 // Internal re-exports (pub(crate))
-pub(crate) use live::write_live_snapshot;
+pub(crate) use live::{live_config_paths, write_live_snapshot};
+pub(crate) use undo::forget_switch;
+
+pub use base_url::{normalize_base_url, NormalizedBaseUrl};
+pub use bulk_import::{ImportStrategy, ProviderImportResult, ProviderImportStatus};
+pub use compare::ProviderDiff;
+pub use config_check::ConfigResetEntry;
+pub use preview::SwitchPreview;
+pub use shared_config::{SharedConfigWarning, SHARED_CONFIG_EVENT};
+pub use sort::ProviderSort;
+pub use undo::UndoSwitchResult;
+pub use validate::ProviderValidation;
+pub use verify::ConfigVerification;
 
 // Internal re-exports
-use live::write_gemini_live;
+use live::{restore_injected_values, settings_from_live, validate_provider_env, write_gemini_live};
+use overrides::effective_settings;
+use preview::preview_live_snapshot;
 use usage::validate_usage_script;
 
 /// Provider business logic service
@@ -53,6 +85,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_provider_settings_checks_provider_env() {
+        let mut provider =
+            Provider::with_id("claude".into(), "Claude".into(), json!({ "env": {} }), None);
+        provider
+            .env
+            .insert("ANTHROPIC_CUSTOM_HEADERS".into(), "X-Team: infra".into());
+        assert!(ProviderService::validate_provider_settings(&AppType::Claude, &provider).is_ok());
+
+        provider.env.insert("1_INVALID".into(), "value".into());
+        assert!(ProviderService::validate_provider_settings(&AppType::Claude, &provider).is_err());
+
+        provider.env.remove("1_INVALID");
+        provider.env.insert("MULTI_LINE".into(), "a\nb".into());
+        assert!(ProviderService::validate_provider_settings(&AppType::Claude, &provider).is_err());
+
+        let mut codex = Provider::with_id(
+            "codex".into(),
+            "Codex".into(),
+            json!({ "auth": {}, "config": "" }),
+            None,
+        );
+        codex
+            .env
+            .insert("OPENAI_BASE_URL".into(), "https://x".into());
+        assert!(ProviderService::validate_provider_settings(&AppType::Codex, &codex).is_err());
+    }
+
     #[test]
     fn extract_credentials_returns_expected_values() {
         let provider = Provider::with_id(
@@ -91,6 +151,15 @@ impl ProviderService {
         state.db.get_all_providers(app_type.as_str())
     }
 
+    /// List all providers for an app type in the given order
+    pub fn list_sorted(
+        state: &AppState,
+        app_type: AppType,
+        sort: ProviderSort,
+    ) -> Result<IndexMap<String, Provider>, AppError> {
+        Ok(sort::sort_providers(Self::list(state, app_type)?, sort))
+    }
+
     /// Get current provider ID
     ///
     /// 使用有效的当前供应商 ID（验证过存在性）。
@@ -101,11 +170,29 @@ impl ProviderService {
             .map(|opt| opt.unwrap_or_default())
     }
 
+    /// The current provider's id and name, or `None` when the app has none
+    pub fn active(
+        state: &AppState,
+        app_type: &AppType,
+    ) -> Result<Option<ActiveProvider>, AppError> {
+        let Some(id) = crate::settings::get_effective_current_provider(&state.db, app_type)? else {
+            return Ok(None);
+        };
+        Ok(state
+            .db
+            .get_provider_by_id(&id, app_type.as_str())?
+            .map(|provider| ActiveProvider {
+                id: provider.id,
+                name: provider.name,
+            }))
+    }
+
     /// Add a new provider
     pub fn add(state: &AppState, app_type: AppType, provider: Provider) -> Result<bool, AppError> {
         let mut provider = provider;
         // Normalize Claude model keys
         Self::normalize_provider_if_claude(&app_type, &mut provider);
+        Self::normalize_base_urls(&app_type, &mut provider)?;
         Self::validate_provider_settings(&app_type, &provider)?;
 
         // Save to database
@@ -124,6 +211,132 @@ impl ProviderService {
         Ok(true)
     }
 
+    /// Normalize the provider's base URL(s) and overrides before saving; notes are only logged
+    fn normalize_base_urls(app_type: &AppType, provider: &mut Provider) -> Result<(), AppError> {
+        let mut notes = base_url::normalize_provider_base_urls(app_type, provider)?;
+        notes.extend(overrides::normalize_overrides(provider)?);
+        for note in notes {
+            log::info!("供应商 {} 的 Base URL: {note}", provider.name);
+        }
+        Ok(())
+    }
+
+    /// Import providers from a JSON array file, resolving id/name collisions with `strategy`
+    ///
+    /// Entries are validated before anything is written and saved in one transaction.
+    pub fn import_providers(
+        state: &AppState,
+        path: &std::path::Path,
+        strategy: ImportStrategy,
+    ) -> Result<Vec<ProviderImportResult>, AppError> {
+        bulk_import::import_providers(state, path, strategy)
+    }
+
+    /// Export providers (all, or only `ids`) as a shareable JSON array with secrets removed
+    pub fn export_providers(
+        state: &AppState,
+        ids: Option<&[String]>,
+    ) -> Result<Vec<Value>, AppError> {
+        share::export_providers(state, ids)
+    }
+
+    /// Duplicate a provider, placing the copy right after the original
+    ///
+    /// The copy gets a fresh id and a " (copy)" suffix, and is never made current.
+    pub fn duplicate(state: &AppState, app_type: AppType, id: &str) -> Result<Provider, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let original = providers
+            .get(id)
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+
+        let base_name = format!("{} (copy)", original.name);
+        let mut name = base_name.clone();
+        let mut n = 2;
+        while providers.values().any(|p| p.name == name) {
+            name = format!("{} (copy {n})", original.name);
+            n += 1;
+        }
+
+        let mut copy = original.clone();
+        copy.id = uuid::Uuid::new_v4().to_string();
+        copy.name = name;
+        copy.created_at = Some(chrono::Utc::now().timestamp_millis());
+        copy.in_failover_queue = false;
+        copy.last_used_at = None;
+        copy.use_count = 0;
+
+        // Re-number every provider so the copy sits directly after the original,
+        // saving the copy and the shifted rows in one transaction
+        let mut ordered: Vec<Provider> = providers.values().cloned().collect();
+        let position = ordered.iter().position(|p| p.id == id).unwrap_or(0);
+        ordered.insert(position + 1, copy.clone());
+
+        let mut changed = Vec::new();
+        for (index, mut provider) in ordered.into_iter().enumerate() {
+            if provider.id == copy.id {
+                copy.sort_index = Some(index);
+                provider.sort_index = Some(index);
+                changed.push((app_type.as_str().to_string(), provider));
+            } else if provider.sort_index != Some(index) {
+                provider.sort_index = Some(index);
+                changed.push((app_type.as_str().to_string(), provider));
+            }
+        }
+        state.db.save_providers(&changed)?;
+
+        Ok(copy)
+    }
+
+    /// Adopt the tool's current Live config as a new provider
+    ///
+    /// The provider is named "Imported from <tool>" (numbered when taken) and appended to
+    /// the list. With `activate` it becomes the current provider; otherwise it only does
+    /// when the app has no current provider (same as [`Self::add`]).
+    pub fn import_from_tool_config(
+        state: &AppState,
+        app_type: AppType,
+        activate: bool,
+    ) -> Result<Provider, AppError> {
+        let settings = settings_from_live(&app_type)?;
+        if verify::config_credentials(&app_type, &settings) == (None, None) {
+            return Err(AppError::localized(
+                "provider.import_live.empty",
+                format!(
+                    "{} 配置文件中没有 Base URL 或 API Key，无法导入",
+                    app_type.as_str()
+                ),
+                format!(
+                    "No base URL or API key found in the {} config",
+                    app_type.as_str()
+                ),
+            ));
+        }
+
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let base_name = format!("Imported from {}", app_type.as_str());
+        let mut name = base_name.clone();
+        let mut n = 2;
+        while providers.values().any(|p| p.name == name) {
+            name = format!("{base_name} ({n})");
+            n += 1;
+        }
+
+        let mut provider =
+            Provider::with_id(uuid::Uuid::new_v4().to_string(), name, settings, None);
+        provider.category = Some("custom".to_string());
+        provider.created_at = Some(chrono::Utc::now().timestamp_millis());
+        provider.sort_index = Some(providers.len());
+
+        Self::add(state, app_type.clone(), provider.clone())?;
+        if activate {
+            Self::switch(state, app_type.clone(), &provider.id)?;
+        }
+        Ok(state
+            .db
+            .get_provider_by_id(&provider.id, app_type.as_str())?
+            .unwrap_or(provider))
+    }
+
     /// Update a provider
     pub fn update(
         state: &AppState,
@@ -133,6 +346,7 @@ impl ProviderService {
         let mut provider = provider;
         // Normalize Claude model keys
         Self::normalize_provider_if_claude(&app_type, &mut provider);
+        Self::normalize_base_urls(&app_type, &mut provider)?;
         Self::validate_provider_settings(&app_type, &provider)?;
 
         // Check if this is current provider (use effective current, not just DB)
@@ -208,20 +422,7 @@ impl ProviderService {
             .get(id)
             .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
 
-        // Check if proxy takeover mode is active AND proxy server is actually running
-        // Both conditions must be true to use hot-switch mode
-        // Use blocking wait since this is a sync function
-        let is_app_taken_over =
-            futures::executor::block_on(state.db.get_live_backup(app_type.as_str()))
-                .ok()
-                .flatten()
-                .is_some();
-        let is_proxy_running = futures::executor::block_on(state.proxy_service.is_running());
-
-        // Hot-switch only when BOTH: this app is taken over AND proxy server is actually running
-        let should_hot_switch = is_app_taken_over && is_proxy_running;
-
-        if should_hot_switch {
+        if Self::should_hot_switch(state, &app_type) {
             // Proxy takeover mode: hot-switch only, don't write Live config
             log::info!(
                 "代理接管模式：热切换 {} 的目标供应商为 {}",
@@ -234,8 +435,8 @@ impl ProviderService {
                 .get(id)
                 .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
 
-            // Update database is_current
-            state.db.set_current_provider(app_type.as_str(), id)?;
+            // Update database is_current and usage stats
+            state.db.record_provider_switch(app_type.as_str(), id)?;
 
             // Update local settings for consistency
             crate::settings::set_current_provider(&app_type, Some(id))?;
@@ -250,6 +451,7 @@ impl ProviderService {
 
             // Note: No Live config write, no MCP sync
             // The proxy server will route requests to the new provider via is_current
+            undo::forget_switch();
             return Ok(());
         }
 
@@ -257,6 +459,179 @@ impl ProviderService {
         Self::switch_normal(state, app_type, id, &providers)
     }
 
+    /// Switch several apps at once: all Live configs and current providers change, or none do
+    ///
+    /// Every selection is validated first. Apps in proxy takeover mode are rejected, since a
+    /// hot-switch writes no files that could be rolled back together with the others.
+    pub fn switch_all(state: &AppState, selections: &[(AppType, String)]) -> Result<(), AppError> {
+        switch_all::switch_all(state, selections)
+    }
+
+    /// Whether switching only retargets the proxy (no Live config write)
+    ///
+    /// Hot-switch only when BOTH: this app is taken over AND proxy server is actually running.
+    /// Use blocking wait since this is a sync function.
+    fn should_hot_switch(state: &AppState, app_type: &AppType) -> bool {
+        let is_app_taken_over =
+            futures::executor::block_on(state.db.get_live_backup(app_type.as_str()))
+                .ok()
+                .flatten()
+                .is_some();
+        is_app_taken_over && futures::executor::block_on(state.proxy_service.is_running())
+    }
+
+    /// Preview the Live config files a switch would write, without writing them
+    ///
+    /// No files are listed in proxy takeover mode (hot-switch writes no files).
+    /// MCP sync performed after a switch is not included.
+    pub fn preview_switch(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+    ) -> Result<SwitchPreview, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let provider = providers
+            .get(id)
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        let (files, shared_config) = if Self::should_hot_switch(state, &app_type) {
+            (Vec::new(), Vec::new())
+        } else {
+            (
+                preview_live_snapshot(&app_type, provider)?,
+                shared_config::shared_config_warnings(&app_type),
+            )
+        };
+        let (model, base_url) = effective_settings(&app_type, provider);
+        Ok(SwitchPreview {
+            files,
+            model,
+            base_url,
+            shared_config,
+        })
+    }
+
+    /// Live config files a switch would write that are also another tool's config
+    ///
+    /// Empty in proxy takeover mode (hot-switch writes no files).
+    pub fn shared_config_warnings(
+        state: &AppState,
+        app_type: &AppType,
+    ) -> Vec<SharedConfigWarning> {
+        if Self::should_hot_switch(state, app_type) {
+            return Vec::new();
+        }
+        shared_config::shared_config_warnings(app_type)
+    }
+
+    /// Connection test targets for every provider of `app_type`, in list order
+    ///
+    /// Base URL and API key are the ones a switch would write (overrides applied).
+    pub fn connection_targets(
+        state: &AppState,
+        app_type: &AppType,
+    ) -> Result<Vec<ConnectionTarget>, AppError> {
+        crate::secret_store::ensure_unlocked()?;
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        providers
+            .into_values()
+            .map(|provider| {
+                let (base_url, api_key) = verify::live_credentials(app_type, &provider)?;
+                Ok(ConnectionTarget {
+                    app_type: app_type.clone(),
+                    provider_id: provider.id,
+                    provider_name: provider.name,
+                    base_url,
+                    api_key,
+                })
+            })
+            .collect()
+    }
+
+    /// Compare the Live config files with the active provider
+    ///
+    /// Reports base URL / API key / model differences (keys masked). Nothing is compared
+    /// in proxy takeover mode or when the app has no current provider.
+    pub fn verify_active_config(
+        state: &AppState,
+        app_type: AppType,
+    ) -> Result<ConfigVerification, AppError> {
+        let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        let provider = match &current {
+            Some(id) => state.db.get_provider_by_id(id, app_type.as_str())?,
+            None => None,
+        };
+        let proxy_takeover = Self::should_hot_switch(state, &app_type);
+        let differences = match &provider {
+            Some(provider) if !proxy_takeover => verify::verify_live(&app_type, provider)?,
+            _ => Vec::new(),
+        };
+        Ok(ConfigVerification {
+            in_sync: differences.is_empty(),
+            provider_id: provider.map(|p| p.id),
+            proxy_takeover,
+            differences,
+        })
+    }
+
+    /// Compare the effective configs of two providers of the same app (secrets as set / unset)
+    pub fn diff_providers(
+        state: &AppState,
+        app_type: &AppType,
+        id_a: &str,
+        id_b: &str,
+    ) -> Result<ProviderDiff, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let find = |id: &str| {
+            providers
+                .get(id)
+                .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))
+        };
+        compare::diff_providers(app_type, find(id_a)?, find(id_b)?)
+    }
+
+    /// Validate providers by id without writing anything, so a bulk switch can be blocked
+    /// until every provider is valid
+    ///
+    /// Ids are looked up in every app; an id present in several apps yields one result per app.
+    pub fn validate_providers(
+        state: &AppState,
+        ids: &[String],
+    ) -> Result<Vec<ProviderValidation>, AppError> {
+        let mut sets = Vec::new();
+        for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+            let providers = state.db.get_all_providers(app_type.as_str())?;
+            sets.push((app_type, providers));
+        }
+
+        let mut results = Vec::new();
+        for id in ids {
+            let before = results.len();
+            for (app_type, providers) in &sets {
+                if let Some(provider) = providers.get(id) {
+                    results.push(validate::validate_provider(app_type, provider));
+                }
+            }
+            if results.len() == before {
+                results.push(ProviderValidation::not_found(id));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Parse errors (with line, column and a masked snippet) in the app's JSON Live configs
+    pub fn check_live_configs(
+        app_type: &AppType,
+    ) -> Result<Vec<crate::config::ConfigParseError>, AppError> {
+        config_check::invalid_live_configs(app_type)
+    }
+
+    /// Back up the app's unparseable JSON Live configs and reset them to a valid skeleton
+    pub fn reset_invalid_live_configs(
+        app_type: &AppType,
+    ) -> Result<Vec<ConfigResetEntry>, AppError> {
+        config_check::reset_invalid_live_configs(app_type)
+    }
+
     /// Normal switch flow (non-proxy mode)
     fn switch_normal(
         state: &AppState,
@@ -268,19 +643,18 @@ impl ProviderService {
             .get(id)
             .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
 
+        // Fail before touching any state when the config directory is read-only
+        check_live_writable(&app_type)?;
+
         // Backfill: Backfill current live config to current provider
         // Use effective current provider (validated existence) to ensure backfill targets valid provider
         let current_id = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
 
-        if let Some(current_id) = current_id {
+        if let Some(current_id) = &current_id {
             if current_id != id {
                 // Only backfill when switching to a different provider
-                if let Ok(live_config) = read_live_settings(app_type.clone()) {
-                    if let Some(mut current_provider) = providers.get(&current_id).cloned() {
-                        current_provider.settings_config = live_config;
-                        // Ignore backfill failure, don't affect switch flow
-                        let _ = state.db.save_provider(app_type.as_str(), &current_provider);
-                    }
+                if let Some(current_provider) = providers.get(current_id) {
+                    Self::backfill_from_live(state, &app_type, current_provider);
                 }
             }
         }
@@ -288,18 +662,51 @@ impl ProviderService {
         // Update local settings (device-level, takes priority)
         crate::settings::set_current_provider(&app_type, Some(id))?;
 
-        // Update database is_current (as default for new devices)
-        state.db.set_current_provider(app_type.as_str(), id)?;
+        // Update database is_current (as default for new devices) and usage stats
+        state.db.record_provider_switch(app_type.as_str(), id)?;
 
         // Sync to live (write_gemini_live handles security flag internally for Gemini)
+        ensure_live_config(&app_type)?;
+        let backups = undo::backup_live_files(&app_type)?;
         write_live_snapshot(&app_type, provider)?;
 
         // Sync MCP
         McpService::sync_all_enabled(state)?;
 
+        // Record after MCP sync so the hashes match the final file contents
+        match current_id {
+            Some(previous_id) if previous_id != id => {
+                undo::record_switch(app_type, previous_id, id.to_string(), backups)
+            }
+            _ => undo::forget_switch(),
+        }
+
         Ok(())
     }
 
+    /// Save the Live config back into `provider` so manual edits survive switching away
+    ///
+    /// Failures are ignored and don't affect the switch flow.
+    pub(crate) fn backfill_from_live(state: &AppState, app_type: &AppType, provider: &Provider) {
+        if let Ok(mut live_config) = read_live_settings(app_type.clone()) {
+            let mut provider = provider.clone();
+            restore_injected_values(app_type, &mut live_config, &provider);
+            provider.settings_config = live_config;
+            let _ = state.db.save_provider(app_type.as_str(), &provider);
+        }
+    }
+
+    /// Undo the last switch: restore the pre-switch Live config files and the previous provider
+    ///
+    /// Refused when the config files were modified after the switch.
+    pub fn undo_last_switch(state: &AppState) -> Result<UndoSwitchResult, AppError> {
+        let result = undo::undo_last_switch(state)?;
+        if let Err(e) = McpService::sync_all_enabled(state) {
+            log::warn!("撤销切换后同步 MCP 失败: {e}");
+        }
+        Ok(result)
+    }
+
     /// Sync current provider to live configuration (re-export)
     pub fn sync_current_to_live(state: &AppState) -> Result<(), AppError> {
         sync_current_to_live(state)
@@ -374,6 +781,47 @@ impl ProviderService {
         Ok(true)
     }
 
+    /// Persist a full provider order; ids missing from the list keep their relative order at the end
+    pub fn reorder(
+        state: &AppState,
+        app_type: AppType,
+        ordered_ids: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        state.db.reorder_providers(app_type.as_str(), ordered_ids)
+    }
+
+    /// Providers whose name or base URL contains `query` and that carry every tag in `tags`
+    ///
+    /// Case- and accent-insensitive; results keep the provider list order.
+    pub fn search(
+        state: &AppState,
+        app_type: AppType,
+        query: &str,
+        tags: &[String],
+    ) -> Result<Vec<Provider>, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        Ok(providers
+            .into_values()
+            .filter(|provider| search::matches(&app_type, provider, query, tags))
+            .collect())
+    }
+
+    /// Replace a provider's tags, returning the normalized list that was saved
+    pub fn set_tags(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+        tags: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let mut provider = state
+            .db
+            .get_provider_by_id(id, app_type.as_str())?
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        provider.tags = search::normalize_tags(tags)?;
+        state.db.save_provider(app_type.as_str(), &provider)?;
+        Ok(provider.tags)
+    }
+
     /// Query provider usage (re-export)
     pub async fn query_usage(
         state: &AppState,
@@ -471,6 +919,8 @@ impl ProviderService {
             }
         }
 
+        validate_provider_env(app_type, &provider.env)?;
+
         // Validate and clean UsageScript configuration (common for all app types)
         if let Some(meta) = &provider.meta {
             if let Some(usage_script) = &meta.usage_script {
@@ -684,6 +1134,14 @@ pub(crate) fn normalize_claude_models_in_value(settings: &mut Value) -> bool {
     changed
 }
 
+/// The current provider of an app, without its settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveProvider {
+    pub id: String,
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ProviderSortUpdate {
     pub id: String,
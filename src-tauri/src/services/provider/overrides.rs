@@ -0,0 +1,247 @@
+//! Per-provider model and base URL overrides
+//!
+//! Applied on top of `settingsConfig` when a provider is written to Live config, so one
+//! endpoint can serve several models without near-duplicate providers. Without an
+//! override the value from `settingsConfig` (or the tool's own default) is used.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::ProxyService;
+
+use super::base_url::normalize_base_url;
+
+/// A model or base URL as a switch would apply it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveSetting {
+    /// `None` when neither an override nor `settingsConfig` sets it (the tool default applies)
+    pub value: Option<String>,
+    /// The value comes from the provider's override
+    pub overridden: bool,
+}
+
+/// Env var names carrying the model and base URL for tools configured through env
+fn env_keys(app_type: &AppType) -> Option<(&'static str, &'static str)> {
+    match app_type {
+        AppType::Claude => Some(("ANTHROPIC_MODEL", "ANTHROPIC_BASE_URL")),
+        AppType::Gemini => Some(("GEMINI_MODEL", "GOOGLE_GEMINI_BASE_URL")),
+        AppType::Codex => None,
+    }
+}
+
+/// Trim and validate the overrides in place; returns base URL notes for the user
+pub(crate) fn normalize_overrides(provider: &mut Provider) -> Result<Vec<String>, AppError> {
+    if let Some(model) = &provider.model {
+        let trimmed = model.trim();
+        if trimmed.is_empty() {
+            return Err(AppError::localized(
+                "provider.model.empty",
+                "模型覆盖不能为空，如需使用默认模型请清除该字段",
+                "Model override must not be empty; clear it to use the default model",
+            ));
+        }
+        provider.model = Some(trimmed.to_string());
+    }
+    match provider.base_url.take() {
+        Some(url) => {
+            let normalized = normalize_base_url(url)?;
+            provider.base_url = Some(normalized.url);
+            Ok(normalized.notes)
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Env vars the overrides set (Claude / Gemini); empty for Codex
+pub(crate) fn override_env(app_type: &AppType, provider: &Provider) -> Vec<(String, String)> {
+    let Some((model_key, base_url_key)) = env_keys(app_type) else {
+        return Vec::new();
+    };
+    [
+        (model_key, provider.model.as_ref()),
+        (base_url_key, provider.base_url.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key.to_string(), value?.clone())))
+    .collect()
+}
+
+/// Codex `config.toml` text with the overrides applied
+///
+/// The base URL goes to the active `model_providers` entry (top-level `base_url` as fallback).
+pub(crate) fn apply_codex_overrides(provider: &Provider, config: &str) -> String {
+    let mut text = config.to_string();
+    if let Some(model) = &provider.model {
+        if let Ok(mut doc) = text.parse::<toml_edit::DocumentMut>() {
+            doc["model"] = toml_edit::value(model.as_str());
+            text = doc.to_string();
+        }
+    }
+    if let Some(base_url) = &provider.base_url {
+        text = ProxyService::update_toml_base_url(&text, base_url);
+    }
+    text
+}
+
+/// Resolve the model and base URL a switch would apply: override first, then `settingsConfig`
+pub(crate) fn effective_settings(
+    app_type: &AppType,
+    provider: &Provider,
+) -> (EffectiveSetting, EffectiveSetting) {
+    let (config_model, config_base_url) = match env_keys(app_type) {
+        Some((model_key, base_url_key)) => {
+            let env = provider.settings_config.get("env");
+            let read = |key: &str| {
+                env.and_then(|env| env.get(key))
+                    .and_then(Value::as_str)
+                    .filter(|s| !s.trim().is_empty())
+                    .map(str::to_string)
+            };
+            (read(model_key), read(base_url_key))
+        }
+        None => codex_config_values(provider),
+    };
+    let resolve =
+        |override_value: &Option<String>, config_value: Option<String>| match override_value {
+            Some(value) => EffectiveSetting {
+                value: Some(value.clone()),
+                overridden: true,
+            },
+            None => EffectiveSetting {
+                value: config_value,
+                overridden: false,
+            },
+        };
+    (
+        resolve(&provider.model, config_model),
+        resolve(&provider.base_url, config_base_url),
+    )
+}
+
+/// `model` and the active provider's `base_url` from a Codex `config.toml`
+fn codex_config_values(provider: &Provider) -> (Option<String>, Option<String>) {
+    let Some(table) = provider
+        .settings_config
+        .get("config")
+        .and_then(Value::as_str)
+        .and_then(|text| toml::from_str::<toml::Table>(text).ok())
+    else {
+        return (None, None);
+    };
+    let as_string =
+        |value: Option<&toml::Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+
+    let model = as_string(table.get("model"));
+    let base_url = table
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .and_then(|name| table.get("model_providers")?.get(name)?.get("base_url"))
+        .or_else(|| table.get("base_url"));
+    (model, as_string(base_url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude_provider() -> Provider {
+        Provider::with_id(
+            "claude".into(),
+            "Claude".into(),
+            json!({ "env": {
+                "ANTHROPIC_MODEL": "claude-sonnet",
+                "ANTHROPIC_BASE_URL": "https://config.example.com"
+            } }),
+            None,
+        )
+    }
+
+    #[test]
+    fn falls_back_to_settings_config_without_overrides() {
+        let (model, base_url) = effective_settings(&AppType::Claude, &claude_provider());
+        assert_eq!(model.value.as_deref(), Some("claude-sonnet"));
+        assert!(!model.overridden);
+        assert_eq!(
+            base_url.value.as_deref(),
+            Some("https://config.example.com")
+        );
+        assert!(!base_url.overridden);
+
+        let bare = Provider::with_id("g".into(), "Gemini".into(), json!({ "env": {} }), None);
+        let (model, base_url) = effective_settings(&AppType::Gemini, &bare);
+        assert_eq!(model.value, None, "tool default applies");
+        assert_eq!(base_url.value, None);
+    }
+
+    #[test]
+    fn override_takes_precedence_over_settings_config() {
+        let mut provider = claude_provider();
+        provider.model = Some("  claude-opus ".into());
+        provider.base_url = Some("override.example.com/".into());
+        normalize_overrides(&mut provider).unwrap();
+        assert_eq!(provider.model.as_deref(), Some("claude-opus"));
+        assert_eq!(
+            provider.base_url.as_deref(),
+            Some("https://override.example.com")
+        );
+
+        let (model, base_url) = effective_settings(&AppType::Claude, &provider);
+        assert_eq!(model.value.as_deref(), Some("claude-opus"));
+        assert!(model.overridden);
+        assert_eq!(
+            base_url.value.as_deref(),
+            Some("https://override.example.com")
+        );
+        assert!(base_url.overridden);
+        assert_eq!(
+            override_env(&AppType::Claude, &provider),
+            vec![
+                ("ANTHROPIC_MODEL".to_string(), "claude-opus".to_string()),
+                (
+                    "ANTHROPIC_BASE_URL".to_string(),
+                    "https://override.example.com".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_blank_model_override() {
+        let mut provider = claude_provider();
+        provider.model = Some("   ".into());
+        assert!(normalize_overrides(&mut provider).is_err());
+    }
+
+    #[test]
+    fn applies_overrides_to_codex_config() {
+        let config = "model = \"gpt-5\"\nmodel_provider = \"relay\"\n\n[model_providers.relay]\nbase_url = \"https://relay.example.com/v1\"\n";
+        let mut provider = Provider::with_id(
+            "codex".into(),
+            "Codex".into(),
+            json!({ "auth": {}, "config": config }),
+            None,
+        );
+        let (model, base_url) = effective_settings(&AppType::Codex, &provider);
+        assert_eq!(model.value.as_deref(), Some("gpt-5"));
+        assert_eq!(
+            base_url.value.as_deref(),
+            Some("https://relay.example.com/v1")
+        );
+        assert_eq!(apply_codex_overrides(&provider, config), config);
+
+        provider.model = Some("gpt-5-codex".into());
+        provider.base_url = Some("https://other.example.com/v1".into());
+        let applied = apply_codex_overrides(&provider, config);
+        let table: toml::Table = toml::from_str(&applied).unwrap();
+        assert_eq!(table["model"].as_str(), Some("gpt-5-codex"));
+        assert_eq!(
+            table["model_providers"]["relay"]["base_url"].as_str(),
+            Some("https://other.example.com/v1")
+        );
+    }
+}
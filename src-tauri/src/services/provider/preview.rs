@@ -0,0 +1,174 @@
+//! Switch preview
+//!
+//! Renders the Live configuration files a switch would write, without touching disk.
+//! Secrets are redacted in both the current and the new content.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::codex_config::{get_codex_auth_path, get_codex_config_path};
+use crate::config::get_claude_settings_path;
+use crate::error::AppError;
+use crate::gemini_config::{
+    apply_selected_type, get_gemini_env_path, get_gemini_settings_path, parse_env_file,
+    serialize_env_file, SELECTED_TYPE_API_KEY, SELECTED_TYPE_OAUTH,
+};
+use crate::provider::Provider;
+use crate::secret_store::{
+    is_secret_key, redact_json_secrets, redact_secrets, REDACTED_PLACEHOLDER,
+};
+
+use super::gemini_auth::{detect_gemini_auth_type, GeminiAuthType};
+use super::live::{
+    claude_live_settings, codex_live_parts, gemini_env_for_provider, merged_gemini_settings,
+};
+use super::overrides::EffectiveSetting;
+use super::shared_config::SharedConfigWarning;
+
+/// One Live config file affected by a switch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChangePreview {
+    pub path: String,
+    /// Current content (empty when the file does not exist)
+    pub before: String,
+    /// Content after the switch
+    pub after: String,
+    /// The switch would create this file
+    pub will_create: bool,
+}
+
+/// What a switch would write: the affected files and the effective model / base URL
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchPreview {
+    /// Empty in proxy takeover mode (hot-switch writes no files)
+    pub files: Vec<FileChangePreview>,
+    pub model: EffectiveSetting,
+    pub base_url: EffectiveSetting,
+    /// Files that are also another tool's Live config (shared via symlink)
+    pub shared_config: Vec<SharedConfigWarning>,
+}
+
+#[derive(Clone, Copy)]
+enum FileFormat {
+    Json,
+    Env,
+    Text,
+}
+
+/// Render the files `write_live_snapshot` would write for a provider
+pub(crate) fn preview_live_snapshot(
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<Vec<FileChangePreview>, AppError> {
+    let files = match app_type {
+        AppType::Claude => vec![(
+            get_claude_settings_path(),
+            pretty_json(&claude_live_settings(provider))?,
+            FileFormat::Json,
+        )],
+        AppType::Codex => {
+            let (auth, config_str) = codex_live_parts(provider)?;
+            vec![
+                (get_codex_auth_path(), pretty_json(auth)?, FileFormat::Json),
+                (get_codex_config_path(), config_str, FileFormat::Text),
+            ]
+        }
+        AppType::Gemini => {
+            let auth_type = detect_gemini_auth_type(provider);
+            let env_map = gemini_env_for_provider(provider, auth_type)?;
+            let mut settings =
+                merged_gemini_settings(provider)?.unwrap_or_else(|| serde_json::json!({}));
+            let selected_type = match auth_type {
+                GeminiAuthType::GoogleOfficial => SELECTED_TYPE_OAUTH,
+                GeminiAuthType::Packycode | GeminiAuthType::Generic => SELECTED_TYPE_API_KEY,
+            };
+            apply_selected_type(&mut settings, selected_type);
+            vec![
+                (
+                    get_gemini_env_path(),
+                    serialize_env_file(&env_map),
+                    FileFormat::Env,
+                ),
+                (
+                    get_gemini_settings_path(),
+                    pretty_json(&settings)?,
+                    FileFormat::Json,
+                ),
+            ]
+        }
+    };
+
+    Ok(files
+        .into_iter()
+        .map(|(path, after, format)| file_preview(&path, &after, format))
+        .collect())
+}
+
+fn file_preview(path: &Path, after: &str, format: FileFormat) -> FileChangePreview {
+    let before = fs::read_to_string(path).ok();
+    FileChangePreview {
+        path: path.to_string_lossy().to_string(),
+        will_create: before.is_none(),
+        before: before
+            .map(|content| redact_content(&content, format))
+            .unwrap_or_default(),
+        after: redact_content(after, format),
+    }
+}
+
+fn pretty_json(value: &Value) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value).map_err(|e| AppError::JsonSerialize { source: e })
+}
+
+/// Redact by key where the format allows it, then mask any remaining key-like strings
+fn redact_content(content: &str, format: FileFormat) -> String {
+    let structured = match format {
+        FileFormat::Json => serde_json::from_str::<Value>(content)
+            .ok()
+            .and_then(|mut value| {
+                redact_json_secrets(&mut value);
+                serde_json::to_string_pretty(&value).ok()
+            }),
+        FileFormat::Env => {
+            let env: HashMap<String, String> = parse_env_file(content)
+                .into_iter()
+                .map(|(key, value)| {
+                    if is_secret_key(&key) {
+                        (key, REDACTED_PLACEHOLDER.to_string())
+                    } else {
+                        (key, value)
+                    }
+                })
+                .collect();
+            Some(serialize_env_file(&env))
+        }
+        FileFormat::Text => None,
+    };
+    redact_secrets(structured.as_deref().unwrap_or(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_secret_keys_in_json_and_env() {
+        let json = r#"{"env":{"ANTHROPIC_AUTH_TOKEN":"sk-secret-token-value","ANTHROPIC_BASE_URL":"https://api.example.com"}}"#;
+        let redacted = redact_content(json, FileFormat::Json);
+        assert!(!redacted.contains("sk-secret-token-value"));
+        assert!(redacted.contains("https://api.example.com"));
+
+        let env =
+            "GEMINI_API_KEY=secret-gemini-key\nGOOGLE_GEMINI_BASE_URL=https://gemini.example.com\n";
+        let redacted = redact_content(env, FileFormat::Env);
+        assert!(!redacted.contains("secret-gemini-key"));
+        assert!(redacted.contains("GOOGLE_GEMINI_BASE_URL=https://gemini.example.com"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Provider search and tags
+//!
+//! Matches providers by name or effective base URL, ignoring case and common Latin accents,
+//! and filters by tags. Results keep the provider list order.
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+
+use super::overrides::effective_settings;
+
+/// Maximum number of tags per provider
+const MAX_TAGS: usize = 20;
+/// Maximum tag length (characters)
+const MAX_TAG_LEN: usize = 32;
+
+/// Map accented Latin letters to their base letter
+fn fold_char(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ì'..='ï' | 'ī' | 'į' | 'ı' => 'i',
+        'ł' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ŕ' | 'ř' => 'r',
+        'ś' | 'š' | 'ş' => 's',
+        'ť' | 'ţ' => 't',
+        'ù'..='ü' | 'ū' | 'ů' | 'ű' | 'ų' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        other => other,
+    }
+}
+
+/// Lowercase and strip accents for comparison
+fn fold(text: &str) -> String {
+    text.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(fold_char)
+        .collect()
+}
+
+/// Trim tags, drop empty ones and duplicates (compared case- and accent-insensitively)
+pub(crate) fn normalize_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    let mut seen = Vec::new();
+    let mut normalized = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(AppError::localized(
+                "provider.tag.too_long",
+                format!("标签“{tag}”超过 {MAX_TAG_LEN} 个字符"),
+                format!("Tag \"{tag}\" is longer than {MAX_TAG_LEN} characters"),
+            ));
+        }
+        let key = fold(tag);
+        if !seen.contains(&key) {
+            seen.push(key);
+            normalized.push(tag.to_string());
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::localized(
+            "provider.tag.too_many",
+            format!("每个供应商最多 {MAX_TAGS} 个标签"),
+            format!("A provider can have at most {MAX_TAGS} tags"),
+        ));
+    }
+    Ok(normalized)
+}
+
+/// Whether `provider` matches `query` (name or base URL substring) and has every tag in `tags`
+///
+/// An empty query matches every provider; an empty tag list applies no tag filter.
+pub(crate) fn matches(
+    app_type: &AppType,
+    provider: &Provider,
+    query: &str,
+    tags: &[String],
+) -> bool {
+    let provider_tags: Vec<String> = provider.tags.iter().map(|t| fold(t)).collect();
+    if !tags.iter().all(|tag| provider_tags.contains(&fold(tag))) {
+        return false;
+    }
+
+    let query = fold(query);
+    if query.is_empty() || fold(&provider.name).contains(&query) {
+        return true;
+    }
+    let (_, base_url) = effective_settings(app_type, provider);
+    base_url
+        .value
+        .is_some_and(|url| fold(&url).contains(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(name: &str, base_url: &str, tags: &[&str]) -> Provider {
+        let mut provider = Provider::with_id(
+            name.to_lowercase(),
+            name.to_string(),
+            json!({"env": {"ANTHROPIC_BASE_URL": base_url}}),
+            None,
+        );
+        provider.tags = tags.iter().map(|t| t.to_string()).collect();
+        provider
+    }
+
+    #[test]
+    fn matches_name_and_base_url_ignoring_case_and_accents() {
+        let p = provider("Café Relay", "https://API.relay.example/v1", &["work"]);
+        let claude = AppType::Claude;
+        assert!(matches(&claude, &p, "cafe", &[]));
+        assert!(matches(&claude, &p, "CAFÉ", &[]));
+        assert!(matches(&claude, &p, "api.relay", &[]));
+        assert!(matches(&claude, &p, "", &[]));
+        assert!(!matches(&claude, &p, "openrouter", &[]));
+    }
+
+    #[test]
+    fn filters_by_all_requested_tags() {
+        let p = provider("Relay", "https://relay.example", &["Work", "Fast"]);
+        let claude = AppType::Claude;
+        assert!(matches(&claude, &p, "", &["work".to_string()]));
+        assert!(matches(
+            &claude,
+            &p,
+            "relay",
+            &["fast".into(), "WORK".into()]
+        ));
+        assert!(!matches(&claude, &p, "", &["personal".to_string()]));
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        let tags = vec![
+            " work ".to_string(),
+            "Work".to_string(),
+            String::new(),
+            "été".to_string(),
+            "ete".to_string(),
+        ];
+        assert_eq!(normalize_tags(&tags).unwrap(), vec!["work", "été"]);
+        assert!(normalize_tags(&["x".repeat(MAX_TAG_LEN + 1)]).is_err());
+        let many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("t{i}")).collect();
+        assert!(normalize_tags(&many).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Shareable provider export
+//!
+//! Produces the JSON array read by bulk import, with secrets cleared so it can be
+//! committed to a repo or pasted in chat. Entries that lost a secret carry
+//! `secretRequired: true`, telling the importer to ask for the user's own key.
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::secret_store::{is_secret_key, redact_secrets, strip_json_secrets};
+use crate::store::AppState;
+
+/// Marker set on exported entries whose secrets were removed
+pub const SECRET_REQUIRED_KEY: &str = "secretRequired";
+
+/// Export providers of all apps (or only `ids`) in display order with secrets removed
+pub(crate) fn export_providers(
+    state: &AppState,
+    ids: Option<&[String]>,
+) -> Result<Vec<Value>, AppError> {
+    let mut entries = Vec::new();
+    let mut found = Vec::new();
+
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        for (id, provider) in crate::tray::sorted_providers(&providers) {
+            if ids.is_some_and(|ids| !ids.contains(id)) {
+                continue;
+            }
+            found.push(id.clone());
+
+            let mut shared = provider.clone();
+            // Order is carried by the array; failover membership is device-local
+            shared.sort_index = None;
+            shared.in_failover_queue = false;
+            let mut entry =
+                serde_json::to_value(&shared).map_err(|e| AppError::JsonSerialize { source: e })?;
+            let mut secret_required = strip_json_secrets(&mut entry);
+            if let Some(config) = entry
+                .pointer_mut("/settingsConfig/config")
+                .filter(|v| v.is_string())
+            {
+                let text = config.as_str().unwrap_or_default();
+                let (stripped, changed) = strip_toml_secrets(text);
+                if changed {
+                    *config = Value::String(stripped);
+                    secret_required = true;
+                }
+            }
+            // Extra env vars under non-secret names may still carry tokens (e.g. custom headers)
+            if let Some(env) = entry.get_mut("env").and_then(|v| v.as_object_mut()) {
+                for value in env.values_mut() {
+                    let text = value.as_str().unwrap_or_default();
+                    if !text.is_empty() && redact_secrets(text) != text {
+                        *value = Value::String(String::new());
+                        secret_required = true;
+                    }
+                }
+            }
+
+            if let Some(obj) = entry.as_object_mut() {
+                obj.insert(
+                    "app".to_string(),
+                    Value::String(app_type.as_str().to_string()),
+                );
+                if secret_required {
+                    obj.insert(SECRET_REQUIRED_KEY.to_string(), Value::Bool(true));
+                }
+            }
+            entries.push(entry);
+        }
+    }
+
+    if let Some(ids) = ids {
+        if let Some(missing) = ids.iter().find(|id| !found.contains(id)) {
+            return Err(AppError::Message(format!("供应商 {missing} 不存在")));
+        }
+    }
+    Ok(entries)
+}
+
+/// Clear quoted secret values (`experimental_bearer_token = "..."`) in Codex TOML text
+fn strip_toml_secrets(text: &str) -> (String, bool) {
+    let Ok(re) = Regex::new(r#"(?m)^(\s*([A-Za-z0-9_\-]+)\s*=\s*)"([^"]+)""#) else {
+        return (text.to_string(), false);
+    };
+    let mut changed = false;
+    let stripped = re.replace_all(text, |caps: &regex::Captures| {
+        if is_secret_key(&caps[2]) {
+            changed = true;
+            format!("{}\"\"", &caps[1])
+        } else {
+            caps[0].to_string()
+        }
+    });
+    (stripped.into_owned(), changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_secret_values_from_toml() {
+        let text = "model = \"gpt-5\"\nexperimental_bearer_token = \"sk-live\"\n";
+        let (stripped, changed) = strip_toml_secrets(text);
+        assert!(changed);
+        assert_eq!(
+            stripped,
+            "model = \"gpt-5\"\nexperimental_bearer_token = \"\"\n"
+        );
+
+        let (_, changed) = strip_toml_secrets("base_url = \"https://example.com\"\n");
+        assert!(!changed);
+    }
+}
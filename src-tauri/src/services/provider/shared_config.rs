@@ -0,0 +1,102 @@
+//! Shared Live config detection
+//!
+//! Users sometimes symlink one tool's config directory into another's (e.g. `~/.gemini`
+//! into `~/.claude`). When two tools' Live config files then resolve to the same file, a
+//! switch for one tool overwrites the other's config. Switching is not blocked; the risk
+//! is reported instead.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::app_config::AppType;
+
+use super::live::live_config_paths;
+
+/// Frontend event emitted after a switch that wrote a config file shared with another tool
+pub const SHARED_CONFIG_EVENT: &str = "shared-config-warning";
+
+/// A Live config file of the switched tool that is also another tool's Live config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedConfigWarning {
+    /// Config path of the switched tool
+    pub path: String,
+    /// The other tool whose config resolves to the same file
+    pub other_app: String,
+    /// Config path of the other tool
+    pub other_path: String,
+    /// The file both paths resolve to
+    pub resolved_path: String,
+}
+
+/// Canonical form of a config path
+///
+/// Files that don't exist yet resolve through their (possibly symlinked) parent directory.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    std::fs::canonicalize(path).ok().or_else(|| {
+        let dir = std::fs::canonicalize(path.parent()?).ok()?;
+        Some(dir.join(path.file_name()?))
+    })
+}
+
+/// Pair each of `paths` with the `others` paths resolving to the same file
+fn find_shared(paths: &[PathBuf], others: &[(&str, Vec<PathBuf>)]) -> Vec<SharedConfigWarning> {
+    let mut warnings = Vec::new();
+    for path in paths {
+        let Some(resolved) = resolve(path) else {
+            continue;
+        };
+        for (other_app, other_paths) in others {
+            for other_path in other_paths {
+                if resolve(other_path).as_ref() == Some(&resolved) {
+                    warnings.push(SharedConfigWarning {
+                        path: path.to_string_lossy().to_string(),
+                        other_app: other_app.to_string(),
+                        other_path: other_path.to_string_lossy().to_string(),
+                        resolved_path: resolved.to_string_lossy().to_string(),
+                    });
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Live config files of `app_type` that resolve to another tool's Live config file
+pub(crate) fn shared_config_warnings(app_type: &AppType) -> Vec<SharedConfigWarning> {
+    let others: Vec<(&str, Vec<PathBuf>)> = [AppType::Claude, AppType::Codex, AppType::Gemini]
+        .iter()
+        .filter(|other| other.as_str() != app_type.as_str())
+        .map(|other| (other.as_str(), live_config_paths(other)))
+        .collect();
+    find_shared(&live_config_paths(app_type), &others)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_files_shared_through_a_symlinked_directory() {
+        let home = tempfile::tempdir().expect("create temp dir");
+        let claude_dir = home.path().join(".claude");
+        std::fs::create_dir_all(&claude_dir).unwrap();
+        std::os::unix::fs::symlink(&claude_dir, home.path().join(".gemini")).unwrap();
+        std::fs::write(claude_dir.join("settings.json"), "{}").unwrap();
+
+        let claude = vec![claude_dir.join("settings.json")];
+        let gemini = vec![
+            home.path().join(".gemini/.env"),
+            home.path().join(".gemini/settings.json"),
+        ];
+
+        let warnings = find_shared(&gemini, &[("claude", claude.clone())]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].other_app, "claude");
+        assert!(warnings[0].path.ends_with(".gemini/settings.json"));
+
+        let codex = vec![home.path().join(".codex/auth.json")];
+        assert!(find_shared(&codex, &[("claude", claude)]).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+//! Provider list ordering
+//!
+//! The stored order is the manual order; other orders are applied on read with a stable
+//! sort, so ties keep their manual position.
+
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+
+use crate::error::AppError;
+use crate::provider::Provider;
+
+/// How to order the provider list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProviderSort {
+    /// Manual order (sort index)
+    #[default]
+    Order,
+    /// Name, case-insensitive
+    Name,
+    /// Most recently switched to first; never-used providers last
+    Recent,
+    /// Most switched to first, then most recent
+    MostUsed,
+}
+
+impl FromStr for ProviderSort {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "order" => Ok(Self::Order),
+            "name" => Ok(Self::Name),
+            "recent" => Ok(Self::Recent),
+            "most_used" | "mostused" => Ok(Self::MostUsed),
+            other => Err(AppError::InvalidInput(format!(
+                "不支持的排序方式: '{other}'。可选值: order, name, recent, most_used。"
+            ))),
+        }
+    }
+}
+
+/// Reorder providers (already in manual order) by `sort`
+pub(crate) fn sort_providers(
+    providers: IndexMap<String, Provider>,
+    sort: ProviderSort,
+) -> IndexMap<String, Provider> {
+    let mut entries: Vec<(String, Provider)> = providers.into_iter().collect();
+    match sort {
+        ProviderSort::Order => {}
+        ProviderSort::Name => entries.sort_by_cached_key(|(_, p)| p.name.to_lowercase()),
+        ProviderSort::Recent => entries.sort_by_key(|(_, p)| Reverse(p.last_used_at)),
+        ProviderSort::MostUsed => {
+            entries.sort_by_key(|(_, p)| Reverse((p.use_count, p.last_used_at)))
+        }
+    }
+    entries.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn providers() -> IndexMap<String, Provider> {
+        [
+            ("a", "beta", None, 0),
+            ("b", "Alpha", Some(100), 3),
+            ("c", "gamma", Some(300), 1),
+            ("d", "delta", Some(200), 3),
+        ]
+        .into_iter()
+        .map(|(id, name, last_used_at, use_count)| {
+            let mut p = Provider::with_id(id.into(), name.into(), json!({}), None);
+            p.last_used_at = last_used_at;
+            p.use_count = use_count;
+            (id.to_string(), p)
+        })
+        .collect()
+    }
+
+    fn ids(sort: ProviderSort) -> Vec<String> {
+        sort_providers(providers(), sort).into_keys().collect()
+    }
+
+    #[test]
+    fn sorts_by_each_option() {
+        assert_eq!(ids(ProviderSort::Order), ["a", "b", "c", "d"]);
+        assert_eq!(ids(ProviderSort::Name), ["b", "a", "d", "c"]);
+        assert_eq!(ids(ProviderSort::Recent), ["c", "d", "b", "a"]);
+        assert_eq!(ids(ProviderSort::MostUsed), ["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn parses_sort_names() {
+        assert_eq!("".parse::<ProviderSort>().unwrap(), ProviderSort::Order);
+        assert_eq!(
+            "most_used".parse::<ProviderSort>().unwrap(),
+            ProviderSort::MostUsed
+        );
+        assert!("popular".parse::<ProviderSort>().is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Switch the active provider of several apps as one transaction
+//!
+//! Every selection is validated before anything is written. Live config files of all
+//! affected apps are then backed up to `.bak` and rewritten one app at a time; if any write,
+//! the settings update or the database commit fails, every file touched so far is restored
+//! from its backup and the previous current providers are put back, so the apps are never
+//! left half-switched.
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::services::mcp::McpService;
+use crate::store::AppState;
+
+use super::live::{ensure_live_config, write_live_snapshot};
+use super::undo::{self, FileBackup};
+use super::{validate, ProviderService};
+
+/// Restore backed-up files, newest first so a file shared by two apps ends at its original
+fn rollback_files(backups: &[FileBackup]) {
+    for file in backups.iter().rev() {
+        if let Err(e) = undo::restore_backup(file) {
+            log::error!("回滚配置文件 {} 失败: {e}", file.path().display());
+        }
+    }
+}
+
+/// Put back the device-level current providers captured before the switch
+fn rollback_settings(previous: &[(AppType, Option<String>)]) {
+    for (app_type, id) in previous {
+        if let Err(e) = crate::settings::set_current_provider(app_type, id.as_deref()) {
+            log::error!("回滚 {} 的当前供应商失败: {e}", app_type.as_str());
+        }
+    }
+}
+
+/// Validate every selection, then switch all apps or none
+pub(crate) fn switch_all(
+    state: &AppState,
+    selections: &[(AppType, String)],
+) -> Result<(), AppError> {
+    if selections.is_empty() {
+        return Err(AppError::Message("未选择要切换的供应商".to_string()));
+    }
+
+    let mut targets = Vec::with_capacity(selections.len());
+    for (app_type, id) in selections {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let provider = providers.get(id).cloned().ok_or_else(|| {
+            AppError::Message(format!("{} 的供应商 {id} 不存在", app_type.as_str()))
+        })?;
+        let validation = validate::validate_provider(app_type, &provider);
+        if !validation.valid {
+            return Err(AppError::Message(format!(
+                "{} 的供应商 {id} 配置无效：{}",
+                app_type.as_str(),
+                validation.errors.join("；")
+            )));
+        }
+        // Hot-switching only retargets the proxy and can't share the file rollback
+        if ProviderService::should_hot_switch(state, app_type) {
+            return Err(AppError::Message(format!(
+                "{} 处于代理接管模式，请单独切换",
+                app_type.as_str()
+            )));
+        }
+        let current = crate::settings::get_effective_current_provider(&state.db, app_type)?;
+        targets.push((app_type.clone(), provider, providers, current));
+    }
+
+    // Fail before touching any file when a config directory or path is unusable
+    for (app_type, ..) in &targets {
+        ensure_live_config(app_type)?;
+    }
+
+    for (app_type, provider, providers, current) in &targets {
+        if let Some(current_provider) = current
+            .as_ref()
+            .filter(|current| **current != provider.id)
+            .and_then(|current| providers.get(current))
+        {
+            ProviderService::backfill_from_live(state, app_type, current_provider);
+        }
+    }
+
+    let mut backups = Vec::new();
+    for (app_type, provider, ..) in &targets {
+        let written = undo::backup_live_files(app_type).and_then(|files| {
+            backups.extend(files);
+            write_live_snapshot(app_type, provider)
+        });
+        if let Err(e) = written {
+            log::warn!("切换 {} 失败，回滚所有配置文件: {e}", app_type.as_str());
+            rollback_files(&backups);
+            return Err(e);
+        }
+    }
+
+    let previous: Vec<(AppType, Option<String>)> = targets
+        .iter()
+        .map(|(app_type, ..)| {
+            (
+                app_type.clone(),
+                crate::settings::get_current_provider(app_type),
+            )
+        })
+        .collect();
+    let committed = targets
+        .iter()
+        .try_for_each(|(app_type, provider, ..)| {
+            crate::settings::set_current_provider(app_type, Some(&provider.id))
+        })
+        .and_then(|()| {
+            let switches: Vec<(&str, &str)> = targets
+                .iter()
+                .map(|(app_type, provider, ..)| (app_type.as_str(), provider.id.as_str()))
+                .collect();
+            state.db.record_provider_switches(&switches)
+        });
+    if let Err(e) = committed {
+        log::warn!("记录批量切换失败，回滚所有配置文件: {e}");
+        rollback_settings(&previous);
+        rollback_files(&backups);
+        return Err(e);
+    }
+
+    // The switch is committed; MCP sync only rewrites the MCP sections
+    if let Err(e) = McpService::sync_all_enabled(state) {
+        log::warn!("批量切换后同步 MCP 失败: {e}");
+    }
+    // Undo tracks a single app, so a batch switch supersedes the recorded one
+    undo::forget_switch();
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Undo the last provider switch
+//!
+//! Before a normal-mode switch writes Live config, every affected file is copied to
+//! `<file>.bak`. The switch is then recorded in memory together with hashes of the
+//! written files, so undo can refuse when the configs were modified afterwards.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::app_config::AppType;
+use crate::config::{atomic_write, backup_config_file, delete_file};
+use crate::error::AppError;
+use crate::store::AppState;
+
+use super::live::live_config_paths;
+
+/// Result of undoing a switch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoSwitchResult {
+    pub app_type: String,
+    /// Provider that is current again after the undo
+    pub provider_id: String,
+    /// Live config files restored from backup (or removed if the switch created them)
+    pub restored_files: Vec<String>,
+}
+
+/// Live config file state captured before a switch
+pub(crate) struct FileBackup {
+    path: PathBuf,
+    backup: Option<PathBuf>,
+    backup_hash: Option<[u8; 32]>,
+}
+
+impl FileBackup {
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+struct SwitchedFile {
+    backup: FileBackup,
+    written_hash: Option<[u8; 32]>,
+}
+
+struct LastSwitch {
+    app_type: AppType,
+    previous_id: String,
+    switched_to: String,
+    files: Vec<SwitchedFile>,
+}
+
+static LAST_SWITCH: OnceLock<Mutex<Option<LastSwitch>>> = OnceLock::new();
+
+fn last_switch_cell() -> &'static Mutex<Option<LastSwitch>> {
+    LAST_SWITCH.get_or_init(|| Mutex::new(None))
+}
+
+fn hash_file(path: &PathBuf) -> Option<[u8; 32]> {
+    fs::read(path).ok().map(|data| Sha256::digest(&data).into())
+}
+
+/// Copy the Live config files of an app to `.bak` before they are overwritten
+pub(crate) fn backup_live_files(app_type: &AppType) -> Result<Vec<FileBackup>, AppError> {
+    live_config_paths(app_type)
+        .into_iter()
+        .map(|path| {
+            let backup = backup_config_file(&path)?;
+            let backup_hash = backup.as_ref().and_then(hash_file);
+            Ok(FileBackup {
+                path,
+                backup,
+                backup_hash,
+            })
+        })
+        .collect()
+}
+
+/// Record a completed switch so it can be undone
+pub(crate) fn record_switch(
+    app_type: AppType,
+    previous_id: String,
+    switched_to: String,
+    backups: Vec<FileBackup>,
+) {
+    let files = backups
+        .into_iter()
+        .map(|backup| SwitchedFile {
+            written_hash: hash_file(&backup.path),
+            backup,
+        })
+        .collect();
+    *last_switch_cell().lock().unwrap_or_else(|e| e.into_inner()) = Some(LastSwitch {
+        app_type,
+        previous_id,
+        switched_to,
+        files,
+    });
+}
+
+/// Drop the recorded switch (a later switch without Live writes supersedes it)
+pub(crate) fn forget_switch() {
+    *last_switch_cell().lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Restore the Live config files of the last switch and reset the current provider
+pub(crate) fn undo_last_switch(state: &AppState) -> Result<UndoSwitchResult, AppError> {
+    let mut guard = last_switch_cell().lock().unwrap_or_else(|e| e.into_inner());
+    let last = guard
+        .as_ref()
+        .ok_or_else(|| AppError::Message("没有可撤销的切换".to_string()))?;
+
+    let current = crate::settings::get_effective_current_provider(&state.db, &last.app_type)?;
+    if current.as_deref() != Some(last.switched_to.as_str()) {
+        return Err(AppError::Message(
+            "当前供应商已变更，无法撤销上次切换".to_string(),
+        ));
+    }
+    if !state
+        .db
+        .get_all_providers(last.app_type.as_str())?
+        .contains_key(&last.previous_id)
+    {
+        return Err(AppError::Message(format!(
+            "供应商 {} 已不存在，无法撤销上次切换",
+            last.previous_id
+        )));
+    }
+
+    // Verify nothing touched the files (or their backups) since the switch
+    for file in &last.files {
+        if hash_file(&file.backup.path) != file.written_hash {
+            return Err(AppError::Message(format!(
+                "配置文件 {} 在切换后已被修改，无法撤销",
+                file.backup.path.display()
+            )));
+        }
+        if let Some(backup) = &file.backup.backup {
+            if hash_file(backup) != file.backup.backup_hash {
+                return Err(AppError::Message(format!(
+                    "备份文件 {} 已变更，无法撤销",
+                    backup.display()
+                )));
+            }
+        }
+    }
+
+    // Keep the switched contents so a failure midway can roll the files forward again
+    let written: Vec<Option<Vec<u8>>> = last
+        .files
+        .iter()
+        .map(|file| fs::read(&file.backup.path).ok())
+        .collect();
+
+    let result = restore_files(&last.files).and_then(|restored| {
+        crate::settings::set_current_provider(&last.app_type, Some(&last.previous_id))?;
+        if let Err(e) = state
+            .db
+            .set_current_provider(last.app_type.as_str(), &last.previous_id)
+        {
+            let _ = crate::settings::set_current_provider(&last.app_type, Some(&last.switched_to));
+            return Err(e);
+        }
+        Ok(restored)
+    });
+
+    let restored_files = match result {
+        Ok(restored) => restored,
+        Err(e) => {
+            for (file, contents) in last.files.iter().zip(written) {
+                let rollback = match contents {
+                    Some(data) => atomic_write(&file.backup.path, &data),
+                    None => delete_file(&file.backup.path),
+                };
+                if let Err(rollback_err) = rollback {
+                    log::error!(
+                        "撤销失败后回滚 {} 失败: {rollback_err}",
+                        file.backup.path.display()
+                    );
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    let outcome = UndoSwitchResult {
+        app_type: last.app_type.as_str().to_string(),
+        provider_id: last.previous_id.clone(),
+        restored_files,
+    };
+    *guard = None;
+    Ok(outcome)
+}
+
+fn restore_files(files: &[SwitchedFile]) -> Result<Vec<String>, AppError> {
+    let mut restored = Vec::with_capacity(files.len());
+    for file in files {
+        restore_backup(&file.backup)?;
+        restored.push(file.backup.path.to_string_lossy().to_string());
+    }
+    Ok(restored)
+}
+
+/// Put a file back to its captured state (removed if it did not exist before)
+pub(crate) fn restore_backup(file: &FileBackup) -> Result<(), AppError> {
+    match &file.backup {
+        Some(backup) => {
+            let data = fs::read(backup).map_err(|e| AppError::io(backup, e))?;
+            atomic_write(&file.path, &data)
+        }
+        None => delete_file(&file.path),
+    }
+}
@@ -0,0 +1,118 @@
+//! Read-only validation of a provider set
+//!
+//! Runs the checks a save or switch would run (required fields, base URL normalization,
+//! API key format) without writing anything, so a bulk switch can be blocked up front
+//! instead of failing halfway through.
+
+use serde::Serialize;
+
+use crate::app_config::AppType;
+use crate::provider::Provider;
+use crate::services::api_key::validate_api_key;
+
+use super::base_url::normalize_provider_base_urls;
+use super::verify::live_credentials;
+use super::ProviderService;
+
+/// Validation result for one provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderValidation {
+    pub id: String,
+    /// App the provider belongs to (`None` when the id was not found)
+    pub app: Option<String>,
+    pub valid: bool,
+    /// Problems that would make a switch fail or produce a broken config
+    pub errors: Vec<String>,
+    /// Warnings that do not block a switch
+    pub notes: Vec<String>,
+}
+
+impl ProviderValidation {
+    pub(crate) fn not_found(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            app: None,
+            valid: false,
+            errors: vec![format!("供应商 {id} 不存在")],
+            notes: Vec::new(),
+        }
+    }
+}
+
+/// Check a provider's required fields, base URL(s) and API key format
+pub(crate) fn validate_provider(app_type: &AppType, provider: &Provider) -> ProviderValidation {
+    let mut errors = Vec::new();
+    let mut notes = Vec::new();
+
+    if let Err(e) = ProviderService::validate_provider_settings(app_type, provider) {
+        errors.push(e.to_string());
+    }
+
+    match normalize_provider_base_urls(app_type, &mut provider.clone()) {
+        Ok(url_notes) => notes.extend(url_notes),
+        Err(e) => errors.push(e.to_string()),
+    }
+
+    // Official-login providers carry no key; only a key that is present is checked
+    match live_credentials(app_type, provider) {
+        Ok((_, Some(key))) if !key.trim().is_empty() => {
+            let validation = validate_api_key(app_type, &key);
+            match (validation.valid, validation.reason) {
+                (false, Some(reason)) => errors.push(reason),
+                (true, Some(reason)) => notes.push(reason),
+                _ => {}
+            }
+        }
+        Ok(_) => {}
+        Err(e) => errors.push(e.to_string()),
+    }
+
+    ProviderValidation {
+        id: provider.id.clone(),
+        app: Some(app_type.as_str().to_string()),
+        valid: errors.is_empty(),
+        errors,
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude(base_url: &str, token: &str) -> Provider {
+        Provider::with_id(
+            "p".to_string(),
+            "p".to_string(),
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": base_url,
+                    "ANTHROPIC_AUTH_TOKEN": token
+                }
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn reports_url_and_key_problems() {
+        let ok = validate_provider(&AppType::Claude, &claude("api.example.com", "sk-abc123"));
+        assert!(ok.valid, "{ok:?}");
+        assert_eq!(ok.app.as_deref(), Some("claude"));
+        assert!(!ok.notes.is_empty(), "missing scheme is only a note");
+
+        let bad = validate_provider(&AppType::Claude, &claude("ftp://example.com", "sk a b"));
+        assert!(!bad.valid);
+        assert_eq!(bad.errors.len(), 2, "{bad:?}");
+
+        let codex = Provider::with_id(
+            "c".to_string(),
+            "c".to_string(),
+            json!({ "config": "" }),
+            None,
+        );
+        assert!(!validate_provider(&AppType::Codex, &codex).valid);
+    }
+}
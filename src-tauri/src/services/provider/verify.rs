@@ -0,0 +1,220 @@
+//! Live config drift check
+//!
+//! Compares the base URL, API key and model in the tool's Live config files with what a
+//! switch to the active provider would write. API keys are only reported masked.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+
+use super::gemini_auth::detect_gemini_auth_type;
+use super::live::{
+    claude_live_settings, codex_live_parts, gemini_env_for_provider, read_live_settings,
+};
+use super::overrides::effective_settings;
+
+/// A field whose Live value differs from the active provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDifference {
+    /// `baseUrl`, `apiKey` or `model`
+    pub field: &'static str,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// Result of comparing the Live config with the active provider
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigVerification {
+    pub in_sync: bool,
+    /// Active provider; `None` when the tool has no current provider
+    pub provider_id: Option<String>,
+    /// The proxy takeover rewrites the Live config, so no comparison is made
+    pub proxy_takeover: bool,
+    pub differences: Vec<ConfigDifference>,
+}
+
+/// Env / auth keys carrying the API key, in priority order
+fn api_key_paths(app_type: &AppType) -> &'static [(&'static str, &'static str)] {
+    match app_type {
+        AppType::Claude => &[
+            ("env", "ANTHROPIC_AUTH_TOKEN"),
+            ("env", "ANTHROPIC_API_KEY"),
+        ],
+        AppType::Codex => &[("auth", "OPENAI_API_KEY")],
+        AppType::Gemini => &[("env", "GEMINI_API_KEY"), ("env", "GOOGLE_API_KEY")],
+    }
+}
+
+/// Base URL, API key and model from a Live-shaped config value
+fn live_fields(app_type: &AppType, config: &Value) -> [(&'static str, Option<String>); 3] {
+    let probe = Provider::with_id(String::new(), String::new(), config.clone(), None);
+    let (model, base_url) = effective_settings(app_type, &probe);
+    let api_key = api_key_paths(app_type).iter().find_map(|(section, key)| {
+        config
+            .get(section)?
+            .get(key)?
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_string())
+    });
+    [
+        (
+            "baseUrl",
+            base_url
+                .value
+                .map(|url| url.trim().trim_end_matches('/').to_string()),
+        ),
+        ("apiKey", api_key),
+        ("model", model.value.map(|m| m.trim().to_string())),
+    ]
+}
+
+/// The Live config a switch to `provider` would write, in `read_live_settings` shape
+pub(super) fn expected_live(app_type: &AppType, provider: &Provider) -> Result<Value, AppError> {
+    Ok(match app_type {
+        AppType::Claude => claude_live_settings(provider),
+        AppType::Codex => {
+            let (auth, config) = codex_live_parts(provider)?;
+            json!({ "auth": auth, "config": config })
+        }
+        AppType::Gemini => {
+            let env = gemini_env_for_provider(provider, detect_gemini_auth_type(provider))?;
+            json!({ "env": env })
+        }
+    })
+}
+
+/// Base URL and API key a switch to `provider` would write to the Live config
+pub(super) fn live_credentials(
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<(Option<String>, Option<String>), AppError> {
+    Ok(config_credentials(
+        app_type,
+        &expected_live(app_type, provider)?,
+    ))
+}
+
+/// Base URL and API key in a Live-shaped config value
+pub(super) fn config_credentials(
+    app_type: &AppType,
+    config: &Value,
+) -> (Option<String>, Option<String>) {
+    let [(_, base_url), (_, api_key), _] = live_fields(app_type, config);
+    (base_url, api_key)
+}
+
+/// Show the first and last four characters of a key
+fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() > 8 {
+        let head: String = chars[..4].iter().collect();
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("{head}...{tail}")
+    } else {
+        "***".to_string()
+    }
+}
+
+/// Compare the Live config (`None` when it can't be read) with the active provider
+pub(crate) fn diff_live(
+    app_type: &AppType,
+    provider: &Provider,
+    live: Option<&Value>,
+) -> Result<Vec<ConfigDifference>, AppError> {
+    let expected = live_fields(app_type, &expected_live(app_type, provider)?);
+    let actual = live_fields(app_type, live.unwrap_or(&Value::Null));
+    Ok(expected
+        .into_iter()
+        .zip(actual)
+        .filter(|((_, expected), (_, actual))| expected != actual)
+        .map(|((field, expected), (_, actual))| {
+            let redact = |value: Option<String>| match field {
+                "apiKey" => value.map(|key| mask_key(&key)),
+                _ => value,
+            };
+            ConfigDifference {
+                field,
+                expected: redact(expected),
+                actual: redact(actual),
+            }
+        })
+        .collect())
+}
+
+/// Read the Live config and compare it with `provider`
+pub(crate) fn verify_live(
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<Vec<ConfigDifference>, AppError> {
+    let live = match read_live_settings(app_type.clone()) {
+        Ok(live) => Some(live),
+        Err(e) => {
+            log::debug!("读取 {} Live 配置失败: {e}", app_type.as_str());
+            None
+        }
+    };
+    diff_live(app_type, provider, live.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claude_provider() -> Provider {
+        Provider::with_id(
+            "p1".into(),
+            "Relay".into(),
+            json!({"env": {
+                "ANTHROPIC_BASE_URL": "https://relay.example/",
+                "ANTHROPIC_AUTH_TOKEN": "sk-relay-0123456789",
+                "ANTHROPIC_MODEL": "claude-sonnet"
+            }}),
+            None,
+        )
+    }
+
+    #[test]
+    fn matching_live_config_has_no_differences() {
+        let provider = claude_provider();
+        let live = json!({"env": {
+            "ANTHROPIC_BASE_URL": "https://relay.example",
+            "ANTHROPIC_AUTH_TOKEN": "sk-relay-0123456789",
+            "ANTHROPIC_MODEL": "claude-sonnet"
+        }, "permissions": {}});
+        let diffs = diff_live(&AppType::Claude, &provider, Some(&live)).unwrap();
+        assert!(diffs.is_empty(), "{diffs:?}");
+    }
+
+    #[test]
+    fn reports_drift_with_masked_keys() {
+        let provider = claude_provider();
+        let live = json!({"env": {
+            "ANTHROPIC_BASE_URL": "https://other.example",
+            "ANTHROPIC_AUTH_TOKEN": "sk-other-9876543210",
+            "ANTHROPIC_MODEL": "claude-sonnet"
+        }});
+        let diffs = diff_live(&AppType::Claude, &provider, Some(&live)).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].field, "baseUrl");
+        assert_eq!(diffs[0].actual.as_deref(), Some("https://other.example"));
+        assert_eq!(diffs[1].field, "apiKey");
+        assert_eq!(diffs[1].expected.as_deref(), Some("sk-r...6789"));
+        assert_eq!(diffs[1].actual.as_deref(), Some("sk-o...3210"));
+    }
+
+    #[test]
+    fn missing_live_config_reports_every_field() {
+        let diffs = diff_live(&AppType::Claude, &claude_provider(), None).unwrap();
+        assert_eq!(
+            diffs.iter().map(|d| d.field).collect::<Vec<_>>(),
+            ["baseUrl", "apiKey", "model"]
+        );
+        assert!(diffs.iter().all(|d| d.actual.is_none()));
+    }
+}
@@ -1369,7 +1369,7 @@ impl ProxyService {
     // ==================== Live 配置读写辅助方法 ====================
 
     /// 更新 TOML 字符串中的 base_url
-    fn update_toml_base_url(toml_str: &str, new_url: &str) -> String {
+    pub(crate) fn update_toml_base_url(toml_str: &str, new_url: &str) -> String {
         use toml_edit::DocumentMut;
 
         let mut doc = match toml_str.parse::<DocumentMut>() {
@@ -0,0 +1,152 @@
+//! npm registry 与 GitHub API 的限流退避
+//!
+//! 收到 `429` 或 `403` + `X-RateLimit-Remaining: 0` 时，按 `Retry-After` / `X-RateLimit-Reset`
+//! 计算解除时间并写入设置文件（重启后仍生效），在此之前跳过对该服务的远程查询，
+//! 由调用方回退到缓存值并标记 `rate_limited`。
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+/// npm registry（版本查询）
+pub const NPM: &str = "npm";
+/// GitHub API（应用版本检查）
+pub const GITHUB: &str = "github";
+
+/// 响应未给出解除时间时的默认退避时长（秒）
+const DEFAULT_BACKOFF_SECS: i64 = 60;
+/// 退避时长上限（秒），避免异常的响应头导致长时间不再检查
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+/// 根据响应判断是否被限流，返回解除限流的 Unix 时间戳（秒）
+pub fn backoff_until(status: StatusCode, headers: &HeaderMap, now: i64) -> Option<i64> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+    let limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && header("x-ratelimit-remaining") == Some("0"));
+    if !limited {
+        return None;
+    }
+
+    let retry_after = header("retry-after").and_then(|value| match value.parse::<i64>() {
+        Ok(secs) => Some(now + secs),
+        Err(_) => chrono::DateTime::parse_from_rfc2822(value)
+            .ok()
+            .map(|t| t.timestamp()),
+    });
+    let reset = header("x-ratelimit-reset").and_then(|v| v.parse::<i64>().ok());
+    let until = retry_after
+        .or(reset)
+        .unwrap_or(now + DEFAULT_BACKOFF_SECS)
+        .clamp(now + 1, now + MAX_BACKOFF_SECS);
+    Some(until)
+}
+
+/// 处于退避期时返回解除时间
+pub fn limited_until(service: &str) -> Option<i64> {
+    let until = *crate::settings::get_settings()
+        .rate_limited_until
+        .get(service)?;
+    (until > chrono::Utc::now().timestamp()).then_some(until)
+}
+
+/// 退避期内的错误信息
+pub fn limited_message(service: &str, until: i64) -> String {
+    let time = chrono::DateTime::from_timestamp(until, 0)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    format!("{service} 请求过于频繁，已暂停远程查询至 {time}")
+}
+
+/// 退避期内返回错误，调用方据此跳过远程请求
+pub fn ensure_allowed(service: &str) -> Result<(), String> {
+    match limited_until(service) {
+        Some(until) => Err(limited_message(service, until)),
+        None => Ok(()),
+    }
+}
+
+/// 检查响应：被限流时记录退避并返回错误
+pub fn check_response(service: &str, response: &reqwest::Response) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let Some(until) = backoff_until(response.status(), response.headers(), now) else {
+        return Ok(());
+    };
+    log::warn!(
+        "{service} 限流（HTTP {}），{} 秒内跳过远程查询",
+        response.status(),
+        until - now
+    );
+    if let Err(e) = crate::settings::set_rate_limited_until(service, until) {
+        log::warn!("保存 {service} 限流状态失败: {e}");
+    }
+    Err(limited_message(service, until))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn reads_retry_after_and_reset_headers() {
+        let now = 1_000_000;
+        let h = headers(&[("retry-after", "120")]);
+        assert_eq!(
+            backoff_until(StatusCode::TOO_MANY_REQUESTS, &h, now),
+            Some(now + 120)
+        );
+
+        let h = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1000300"),
+        ]);
+        assert_eq!(
+            backoff_until(StatusCode::FORBIDDEN, &h, now),
+            Some(1_000_300)
+        );
+
+        let h = headers(&[("retry-after", "Thu, 01 Jan 1970 00:16:50 GMT")]);
+        assert_eq!(
+            backoff_until(StatusCode::TOO_MANY_REQUESTS, &h, 1_000),
+            Some(1_010)
+        );
+    }
+
+    #[test]
+    fn ignores_other_errors_and_clamps_backoff() {
+        let now = 1_000_000;
+        assert_eq!(
+            backoff_until(StatusCode::FORBIDDEN, &HeaderMap::new(), now),
+            None
+        );
+        assert_eq!(
+            backoff_until(StatusCode::INTERNAL_SERVER_ERROR, &HeaderMap::new(), now),
+            None
+        );
+        assert_eq!(
+            backoff_until(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), now),
+            Some(now + DEFAULT_BACKOFF_SECS)
+        );
+        let h = headers(&[("retry-after", "999999")]);
+        assert_eq!(
+            backoff_until(StatusCode::TOO_MANY_REQUESTS, &h, now),
+            Some(now + MAX_BACKOFF_SECS)
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! 应用自身的版本检查（GitHub Releases）
+
+use crate::services::rate_limit;
+use crate::services::retry::{self, RequestError};
+use crate::settings::UpdateChannel;
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// GitHub 最新正式版 API
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/farion1231/cc-switch/releases/latest";
+
+/// GitHub Release 列表 API（包含预发布版本）
+const RELEASES_URL: &str = "https://api.github.com/repos/farion1231/cc-switch/releases?per_page=20";
+
+/// GitHub API 请求超时时间
+const RELEASE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 版本信息的内存缓存时长，避免频繁请求触发 GitHub 限流
+const RELEASE_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// GitHub API 限流时返回的错误标识，前端可据此静默忽略
+pub const RELEASE_RATE_LIMITED: &str = "github_rate_limited";
+
+/// 最新版本信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseInfo {
+    /// 最新版本号（已去掉 `v` 前缀）
+    pub latest: String,
+    /// 当前运行的版本号
+    pub current: String,
+    pub update_available: bool,
+    pub html_url: String,
+    /// 更新日志（Markdown）
+    pub body: Option<String>,
+    /// 发布时间（RFC 3339）
+    pub published_at: Option<String>,
+    pub assets: Vec<ReleaseAsset>,
+    /// GitHub API 限流中，结果来自上次缓存
+    pub rate_limited: bool,
+}
+
+/// Release 附带的安装包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseAsset {
+    pub name: String,
+    #[serde(alias = "browser_download_url")]
+    pub browser_download_url: String,
+    pub size: u64,
+    /// GitHub 提供的文件摘要（形如 `sha256:…`），旧版本的附件没有该字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl ReleaseAsset {
+    /// 摘要中的 SHA-256（小写十六进制）
+    pub fn sha256(&self) -> Option<String> {
+        self.digest
+            .as_deref()?
+            .strip_prefix("sha256:")
+            .map(str::to_ascii_lowercase)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    html_url: String,
+    body: Option<String>,
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+type ReleaseCache = Option<(UpdateChannel, Instant, ReleaseInfo)>;
+
+static RELEASE_CACHE: OnceLock<RwLock<ReleaseCache>> = OnceLock::new();
+
+fn cache_cell() -> &'static RwLock<ReleaseCache> {
+    RELEASE_CACHE.get_or_init(|| RwLock::new(None))
+}
+
+fn cached_release(channel: UpdateChannel) -> Option<ReleaseInfo> {
+    let guard = cache_cell().read().ok()?;
+    let (cached_channel, fetched_at, info) = guard.as_ref()?;
+    (*cached_channel == channel && fetched_at.elapsed() < RELEASE_CACHE_TTL).then(|| info.clone())
+}
+
+/// 限流时的回退：返回上次缓存（忽略有效期）并标记 `rate_limited`，无缓存时返回
+/// [`RELEASE_RATE_LIMITED`]
+fn rate_limited_fallback(channel: UpdateChannel) -> Result<ReleaseInfo, String> {
+    let guard = cache_cell()
+        .read()
+        .map_err(|_| RELEASE_RATE_LIMITED.to_string())?;
+    match guard.as_ref() {
+        Some((cached_channel, _, info)) if *cached_channel == channel => Ok(ReleaseInfo {
+            rate_limited: true,
+            ..info.clone()
+        }),
+        _ => Err(RELEASE_RATE_LIMITED.to_string()),
+    }
+}
+
+fn parse_version(tag: &str) -> Option<semver::Version> {
+    semver::Version::parse(tag.trim().trim_start_matches('v')).ok()
+}
+
+/// 查询 GitHub 最新版本并与当前版本比较
+///
+/// `stable` 通道查询最新正式版；`beta` 通道从 Release 列表中按 semver 选出最新版本（含预发布）。
+/// 结果在内存中缓存 10 分钟；遇到限流（403 / 429）时记录退避时间，退避期内不再请求，
+/// 回退到上次缓存的结果（`rate_limited` 为 true），无缓存时返回 [`RELEASE_RATE_LIMITED`]。
+pub async fn fetch_latest_release(
+    client: &reqwest::Client,
+    channel: UpdateChannel,
+) -> Result<ReleaseInfo, String> {
+    if let Some(info) = cached_release(channel) {
+        return Ok(info);
+    }
+    if rate_limit::limited_until(rate_limit::GITHUB).is_some() {
+        return rate_limited_fallback(channel);
+    }
+
+    let url = match channel {
+        UpdateChannel::Stable => LATEST_RELEASE_URL,
+        UpdateChannel::Beta => RELEASES_URL,
+    };
+    let response = retry::retry_with_backoff("查询最新版本", &retry::DEFAULT_POLICY, || async {
+        let response = client
+            .get(url)
+            .header("Accept", "application/vnd.github+json")
+            .timeout(RELEASE_REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| RequestError::from_reqwest("查询最新版本失败", &e))?;
+        RequestError::check_server_error("查询最新版本失败", response)
+    })
+    .await?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        log::warn!("GitHub API 限流（HTTP {status}），跳过版本检查");
+        let _ = rate_limit::check_response(rate_limit::GITHUB, &response);
+        return rate_limited_fallback(channel);
+    }
+    if !status.is_success() {
+        return Err(format!("查询最新版本失败: HTTP {status}"));
+    }
+
+    let release: GithubRelease = match channel {
+        UpdateChannel::Stable => response
+            .json()
+            .await
+            .map_err(|e| format!("解析版本信息失败: {e}"))?,
+        UpdateChannel::Beta => {
+            let releases: Vec<GithubRelease> = response
+                .json()
+                .await
+                .map_err(|e| format!("解析版本信息失败: {e}"))?;
+            pick_newest_release(releases).ok_or_else(|| "未找到可用的版本".to_string())?
+        }
+    };
+
+    let current = env!("CARGO_PKG_VERSION").to_string();
+    let latest = release.tag_name.trim().trim_start_matches('v').to_string();
+    let update_available =
+        super::cli_tools::compute_update_available(Some(&current), Some(&latest)).unwrap_or(false);
+
+    let info = ReleaseInfo {
+        latest,
+        current,
+        update_available,
+        html_url: release.html_url,
+        body: release.body,
+        published_at: release.published_at,
+        assets: release.assets,
+        rate_limited: false,
+    };
+    if let Ok(mut guard) = cache_cell().write() {
+        *guard = Some((channel, Instant::now(), info.clone()));
+    }
+    Ok(info)
+}
+
+/// 各平台可直接安装的安装包扩展名（按优先级）
+fn installer_extensions(os: &str) -> &'static [&'static str] {
+    match os {
+        "windows" => &[".msi", ".exe"],
+        "macos" => &[".dmg"],
+        "linux" => &[".appimage", ".deb", ".rpm"],
+        _ => &[],
+    }
+}
+
+/// 文件名中可能出现的架构标识（`x86_64` 已预先替换为 `x64`）
+const ARCH_TOKENS: [&str; 7] = ["x64", "amd64", "aarch64", "arm64", "x86", "i686", "i386"];
+
+fn arch_aliases(arch: &str) -> &'static [&'static str] {
+    match arch {
+        "x86_64" => &["x64", "amd64"],
+        "aarch64" => &["aarch64", "arm64"],
+        "x86" => &["x86", "i686", "i386"],
+        _ => &[],
+    }
+}
+
+/// 从 Release 附件中选出适用于指定系统与架构的安装包
+///
+/// 按扩展名优先级查找；文件名标注了其它架构的附件被排除，标注当前架构（或 `universal`）
+/// 的附件优先于未标注架构的。
+pub fn select_platform_asset<'a>(
+    assets: &'a [ReleaseAsset],
+    os: &str,
+    arch: &str,
+) -> Option<&'a ReleaseAsset> {
+    let own_arch = arch_aliases(arch);
+    installer_extensions(os).iter().find_map(|ext| {
+        assets
+            .iter()
+            .filter_map(|asset| {
+                let name = asset.name.to_ascii_lowercase();
+                let stem = name.strip_suffix(ext)?.replace("x86_64", "x64");
+                let tokens: Vec<&str> = stem.split(['-', '_', '.', ' ']).collect();
+                let rank = if tokens
+                    .iter()
+                    .any(|t| own_arch.contains(t) || *t == "universal")
+                {
+                    2
+                } else if tokens.iter().any(|t| ARCH_TOKENS.contains(t)) {
+                    return None;
+                } else {
+                    1
+                };
+                Some((rank, asset))
+            })
+            .min_by_key(|(rank, _)| std::cmp::Reverse(*rank))
+            .map(|(_, asset)| asset)
+    })
+}
+
+/// 当前平台的安装包
+pub fn platform_asset(assets: &[ReleaseAsset]) -> Option<&ReleaseAsset> {
+    select_platform_asset(assets, std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// 从 Release 列表中选出 semver 最大的非草稿版本（预发布低于同号正式版）
+fn pick_newest_release(releases: Vec<GithubRelease>) -> Option<GithubRelease> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter_map(|r| parse_version(&r.tag_name).map(|v| (v, r)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, r)| r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, draft: bool) -> GithubRelease {
+        GithubRelease {
+            tag_name: tag.to_string(),
+            draft,
+            html_url: String::new(),
+            body: None,
+            published_at: None,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn beta_channel_picks_highest_semver_excluding_drafts() {
+        let picked = pick_newest_release(vec![
+            release("v1.1.0", false),
+            release("v1.3.0", true),
+            release("v1.2.0-beta.2", false),
+            release("v1.2.0-beta.10", false),
+        ])
+        .expect("should pick a release");
+        assert_eq!(picked.tag_name, "v1.2.0-beta.10");
+    }
+
+    fn assets(names: &[&str]) -> Vec<ReleaseAsset> {
+        names
+            .iter()
+            .map(|name| ReleaseAsset {
+                name: name.to_string(),
+                browser_download_url: format!("https://github.com/x/y/releases/download/v1/{name}"),
+                size: 1,
+                digest: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selects_asset_for_os_and_arch() {
+        let all = assets(&[
+            "CC-Switch_3.5.0_x64_en-US.msi",
+            "CC-Switch_3.5.0_arm64_en-US.msi",
+            "CC-Switch_3.5.0_x64-setup.exe",
+            "CC-Switch_3.5.0_aarch64.dmg",
+            "CC-Switch_3.5.0_x64.dmg",
+            "CC-Switch_3.5.0_x64.app.tar.gz.sig",
+            "cc-switch_3.5.0_amd64.deb",
+            "cc-switch_3.5.0_amd64.AppImage",
+        ]);
+        let pick = |os, arch| select_platform_asset(&all, os, arch).map(|a| a.name.as_str());
+        assert_eq!(
+            pick("windows", "x86_64"),
+            Some("CC-Switch_3.5.0_x64_en-US.msi")
+        );
+        assert_eq!(
+            pick("windows", "aarch64"),
+            Some("CC-Switch_3.5.0_arm64_en-US.msi")
+        );
+        assert_eq!(
+            pick("macos", "aarch64"),
+            Some("CC-Switch_3.5.0_aarch64.dmg")
+        );
+        assert_eq!(
+            pick("linux", "x86_64"),
+            Some("cc-switch_3.5.0_amd64.AppImage")
+        );
+        assert_eq!(pick("linux", "aarch64"), None);
+    }
+
+    #[test]
+    fn prefers_matching_arch_over_unlabelled_asset() {
+        let all = assets(&["CC-Switch-Windows.msi", "CC-Switch-Windows-x86_64.msi"]);
+        let picked = select_platform_asset(&all, "windows", "x86_64").unwrap();
+        assert_eq!(picked.name, "CC-Switch-Windows-x86_64.msi");
+        let picked = select_platform_asset(&all, "windows", "aarch64").unwrap();
+        assert_eq!(picked.name, "CC-Switch-Windows.msi");
+    }
+
+    #[test]
+    fn beta_channel_prefers_release_over_same_prerelease() {
+        let picked = pick_newest_release(vec![
+            release("v1.2.0-rc.1", false),
+            release("v1.2.0", false),
+        ])
+        .expect("should pick a release");
+        assert_eq!(picked.tag_name, "v1.2.0");
+    }
+}
@@ -0,0 +1,248 @@
+//! 网络请求的重试与指数退避
+//!
+//! 仅在超时、连接失败与 5xx 时重试，4xx（含限流）直接返回。两次尝试之间按指数退避并加入
+//! 随机抖动；超过总耗时上限后不再发起新的尝试，避免弱网下长时间卡住。
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// 请求失败的类别，决定是否重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Timeout,
+    Connect,
+    /// HTTP 5xx
+    ServerError,
+    /// HTTP 4xx
+    ClientError,
+    /// 响应解析失败等其它错误
+    Other,
+}
+
+impl FailureKind {
+    /// 按响应状态码分类（非错误状态返回 `None`）
+    pub fn of_status(status: reqwest::StatusCode) -> Option<Self> {
+        if status.is_server_error() {
+            Some(Self::ServerError)
+        } else if status.is_client_error() {
+            Some(Self::ClientError)
+        } else {
+            None
+        }
+    }
+
+    pub fn of_error(err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::Connect
+        } else if let Some(kind) = err.status().and_then(Self::of_status) {
+            kind
+        } else if err.is_request() {
+            // 请求发送中断（连接被重置等），与连接失败同样视为暂时性错误
+            Self::Connect
+        } else {
+            Self::Other
+        }
+    }
+
+    /// 是否为暂时性错误（值得重试）
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::Timeout | Self::Connect | Self::ServerError)
+    }
+}
+
+/// 带失败类别的请求错误
+#[derive(Debug, Clone)]
+pub struct RequestError {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// 不重试的错误
+    pub fn other(message: impl Into<String>) -> Self {
+        Self::new(FailureKind::Other, message)
+    }
+
+    pub fn from_reqwest(context: &str, err: &reqwest::Error) -> Self {
+        Self::new(FailureKind::of_error(err), format!("{context}: {err}"))
+    }
+
+    /// 5xx 响应转换为可重试的错误，其它状态原样放行
+    pub fn check_server_error(
+        context: &str,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, Self> {
+        let status = response.status();
+        if status.is_server_error() {
+            return Err(Self::new(
+                FailureKind::ServerError,
+                format!("{context}: HTTP {status}"),
+            ));
+        }
+        Ok(response)
+    }
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<RequestError> for String {
+    fn from(err: RequestError) -> Self {
+        err.message
+    }
+}
+
+/// 重试策略
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（含首次）
+    pub max_attempts: u32,
+    /// 首次重试前的基础等待时间，之后每次翻倍
+    pub base_delay: Duration,
+    /// 总耗时上限：超过后不再发起新的尝试
+    pub max_elapsed: Duration,
+}
+
+/// 版本查询等轻量请求的默认策略：最多 3 次，退避约 0.5s / 1s
+pub const DEFAULT_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    base_delay: Duration::from_millis(500),
+    max_elapsed: Duration::from_secs(12),
+};
+
+/// 第 `attempt` 次失败后的等待时间，`jitter` 取值 [0, 1)，实际等待为基准值的 50%–150%
+fn backoff_delay(policy: &RetryPolicy, attempt: u32, jitter: f64) -> Duration {
+    let exponential = policy.base_delay * 2u32.saturating_pow(attempt.saturating_sub(1));
+    exponential.mul_f64(0.5 + jitter.clamp(0.0, 1.0))
+}
+
+/// [0, 1) 的随机抖动（取系统时间的纳秒部分，足以错开并发请求）
+fn jitter() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    f64::from(nanos % 1000) / 1000.0
+}
+
+/// 执行 `op`，遇到暂时性错误时按策略退避重试，返回最后一次的结果
+pub async fn retry_with_backoff<T, F, Fut>(
+    label: &str,
+    policy: &RetryPolicy,
+    mut op: F,
+) -> Result<T, RequestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RequestError>>,
+{
+    let started_at = Instant::now();
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        if attempt >= policy.max_attempts || !err.kind.is_retryable() {
+            return Err(err);
+        }
+        let delay = backoff_delay(policy, attempt, jitter());
+        if started_at.elapsed() + delay > policy.max_elapsed {
+            return Err(err);
+        }
+        log::debug!(
+            "{label}失败（{}），{}ms 后重试（第 {attempt} 次）",
+            err.message,
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use std::cell::Cell;
+
+    const FAST: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(1),
+        max_elapsed: Duration::from_secs(5),
+    };
+
+    #[test]
+    fn retries_only_transient_failures() {
+        assert!(FailureKind::Timeout.is_retryable());
+        assert!(FailureKind::Connect.is_retryable());
+        assert!(FailureKind::ServerError.is_retryable());
+        assert!(!FailureKind::ClientError.is_retryable());
+        assert!(!FailureKind::Other.is_retryable());
+
+        assert_eq!(
+            FailureKind::of_status(StatusCode::BAD_GATEWAY),
+            Some(FailureKind::ServerError)
+        );
+        assert_eq!(
+            FailureKind::of_status(StatusCode::TOO_MANY_REQUESTS),
+            Some(FailureKind::ClientError)
+        );
+        assert_eq!(FailureKind::of_status(StatusCode::OK), None);
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_within_jitter_bounds() {
+        let policy = DEFAULT_POLICY;
+        assert_eq!(backoff_delay(&policy, 1, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(&policy, 2, 0.5), Duration::from_millis(1000));
+        assert!(backoff_delay(&policy, 2, 0.999) < Duration::from_millis(1500));
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_attempts_or_on_client_error() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry_with_backoff("测试", &FAST, || {
+            calls.set(calls.get() + 1);
+            async { Err(RequestError::new(FailureKind::Timeout, "timeout")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 3);
+
+        calls.set(0);
+        let result: Result<(), _> = retry_with_backoff("测试", &FAST, || {
+            calls.set(calls.get() + 1);
+            async { Err(RequestError::new(FailureKind::ClientError, "404")) }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().kind, FailureKind::ClientError);
+        assert_eq!(calls.get(), 1);
+
+        calls.set(0);
+        let result = retry_with_backoff("测试", &FAST, || {
+            calls.set(calls.get() + 1);
+            let n = calls.get();
+            async move {
+                if n < 2 {
+                    Err(RequestError::new(FailureKind::ServerError, "502"))
+                } else {
+                    Ok(n)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+}
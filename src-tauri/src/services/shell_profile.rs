@@ -0,0 +1,182 @@
+//! 用户 Shell 与启动配置文件检测
+//!
+//! 根据 `$SHELL` 判断用户使用的 Shell，并按各 Shell 的惯例找到写入环境变量应修改的配置文件。
+//! bash 在 macOS 终端中默认以登录 Shell 启动（读取 `~/.bash_profile`），在 Linux 中通常为
+//! 非登录交互 Shell（读取 `~/.bashrc`）。
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// 检测到的 Shell 及其配置文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellProfile {
+    /// Shell 名称（如 `zsh`、`bash`、`fish`、`powershell`）
+    pub shell: String,
+    /// 应修改的配置文件路径
+    pub profile_path: String,
+    /// 配置文件是否已存在
+    pub exists: bool,
+    /// 该文件是否仅在登录 Shell 中读取
+    pub login_shell: bool,
+}
+
+/// 按 Shell 路径与环境推断配置文件（`env` 读取环境变量，`exists` 判断文件是否存在）
+fn resolve_profile(
+    shell_path: Option<&str>,
+    home: &Path,
+    is_macos: bool,
+    env: impl Fn(&str) -> Option<String>,
+    exists: impl Fn(&Path) -> bool,
+) -> ShellProfile {
+    let shell = shell_path
+        .map(|path| {
+            path.rsplit(['/', '\\'])
+                .next()
+                .unwrap_or(path)
+                .trim_end_matches(".exe")
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "windows") {
+                "powershell".to_string()
+            } else if is_macos {
+                // macOS 10.15 起默认 Shell 为 zsh
+                "zsh".to_string()
+            } else {
+                "bash".to_string()
+            }
+        });
+    let env_dir = |key: &str| env(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    let (profile_path, login_shell) = match shell.as_str() {
+        "zsh" => (
+            env_dir("ZDOTDIR")
+                .unwrap_or_else(|| home.to_path_buf())
+                .join(".zshrc"),
+            false,
+        ),
+        "bash" if is_macos => {
+            // 登录 Shell 只读取以下文件中第一个存在的
+            let candidates = [".bash_profile", ".bash_login", ".profile"].map(|f| home.join(f));
+            let path = candidates
+                .iter()
+                .find(|p| exists(p))
+                .unwrap_or(&candidates[0])
+                .clone();
+            (path, true)
+        }
+        "bash" => (home.join(".bashrc"), false),
+        "fish" => (
+            env_dir("XDG_CONFIG_HOME")
+                .unwrap_or_else(|| home.join(".config"))
+                .join("fish")
+                .join("config.fish"),
+            false,
+        ),
+        "tcsh" => (home.join(".tcshrc"), false),
+        "csh" => (home.join(".cshrc"), false),
+        "pwsh" | "powershell" => {
+            let dir = if shell == "pwsh" || !cfg!(target_os = "windows") {
+                "PowerShell"
+            } else {
+                "WindowsPowerShell"
+            };
+            let base = if cfg!(target_os = "windows") {
+                home.join("Documents").join(dir)
+            } else {
+                env_dir("XDG_CONFIG_HOME")
+                    .unwrap_or_else(|| home.join(".config"))
+                    .join("powershell")
+            };
+            (base.join("Microsoft.PowerShell_profile.ps1"), false)
+        }
+        // sh / dash / ksh 等 POSIX Shell 仅在登录时读取 ~/.profile
+        _ => (home.join(".profile"), true),
+    };
+
+    ShellProfile {
+        shell,
+        exists: exists(&profile_path),
+        profile_path: profile_path.to_string_lossy().to_string(),
+        login_shell,
+    }
+}
+
+/// 检测当前用户的 Shell 及其配置文件
+pub fn detect_user_shell() -> Result<ShellProfile, String> {
+    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+    let shell = std::env::var("SHELL").ok();
+    Ok(resolve_profile(
+        shell.as_deref(),
+        &home,
+        cfg!(target_os = "macos"),
+        |key| std::env::var(key).ok(),
+        Path::is_file,
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn resolve(
+        shell: &str,
+        is_macos: bool,
+        vars: &[(&str, &str)],
+        existing: &[&str],
+    ) -> ShellProfile {
+        resolve_profile(
+            Some(shell),
+            Path::new("/home/u"),
+            is_macos,
+            |key| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            },
+            |path| existing.iter().any(|e| Path::new(e) == path),
+        )
+    }
+
+    #[test]
+    fn maps_shells_to_conventional_profiles() {
+        let zsh = resolve("/bin/zsh", true, &[], &["/home/u/.zshrc"]);
+        assert_eq!(zsh.shell, "zsh");
+        assert_eq!(zsh.profile_path, "/home/u/.zshrc");
+        assert!(zsh.exists);
+        assert!(!zsh.login_shell);
+
+        let zsh = resolve("/usr/bin/zsh", false, &[("ZDOTDIR", "/home/u/.zsh")], &[]);
+        assert_eq!(zsh.profile_path, "/home/u/.zsh/.zshrc");
+        assert!(!zsh.exists);
+
+        let fish = resolve("/opt/homebrew/bin/fish", true, &[], &[]);
+        assert_eq!(fish.profile_path, "/home/u/.config/fish/config.fish");
+        let fish = resolve("/usr/bin/fish", false, &[("XDG_CONFIG_HOME", "/x")], &[]);
+        assert_eq!(fish.profile_path, "/x/fish/config.fish");
+
+        let sh = resolve("/bin/dash", false, &[], &[]);
+        assert_eq!(sh.profile_path, "/home/u/.profile");
+        assert!(sh.login_shell);
+    }
+
+    #[test]
+    fn bash_profile_depends_on_login_shell_convention() {
+        let linux = resolve("/bin/bash", false, &[], &[]);
+        assert_eq!(linux.profile_path, "/home/u/.bashrc");
+        assert!(!linux.login_shell);
+
+        let mac = resolve("/bin/bash", true, &[], &[]);
+        assert_eq!(mac.profile_path, "/home/u/.bash_profile");
+        assert!(mac.login_shell);
+        assert!(!mac.exists);
+
+        // 没有 .bash_profile 时 bash 登录 Shell 读取 .profile
+        let mac = resolve("/bin/bash", true, &[], &["/home/u/.profile"]);
+        assert_eq!(mac.profile_path, "/home/u/.profile");
+        assert!(mac.exists);
+    }
+}
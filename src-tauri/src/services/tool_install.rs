@@ -0,0 +1,301 @@
+//! 受管理 CLI 的安装与更新
+//!
+//! 按检测到的安装方式选择包管理器（pnpm / yarn，其余使用 npm）执行全局安装，
+//! 输出逐行回调给调用方（命令层转为前端事件），完成后重新检测本地版本。
+//! 指定版本即可回退到旧版本。
+
+use std::collections::HashSet;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::services::cli_tools::{self, ManagedTool};
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 安装失败时错误信息中保留的输出行数
+const ERROR_TAIL_LINES: usize = 8;
+
+static INSTALLING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn installing() -> &'static Mutex<HashSet<String>> {
+    INSTALLING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// 安装进行期间持有，释放时允许再次安装同一工具
+struct InstallGuard {
+    tool: String,
+}
+
+impl InstallGuard {
+    fn acquire(tool: &str) -> Result<Self, String> {
+        let mut set = installing().lock().unwrap_or_else(|e| e.into_inner());
+        if !set.insert(tool.to_string()) {
+            return Err(format!("{tool} 正在安装中，请等待当前安装完成"));
+        }
+        Ok(Self {
+            tool: tool.to_string(),
+        })
+    }
+}
+
+impl Drop for InstallGuard {
+    fn drop(&mut self) {
+        installing()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.tool);
+    }
+}
+
+/// 安装过程中的一行输出（`tool-install-output` 事件载荷）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallOutputLine {
+    pub tool: String,
+    /// `stdout` / `stderr`
+    pub stream: &'static str,
+    pub line: String,
+}
+
+/// 安装结束的结果（`tool-install-done` 事件载荷）
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallDone {
+    pub tool: String,
+    pub success: bool,
+    /// 包管理器的退出码；未能启动或被信号终止时为 `None`
+    pub exit_code: Option<i32>,
+    /// 安装后检测到的版本
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+impl InstallDone {
+    fn failed(tool: &str, exit_code: Option<i32>, error: impl Into<String>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            exit_code,
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 校验并规范化要安装的版本号（去掉 `v` 前缀）
+///
+/// 版本号会拼入包管理器命令行（Windows 上经 cmd 解析），只接受合法的 semver。
+pub fn normalize_version(version: &str) -> Result<String, String> {
+    let trimmed = version.trim().trim_start_matches('v');
+    semver::Version::parse(trimmed)
+        .map(|v| v.to_string())
+        .map_err(|_| format!("无效的版本号: {version}"))
+}
+
+/// 按安装方式选择包管理器及参数；未指定版本时安装 `latest`
+fn install_args(
+    install_method: Option<&str>,
+    npm_package: &str,
+    version: Option<&str>,
+) -> (&'static str, Vec<String>) {
+    let spec = format!("{npm_package}@{}", version.unwrap_or("latest"));
+    match install_method {
+        Some("pnpm") => ("pnpm", vec!["add".into(), "-g".into(), spec]),
+        Some("yarn") => ("yarn", vec!["global".into(), "add".into(), spec]),
+        _ => ("npm", vec!["install".into(), "-g".into(), spec]),
+    }
+}
+
+/// 构建安装命令；Windows 上包管理器是 `.cmd` shim，需经 cmd 执行
+fn build_command(program: &str, args: &[String]) -> Command {
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C")
+            .arg(program)
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW);
+        cmd
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        cmd
+    };
+
+    // npm / pnpm / yarn v1 均读取该变量，与版本查询使用同一 registry
+    cmd.env("npm_config_registry", crate::settings::get_npm_registry())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    cmd
+}
+
+/// 在阻塞线程中检测本地版本
+async fn detect_local_version(command: &'static str) -> cli_tools::LocalToolVersion {
+    tauri::async_runtime::spawn_blocking(move || cli_tools::detect_local_version(command))
+        .await
+        .unwrap_or_default()
+}
+
+/// 逐行读取输出并回调，同时保留最后几行用于错误信息
+async fn forward_lines(
+    reader: Option<impl AsyncRead + Unpin>,
+    stream: &'static str,
+    tool: &str,
+    tail: &Mutex<Vec<String>>,
+    on_output: &(impl Fn(InstallOutputLine) + Send + Sync),
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        {
+            let mut tail = tail.lock().unwrap_or_else(|e| e.into_inner());
+            tail.push(line.clone());
+            if tail.len() > ERROR_TAIL_LINES {
+                tail.remove(0);
+            }
+        }
+        on_output(InstallOutputLine {
+            tool: tool.to_string(),
+            stream,
+            line,
+        });
+    }
+}
+
+/// 安装、更新或回退 CLI，结束后重新检测版本
+///
+/// `version` 为 `None` 时安装最新版，否则须为 [`normalize_version`] 校验过的版本号。
+/// `on_output` 逐行接收安装输出。同一工具同时只允许一个安装任务。
+pub async fn install_tool(
+    tool: &ManagedTool,
+    version: Option<&str>,
+    on_output: impl Fn(InstallOutputLine) + Send + Sync,
+) -> InstallDone {
+    let _guard = match InstallGuard::acquire(tool.id) {
+        Ok(guard) => guard,
+        Err(e) => return InstallDone::failed(tool.id, None, e),
+    };
+
+    let installed = detect_local_version(tool.command).await;
+    let (program, args) = install_args(
+        installed.install_method.as_deref(),
+        tool.npm_package,
+        version,
+    );
+    // Windows 上经 cmd 解析命令行，包名与版本号拼入前先校验
+    if let Some(spec) = args.last() {
+        if let Err(e) = crate::shell_args::ensure_safe_shell_arg(spec) {
+            return InstallDone::failed(tool.id, None, e);
+        }
+    }
+    log::info!("开始安装 {}: {program} {}", tool.id, args.join(" "));
+
+    let mut child = match build_command(program, &args).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return InstallDone::failed(
+                tool.id,
+                None,
+                format!("无法启动 {program}，请确认已安装 Node.js: {e}"),
+            )
+        }
+    };
+
+    let tail = Mutex::new(Vec::new());
+    tokio::join!(
+        forward_lines(child.stdout.take(), "stdout", tool.id, &tail, &on_output),
+        forward_lines(child.stderr.take(), "stderr", tool.id, &tail, &on_output),
+    );
+    let status = match child.wait().await {
+        Ok(status) => status,
+        Err(e) => {
+            return InstallDone::failed(tool.id, None, format!("等待 {program} 结束失败: {e}"))
+        }
+    };
+    if !status.success() {
+        let tail = tail.into_inner().unwrap_or_else(|e| e.into_inner());
+        return InstallDone::failed(
+            tool.id,
+            status.code(),
+            format!(
+                "安装 {} 失败（{status}）:\n{}",
+                tool.npm_package,
+                tail.join("\n")
+            ),
+        );
+    }
+
+    let local = detect_local_version(tool.command).await;
+    match local.version {
+        Some(version) => {
+            log::info!("{} 安装完成，版本 {version}", tool.id);
+            InstallDone {
+                tool: tool.id.to_string(),
+                success: true,
+                exit_code: status.code(),
+                version: Some(version),
+                error: None,
+            }
+        }
+        None => InstallDone::failed(
+            tool.id,
+            status.code(),
+            format!(
+                "{} 已安装，但未能检测到版本: {}",
+                tool.npm_package,
+                local.error.unwrap_or_default()
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_package_manager_from_install_method() {
+        let (program, args) = install_args(Some("pnpm"), "@openai/codex", None);
+        assert_eq!(program, "pnpm");
+        assert_eq!(args, ["add", "-g", "@openai/codex@latest"]);
+
+        let (program, args) = install_args(Some("yarn"), "@openai/codex", None);
+        assert_eq!(program, "yarn");
+        assert_eq!(args, ["global", "add", "@openai/codex@latest"]);
+
+        for method in [None, Some("nvm"), Some("homebrew")] {
+            let (program, args) = install_args(method, "@openai/codex", Some("0.40.0"));
+            assert_eq!(program, "npm");
+            assert_eq!(args, ["install", "-g", "@openai/codex@0.40.0"]);
+        }
+    }
+
+    #[test]
+    fn rollback_version_must_be_semver() {
+        assert_eq!(normalize_version(" v1.0.3 ").unwrap(), "1.0.3");
+        assert_eq!(normalize_version("0.1.0-beta.2").unwrap(), "0.1.0-beta.2");
+        for bad in ["latest", "1.0", "1.0.0 && calc", "1.0.0|x", ""] {
+            assert!(normalize_version(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn rejects_concurrent_install_of_same_tool() {
+        let guard = InstallGuard::acquire("cc-switch-fake-cli").expect("first install");
+        assert!(InstallGuard::acquire("cc-switch-fake-cli").is_err());
+        assert!(InstallGuard::acquire("cc-switch-other-cli").is_ok());
+        drop(guard);
+        assert!(InstallGuard::acquire("cc-switch-fake-cli").is_ok());
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{OnceLock, RwLock};
@@ -16,6 +17,70 @@ pub struct CustomEndpoint {
     pub last_used: Option<i64>,
 }
 
+/// 默认 npm registry 地址
+pub const DEFAULT_NPM_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// 网络代理模式（用于下载更新与版本检查等出站请求）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum NetworkProxy {
+    /// 跟随系统环境变量（HTTPS_PROXY / HTTP_PROXY）
+    #[default]
+    System,
+    /// 直连，不使用任何代理
+    Direct,
+    /// 使用显式指定的代理地址
+    Custom { url: String },
+}
+
+/// 应用自身的更新通道
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateChannel {
+    /// 仅正式版
+    #[default]
+    Stable,
+    /// 包含预发布版本
+    Beta,
+}
+
+/// 应用更新安装包的下载来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateSource {
+    /// 123 云盘
+    #[default]
+    Pan123,
+    /// GitHub Release 附件（适合海外网络）
+    Github,
+}
+
+/// 界面外观（主题）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Appearance {
+    /// 跟随系统
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl std::str::FromStr for Appearance {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "system" => Ok(Self::System),
+            "light" => Ok(Self::Light),
+            "dark" => Ok(Self::Dark),
+            other => Err(AppError::InvalidInput(format!(
+                "不支持的外观模式: '{other}'。可选值: system, light, dark。"
+            ))),
+        }
+    }
+}
+
 /// 应用设置结构
 ///
 /// 存储设备级别设置，保存在本地 `~/.cc-switch/settings.json`，不随数据库同步。
@@ -28,6 +93,9 @@ pub struct AppSettings {
     pub show_in_tray: bool,
     #[serde(default = "default_minimize_to_tray_on_close")]
     pub minimize_to_tray_on_close: bool,
+    /// 界面外观（跟随系统 / 浅色 / 深色）
+    #[serde(default)]
+    pub appearance: Appearance,
     /// 是否启用 Claude 插件联动
     #[serde(default)]
     pub enable_claude_plugin_integration: bool,
@@ -40,6 +108,68 @@ pub struct AppSettings {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
 
+    /// 在版本面板中隐藏的 CLI 工具 ID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_tools: Vec<String>,
+    /// 固定版本的 CLI 工具（工具 ID → 版本），本地版本与之相同时不提示更新
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned_tool_versions: BTreeMap<String, String>,
+
+    // ===== 设备级网络设置 =====
+    /// 下载更新与版本检查使用的网络代理
+    #[serde(default)]
+    pub network_proxy: NetworkProxy,
+    /// 版本检查使用的 npm registry 镜像（为空时使用官方 registry）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub npm_registry: Option<String>,
+    /// npm 最新版本查询结果的缓存有效期（秒）
+    #[serde(default = "default_npm_version_cache_ttl_secs")]
+    pub npm_version_cache_ttl_secs: u64,
+    /// 应用更新通道
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+    /// 更新安装包的下载来源
+    #[serde(default)]
+    pub update_source: UpdateSource,
+    /// 是否在后台定时检查应用更新（仅提醒，不自动下载）
+    #[serde(default = "default_true")]
+    pub auto_update_check: bool,
+    /// 是否显示系统通知（工具更新完成、发现新版本、快捷键切换等）
+    #[serde(default = "default_true")]
+    pub notifications_enabled: bool,
+    /// 是否在启动后于后台预取 CLI 版本信息
+    #[serde(default)]
+    pub prefetch_versions: bool,
+    /// npm / GitHub 被限流后解除限流的 Unix 时间戳（秒），按服务名索引
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rate_limited_until: BTreeMap<String, i64>,
+    /// 更新安装包下载缓存目录（为空时使用默认位置）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_cache_dir: Option<String>,
+    /// 下载更新安装包时使用的 User-Agent（为空时使用默认值）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_user_agent: Option<String>,
+    /// 更新安装包的下载基础地址（镜像），相对路径的下载链接按此解析；与 npm registry 相互独立
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_download_base: Option<String>,
+
+    /// 主窗口尺寸与位置（关闭时保存，启动时恢复）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_state: Option<crate::window_state::WindowState>,
+
+    /// 循环切换当前工具供应商的全局快捷键（如 `CmdOrCtrl+Shift+P`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub switch_hotkey: Option<String>,
+
+    /// 文件日志级别（trace / debug / info / warn / error），未设置时为 info
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+
+    // ===== 设备级安全设置 =====
+    /// 启动时锁定 API Key 存储，需手动解锁后才能查看与切换
+    #[serde(default)]
+    pub require_unlock: bool,
+
     // ===== 设备级目录覆盖 =====
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub claude_config_dir: Option<String>,
@@ -72,6 +202,10 @@ fn default_true() -> bool {
     true
 }
 
+fn default_npm_version_cache_ttl_secs() -> u64 {
+    6 * 60 * 60
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -81,6 +215,25 @@ impl Default for AppSettings {
             skip_claude_onboarding: true,
             launch_on_startup: false,
             language: None,
+            hidden_tools: Vec::new(),
+            pinned_tool_versions: BTreeMap::new(),
+            network_proxy: NetworkProxy::System,
+            npm_registry: None,
+            npm_version_cache_ttl_secs: default_npm_version_cache_ttl_secs(),
+            update_channel: UpdateChannel::Stable,
+            update_source: UpdateSource::Pan123,
+            appearance: Appearance::System,
+            auto_update_check: true,
+            notifications_enabled: true,
+            prefetch_versions: false,
+            rate_limited_until: BTreeMap::new(),
+            download_cache_dir: None,
+            download_user_agent: None,
+            update_download_base: None,
+            window_state: None,
+            switch_hotkey: None,
+            log_level: None,
+            require_unlock: false,
             claude_config_dir: None,
             codex_config_dir: None,
             gemini_config_dir: None,
@@ -93,11 +246,8 @@ impl Default for AppSettings {
 
 impl AppSettings {
     fn settings_path() -> PathBuf {
-        // settings.json 保留用于旧版本迁移和无数据库场景
-        dirs::home_dir()
-            .expect("无法获取用户主目录")
-            .join(".cc-switch")
-            .join("settings.json")
+        // settings.json 保留用于旧版本迁移和无数据库场景（便携模式下随数据目录迁移）
+        crate::config::get_data_dir().join("settings.json")
     }
 
     fn normalize_paths(&mut self) {
@@ -122,12 +272,51 @@ impl AppSettings {
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string());
 
+        self.download_cache_dir = self
+            .download_cache_dir
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        self.download_user_agent = self
+            .download_user_agent
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty() && !s.chars().any(char::is_control))
+            .map(|s| s.to_string());
+
         self.language = self
             .language
             .as_ref()
             .map(|s| s.trim())
             .filter(|s| matches!(*s, "en" | "zh" | "ja"))
             .map(|s| s.to_string());
+
+        self.npm_registry = self
+            .npm_registry
+            .as_ref()
+            .map(|s| s.trim().trim_end_matches('/'))
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        self.update_download_base = self
+            .update_download_base
+            .as_ref()
+            .map(|s| s.trim().trim_end_matches('/'))
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        if let NetworkProxy::Custom { url } = &self.network_proxy {
+            let trimmed = url.trim();
+            self.network_proxy = if trimmed.is_empty() {
+                NetworkProxy::System
+            } else {
+                NetworkProxy::Custom {
+                    url: trimmed.to_string(),
+                }
+            };
+        }
     }
 
     fn load_from_file() -> Self {
@@ -174,7 +363,7 @@ fn settings_store() -> &'static RwLock<AppSettings> {
     SETTINGS_STORE.get_or_init(|| RwLock::new(AppSettings::load_from_file()))
 }
 
-fn resolve_override_path(raw: &str) -> PathBuf {
+pub(crate) fn resolve_override_path(raw: &str) -> PathBuf {
     if raw == "~" {
         if let Some(home) = dirs::home_dir() {
             return home;
@@ -214,6 +403,114 @@ pub fn reload_settings() -> Result<(), AppError> {
     Ok(())
 }
 
+/// 可通过 [`reset_setting`] 恢复默认值的设置项（与 settings.json 中的键名一致）
+///
+/// 当前供应商、限流记录等由应用内部维护的状态，以及开机自启、全局快捷键等需要同步系统
+/// 状态的设置不在此列，请使用对应的 `set_*` 命令修改。
+pub const RESETTABLE_SETTINGS: &[&str] = &[
+    "showInTray",
+    "minimizeToTrayOnClose",
+    "appearance",
+    "enableClaudePluginIntegration",
+    "skipClaudeOnboarding",
+    "language",
+    "hiddenTools",
+    "pinnedToolVersions",
+    "networkProxy",
+    "npmRegistry",
+    "npmVersionCacheTtlSecs",
+    "updateChannel",
+    "updateSource",
+    "autoUpdateCheck",
+    "notificationsEnabled",
+    "prefetchVersions",
+    "downloadCacheDir",
+    "downloadUserAgent",
+    "updateDownloadBase",
+    "windowState",
+    "logLevel",
+    "requireUnlock",
+    "claudeConfigDir",
+    "codexConfigDir",
+    "geminiConfigDir",
+];
+
+/// 去掉 `settings` 中的 `key`，按字段默认值重新构造
+fn without_key(settings: &AppSettings, key: &str) -> Result<AppSettings, AppError> {
+    if !RESETTABLE_SETTINGS.contains(&key) {
+        return Err(AppError::InvalidInput(format!("未知的设置项: {key}")));
+    }
+    let mut value =
+        serde_json::to_value(settings).map_err(|e| AppError::JsonSerialize { source: e })?;
+    if let Some(map) = value.as_object_mut() {
+        map.remove(key);
+    }
+    serde_json::from_value(value).map_err(|e| AppError::Config(format!("重置设置失败: {e}")))
+}
+
+/// 将单个设置项恢复为默认值，返回恢复后实际生效的值
+///
+/// 未设置时使用内置默认值的项（npm registry、下载缓存目录、日志级别、配置目录）返回
+/// 实际使用的值，其余返回设置中保存的值。
+pub fn reset_setting(key: &str) -> Result<serde_json::Value, AppError> {
+    use serde_json::Value;
+
+    let settings = without_key(&get_settings(), key)?;
+    update_settings(settings.clone())?;
+
+    let path = |p: PathBuf| Value::String(p.to_string_lossy().to_string());
+    Ok(match key {
+        "npmRegistry" => Value::String(get_npm_registry()),
+        "downloadCacheDir" => path(get_download_cache_dir()),
+        "logLevel" => Value::String(crate::logging::DEFAULT_LOG_LEVEL.to_string()),
+        "claudeConfigDir" => path(crate::config::get_claude_config_dir()),
+        "codexConfigDir" => path(crate::codex_config::get_codex_config_dir()),
+        "geminiConfigDir" => path(crate::gemini_config::get_gemini_dir()),
+        _ => serde_json::to_value(&settings)
+            .ok()
+            .and_then(|v| v.get(key).cloned())
+            .unwrap_or(Value::Null),
+    })
+}
+
+/// 获取版本检查使用的 npm registry 地址（不含末尾斜杠）
+pub fn get_npm_registry() -> String {
+    settings_store()
+        .read()
+        .ok()
+        .and_then(|s| s.npm_registry.clone())
+        .unwrap_or_else(|| DEFAULT_NPM_REGISTRY.to_string())
+}
+
+/// 记录服务的限流解除时间，同时清理已过期的记录
+pub fn set_rate_limited_until(service: &str, until: i64) -> Result<(), AppError> {
+    let mut settings = get_settings();
+    let now = chrono::Utc::now().timestamp();
+    settings.rate_limited_until.retain(|_, t| *t > now);
+    settings
+        .rate_limited_until
+        .insert(service.to_string(), until);
+    update_settings(settings)
+}
+
+/// 默认的更新安装包缓存目录：便携模式下放在数据目录中，否则使用系统临时目录
+pub fn default_download_cache_dir() -> PathBuf {
+    if crate::config::is_portable_mode() {
+        crate::config::get_data_dir().join("aicodewith-updates")
+    } else {
+        std::env::temp_dir().join("aicodewith-updates")
+    }
+}
+
+/// 更新安装包缓存目录（设置中配置的目录优先）
+pub fn get_download_cache_dir() -> PathBuf {
+    settings_store()
+        .read()
+        .ok()
+        .and_then(|s| s.download_cache_dir.as_deref().map(resolve_override_path))
+        .unwrap_or_else(default_download_cache_dir)
+}
+
 pub fn get_claude_override_dir() -> Option<PathBuf> {
     let settings = settings_store().read().ok()?;
     settings
@@ -303,3 +600,35 @@ pub fn get_effective_current_provider(
     // Fallback 到数据库的 is_current
     db.get_current_provider(app_type.as_str())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_key_restores_coded_default_only_for_that_key() {
+        let mut settings = AppSettings {
+            npm_registry: Some("https://registry.npmmirror.com".to_string()),
+            auto_update_check: false,
+            skip_claude_onboarding: false,
+            ..AppSettings::default()
+        };
+        settings.hidden_tools.push("gemini".to_string());
+
+        let reset = without_key(&settings, "autoUpdateCheck").unwrap();
+        assert!(reset.auto_update_check);
+        assert!(!reset.skip_claude_onboarding);
+        assert_eq!(reset.npm_registry, settings.npm_registry);
+
+        let reset = without_key(&reset, "npmRegistry").unwrap();
+        assert_eq!(reset.npm_registry, None);
+        assert_eq!(reset.hidden_tools, vec!["gemini".to_string()]);
+
+        for key in ["currentProviderClaude", "rateLimitedUntil", "unknown"] {
+            assert!(matches!(
+                without_key(&settings, key),
+                Err(AppError::InvalidInput(_))
+            ));
+        }
+    }
+}
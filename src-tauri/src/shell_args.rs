@@ -0,0 +1,57 @@
+//! 拼入 shell 命令行的参数校验
+//!
+//! 启动外部进程时优先直接传递参数向量，不经 shell 解析。确实需要 shell 的场景（Windows 上
+//! npm 等 `.cmd` shim 须经 `cmd /C` 执行、macOS 借登录 shell 加载 nvm / brew 的 PATH）中，
+//! 拼入命令行的工具名、包名与版本号只允许 `[A-Za-z0-9._@/-]`，且不能以 `-` 开头，
+//! 避免被解析为 shell 语法或命令行选项。
+
+/// 是否可以安全地拼入 shell 命令行
+pub fn is_safe_shell_arg(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '@' | '/' | '-'))
+}
+
+/// 校验将拼入 shell 命令行的参数
+pub fn ensure_safe_shell_arg(value: &str) -> Result<(), String> {
+    if is_safe_shell_arg(value) {
+        Ok(())
+    } else {
+        Err(format!("参数包含不允许的字符，已拒绝执行: {value:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_plain_names_and_versions() {
+        for ok in [
+            "claude",
+            "@anthropic-ai/claude-code@2.0.14",
+            "gemini",
+            "1.2.0-beta.1",
+        ] {
+            assert!(is_safe_shell_arg(ok), "{ok}");
+        }
+        for bad in [
+            "",
+            "claude; rm -rf ~",
+            "$(whoami)",
+            "a`id`",
+            "x & calc",
+            "a|b",
+            "--version",
+            "name with space",
+            "1.0.0+build",
+            "\"quoted\"",
+        ] {
+            assert!(!is_safe_shell_arg(bad), "{bad}");
+        }
+        assert!(ensure_safe_shell_arg("codex").is_ok());
+        assert!(ensure_safe_shell_arg("codex%PATH%").is_err());
+    }
+}
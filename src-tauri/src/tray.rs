@@ -2,11 +2,13 @@
 //!
 //! 负责系统托盘图标和菜单的创建、更新和事件处理。
 
+use indexmap::IndexMap;
 use tauri::menu::{CheckMenuItem, Menu, MenuBuilder, MenuItem};
 use tauri::{Emitter, Manager};
 
 use crate::app_config::AppType;
 use crate::error::AppError;
+use crate::provider::Provider;
 use crate::store::AppState;
 
 /// 托盘菜单文本（国际化）
@@ -14,6 +16,7 @@ use crate::store::AppState;
 pub struct TrayTexts {
     pub show_main: &'static str,
     pub no_provider_hint: &'static str,
+    pub check_updates: &'static str,
     pub quit: &'static str,
 }
 
@@ -23,17 +26,20 @@ impl TrayTexts {
             "en" => Self {
                 show_main: "Open main window",
                 no_provider_hint: "  (No providers yet, please add them from the main window)",
+                check_updates: "Check for updates",
                 quit: "Quit",
             },
             "ja" => Self {
                 show_main: "メインウィンドウを開く",
                 no_provider_hint:
                     "  (プロバイダーがまだありません。メイン画面から追加してください)",
+                check_updates: "アップデートを確認",
                 quit: "終了",
             },
             _ => Self {
                 show_main: "打开主界面",
                 no_provider_hint: "  (无供应商，请在主界面添加)",
+                check_updates: "检查更新",
                 quit: "退出",
             },
         }
@@ -111,24 +117,7 @@ fn append_provider_section<'a>(
         return Ok(menu_builder.item(&empty_hint));
     }
 
-    let mut sorted_providers: Vec<_> = manager.providers.iter().collect();
-    sorted_providers.sort_by(|(_, a), (_, b)| {
-        match (a.sort_index, b.sort_index) {
-            (Some(idx_a), Some(idx_b)) => return idx_a.cmp(&idx_b),
-            (Some(_), None) => return std::cmp::Ordering::Less,
-            (None, Some(_)) => return std::cmp::Ordering::Greater,
-            _ => {}
-        }
-
-        match (a.created_at, b.created_at) {
-            (Some(time_a), Some(time_b)) => return time_a.cmp(&time_b),
-            (Some(_), None) => return std::cmp::Ordering::Greater,
-            (None, Some(_)) => return std::cmp::Ordering::Less,
-            _ => {}
-        }
-
-        a.name.cmp(&b.name)
-    });
+    let sorted_providers = sorted_providers(&manager.providers);
 
     for (id, provider) in sorted_providers {
         let is_current = manager.current == *id;
@@ -147,6 +136,29 @@ fn append_provider_section<'a>(
     Ok(menu_builder)
 }
 
+/// 按托盘/列表显示顺序排序供应商：sort_index → 创建时间 → 名称
+pub fn sorted_providers(providers: &IndexMap<String, Provider>) -> Vec<(&String, &Provider)> {
+    let mut sorted: Vec<_> = providers.iter().collect();
+    sorted.sort_by(|(_, a), (_, b)| {
+        match (a.sort_index, b.sort_index) {
+            (Some(idx_a), Some(idx_b)) => return idx_a.cmp(&idx_b),
+            (Some(_), None) => return std::cmp::Ordering::Less,
+            (None, Some(_)) => return std::cmp::Ordering::Greater,
+            _ => {}
+        }
+
+        match (a.created_at, b.created_at) {
+            (Some(time_a), Some(time_b)) => return time_a.cmp(&time_b),
+            (Some(_), None) => return std::cmp::Ordering::Greater,
+            (None, Some(_)) => return std::cmp::Ordering::Less,
+            _ => {}
+        }
+
+        a.name.cmp(&b.name)
+    });
+    sorted
+}
+
 /// 处理供应商托盘事件
 pub fn handle_provider_tray_event(app: &tauri::AppHandle, event_id: &str) -> bool {
     for section in TRAY_SECTIONS.iter() {
@@ -201,11 +213,22 @@ pub fn create_tray_menu(
             append_provider_section(app, menu_builder, Some(&manager), section, &tray_texts)?;
     }
 
-    // 分隔符和退出菜单
+    // 分隔符、检查更新和退出菜单
+    let check_updates_item = MenuItem::with_id(
+        app,
+        "check_updates",
+        tray_texts.check_updates,
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| AppError::Message(format!("创建检查更新菜单失败: {e}")))?;
     let quit_item = MenuItem::with_id(app, "quit", tray_texts.quit, true, None::<&str>)
         .map_err(|e| AppError::Message(format!("创建退出菜单失败: {e}")))?;
 
-    menu_builder = menu_builder.separator().item(&quit_item);
+    menu_builder = menu_builder
+        .separator()
+        .item(&check_updates_item)
+        .item(&quit_item);
 
     menu_builder
         .build()
@@ -251,6 +274,12 @@ pub fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
                 }
             }
         }
+        "check_updates" => {
+            let app_handle = app.clone();
+            tauri::async_runtime::spawn(async move {
+                check_updates_from_tray(&app_handle).await;
+            });
+        }
         "quit" => {
             log::info!("退出应用");
             app.exit(0);
@@ -264,6 +293,48 @@ pub fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
     }
 }
 
+/// 托盘"检查更新"：查询 GitHub 最新版本并通知前端
+///
+/// 成功时发送 `update-check-result`（载荷为 `ReleaseInfo`），有新版本时同时打开主界面；
+/// 失败时发送 `update-check-failed`（载荷为错误字符串）。
+async fn check_updates_from_tray(app: &tauri::AppHandle) {
+    match crate::commands::get_latest_release().await {
+        Ok(info) => {
+            log::info!("托盘检查更新: 当前 {}，最新 {}", info.current, info.latest);
+            let update_available = info.update_available;
+            if let Err(e) = app.emit("update-check-result", info) {
+                log::error!("发射检查更新事件失败: {e}");
+            }
+            if update_available {
+                handle_tray_menu_event(app, "show_main");
+            }
+        }
+        Err(e) => {
+            log::warn!("托盘检查更新失败: {e}");
+            if let Err(emit_err) = app.emit("update-check-failed", e) {
+                log::error!("发射检查更新事件失败: {emit_err}");
+            }
+        }
+    }
+}
+
+/// 按当前数据重建托盘菜单（供应商增删改或排序变化后调用）
+pub fn refresh_tray_menu(app: &tauri::AppHandle) {
+    let Some(app_state) = app.try_state::<AppState>() else {
+        return;
+    };
+    match create_tray_menu(app, app_state.inner()) {
+        Ok(new_menu) => {
+            if let Some(tray) = app.tray_by_id("main") {
+                if let Err(e) = tray.set_menu(Some(new_menu)) {
+                    log::error!("更新托盘菜单失败: {e}");
+                }
+            }
+        }
+        Err(e) => log::error!("创建托盘菜单失败: {e}"),
+    }
+}
+
 /// 内部切换供应商函数
 pub fn switch_provider_internal(
     app: &tauri::AppHandle,
@@ -271,30 +342,17 @@ pub fn switch_provider_internal(
     provider_id: String,
 ) -> Result<(), AppError> {
     if let Some(app_state) = app.try_state::<AppState>() {
-        // 在使用前先保存需要的值
-        let app_type_str = app_type.as_str().to_string();
-        let provider_id_clone = provider_id.clone();
-
-        crate::commands::switch_provider(app_state.clone(), app_type_str.clone(), provider_id)
-            .map_err(AppError::Message)?;
+        // 切换命令会发射 provider-switched 事件
+        crate::commands::switch_provider(
+            app_state.clone(),
+            app.clone(),
+            app_type.as_str().to_string(),
+            provider_id,
+        )
+        .map_err(AppError::Message)?;
 
         // 切换成功后重新创建托盘菜单
-        if let Ok(new_menu) = create_tray_menu(app, app_state.inner()) {
-            if let Some(tray) = app.tray_by_id("main") {
-                if let Err(e) = tray.set_menu(Some(new_menu)) {
-                    log::error!("更新托盘菜单失败: {e}");
-                }
-            }
-        }
-
-        // 发射事件到前端，通知供应商已切换
-        let event_data = serde_json::json!({
-            "appType": app_type_str,
-            "providerId": provider_id_clone
-        });
-        if let Err(e) = app.emit("provider-switched", event_data) {
-            log::error!("发射供应商切换事件失败: {e}");
-        }
+        refresh_tray_menu(app);
     }
     Ok(())
 }
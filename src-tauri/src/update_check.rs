@@ -0,0 +1,106 @@
+//! 后台定时检查应用更新
+//!
+//! 启动后检查一次，之后每 24 小时检查一次（可在设置中关闭）。发现新版本时发送
+//! `update-available` 事件（载荷为 `ReleaseInfo`）并弹出系统通知，不会自动下载。
+//! 遇到 GitHub 限流时按指数退避延后下一次检查。
+
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::services::release::{self, RELEASE_RATE_LIMITED};
+
+/// 启动后首次检查前的等待时间，避免与启动流程争抢网络
+const INITIAL_DELAY: Duration = Duration::from_secs(30);
+/// 正常检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// 请求失败后的重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 限流退避的初始时长（每次连续限流翻倍，最长为正常检查间隔）
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+static NOTIFIED_VERSION: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+
+fn notified_version_cell() -> &'static Mutex<Option<String>> {
+    NOTIFIED_VERSION.get_or_init(|| Mutex::new(None))
+}
+
+/// 第 `attempt` 次连续限流后的等待时长（从 1 开始）
+fn rate_limit_delay(attempt: u32) -> Duration {
+    RATE_LIMIT_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(CHECK_INTERVAL)
+}
+
+/// 启动后台检查任务
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INITIAL_DELAY).await;
+        let mut rate_limited = 0u32;
+        loop {
+            let delay = if crate::settings::get_settings().auto_update_check {
+                match check_once(&app).await {
+                    Ok(()) => {
+                        rate_limited = 0;
+                        CHECK_INTERVAL
+                    }
+                    Err(e) if e == RELEASE_RATE_LIMITED => {
+                        rate_limited += 1;
+                        let delay = rate_limit_delay(rate_limited);
+                        log::info!("后台检查更新遇到限流，{} 分钟后重试", delay.as_secs() / 60);
+                        delay
+                    }
+                    Err(e) => {
+                        log::warn!("后台检查更新失败: {e}");
+                        RETRY_INTERVAL
+                    }
+                }
+            } else {
+                CHECK_INTERVAL
+            };
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+async fn check_once(app: &AppHandle) -> Result<(), String> {
+    let client = crate::commands::shared_http_client()?;
+    let channel = crate::settings::get_settings().update_channel;
+    let info = release::fetch_latest_release(&client, channel).await?;
+    if !info.update_available {
+        log::debug!("后台检查更新: 已是最新版本 {}", info.current);
+        return Ok(());
+    }
+
+    // 同一版本每次运行只提醒一次
+    {
+        let mut notified = notified_version_cell()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if notified.as_deref() == Some(info.latest.as_str()) {
+            return Ok(());
+        }
+        *notified = Some(info.latest.clone());
+    }
+
+    log::info!("发现新版本 {}（当前 {}）", info.latest, info.current);
+    crate::show_notification(app, &format!("发现新版本 v{}", info.latest));
+    if let Err(e) = app.emit("update-available", info) {
+        log::error!("发射 update-available 事件失败: {e}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_backoff_doubles_up_to_check_interval() {
+        assert_eq!(rate_limit_delay(1), RATE_LIMIT_BACKOFF);
+        assert_eq!(rate_limit_delay(2), RATE_LIMIT_BACKOFF * 2);
+        assert_eq!(rate_limit_delay(3), RATE_LIMIT_BACKOFF * 4);
+        assert_eq!(rate_limit_delay(40), CHECK_INTERVAL);
+    }
+}
@@ -0,0 +1,72 @@
+//! 启动后预取 CLI 版本信息
+//!
+//! 启用 `prefetchVersions` 设置后，启动完成片刻后在后台检测一次各 CLI 工具的本地与最新版本
+//! （最新版本优先使用缓存，请求遵循网络代理设置），结果暂存在内存中并发送
+//! `tool-versions-ready` 事件（载荷为 `ToolVersion` 列表）。版本面板首次打开时直接使用该结果。
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::services::cli_tools::{self, ToolVersion};
+use crate::store::AppState;
+
+/// 启动后开始预取前的等待时间，避免与启动流程争抢 CPU 与网络
+const INITIAL_DELAY: Duration = Duration::from_secs(5);
+/// 预取结果的有效期，过期后重新检测
+const PREFETCH_TTL: Duration = Duration::from_secs(10 * 60);
+
+pub const VERSIONS_READY_EVENT: &str = "tool-versions-ready";
+
+/// 预取时间与结果
+type Prefetched = Option<(Instant, Vec<ToolVersion>)>;
+
+static PREFETCHED: OnceLock<Mutex<Prefetched>> = OnceLock::new();
+
+fn prefetched_cell() -> &'static Mutex<Prefetched> {
+    PREFETCHED.get_or_init(|| Mutex::new(None))
+}
+
+/// 启动预取任务（设置中未启用时不执行，不阻塞启动）
+pub fn start(app: AppHandle) {
+    if !crate::settings::get_settings().prefetch_versions {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INITIAL_DELAY).await;
+        let client = match crate::commands::shared_http_client() {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("预取 CLI 版本失败: {e}");
+                return;
+            }
+        };
+        let state = app.state::<AppState>();
+        let versions = cli_tools::detect_tool_versions(&client, &state.db, false).await;
+        *prefetched_cell().lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((Instant::now(), versions.clone()));
+        log::debug!("已预取 {} 个 CLI 工具的版本信息", versions.len());
+        if let Err(e) = app.emit(VERSIONS_READY_EVENT, &versions) {
+            log::warn!("发射 {VERSIONS_READY_EVENT} 事件失败: {e}");
+        }
+    });
+}
+
+/// 取出仍在有效期内的预取结果（只使用一次，之后的查询照常检测）
+pub fn take_prefetched() -> Option<Vec<ToolVersion>> {
+    prefetched_cell()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .filter(|(fetched_at, _)| fetched_at.elapsed() < PREFETCH_TTL)
+        .map(|(_, versions)| versions)
+}
+
+/// 丢弃预取结果（安装或更新 CLI 后版本已变化）
+pub fn clear() {
+    prefetched_cell()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+}
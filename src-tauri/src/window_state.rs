@@ -0,0 +1,245 @@
+//! 主窗口尺寸与位置的保存和恢复
+//!
+//! 移动/缩放时记录到内存，关闭窗口或退出时写入设置文件（便携模式下随数据目录保存）；
+//! 启动时恢复，并限制在当前可用的显示器范围内，避免窗口落在已断开的屏幕上。
+
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+/// 窗口几何信息（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// 显示器工作区（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl MonitorRect {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        let right = self.x.saturating_add(self.width as i32);
+        let bottom = self.y.saturating_add(self.height as i32);
+        x >= self.x && x < right && y >= self.y && y < bottom
+    }
+}
+
+static CURRENT_STATE: OnceLock<RwLock<Option<WindowState>>> = OnceLock::new();
+
+fn current_state_cell() -> &'static RwLock<Option<WindowState>> {
+    CURRENT_STATE.get_or_init(|| RwLock::new(None))
+}
+
+/// 将窗口限制在可用显示器内
+///
+/// 窗口中心所在的显示器优先；不在任何显示器上时移到第一个（主）显示器居中。
+/// 窗口尺寸不超过目标显示器尺寸。
+pub fn clamp_to_monitors(state: WindowState, monitors: &[MonitorRect]) -> WindowState {
+    let center_x = state.x.saturating_add((state.width / 2) as i32);
+    let center_y = state.y.saturating_add((state.height / 2) as i32);
+    let (monitor, on_screen) = match monitors.iter().find(|m| m.contains(center_x, center_y)) {
+        Some(monitor) => (monitor, true),
+        None => match monitors.first() {
+            Some(monitor) => (monitor, false),
+            None => return state,
+        },
+    };
+
+    let width = state.width.min(monitor.width);
+    let height = state.height.min(monitor.height);
+    let max_x = monitor.x + (monitor.width - width) as i32;
+    let max_y = monitor.y + (monitor.height - height) as i32;
+    let (x, y) = if on_screen {
+        (
+            state.x.clamp(monitor.x, max_x),
+            state.y.clamp(monitor.y, max_y),
+        )
+    } else {
+        (
+            monitor.x + ((monitor.width - width) / 2) as i32,
+            monitor.y + ((monitor.height - height) / 2) as i32,
+        )
+    };
+
+    WindowState {
+        x,
+        y,
+        width,
+        height,
+        maximized: state.maximized,
+    }
+}
+
+/// 记录窗口当前几何信息（最大化/最小化时仅更新标记，保留还原后的尺寸）
+pub fn track(window: &WebviewWindow) {
+    let maximized = window.is_maximized().unwrap_or(false);
+    let minimized = window.is_minimized().unwrap_or(false);
+    if minimized {
+        return;
+    }
+
+    let mut current = current_state_cell()
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    if maximized {
+        if let Some(state) = current.as_mut() {
+            state.maximized = true;
+        }
+        return;
+    }
+
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+    *current = Some(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: false,
+    });
+}
+
+/// 将记录的窗口状态写入设置
+pub fn save() {
+    let Some(state) = *current_state_cell()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+    else {
+        return;
+    };
+    let mut settings = crate::settings::get_settings();
+    if settings.window_state == Some(state) {
+        return;
+    }
+    settings.window_state = Some(state);
+    if let Err(e) = crate::settings::update_settings(settings) {
+        log::warn!("保存窗口状态失败: {e}");
+    }
+}
+
+/// 启动时恢复窗口状态
+pub fn restore(window: &WebviewWindow) {
+    let Some(saved) = crate::settings::get_settings().window_state else {
+        return;
+    };
+    // 主显示器排在首位，作为窗口不在任何显示器上时的回退目标
+    let primary = window.primary_monitor().ok().flatten();
+    let mut monitors: Vec<tauri::Monitor> = primary.into_iter().collect();
+    monitors.extend(window.available_monitors().unwrap_or_default());
+    let monitors: Vec<MonitorRect> = monitors
+        .iter()
+        .map(|m| MonitorRect {
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+        })
+        .collect();
+    let state = clamp_to_monitors(saved, &monitors);
+
+    if let Err(e) = window.set_size(PhysicalSize::new(state.width, state.height)) {
+        log::warn!("恢复窗口尺寸失败: {e}");
+    }
+    if let Err(e) = window.set_position(PhysicalPosition::new(state.x, state.y)) {
+        log::warn!("恢复窗口位置失败: {e}");
+    }
+    if state.maximized {
+        let _ = window.maximize();
+    }
+    *current_state_cell()
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = Some(state);
+}
+
+/// 清除保存的窗口状态，并将主窗口恢复为默认尺寸居中显示
+pub fn reset(app: &tauri::AppHandle) -> Result<(), String> {
+    *current_state_cell()
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = None;
+
+    let mut settings = crate::settings::get_settings();
+    settings.window_state = None;
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let _ = window.unmaximize();
+    if let Some(config) = app.config().app.windows.iter().find(|w| w.label == "main") {
+        window
+            .set_size(tauri::LogicalSize::new(config.width, config.height))
+            .map_err(|e| format!("重置窗口尺寸失败: {e}"))?;
+    }
+    window
+        .center()
+        .map_err(|e| format!("重置窗口位置失败: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: MonitorRect = MonitorRect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    const SECONDARY: MonitorRect = MonitorRect {
+        x: 1920,
+        y: 0,
+        width: 1280,
+        height: 1024,
+    };
+
+    fn state(x: i32, y: i32, width: u32, height: u32) -> WindowState {
+        WindowState {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn keeps_window_that_fits_on_screen() {
+        let saved = state(2000, 100, 1000, 650);
+        assert_eq!(clamp_to_monitors(saved, &[PRIMARY, SECONDARY]), saved);
+    }
+
+    #[test]
+    fn pulls_partially_offscreen_window_back() {
+        let clamped = clamp_to_monitors(state(1300, 600, 1000, 650), &[PRIMARY]);
+        assert_eq!(clamped, state(920, 430, 1000, 650));
+    }
+
+    #[test]
+    fn centers_window_from_disconnected_monitor_on_primary() {
+        let mut saved = state(3500, 200, 1000, 650);
+        saved.maximized = true;
+        let clamped = clamp_to_monitors(saved, &[PRIMARY]);
+        assert_eq!((clamped.x, clamped.y), (460, 215));
+        assert!(clamped.maximized);
+    }
+
+    #[test]
+    fn shrinks_window_larger_than_monitor() {
+        let clamped = clamp_to_monitors(state(2000, 0, 1600, 1200), &[PRIMARY, SECONDARY]);
+        assert_eq!(clamped, state(1920, 0, 1280, 1024));
+    }
+}
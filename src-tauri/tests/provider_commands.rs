@@ -1,8 +1,9 @@
 use serde_json::json;
 
 use cc_switch_lib::{
-    get_codex_auth_path, get_codex_config_path, read_json_file, switch_provider_test_hook,
-    write_codex_live_atomic, AppError, AppType, McpApps, McpServer, MultiAppConfig, Provider,
+    ensure_tool_config, get_claude_settings_path, get_codex_auth_path, get_codex_config_path,
+    read_json_file, switch_all_tools_test_hook, switch_provider_test_hook, write_codex_live_atomic,
+    AppError, AppType, McpApps, McpServer, MultiAppConfig, Provider,
 };
 
 #[path = "support.rs"]
@@ -356,3 +357,156 @@ fn switch_provider_codex_missing_auth_returns_error_and_keeps_state() {
         "current provider should remain empty or be the attempted id on failure, got: {current_id:?}"
     );
 }
+
+#[test]
+fn ensure_tool_config_creates_missing_files_once() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    assert!(ensure_tool_config("codex".to_string()).expect("create codex config"));
+    let auth: serde_json::Value = read_json_file(&get_codex_auth_path()).expect("read auth");
+    assert_eq!(auth, json!({}));
+    assert!(get_codex_config_path().is_file());
+
+    assert!(!ensure_tool_config("codex".to_string()).expect("second call is a no-op"));
+    assert!(ensure_tool_config("unknown".to_string()).is_err());
+}
+
+#[test]
+fn ensure_tool_config_rejects_directory_in_place_of_file() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let settings_path = get_claude_settings_path();
+    std::fs::create_dir_all(&settings_path).expect("create directory at settings path");
+
+    let err = ensure_tool_config("claude".to_string()).expect_err("directory should be rejected");
+    assert!(err.contains("目录"), "unexpected error: {err}");
+}
+
+fn seed_claude_and_codex(codex_new: serde_json::Value) -> MultiAppConfig {
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "claude-old".to_string();
+        for (id, key) in [
+            ("claude-old", "sk-old-claude"),
+            ("claude-new", "sk-new-claude"),
+        ] {
+            manager.providers.insert(
+                id.to_string(),
+                Provider::with_id(
+                    id.to_string(),
+                    id.to_string(),
+                    json!({ "env": { "ANTHROPIC_AUTH_TOKEN": key } }),
+                    None,
+                ),
+            );
+        }
+    }
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Codex)
+            .expect("codex manager");
+        manager.current = "codex-old".to_string();
+        manager.providers.insert(
+            "codex-old".to_string(),
+            Provider::with_id(
+                "codex-old".to_string(),
+                "codex-old".to_string(),
+                json!({
+                    "auth": { "OPENAI_API_KEY": "sk-old-codex" },
+                    "config": ""
+                }),
+                None,
+            ),
+        );
+        manager.providers.insert(
+            "codex-new".to_string(),
+            Provider::with_id(
+                "codex-new".to_string(),
+                "codex-new".to_string(),
+                codex_new,
+                None,
+            ),
+        );
+    }
+    config
+}
+
+fn seed_claude_live() -> serde_json::Value {
+    let live = json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "sk-old-claude" } });
+    let settings_path = get_claude_settings_path();
+    std::fs::create_dir_all(settings_path.parent().expect("settings dir"))
+        .expect("create claude settings dir");
+    std::fs::write(&settings_path, serde_json::to_string_pretty(&live).unwrap())
+        .expect("seed claude live config");
+    live
+}
+
+#[test]
+fn switch_all_tools_switches_every_selected_tool() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+    seed_claude_live();
+
+    let config = seed_claude_and_codex(json!({
+        "auth": { "OPENAI_API_KEY": "sk-new-codex" },
+        "config": ""
+    }));
+    let app_state = create_test_state_with_config(&config).expect("create test state");
+
+    let selections = HashMap::from([
+        ("claude".to_string(), "claude-new".to_string()),
+        ("codex".to_string(), "codex-new".to_string()),
+    ]);
+    switch_all_tools_test_hook(&app_state, &selections).expect("switch all tools");
+
+    let claude: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude settings");
+    assert_eq!(claude["env"]["ANTHROPIC_AUTH_TOKEN"], "sk-new-claude");
+    let auth: serde_json::Value = read_json_file(&get_codex_auth_path()).expect("read auth");
+    assert_eq!(auth["OPENAI_API_KEY"], "sk-new-codex");
+
+    for (app, id) in [("claude", "claude-new"), ("codex", "codex-new")] {
+        let current = app_state.db.get_current_provider(app).expect("current");
+        assert_eq!(current.as_deref(), Some(id));
+    }
+}
+
+#[test]
+fn switch_all_tools_rejects_invalid_selection_without_writing() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+    let live = seed_claude_live();
+
+    // Codex 供应商缺少 auth，整个批量切换都应被拒绝
+    let config = seed_claude_and_codex(json!({ "config": "" }));
+    let app_state = create_test_state_with_config(&config).expect("create test state");
+
+    let selections = HashMap::from([
+        ("claude".to_string(), "claude-new".to_string()),
+        ("codex".to_string(), "codex-new".to_string()),
+    ]);
+    assert!(switch_all_tools_test_hook(&app_state, &selections).is_err());
+
+    let claude: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude settings");
+    assert_eq!(claude, live, "claude live config must be untouched");
+    for (app, id) in [("claude", "claude-old"), ("codex", "codex-old")] {
+        let current = app_state.db.get_current_provider(app).expect("current");
+        assert_eq!(current.as_deref(), Some(id));
+    }
+
+    let duplicate = HashMap::from([
+        ("claude".to_string(), "claude-new".to_string()),
+        ("Claude".to_string(), "claude-old".to_string()),
+    ]);
+    assert!(switch_all_tools_test_hook(&app_state, &duplicate).is_err());
+}
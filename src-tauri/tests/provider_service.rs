@@ -2,7 +2,7 @@ use serde_json::json;
 
 use cc_switch_lib::{
     get_claude_settings_path, read_json_file, write_codex_live_atomic, AppError, AppType, McpApps,
-    McpServer, MultiAppConfig, Provider, ProviderMeta, ProviderService,
+    McpServer, MultiAppConfig, ProfileService, Provider, ProviderMeta, ProviderService,
 };
 
 #[path = "support.rs"]
@@ -419,6 +419,221 @@ fn provider_service_switch_claude_updates_live_and_state() {
     );
 }
 
+#[test]
+fn provider_service_switch_claude_injects_provider_env() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "with-env".to_string();
+        let mut with_env = Provider::with_id(
+            "with-env".to_string(),
+            "Claude With Env".to_string(),
+            json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "env-key" } }),
+            None,
+        );
+        with_env.env.insert(
+            "ANTHROPIC_CUSTOM_HEADERS".to_string(),
+            "X-Team: infra".to_string(),
+        );
+        manager.providers.insert("with-env".to_string(), with_env);
+        manager.providers.insert(
+            "plain".to_string(),
+            Provider::with_id(
+                "plain".to_string(),
+                "Plain Claude".to_string(),
+                json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "plain-key" } }),
+                None,
+            ),
+        );
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+
+    ProviderService::sync_current_to_live(&state).expect("sync live settings");
+    let live: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude live settings");
+    assert_eq!(live["env"]["ANTHROPIC_CUSTOM_HEADERS"], "X-Team: infra");
+    assert_eq!(live["env"]["ANTHROPIC_AUTH_TOKEN"], "env-key");
+
+    // 切走时回填 live 配置，注入的变量不应并入 settingsConfig
+    ProviderService::switch(&state, AppType::Claude, "plain").expect("switch provider");
+    let live: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude live settings");
+    assert!(live["env"].get("ANTHROPIC_CUSTOM_HEADERS").is_none());
+
+    let backfilled = state
+        .db
+        .get_provider_by_id("with-env", AppType::Claude.as_str())
+        .expect("load provider")
+        .expect("provider exists");
+    assert!(backfilled.settings_config["env"]
+        .get("ANTHROPIC_CUSTOM_HEADERS")
+        .is_none());
+    assert_eq!(backfilled.env["ANTHROPIC_CUSTOM_HEADERS"], "X-Team: infra");
+
+    let mut invalid = backfilled.clone();
+    invalid.env.insert("BAD NAME".to_string(), "x".to_string());
+    assert!(ProviderService::update(&state, AppType::Claude, invalid).is_err());
+}
+
+#[test]
+fn provider_service_switch_applies_model_and_base_url_overrides() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "override".to_string();
+        let mut with_override = Provider::with_id(
+            "override".to_string(),
+            "Claude Override".to_string(),
+            json!({ "env": {
+                "ANTHROPIC_AUTH_TOKEN": "override-key",
+                "ANTHROPIC_MODEL": "claude-sonnet"
+            } }),
+            None,
+        );
+        with_override.model = Some("claude-opus".to_string());
+        with_override.base_url = Some("https://relay.example.com".to_string());
+        manager
+            .providers
+            .insert("override".to_string(), with_override);
+        manager.providers.insert(
+            "plain".to_string(),
+            Provider::with_id(
+                "plain".to_string(),
+                "Plain Claude".to_string(),
+                json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "plain-key" } }),
+                None,
+            ),
+        );
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+
+    let preview = ProviderService::preview_switch(&state, AppType::Claude, "override")
+        .expect("preview switch");
+    assert_eq!(preview.model.value.as_deref(), Some("claude-opus"));
+    assert!(preview.model.overridden);
+    assert_eq!(
+        preview.base_url.value.as_deref(),
+        Some("https://relay.example.com")
+    );
+
+    ProviderService::sync_current_to_live(&state).expect("sync live settings");
+    let live: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude live settings");
+    assert_eq!(live["env"]["ANTHROPIC_MODEL"], "claude-opus");
+    assert_eq!(
+        live["env"]["ANTHROPIC_BASE_URL"],
+        "https://relay.example.com"
+    );
+
+    // 回填时恢复被覆盖的原值，覆盖字段保持不变
+    ProviderService::switch(&state, AppType::Claude, "plain").expect("switch provider");
+    let backfilled = state
+        .db
+        .get_provider_by_id("override", AppType::Claude.as_str())
+        .expect("load provider")
+        .expect("provider exists");
+    assert_eq!(
+        backfilled.settings_config["env"]["ANTHROPIC_MODEL"],
+        "claude-sonnet"
+    );
+    assert!(backfilled.settings_config["env"]
+        .get("ANTHROPIC_BASE_URL")
+        .is_none());
+    assert_eq!(backfilled.model.as_deref(), Some("claude-opus"));
+
+    let preview =
+        ProviderService::preview_switch(&state, AppType::Claude, "plain").expect("preview");
+    assert_eq!(preview.model.value, None, "tool default applies");
+    assert!(!preview.model.overridden);
+
+    let mut blank = backfilled.clone();
+    blank.model = Some("  ".to_string());
+    assert!(ProviderService::update(&state, AppType::Claude, blank).is_err());
+}
+
+#[test]
+fn provider_service_undo_last_switch_restores_live_and_current() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let settings_path = get_claude_settings_path();
+    if let Some(parent) = settings_path.parent() {
+        std::fs::create_dir_all(parent).expect("create claude settings dir");
+    }
+    let legacy_text = serde_json::to_string_pretty(&json!({
+        "env": { "ANTHROPIC_API_KEY": "legacy-key" }
+    }))
+    .expect("serialize legacy live");
+    std::fs::write(&settings_path, &legacy_text).expect("seed claude live config");
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "old-provider".to_string();
+        for (id, key) in [("old-provider", "stale-key"), ("new-provider", "fresh-key")] {
+            manager.providers.insert(
+                id.to_string(),
+                Provider::with_id(
+                    id.to_string(),
+                    id.to_string(),
+                    json!({ "env": { "ANTHROPIC_API_KEY": key } }),
+                    None,
+                ),
+            );
+        }
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+
+    ProviderService::switch(&state, AppType::Claude, "new-provider").expect("switch provider");
+    let result = ProviderService::undo_last_switch(&state).expect("undo last switch");
+    assert_eq!(result.provider_id, "old-provider");
+    assert_eq!(
+        result.restored_files,
+        vec![settings_path.to_string_lossy().to_string()]
+    );
+    assert_eq!(
+        std::fs::read_to_string(&settings_path).expect("read restored live"),
+        legacy_text,
+        "live settings.json should be restored from backup"
+    );
+    assert_eq!(
+        state
+            .db
+            .get_current_provider(AppType::Claude.as_str())
+            .expect("get current provider")
+            .as_deref(),
+        Some("old-provider")
+    );
+    assert!(
+        ProviderService::undo_last_switch(&state).is_err(),
+        "undo should only apply once"
+    );
+
+    // External edits after the switch block the undo
+    ProviderService::switch(&state, AppType::Claude, "new-provider").expect("switch again");
+    std::fs::write(&settings_path, "{}").expect("modify live config externally");
+    let err = ProviderService::undo_last_switch(&state).expect_err("undo should be refused");
+    assert!(
+        err.to_string().contains("已被修改"),
+        "unexpected error: {err}"
+    );
+}
+
 #[test]
 fn provider_service_switch_missing_provider_returns_error() {
     let _guard = test_mutex().lock().expect("acquire test mutex");
@@ -648,3 +863,309 @@ fn provider_service_delete_current_provider_returns_error() {
         other => panic!("expected Config/Message error, got {other:?}"),
     }
 }
+
+#[test]
+fn provider_service_duplicate_twice_yields_unique_copies() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "first".to_string();
+        for (id, name) in [("first", "First"), ("second", "Second")] {
+            manager.providers.insert(
+                id.to_string(),
+                Provider::with_id(
+                    id.to_string(),
+                    name.to_string(),
+                    json!({ "env": { "ANTHROPIC_API_KEY": format!("{id}-key") } }),
+                    None,
+                ),
+            );
+        }
+    }
+
+    let app_state = create_test_state_with_config(&config).expect("create test state");
+
+    let copy1 = ProviderService::duplicate(&app_state, AppType::Claude, "first")
+        .expect("duplicate first time");
+    let copy2 = ProviderService::duplicate(&app_state, AppType::Claude, "first")
+        .expect("duplicate second time");
+
+    assert_ne!(copy1.id, copy2.id, "copies should get unique ids");
+    assert_ne!(copy1.id, "first");
+    assert_ne!(copy1.name, copy2.name, "copies should get distinct names");
+    assert_eq!(copy1.name, "First (copy)");
+    assert_eq!(copy1.settings_config, copy2.settings_config);
+
+    let current = app_state
+        .db
+        .get_current_provider(AppType::Claude.as_str())
+        .expect("get current provider");
+    assert_eq!(
+        current.as_deref(),
+        Some("first"),
+        "copy must not become current"
+    );
+
+    let ids: Vec<String> = app_state
+        .db
+        .get_all_providers(AppType::Claude.as_str())
+        .expect("get all providers")
+        .keys()
+        .cloned()
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            "first".to_string(),
+            copy2.id.clone(),
+            copy1.id.clone(),
+            "second".to_string()
+        ],
+        "copies should be placed right after the original"
+    );
+}
+
+#[test]
+fn profile_switch_swaps_providers_and_rewrites_live() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.current = "personal".to_string();
+        manager.providers.insert(
+            "personal".to_string(),
+            Provider::with_id(
+                "personal".to_string(),
+                "Personal".to_string(),
+                json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "personal-key" } }),
+                None,
+            ),
+        );
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+    ProviderService::sync_current_to_live(&state).expect("sync live settings");
+
+    let profiles = ProfileService::list(&state).expect("list profiles");
+    assert_eq!(profiles.len(), 1, "default profile exists");
+    assert!(profiles[0].active);
+    let default_id = profiles[0].id.clone();
+
+    let work = ProfileService::create(&state, " Work ").expect("create profile");
+    assert_eq!(work.name, "Work");
+    assert!(
+        ProfileService::create(&state, "Work").is_err(),
+        "duplicate name"
+    );
+
+    ProfileService::switch(&state, &work.id).expect("switch to work");
+    assert!(ProviderService::list(&state, AppType::Claude)
+        .expect("list providers")
+        .is_empty());
+    ProviderService::add(
+        &state,
+        AppType::Claude,
+        Provider::with_id(
+            "work".to_string(),
+            "Work Relay".to_string(),
+            json!({ "env": { "ANTHROPIC_AUTH_TOKEN": "work-key" } }),
+            None,
+        ),
+    )
+    .expect("add work provider");
+    ProviderService::switch(&state, AppType::Claude, "work").expect("switch provider");
+    assert!(
+        ProfileService::delete(&state, &work.id).is_err(),
+        "active profile cannot be deleted"
+    );
+
+    ProfileService::switch(&state, &default_id).expect("switch back to default");
+    let providers = ProviderService::list(&state, AppType::Claude).expect("list providers");
+    assert_eq!(providers.keys().collect::<Vec<_>>(), vec!["personal"]);
+    assert_eq!(
+        ProviderService::current(&state, AppType::Claude).expect("current provider"),
+        "personal"
+    );
+    let live: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude live settings");
+    assert_eq!(live["env"]["ANTHROPIC_AUTH_TOKEN"], "personal-key");
+
+    ProfileService::switch(&state, &work.id).expect("switch to work again");
+    let live: serde_json::Value =
+        read_json_file(&get_claude_settings_path()).expect("read claude live settings");
+    assert_eq!(live["env"]["ANTHROPIC_AUTH_TOKEN"], "work-key");
+
+    ProfileService::switch(&state, &default_id).expect("switch back to default");
+    ProfileService::delete(&state, &work.id).expect("delete work profile");
+    assert!(ProfileService::delete(&state, &default_id).is_err());
+    assert_eq!(ProfileService::list(&state).expect("list").len(), 1);
+}
+
+#[test]
+fn switch_records_last_used_and_use_count() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        for id in ["a", "b"] {
+            manager.providers.insert(
+                id.to_string(),
+                Provider::with_id(
+                    id.to_string(),
+                    id.to_uppercase(),
+                    json!({ "env": { "ANTHROPIC_API_KEY": format!("key-{id}") } }),
+                    None,
+                ),
+            );
+        }
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+
+    for id in ["b", "a", "b"] {
+        ProviderService::switch(&state, AppType::Claude, id).expect("switch provider");
+    }
+
+    let providers = state
+        .db
+        .get_all_providers(AppType::Claude.as_str())
+        .expect("get all providers");
+    assert_eq!(providers["b"].use_count, 2);
+    assert_eq!(providers["a"].use_count, 1);
+    assert!(providers["b"].last_used_at >= providers["a"].last_used_at);
+    assert!(providers["a"].last_used_at.is_some());
+}
+
+#[test]
+fn import_from_tool_config_adopts_live_env() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let state = create_test_state().expect("create test state");
+    let err = ProviderService::import_from_tool_config(&state, AppType::Claude, false)
+        .expect_err("missing settings.json should be rejected");
+    assert!(
+        err.to_string().contains("不存在"),
+        "unexpected error: {err}"
+    );
+
+    let settings_path = get_claude_settings_path();
+    std::fs::create_dir_all(settings_path.parent().unwrap()).expect("create claude dir");
+    std::fs::write(
+        &settings_path,
+        serde_json::to_string(&json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": "https://relay.example",
+                "ANTHROPIC_AUTH_TOKEN": "sk-hand-written",
+                "ANTHROPIC_MODEL": "claude-sonnet"
+            },
+            "permissions": { "allow": ["Bash"] }
+        }))
+        .unwrap(),
+    )
+    .expect("write settings.json");
+
+    let first = ProviderService::import_from_tool_config(&state, AppType::Claude, false)
+        .expect("import live config");
+    assert_eq!(first.name, "Imported from claude");
+    assert!(first.settings_config.get("permissions").is_none());
+    assert_eq!(
+        first.settings_config["env"]["ANTHROPIC_BASE_URL"],
+        "https://relay.example"
+    );
+
+    let second = ProviderService::import_from_tool_config(&state, AppType::Claude, true)
+        .expect("import again");
+    assert_eq!(second.name, "Imported from claude (2)");
+    assert_eq!(
+        state
+            .db
+            .get_current_provider(AppType::Claude.as_str())
+            .expect("read current")
+            .as_deref(),
+        Some(second.id.as_str())
+    );
+}
+
+#[test]
+fn active_provider_follows_switch() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let mut config = MultiAppConfig::default();
+    {
+        let manager = config
+            .get_manager_mut(&AppType::Claude)
+            .expect("claude manager");
+        manager.providers.insert(
+            "a".to_string(),
+            Provider::with_id(
+                "a".to_string(),
+                "Relay A".to_string(),
+                json!({ "env": { "ANTHROPIC_API_KEY": "key-a" } }),
+                None,
+            ),
+        );
+    }
+    let state = create_test_state_with_config(&config).expect("create test state");
+    assert!(ProviderService::active(&state, &AppType::Codex)
+        .expect("query codex")
+        .is_none());
+
+    ProviderService::switch(&state, AppType::Claude, "a").expect("switch provider");
+    let active = ProviderService::active(&state, &AppType::Claude)
+        .expect("query claude")
+        .expect("claude has an active provider");
+    assert_eq!(active.id, "a");
+    assert_eq!(active.name, "Relay A");
+}
+
+#[test]
+fn invalid_live_config_is_reported_and_reset() {
+    let _guard = test_mutex().lock().expect("acquire test mutex");
+    reset_test_fs();
+    let _home = ensure_test_home();
+
+    let settings_path = get_claude_settings_path();
+    std::fs::create_dir_all(settings_path.parent().unwrap()).expect("create claude dir");
+    std::fs::write(&settings_path, "{\n  \"env\": {\n    \"A\": 1,\n  }\n}").expect("write");
+
+    let errors = ProviderService::check_live_configs(&AppType::Claude).expect("check configs");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 4);
+    assert!(errors[0].snippet.contains("\"A\": 1,"));
+
+    let reset = ProviderService::reset_invalid_live_configs(&AppType::Claude).expect("reset");
+    assert_eq!(reset.len(), 1);
+    assert!(reset[0].backup_path.contains("settings.json.invalid-"));
+    let backup = std::fs::read_to_string(&reset[0].backup_path).expect("read backup");
+    assert!(backup.contains("\"A\": 1,"));
+    assert_eq!(
+        std::fs::read_to_string(&settings_path).expect("read settings"),
+        "{}\n"
+    );
+    assert!(ProviderService::check_live_configs(&AppType::Claude)
+        .expect("recheck")
+        .is_empty());
+    assert!(
+        ProviderService::reset_invalid_live_configs(&AppType::Claude)
+            .expect("reset valid config")
+            .is_empty()
+    );
+}